@@ -0,0 +1,17 @@
+//! Percentile conformance report
+//!
+//! Checks every exact engine and method against the NumPy and R reference
+//! vectors in `outlier::accuracy`, prints a Markdown report, and exits
+//! non-zero if any result differs.
+//!
+//! Run with:
+//!   cargo run --example conformance > conformance.md
+
+fn main() -> anyhow::Result<()> {
+    let results = outlier::accuracy::check()?;
+    print!("{}", outlier::accuracy::report(&results));
+    if !results.iter().all(|r| r.passed()) {
+        std::process::exit(1);
+    }
+    Ok(())
+}
@@ -1,22 +1,27 @@
 //! Volume test script for outlier API
 //!
 //! Tests the percentile calculation with 1 million values at both
-//! 95th and 90th percentile thresholds.
+//! 95th and 90th percentile thresholds, then compares the built-in
+//! percentile engines on the same data.
 //!
 //! Run with:
 //!   cargo run --example volume_test                    # Library tests only (1M values)
 //!   cargo run --example volume_test -- --count 100000  # Custom value count
+//!   cargo run --example volume_test -- --seed 7        # Different (reproducible) dataset
 //!   cargo run --example volume_test -- --with-api      # Include API tests (start server first)
 //!   cargo run --example volume_test -- --api-url http://localhost:8080  # Custom API URL
 //!
 //! To start the server:
 //!   cargo run --features server -- --serve
 
-use outlier::{CalculateRequest, CalculateResponse, PercentileMethod, calculate_percentile};
+use outlier::{
+    CalculateRequest, CalculateResponse, EngineKind, PercentileMethod, calculate_percentile,
+};
 use std::time::Instant;
 
 const DEFAULT_NUM_VALUES: usize = 1_000_000;
 const DEFAULT_API_URL: &str = "http://localhost:3000";
+const DEFAULT_SEED: u64 = 42;
 
 fn main() {
     let args: Vec<String> = std::env::args().collect();
@@ -33,6 +38,12 @@ fn main() {
         .and_then(|i| args.get(i + 1))
         .and_then(|s| s.parse::<usize>().ok())
         .unwrap_or(DEFAULT_NUM_VALUES);
+    let seed = args
+        .iter()
+        .position(|a| a == "--seed")
+        .and_then(|i| args.get(i + 1))
+        .and_then(|s| s.parse::<u64>().ok())
+        .unwrap_or(DEFAULT_SEED);
 
     println!("=================================================");
     println!("  Outlier Volume Test - {} Values", num_values);
@@ -40,9 +51,9 @@ fn main() {
     println!();
 
     // Generate random values using a simple LCG
-    println!("Generating {} values...", num_values);
+    println!("Generating {} values (seed {})...", num_values, seed);
     let gen_start = Instant::now();
-    let values = generate_values(num_values);
+    let values = generate_values(num_values, seed);
     let gen_duration = gen_start.elapsed();
     println!("Generated {} values in {:?}", values.len(), gen_duration);
     println!();
@@ -54,6 +65,7 @@ fn main() {
     let mean = sum / values.len() as f64;
 
     println!("Dataset Statistics:");
+    println!("  Seed:  {}", seed);
     println!("  Count: {}", values.len());
     println!("  Min:   {:.4}", min);
     println!("  Max:   {:.4}", max);
@@ -88,6 +100,17 @@ fn main() {
     run_percentile_test(&values, 75.0);
     run_percentile_test(&values, 50.0);
 
+    println!("-------------------------------------------------");
+    println!("Engine Comparison (Library)");
+    println!("-------------------------------------------------");
+    for engine in [
+        EngineKind::ExactSort,
+        EngineKind::QuickSelect,
+        EngineKind::TDigest,
+    ] {
+        run_engine_test(&values, engine, &[50.0, 90.0, 95.0, 99.0]);
+    }
+
     // ===========================================
     // API Endpoint Tests
     // ===========================================
@@ -162,8 +185,8 @@ fn main() {
 }
 
 /// Generate a vector of pseudo-random values using a Linear Congruential Generator
-/// Values are in the range [0, 10000)
-fn generate_values(count: usize) -> Vec<f64> {
+/// Values are in the range [0, 10000); the same seed always yields the same values
+fn generate_values(count: usize, seed: u64) -> Vec<f64> {
     let mut values = Vec::with_capacity(count);
 
     // LCG parameters (same as glibc)
@@ -171,7 +194,7 @@ fn generate_values(count: usize) -> Vec<f64> {
     let c: u64 = 12345;
     let m: u64 = 2147483648; // 2^31
 
-    let mut seed: u64 = 42; // Fixed seed for reproducibility
+    let mut seed = seed % m;
 
     for _ in 0..count {
         seed = (a.wrapping_mul(seed).wrapping_add(c)) % m;
@@ -206,6 +229,32 @@ fn run_percentile_test(values: &[f64], percentile: f64) -> Option<f64> {
     }
 }
 
+/// Time one engine over several percentiles and print each result
+fn run_engine_test(values: &[f64], engine: EngineKind, percentiles: &[f64]) {
+    let start = Instant::now();
+    let results = engine
+        .build(None)
+        .and_then(|e| e.percentiles(values, percentiles, PercentileMethod::Linear));
+    let duration = start.elapsed();
+
+    match results {
+        Ok(results) => {
+            let results: Vec<String> = percentiles
+                .iter()
+                .zip(&results)
+                .map(|(p, r)| format!("P{p}={r:.4}"))
+                .collect();
+            println!("  {engine}: {}", results.join(", "));
+            println!("  Calculation time: {:?}", duration);
+            println!();
+        }
+        Err(e) => {
+            println!("  Error with engine {engine}: {e}");
+            println!();
+        }
+    }
+}
+
 /// Check if the server is available
 async fn check_server_health(url: &str) -> bool {
     let client = reqwest::Client::new();
@@ -225,10 +274,18 @@ async fn run_api_percentile_test(base_url: &str, values: &[f64], percentile: f64
     let client = reqwest::Client::new();
     let url = format!("{}/calculate", base_url);
 
-    let request = CalculateRequest {
-        values: values.to_vec(),
-        percentile,
-        method: PercentileMethod::Linear,
+    let request = match CalculateRequest::builder()
+        .values(values)
+        .percentile(percentile)
+        .method(PercentileMethod::Linear)
+        .build()
+    {
+        Ok(request) => request,
+        Err(e) => {
+            println!("  Invalid request for P{}: {}", percentile, e);
+            println!();
+            return None;
+        }
     };
 
     let start = Instant::now();
@@ -0,0 +1,379 @@
+//! Conformance of the percentile methods with the wider ecosystem
+//!
+//! Each method is checked against reference vectors: small datasets with the
+//! results NumPy's `quantile` methods (and R's `quantile` type 7, which is
+//! NumPy's `linear`) give for them. NumPy and R both work from the virtual
+//! index `p * (n - 1)` into the sorted values, as outlier does, so the
+//! expected results were worked exactly from their documented definitions and
+//! agree with the examples published alongside the datasets.
+//!
+//! [`check`] runs every exact engine over every vector, and [`report`] turns
+//! the results into a Markdown conformance report:
+//!
+//! ```
+//! let results = outlier::accuracy::check().unwrap();
+//! assert!(results.iter().all(|r| r.passed()));
+//! println!("{}", outlier::accuracy::report(&results));
+//! ```
+
+use crate::engine::EngineKind;
+use crate::{PercentileMethod, format_percentile_label};
+use anyhow::Result;
+use std::fmt::Write;
+
+/// Percentiles every reference vector gives a result for
+pub const PERCENTILES: [f64; 8] = [0.0, 10.0, 25.0, 40.0, 50.0, 75.0, 90.0, 100.0];
+
+/// Largest difference from a reference result still counted as a match,
+/// relative to the result's magnitude (or absolute below 1)
+pub const TOLERANCE: f64 = 1e-12;
+
+/// Engines whose results must match the references exactly; the t-digest is
+/// approximate and the external engine is whatever its command computes
+const EXACT_ENGINES: [EngineKind; 2] = [EngineKind::ExactSort, EngineKind::QuickSelect];
+
+/// What each method is called elsewhere
+pub const EQUIVALENTS: [(PercentileMethod, &str); 6] = [
+    (
+        PercentileMethod::Linear,
+        "NumPy `linear` (the default), R type 7 (the default), Excel `PERCENTILE.INC`",
+    ),
+    (PercentileMethod::Lower, "NumPy `lower`"),
+    (PercentileMethod::Upper, "NumPy `higher`"),
+    (PercentileMethod::Midpoint, "NumPy `midpoint`"),
+    (PercentileMethod::NearestEven, "NumPy `nearest`"),
+    (
+        PercentileMethod::NearestRank,
+        "None: rounds NumPy's virtual index half away from zero, which differs from \
+         both NumPy `nearest` and the textbook nearest-rank method (R type 1)",
+    ),
+];
+
+/// A dataset with the expected result of every method at [`PERCENTILES`]
+pub struct ReferenceDataset {
+    pub name: &'static str,
+    /// Where the values come from
+    pub source: &'static str,
+    pub values: &'static [f64],
+    pub expected: [(PercentileMethod, [f64; PERCENTILES.len()]); 6],
+}
+
+pub const REFERENCE_DATASETS: &[ReferenceDataset] = &[
+    ReferenceDataset {
+        name: "numpy-doc",
+        source: "The array in the `numpy.percentile` documentation examples",
+        values: &[10.0, 7.0, 4.0, 3.0, 2.0, 1.0],
+        expected: [
+            (
+                PercentileMethod::Linear,
+                [1.0, 1.5, 2.25, 3.0, 3.5, 6.25, 8.5, 10.0],
+            ),
+            (
+                PercentileMethod::Lower,
+                [1.0, 1.0, 2.0, 3.0, 3.0, 4.0, 7.0, 10.0],
+            ),
+            (
+                PercentileMethod::Upper,
+                [1.0, 2.0, 3.0, 3.0, 4.0, 7.0, 10.0, 10.0],
+            ),
+            (
+                PercentileMethod::Midpoint,
+                [1.0, 1.5, 2.5, 3.0, 3.5, 5.5, 8.5, 10.0],
+            ),
+            (
+                PercentileMethod::NearestEven,
+                [1.0, 1.0, 2.0, 3.0, 3.0, 7.0, 7.0, 10.0],
+            ),
+            (
+                PercentileMethod::NearestRank,
+                [1.0, 2.0, 2.0, 3.0, 4.0, 7.0, 10.0, 10.0],
+            ),
+        ],
+    },
+    ReferenceDataset {
+        name: "wikipedia-5",
+        source: "The linear interpolation example in Wikipedia's \"Percentile\" article",
+        values: &[15.0, 20.0, 35.0, 40.0, 50.0],
+        expected: [
+            (
+                PercentileMethod::Linear,
+                [15.0, 17.0, 20.0, 29.0, 35.0, 40.0, 46.0, 50.0],
+            ),
+            (
+                PercentileMethod::Lower,
+                [15.0, 15.0, 20.0, 20.0, 35.0, 40.0, 40.0, 50.0],
+            ),
+            (
+                PercentileMethod::Upper,
+                [15.0, 20.0, 20.0, 35.0, 35.0, 40.0, 50.0, 50.0],
+            ),
+            (
+                PercentileMethod::Midpoint,
+                [15.0, 17.5, 20.0, 27.5, 35.0, 40.0, 45.0, 50.0],
+            ),
+            (
+                PercentileMethod::NearestEven,
+                [15.0, 15.0, 20.0, 35.0, 35.0, 40.0, 50.0, 50.0],
+            ),
+            (
+                PercentileMethod::NearestRank,
+                [15.0, 15.0, 20.0, 35.0, 35.0, 40.0, 50.0, 50.0],
+            ),
+        ],
+    },
+    ReferenceDataset {
+        name: "wikipedia-10",
+        source: "The nearest-rank example in Wikipedia's \"Percentile\" article",
+        values: &[3.0, 6.0, 7.0, 8.0, 8.0, 10.0, 13.0, 15.0, 16.0, 20.0],
+        expected: [
+            (
+                PercentileMethod::Linear,
+                [3.0, 5.7, 7.25, 8.0, 9.0, 14.5, 16.4, 20.0],
+            ),
+            (
+                PercentileMethod::Lower,
+                [3.0, 3.0, 7.0, 8.0, 8.0, 13.0, 16.0, 20.0],
+            ),
+            (
+                PercentileMethod::Upper,
+                [3.0, 6.0, 8.0, 8.0, 10.0, 15.0, 20.0, 20.0],
+            ),
+            (
+                PercentileMethod::Midpoint,
+                [3.0, 4.5, 7.5, 8.0, 9.0, 14.0, 18.0, 20.0],
+            ),
+            (
+                PercentileMethod::NearestEven,
+                [3.0, 6.0, 7.0, 8.0, 8.0, 15.0, 16.0, 20.0],
+            ),
+            (
+                PercentileMethod::NearestRank,
+                [3.0, 6.0, 7.0, 8.0, 10.0, 15.0, 16.0, 20.0],
+            ),
+        ],
+    },
+    ReferenceDataset {
+        name: "single",
+        source: "A single value",
+        values: &[42.0],
+        expected: [
+            (
+                PercentileMethod::Linear,
+                [42.0, 42.0, 42.0, 42.0, 42.0, 42.0, 42.0, 42.0],
+            ),
+            (
+                PercentileMethod::Lower,
+                [42.0, 42.0, 42.0, 42.0, 42.0, 42.0, 42.0, 42.0],
+            ),
+            (
+                PercentileMethod::Upper,
+                [42.0, 42.0, 42.0, 42.0, 42.0, 42.0, 42.0, 42.0],
+            ),
+            (
+                PercentileMethod::Midpoint,
+                [42.0, 42.0, 42.0, 42.0, 42.0, 42.0, 42.0, 42.0],
+            ),
+            (
+                PercentileMethod::NearestEven,
+                [42.0, 42.0, 42.0, 42.0, 42.0, 42.0, 42.0, 42.0],
+            ),
+            (
+                PercentileMethod::NearestRank,
+                [42.0, 42.0, 42.0, 42.0, 42.0, 42.0, 42.0, 42.0],
+            ),
+        ],
+    },
+    ReferenceDataset {
+        name: "pair",
+        source: "Two values, where every percentile interpolates",
+        values: &[1.0, 2.0],
+        expected: [
+            (
+                PercentileMethod::Linear,
+                [1.0, 1.1, 1.25, 1.4, 1.5, 1.75, 1.9, 2.0],
+            ),
+            (
+                PercentileMethod::Lower,
+                [1.0, 1.0, 1.0, 1.0, 1.0, 1.0, 1.0, 2.0],
+            ),
+            (
+                PercentileMethod::Upper,
+                [1.0, 2.0, 2.0, 2.0, 2.0, 2.0, 2.0, 2.0],
+            ),
+            (
+                PercentileMethod::Midpoint,
+                [1.0, 1.5, 1.5, 1.5, 1.5, 1.5, 1.5, 2.0],
+            ),
+            (
+                PercentileMethod::NearestEven,
+                [1.0, 1.0, 1.0, 1.0, 1.0, 2.0, 2.0, 2.0],
+            ),
+            (
+                PercentileMethod::NearestRank,
+                [1.0, 1.0, 1.0, 1.0, 2.0, 2.0, 2.0, 2.0],
+            ),
+        ],
+    },
+    ReferenceDataset {
+        name: "spread",
+        source: "Negative, fractional and large values together",
+        values: &[-5.5, 0.0, 0.25, 1000.0, 2500000.0],
+        expected: [
+            (
+                PercentileMethod::Linear,
+                [-5.5, -3.3, 0.0, 0.15, 0.25, 1000.0, 1500400.0, 2500000.0],
+            ),
+            (
+                PercentileMethod::Lower,
+                [-5.5, -5.5, 0.0, 0.0, 0.25, 1000.0, 1000.0, 2500000.0],
+            ),
+            (
+                PercentileMethod::Upper,
+                [-5.5, 0.0, 0.0, 0.25, 0.25, 1000.0, 2500000.0, 2500000.0],
+            ),
+            (
+                PercentileMethod::Midpoint,
+                [-5.5, -2.75, 0.0, 0.125, 0.25, 1000.0, 1250500.0, 2500000.0],
+            ),
+            (
+                PercentileMethod::NearestEven,
+                [-5.5, -5.5, 0.0, 0.25, 0.25, 1000.0, 2500000.0, 2500000.0],
+            ),
+            (
+                PercentileMethod::NearestRank,
+                [-5.5, -5.5, 0.0, 0.25, 0.25, 1000.0, 2500000.0, 2500000.0],
+            ),
+        ],
+    },
+];
+
+/// One engine's result for one reference percentile
+#[derive(Debug, Clone, PartialEq)]
+pub struct CaseResult {
+    pub dataset: &'static str,
+    pub engine: EngineKind,
+    pub method: PercentileMethod,
+    pub percentile: f64,
+    pub expected: f64,
+    pub actual: f64,
+}
+
+impl CaseResult {
+    /// Whether `actual` is within [`TOLERANCE`] of `expected`
+    pub fn passed(&self) -> bool {
+        (self.actual - self.expected).abs() <= TOLERANCE * self.expected.abs().max(1.0)
+    }
+}
+
+/// Run every exact engine over every reference vector
+pub fn check() -> Result<Vec<CaseResult>> {
+    let mut results = Vec::new();
+    for dataset in REFERENCE_DATASETS {
+        for engine in EXACT_ENGINES {
+            let engine_impl = engine.build(None)?;
+            for (method, expected) in &dataset.expected {
+                let actual = engine_impl.percentiles(dataset.values, &PERCENTILES, *method)?;
+                for ((&percentile, &expected), actual) in
+                    PERCENTILES.iter().zip(expected).zip(actual)
+                {
+                    results.push(CaseResult {
+                        dataset: dataset.name,
+                        engine,
+                        method: *method,
+                        percentile,
+                        expected,
+                        actual,
+                    });
+                }
+            }
+        }
+    }
+    Ok(results)
+}
+
+/// A Markdown conformance report: the method equivalents, a pass count per
+/// method and engine, and every mismatch
+pub fn report(results: &[CaseResult]) -> String {
+    let passed = results.iter().filter(|r| r.passed()).count();
+    let mut out = format!(
+        "# Percentile Conformance\n\n{passed} of {} reference results match (tolerance {TOLERANCE:e}).\n\n",
+        results.len()
+    );
+
+    out.push_str("| Method | Equivalent | ");
+    out.push_str(&EXACT_ENGINES.map(|e| e.to_string()).join(" | "));
+    out.push_str(" |\n|---|---|");
+    out.push_str(&"---|".repeat(EXACT_ENGINES.len()));
+    out.push('\n');
+    for (method, equivalent) in EQUIVALENTS {
+        let _ = write!(out, "| `{method}` | {equivalent} |");
+        for engine in EXACT_ENGINES {
+            let cases: Vec<_> = results
+                .iter()
+                .filter(|r| r.method == method && r.engine == engine)
+                .collect();
+            let passed = cases.iter().filter(|r| r.passed()).count();
+            let _ = write!(out, " {passed}/{} |", cases.len());
+        }
+        out.push('\n');
+    }
+
+    out.push_str("\n## Datasets\n\n");
+    for dataset in REFERENCE_DATASETS {
+        let values: Vec<String> = dataset.values.iter().map(|v| v.to_string()).collect();
+        let _ = writeln!(
+            out,
+            "- `{}`: {} ({})",
+            dataset.name,
+            dataset.source,
+            values.join(", ")
+        );
+    }
+
+    let failures: Vec<_> = results.iter().filter(|r| !r.passed()).collect();
+    if !failures.is_empty() {
+        out.push_str("\n## Mismatches\n\n| Dataset | Engine | Method | Percentile | Expected | Actual |\n|---|---|---|---|---|---|\n");
+        for r in failures {
+            let _ = writeln!(
+                out,
+                "| `{}` | `{}` | `{}` | {} | {:?} | {:?} |",
+                r.dataset,
+                r.engine,
+                r.method,
+                format_percentile_label(r.percentile),
+                r.expected,
+                r.actual
+            );
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn every_exact_engine_matches_the_references() {
+        let results = check().unwrap();
+        assert_eq!(
+            results.len(),
+            REFERENCE_DATASETS.len() * EXACT_ENGINES.len() * 6 * PERCENTILES.len()
+        );
+        let failures: Vec<_> = results.iter().filter(|r| !r.passed()).collect();
+        assert!(failures.is_empty(), "{}", report(&results));
+    }
+
+    #[test]
+    fn report_lists_methods_and_mismatches() {
+        let mut results = check().unwrap();
+        let report_text = report(&results);
+        assert!(report_text.contains("| `linear` | NumPy `linear` (the default)"));
+        assert!(!report_text.contains("## Mismatches"));
+
+        results[0].actual += 1.0;
+        let report_text = report(&results);
+        assert!(report_text.contains("## Mismatches"));
+        assert!(report_text.contains("| `numpy-doc` | `exact_sort` | `linear` | P0 | 1.0 | 2.0 |"));
+    }
+}
@@ -0,0 +1,156 @@
+use crate::{
+    CsvOptions, InputFormat, PercentileMethod, RoundingMode, parse_localized_number,
+    percentile_of_sorted, resolve_input_format, round_to_precision, sort_values,
+};
+use anyhow::{Context, Result};
+
+/// Columns [`annotate_csv`] appends to each row
+pub const ANNOTATION_COLUMNS: [&str; 3] = ["percentile_rank", "is_outlier", "zscore"];
+
+/// Interquartile ranges beyond the quartiles that Tukey's fences sit by default
+pub const DEFAULT_FENCE: f64 = 1.5;
+
+/// Decimal places of the percentile ranks and z-scores [`annotate_csv`] writes
+const ANNOTATION_PRECISION: u32 = 4;
+
+/// What [`annotate`] works out about one value
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Annotation {
+    /// Percentage of values below this one, counting equal values as half
+    /// (SciPy's `percentileofscore` with `kind="mean"`)
+    pub percentile_rank: f64,
+    /// Whether the value lies outside Tukey's fences
+    pub is_outlier: bool,
+    /// Sample standard deviations from the mean; `None` when every value is
+    /// the same or there is only one
+    pub zscore: Option<f64>,
+}
+
+/// Tukey's fences `[Q1 - k*IQR, Q3 + k*IQR]` of sorted values, using
+/// linearly interpolated quartiles
+pub fn tukey_fences(sorted: &[f64], k: f64) -> Result<(f64, f64)> {
+    let q1 = percentile_of_sorted(sorted, 25.0, PercentileMethod::Linear)?;
+    let q3 = percentile_of_sorted(sorted, 75.0, PercentileMethod::Linear)?;
+    let iqr = q3 - q1;
+    Ok((q1 - k * iqr, q3 + k * iqr))
+}
+
+/// Annotate each value with its percentile rank, whether it lies outside
+/// Tukey's fences `k` interquartile ranges beyond the quartiles, and its
+/// z-score, in input order
+///
+/// # Examples
+/// ```
+/// use outlier::annotate;
+///
+/// let annotations = annotate(&[1.0, 2.0, 3.0, 4.0, 100.0], 1.5).unwrap();
+/// assert_eq!(annotations[0].percentile_rank, 10.0);
+/// assert!(annotations[4].is_outlier);
+/// ```
+pub fn annotate(values: &[f64], k: f64) -> Result<Vec<Annotation>> {
+    let sorted = sort_values(values);
+    let (lower, upper) = tukey_fences(&sorted, k)?;
+    let count = values.len() as f64;
+    let mean = values.iter().sum::<f64>() / count;
+    let std = (values.len() > 1)
+        .then(|| {
+            let sum_sq: f64 = values.iter().map(|v| (v - mean).powi(2)).sum();
+            (sum_sq / (count - 1.0)).sqrt()
+        })
+        .filter(|std| *std > 0.0);
+
+    Ok(values
+        .iter()
+        .map(|&value| {
+            let below = sorted.partition_point(|v| *v < value);
+            let equal = sorted.partition_point(|v| *v <= value) - below;
+            Annotation {
+                percentile_rank: (below as f64 + equal as f64 / 2.0) / count * 100.0,
+                is_outlier: value < lower || value > upper,
+                zscore: std.map(|std| (value - mean) / std),
+            }
+        })
+        .collect())
+}
+
+/// Write a dataset back out as CSV with [`ANNOTATION_COLUMNS`] appended, so
+/// flagged rows can be filtered in a spreadsheet
+///
+/// CSV input keeps every original column, row and delimiter. Other formats
+/// become a single `value` column. The statistics are computed over every
+/// row, as with [`annotate`].
+pub fn annotate_csv(
+    bytes: &[u8],
+    filename: &str,
+    format: InputFormat,
+    options: &CsvOptions,
+    k: f64,
+) -> Result<Vec<u8>> {
+    let format = resolve_input_format(bytes, filename, format)?;
+    let (header, records, values) = if format == InputFormat::Csv {
+        read_csv_rows(bytes, options)?
+    } else {
+        let values = format.parse_stream(&mut &bytes[..], options)?;
+        let records = values
+            .iter()
+            .map(|v| csv::StringRecord::from(vec![v.to_string()]))
+            .collect();
+        (csv::StringRecord::from(vec!["value"]), records, values)
+    };
+    let annotations = annotate(&values, k)?;
+
+    let mut writer = csv::WriterBuilder::new()
+        .delimiter(options.delimiter)
+        .from_writer(Vec::new());
+    let mut header = header;
+    header.extend(ANNOTATION_COLUMNS);
+    writer.write_record(&header)?;
+    let rounded = |v: f64| round_to_precision(v, ANNOTATION_PRECISION, RoundingMode::HalfEven);
+    for (mut record, annotation) in records.into_iter().zip(annotations) {
+        record.push_field(&rounded(annotation.percentile_rank)?.to_string());
+        record.push_field(&annotation.is_outlier.to_string());
+        match annotation.zscore {
+            Some(zscore) => record.push_field(&rounded(zscore)?.to_string()),
+            None => record.push_field(""),
+        }
+        writer.write_record(&record)?;
+    }
+    writer.into_inner().context("Failed to write annotated CSV")
+}
+
+/// The header, rows and `value` column of a CSV file
+fn read_csv_rows(
+    bytes: &[u8],
+    options: &CsvOptions,
+) -> Result<(csv::StringRecord, Vec<csv::StringRecord>, Vec<f64>)> {
+    let mut reader = csv::ReaderBuilder::new()
+        .delimiter(options.delimiter)
+        .from_reader(bytes);
+    let header = reader
+        .headers()
+        .context("Failed to parse CSV record")?
+        .clone();
+    let column = header
+        .iter()
+        .position(|h| h == "value")
+        .context("Failed to parse CSV record: missing \"value\" header")?;
+
+    let mut records = Vec::new();
+    let mut values = Vec::new();
+    for (row, result) in reader.records().enumerate() {
+        let record = result.context("Failed to parse CSV record")?;
+        let field = record.get(column).unwrap_or_default();
+        let value = parse_localized_number(field, options)
+            .or_else(|| options.coerce.apply(field, options))
+            .with_context(|| {
+                format!(
+                    "Failed to parse CSV record {}: invalid number {:?}",
+                    row + 1,
+                    field
+                )
+            })?;
+        values.push(value);
+        records.push(record);
+    }
+    Ok((header, records, values))
+}
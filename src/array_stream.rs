@@ -0,0 +1,157 @@
+use anyhow::{Result, bail};
+
+/// Longest number accepted; anything longer is no more precise as an f64
+const MAX_NUMBER_BYTES: usize = 64;
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+enum State {
+    /// Before the opening `[`
+    #[default]
+    Start,
+    /// After `[`, expecting a number or `]`
+    First,
+    /// After `,`, expecting a number
+    Value,
+    /// Inside a number
+    Number,
+    /// After a number, expecting `,` or `]`
+    Next,
+    /// After the closing `]`
+    End,
+}
+
+/// Parses a JSON array of numbers arriving in arbitrary chunks
+///
+/// Only the number being read is buffered, so the array's text never has to
+/// be held in memory. Unlike the NDJSON parser behind `POST /ingest`, the
+/// first value that isn't a finite number fails the whole array, as
+/// `POST /calculate` would.
+#[derive(Debug, Default)]
+pub struct ArrayParser {
+    state: State,
+    number: Vec<u8>,
+    count: usize,
+}
+
+impl ArrayParser {
+    /// Parse the numbers completed by `chunk` into `values`, holding back a
+    /// trailing partial number for the next chunk
+    pub fn feed(&mut self, chunk: &[u8], values: &mut Vec<f64>) -> Result<()> {
+        for &byte in chunk {
+            self.byte(byte, values)?;
+        }
+        Ok(())
+    }
+
+    /// Check the array was closed once the body has ended
+    pub fn finish(&mut self, values: &mut Vec<f64>) -> Result<()> {
+        match self.state {
+            State::End => Ok(()),
+            State::Start => bail!("Expected a JSON array of numbers"),
+            _ => {
+                // A number running into the end of the body is still unclosed
+                if self.state == State::Number {
+                    self.end_number(values)?;
+                }
+                bail!("The array ended without a closing ']'")
+            }
+        }
+    }
+
+    fn byte(&mut self, byte: u8, values: &mut Vec<f64>) -> Result<()> {
+        let space = matches!(byte, b' ' | b'\t' | b'\n' | b'\r');
+        match self.state {
+            State::Number if space || byte == b',' || byte == b']' => {
+                self.end_number(values)?;
+                if !space {
+                    return self.byte(byte, values);
+                }
+            }
+            State::Number => {
+                if self.number.len() == MAX_NUMBER_BYTES {
+                    bail!(
+                        "Value at index {} is longer than {MAX_NUMBER_BYTES} bytes",
+                        self.count
+                    );
+                }
+                self.number.push(byte);
+            }
+            _ if space => {}
+            State::Start if byte == b'[' => self.state = State::First,
+            State::First if byte == b']' => self.state = State::End,
+            State::First | State::Value if byte == b'-' || byte.is_ascii_digit() => {
+                self.number.push(byte);
+                self.state = State::Number;
+            }
+            State::Next if byte == b',' => self.state = State::Value,
+            State::Next if byte == b']' => self.state = State::End,
+            State::Start => bail!("Expected a JSON array of numbers"),
+            State::First | State::Value => {
+                bail!("Value at index {} is not a number", self.count)
+            }
+            State::Next => bail!(
+                "Expected ',' or ']' after the value at index {}",
+                self.count - 1
+            ),
+            State::End => bail!("Unexpected data after the array"),
+        }
+        Ok(())
+    }
+
+    fn end_number(&mut self, values: &mut Vec<f64>) -> Result<()> {
+        let value = serde_json::from_slice::<f64>(&self.number)
+            .ok()
+            .filter(|v| v.is_finite());
+        let Some(value) = value else {
+            bail!("Value at index {} is not a finite number", self.count);
+        };
+        values.push(value);
+        self.number.clear();
+        self.count += 1;
+        self.state = State::Next;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(chunks: &[&str]) -> Result<Vec<f64>> {
+        let mut parser = ArrayParser::default();
+        let mut values = Vec::new();
+        for chunk in chunks {
+            parser.feed(chunk.as_bytes(), &mut values)?;
+        }
+        parser.finish(&mut values)?;
+        Ok(values)
+    }
+
+    #[test]
+    fn numbers_split_across_chunks() {
+        let values = parse(&[" [1.5, -2", "e1,3", "\n,4]", " \n"]).unwrap();
+        assert_eq!(values, vec![1.5, -20.0, 3.0, 4.0]);
+        assert!(parse(&["[", "]"]).unwrap().is_empty());
+    }
+
+    #[test]
+    fn malformed_arrays_fail() {
+        for (chunks, message) in [
+            (&["{}"][..], "Expected a JSON array of numbers"),
+            (&["[1,", "\"2\"]"], "Value at index 1 is not a number"),
+            (
+                &["[1,2", " 3]"],
+                "Expected ',' or ']' after the value at index 1",
+            ),
+            (&["[1,01]"], "Value at index 1 is not a finite number"),
+            (&["[1e400]"], "Value at index 0 is not a finite number"),
+            (&["[1,]"], "Value at index 1 is not a number"),
+            (&["[1, 2"], "The array ended without a closing ']'"),
+            (&["[1] 2"], "Unexpected data after the array"),
+            (&[""], "Expected a JSON array of numbers"),
+        ] {
+            let error = parse(chunks).unwrap_err();
+            assert_eq!(error.to_string(), message, "{chunks:?}");
+        }
+    }
+}
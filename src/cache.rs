@@ -0,0 +1,203 @@
+use anyhow::{Context, Result};
+use sha2::{Digest, Sha256};
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+
+/// Files smaller than this parse faster than a cache lookup is worth
+pub const MIN_CACHED_BYTES: u64 = 1024 * 1024;
+
+/// First bytes of every entry, changed whenever the layout does
+const MAGIC: &[u8] = b"outlier-values-v1\n";
+
+/// Parsed values of local input files, keyed by path, size, modification
+/// time and parse options so any change to the file or the options misses
+pub struct ValueCache {
+    dir: PathBuf,
+}
+
+impl ValueCache {
+    pub fn new(dir: PathBuf) -> Self {
+        Self { dir }
+    }
+
+    /// `$OUTLIER_CACHE_DIR`, else `$XDG_CACHE_HOME/outlier`, else
+    /// `~/.cache/outlier`; `None` when none of them is set
+    pub fn default_location() -> Option<Self> {
+        let var = |name| std::env::var_os(name).filter(|v| !v.is_empty());
+        let root = var("OUTLIER_CACHE_DIR")
+            .map(PathBuf::from)
+            .or_else(|| var("XDG_CACHE_HOME").map(|d| PathBuf::from(d).join("outlier")))
+            .or_else(|| var("HOME").map(|d| PathBuf::from(d).join(".cache").join("outlier")))?;
+        Some(Self::new(root.join("values")))
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Entry for `path` read with `options`, or `None` for files too small to cache
+    fn entry(&self, path: &Path, options: &str) -> Result<Option<PathBuf>> {
+        let metadata = std::fs::metadata(path)
+            .with_context(|| format!("Failed to open input file {}", path.display()))?;
+        if metadata.len() < MIN_CACHED_BYTES {
+            return Ok(None);
+        }
+        let modified = metadata
+            .modified()?
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default();
+        let path = std::fs::canonicalize(path)?;
+
+        let mut hasher = Sha256::new();
+        for part in [
+            path.to_string_lossy().as_bytes(),
+            &metadata.len().to_le_bytes(),
+            &modified.as_nanos().to_le_bytes(),
+            options.as_bytes(),
+        ] {
+            hasher.update((part.len() as u64).to_le_bytes());
+            hasher.update(part);
+        }
+        Ok(Some(self.dir.join(hex::encode(hasher.finalize()))))
+    }
+
+    /// The values of `path`, from the cache when an entry matches, otherwise
+    /// from `parse`, storing them for next time
+    ///
+    /// A cache that can't be read or written is skipped rather than failing
+    /// the run.
+    pub fn values(
+        &self,
+        path: &Path,
+        options: &str,
+        parse: impl FnOnce() -> Result<Vec<f64>>,
+    ) -> Result<Vec<f64>> {
+        let Some(entry) = self.entry(path, options)? else {
+            return parse();
+        };
+        if let Some(values) = read_entry(&entry) {
+            tracing::debug!(
+                "Read {} cached values from {}",
+                values.len(),
+                entry.display()
+            );
+            return Ok(values);
+        }
+        let values = parse()?;
+        if let Err(e) = self.write_entry(&entry, &values) {
+            tracing::debug!("Failed to cache values in {}: {e}", entry.display());
+        }
+        Ok(values)
+    }
+
+    fn write_entry(&self, entry: &Path, values: &[f64]) -> Result<()> {
+        std::fs::create_dir_all(&self.dir)?;
+        let mut bytes = Vec::with_capacity(MAGIC.len() + values.len() * 8);
+        bytes.extend_from_slice(MAGIC);
+        for value in values {
+            bytes.extend_from_slice(&value.to_le_bytes());
+        }
+        // Write then rename so a concurrent run never reads half an entry
+        let staged = entry.with_extension("tmp");
+        std::fs::write(&staged, bytes)?;
+        std::fs::rename(&staged, entry)?;
+        Ok(())
+    }
+
+    /// Number of entries and their total size in bytes
+    pub fn usage(&self) -> Result<(usize, u64)> {
+        let mut usage = (0, 0);
+        for entry in self.entries()? {
+            usage.0 += 1;
+            usage.1 += entry.metadata()?.len();
+        }
+        Ok(usage)
+    }
+
+    /// Delete every entry, returning how many there were and their size
+    pub fn clear(&self) -> Result<(usize, u64)> {
+        let usage = self.usage()?;
+        for entry in self.entries()? {
+            std::fs::remove_file(entry.path())?;
+        }
+        Ok(usage)
+    }
+
+    fn entries(&self) -> Result<Vec<std::fs::DirEntry>> {
+        match std::fs::read_dir(&self.dir) {
+            Ok(entries) => Ok(entries.collect::<std::io::Result<_>>()?),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Vec::new()),
+            Err(e) => Err(e).with_context(|| format!("Failed to read {}", self.dir.display())),
+        }
+    }
+}
+
+fn read_entry(entry: &Path) -> Option<Vec<f64>> {
+    let bytes = std::fs::read(entry).ok()?;
+    let data = bytes.strip_prefix(MAGIC)?;
+    if data.len() % 8 != 0 {
+        return None;
+    }
+    Some(
+        data.chunks_exact(8)
+            .map(|chunk| f64::from_le_bytes(chunk.try_into().expect("8-byte chunk")))
+            .collect(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("outlier-{name}-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn values_are_parsed_once_per_file_and_options() {
+        let dir = temp_dir("value-cache");
+        let input = dir.join("big.csv");
+        std::fs::write(&input, vec![b'x'; MIN_CACHED_BYTES as usize]).unwrap();
+        let cache = ValueCache::new(dir.join("cache"));
+        let parses = std::cell::Cell::new(0);
+        let read = |options: &str| {
+            cache
+                .values(&input, options, || {
+                    parses.set(parses.get() + 1);
+                    Ok(vec![1.5, f64::INFINITY, -2.0])
+                })
+                .unwrap()
+        };
+
+        assert_eq!(read("csv"), vec![1.5, f64::INFINITY, -2.0]);
+        assert_eq!(read("csv"), vec![1.5, f64::INFINITY, -2.0]);
+        read("json");
+        assert_eq!(parses.get(), 2);
+        assert_eq!(cache.usage().unwrap().0, 2);
+
+        // Changing the file invalidates its entries
+        std::fs::write(&input, vec![b'y'; MIN_CACHED_BYTES as usize + 1]).unwrap();
+        read("csv");
+        assert_eq!(parses.get(), 3);
+
+        assert_eq!(cache.clear().unwrap().0, 3);
+        assert_eq!(cache.usage().unwrap(), (0, 0));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn small_files_are_not_cached() {
+        let dir = temp_dir("value-cache-small");
+        let input = dir.join("small.json");
+        std::fs::write(&input, "[1, 2]").unwrap();
+        let cache = ValueCache::new(dir.join("cache"));
+
+        let values = cache.values(&input, "", || Ok(vec![1.0, 2.0])).unwrap();
+        assert_eq!(values, vec![1.0, 2.0]);
+        assert_eq!(cache.usage().unwrap(), (0, 0));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
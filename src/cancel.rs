@@ -0,0 +1,111 @@
+use std::fmt;
+use std::io::Read;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// Shared flag asking a long computation to stop
+///
+/// Clones share the flag, so one handle can be cancelled while another is
+/// checked from the thread doing the work. Cancellation is cooperative: the
+/// `*_cancellable` functions check it between steps and give up with
+/// [`Cancelled`].
+///
+/// # Examples
+/// ```
+/// use outlier::{CancellationToken, sort_values_cancellable};
+///
+/// let token = CancellationToken::new();
+/// assert_eq!(sort_values_cancellable(&[2.0, 1.0], &token).unwrap(), vec![1.0, 2.0]);
+///
+/// token.clone().cancel();
+/// assert!(sort_values_cancellable(&[2.0, 1.0], &token).is_err());
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct CancellationToken {
+    cancelled: Arc<AtomicBool>,
+}
+
+impl CancellationToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Ask every holder of this token to stop
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Relaxed)
+    }
+
+    /// Fail with [`Cancelled`] once the token has been cancelled
+    pub fn check(&self) -> Result<(), Cancelled> {
+        if self.is_cancelled() {
+            Err(Cancelled)
+        } else {
+            Ok(())
+        }
+    }
+
+    /// A guard that cancels the token when dropped, unless disarmed first
+    ///
+    /// Hold one in an async task so the work it started stops if the task is
+    /// dropped, e.g. because the client disconnected.
+    pub fn drop_guard(self) -> DropGuard {
+        DropGuard(Some(self))
+    }
+
+    /// Wrap a reader so reads fail with [`Cancelled`] once the token is
+    /// cancelled, stopping a parser at its next read
+    pub fn reader<R: Read>(&self, inner: R) -> CancellableReader<R> {
+        CancellableReader {
+            inner,
+            token: self.clone(),
+        }
+    }
+}
+
+/// Error returned by work stopped through a [`CancellationToken`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Cancelled;
+
+impl fmt::Display for Cancelled {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Calculation was cancelled")
+    }
+}
+
+impl std::error::Error for Cancelled {}
+
+/// Cancels its token when dropped; see [`CancellationToken::drop_guard`]
+#[derive(Debug)]
+pub struct DropGuard(Option<CancellationToken>);
+
+impl DropGuard {
+    /// Drop the guard without cancelling, returning the token
+    pub fn disarm(mut self) -> CancellationToken {
+        self.0.take().expect("guard holds its token until dropped")
+    }
+}
+
+impl Drop for DropGuard {
+    fn drop(&mut self) {
+        if let Some(token) = &self.0 {
+            token.cancel();
+        }
+    }
+}
+
+/// Reader returned by [`CancellationToken::reader`]
+pub struct CancellableReader<R> {
+    inner: R,
+    token: CancellationToken,
+}
+
+impl<R: Read> Read for CancellableReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        self.token.check().map_err(std::io::Error::other)?;
+        self.inner.read(buf)
+    }
+}
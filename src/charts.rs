@@ -0,0 +1,111 @@
+use crate::format_percentile_label;
+use crate::report::Report;
+use anyhow::{Context, Result};
+use plotters::prelude::*;
+use std::path::Path;
+
+/// Size of a PNG chart in pixels
+const WIDTH: u32 = 800;
+const HEIGHT: u32 = 400;
+/// Colours of the chart marks and labels, matching the report page
+const ACCENT: RGBColor = RGBColor(0x2f, 0x6f, 0xdf);
+const MUTED: RGBColor = RGBColor(0x5f, 0x6b, 0x7a);
+
+/// Draw a report's histogram, or with `curve` its percentile curve, as a PNG
+/// image at `path`
+///
+/// These are the charts of [`Report::histogram_svg`] and
+/// [`Report::curve_svg`], with labelled axes since a PNG has no tooltips.
+pub fn write_png(report: &Report, curve: bool, path: &Path) -> Result<()> {
+    let root = BitMapBackend::new(path, (WIDTH, HEIGHT)).into_drawing_area();
+    root.fill(&WHITE)?;
+    if curve {
+        draw_curve(report, &root)?;
+    } else {
+        draw_histogram(report, &root)?;
+    }
+    root.present()
+        .with_context(|| format!("Failed to write {}", path.display()))?;
+    Ok(())
+}
+
+/// The range `lower..upper`, widened around a single value so it has width
+fn span(lower: f64, upper: f64) -> std::ops::Range<f64> {
+    if upper > lower {
+        lower..upper
+    } else {
+        lower - 0.5..upper + 0.5
+    }
+}
+
+fn draw_histogram<DB: DrawingBackend>(
+    report: &Report,
+    root: &DrawingArea<DB, plotters::coord::Shift>,
+) -> Result<()>
+where
+    DB::ErrorType: 'static,
+{
+    let buckets = &report.analysis.histogram.buckets;
+    let (first, last) = (&buckets[0], &buckets[buckets.len() - 1]);
+    let max = buckets.iter().map(|b| b.count).max().unwrap_or(0).max(1);
+    // Headroom so the tallest bar doesn't touch the frame
+    let top = max + max / 10 + 1;
+
+    let mut chart = ChartBuilder::on(root)
+        .margin(15)
+        .x_label_area_size(40)
+        .y_label_area_size(60)
+        .build_cartesian_2d(span(first.lower, last.upper), 0..top)?;
+    chart
+        .configure_mesh()
+        .disable_x_mesh()
+        .x_desc("Value")
+        .y_desc("Count")
+        .axis_desc_style(("sans-serif", 14, &MUTED))
+        .label_style(("sans-serif", 12, &MUTED))
+        .light_line_style(TRANSPARENT)
+        .draw()?;
+    chart.draw_series(
+        buckets
+            .iter()
+            .map(|b| Rectangle::new([(b.lower, 0), (b.upper, b.count)], ACCENT.filled())),
+    )?;
+    Ok(())
+}
+
+fn draw_curve<DB: DrawingBackend>(
+    report: &Report,
+    root: &DrawingArea<DB, plotters::coord::Shift>,
+) -> Result<()>
+where
+    DB::ErrorType: 'static,
+{
+    let summary = &report.analysis.summary;
+    let mut chart = ChartBuilder::on(root)
+        .margin(15)
+        .x_label_area_size(40)
+        .y_label_area_size(60)
+        .build_cartesian_2d(0.0..100.0, span(summary.min, summary.max))?;
+    chart
+        .configure_mesh()
+        .x_desc("Percentile")
+        .y_desc("Value")
+        .axis_desc_style(("sans-serif", 14, &MUTED))
+        .label_style(("sans-serif", 12, &MUTED))
+        .light_line_style(TRANSPARENT)
+        .draw()?;
+    chart.draw_series(LineSeries::new(
+        report.curve.iter().map(|p| (p.percentile, p.value)),
+        ACCENT.stroke_width(2),
+    ))?;
+    chart.draw_series(report.analysis.percentiles.iter().map(|p| {
+        EmptyElement::at((p.percentile, p.value))
+            + Circle::new((0, 0), 4, ACCENT.filled())
+            + Text::new(
+                format_percentile_label(p.percentile),
+                (-30, -18),
+                ("sans-serif", 12).into_font().color(&MUTED),
+            )
+    }))?;
+    Ok(())
+}
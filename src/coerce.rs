@@ -0,0 +1,133 @@
+use crate::{CsvOptions, parse_localized_number};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+#[cfg(feature = "server")]
+use utoipa::ToSchema;
+
+/// Currency symbols the `currency` rule strips
+const CURRENCY_SYMBOLS: [char; 5] = ['$', '€', '£', '¥', '₹'];
+
+/// Duration suffixes and how many milliseconds each stands for; multi-letter
+/// units come first so `5ms` isn't read as `5m` followed by `s`
+const DURATION_UNITS: [(&str, f64); 9] = [
+    ("min", 60_000.0),
+    ("ns", 1e-6),
+    ("us", 1e-3),
+    ("µs", 1e-3),
+    ("ms", 1.0),
+    ("s", 1000.0),
+    ("m", 60_000.0),
+    ("h", 3_600_000.0),
+    ("d", 86_400_000.0),
+];
+
+/// A rule for reading CSV cells that aren't plain numbers
+#[cfg_attr(feature = "server", derive(ToSchema))]
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+#[cfg_attr(feature = "cli", clap(rename_all = "snake_case"))]
+pub enum CoercionRule {
+    /// Strip a leading or trailing currency symbol: `$1,234.50` → `1234.5`
+    Currency,
+    /// Strip a trailing percent sign: `12.5%` → `12.5`
+    Percent,
+    /// Convert durations with a unit to milliseconds: `1.5s` → `1500`
+    Duration,
+}
+
+impl fmt::Display for CoercionRule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CoercionRule::Currency => write!(f, "currency"),
+            CoercionRule::Percent => write!(f, "percent"),
+            CoercionRule::Duration => write!(f, "duration"),
+        }
+    }
+}
+
+/// The coercion rules enabled for a parse; none by default
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Coercions {
+    pub currency: bool,
+    pub percent: bool,
+    pub duration: bool,
+}
+
+impl Coercions {
+    /// Enable each of `rules`
+    pub fn from_rules(rules: &[CoercionRule]) -> Self {
+        Self {
+            currency: rules.contains(&CoercionRule::Currency),
+            percent: rules.contains(&CoercionRule::Percent),
+            duration: rules.contains(&CoercionRule::Duration),
+        }
+    }
+
+    /// Read a cell that didn't parse as a plain number, or `None` if no rule applies
+    ///
+    /// Thousands and decimal separators follow `options`, so `$1,234.50` needs
+    /// a locale with `,` grouping such as `en`.
+    pub fn apply(&self, text: &str, options: &CsvOptions) -> Option<f64> {
+        let text = text.trim();
+        if self.duration
+            && let Some(ms) = parse_duration_ms(text, options)
+        {
+            return Some(ms);
+        }
+
+        let (sign, mut body) = match text.strip_prefix('-') {
+            Some(rest) => ("-", rest.trim_start()),
+            None => ("", text),
+        };
+        let mut changed = false;
+        if self.currency {
+            if let Some(rest) = body.strip_prefix(CURRENCY_SYMBOLS) {
+                body = rest.trim_start();
+                changed = true;
+            } else if let Some(rest) = body.strip_suffix(CURRENCY_SYMBOLS) {
+                body = rest.trim_end();
+                changed = true;
+            }
+        }
+        if self.percent
+            && let Some(rest) = body.strip_suffix('%')
+        {
+            body = rest.trim_end();
+            changed = true;
+        }
+        if !changed {
+            return None;
+        }
+        parse_localized_number(&format!("{sign}{body}"), options)
+    }
+}
+
+/// Parse a duration such as `250ms`, `1.5 s` or `2min` to milliseconds
+///
+/// Understands `ns`, `us`/`µs`, `ms`, `s`, `m`/`min`, `h` and `d`.
+pub fn parse_duration_ms(text: &str, options: &CsvOptions) -> Option<f64> {
+    let text = text.trim();
+    DURATION_UNITS.iter().find_map(|(unit, ms)| {
+        let number = text.strip_suffix(unit)?.trim_end();
+        parse_localized_number(number, options).map(|n| n * ms)
+    })
+}
+
+/// Whether a cell looks like a date or date-time (`YYYY-MM-DD`, optionally
+/// followed by a time)
+pub(crate) fn looks_like_timestamp(text: &str) -> bool {
+    let bytes = text.trim().as_bytes();
+    let digits = |range: std::ops::Range<usize>| {
+        bytes
+            .get(range)
+            .is_some_and(|part| part.iter().all(u8::is_ascii_digit))
+    };
+    let date = digits(0..4)
+        && bytes.get(4) == Some(&b'-')
+        && digits(5..7)
+        && bytes.get(7) == Some(&b'-')
+        && digits(8..10);
+    date && matches!(bytes.get(10), None | Some(b'T' | b' '))
+}
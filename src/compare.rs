@@ -0,0 +1,181 @@
+use crate::{
+    DEFAULT_PERCENTILES, FieldError, FieldReader, PercentileMethod, PercentileValue,
+    ValidationErrors, calculate_percentiles_with,
+};
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+#[cfg(feature = "server")]
+use utoipa::ToSchema;
+
+/// Request structure for the paired compare API endpoint
+#[cfg_attr(feature = "server", derive(ToSchema))]
+#[derive(Debug, Deserialize, Serialize)]
+pub struct PairedCompareRequest {
+    /// Baseline measurements
+    pub before: Vec<f64>,
+    /// Measurements after the change, aligned index-by-index with `before`
+    pub after: Vec<f64>,
+    /// Percentiles to report (defaults to 50, 90, 95, 99)
+    #[serde(default = "default_percentiles")]
+    pub percentiles: Vec<f64>,
+    /// Interpolation method (defaults to linear)
+    #[serde(default)]
+    pub method: PercentileMethod,
+}
+
+fn default_percentiles() -> Vec<f64> {
+    DEFAULT_PERCENTILES.to_vec()
+}
+
+impl PairedCompareRequest {
+    /// Validate the request, collecting every problem rather than stopping at the first
+    pub fn validate(&self) -> std::result::Result<(), ValidationErrors> {
+        let mut errors = Vec::new();
+
+        for (name, series) in [("before", &self.before), ("after", &self.after)] {
+            if series.is_empty() {
+                errors.push(FieldError::new(
+                    name,
+                    "empty",
+                    format!("{name} must contain at least one value"),
+                ));
+            } else if series.iter().any(|v| !v.is_finite()) {
+                errors.push(FieldError::new(
+                    name,
+                    "non_finite",
+                    "Values must be finite numbers",
+                ));
+            }
+        }
+        if self.before.len() != self.after.len() && !errors.iter().any(|e| e.field == "after") {
+            errors.push(FieldError::new(
+                "after",
+                "length_mismatch",
+                format!(
+                    "Series must be the same length (before has {}, after has {})",
+                    self.before.len(),
+                    self.after.len()
+                ),
+            ));
+        }
+        if self.percentiles.is_empty() {
+            errors.push(FieldError::new(
+                "percentiles",
+                "empty",
+                "At least one percentile is required",
+            ));
+        } else if self.percentiles.iter().any(|p| !(0.0..=100.0).contains(p)) {
+            errors.push(FieldError::new(
+                "percentiles",
+                "out_of_range",
+                "Percentiles must be between 0 and 100",
+            ));
+        }
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(ValidationErrors(errors))
+        }
+    }
+
+    /// Build a request from a JSON object, reporting type errors for every field
+    /// together with the semantic checks from [`PairedCompareRequest::validate`]
+    pub fn from_json_value(
+        value: serde_json::Value,
+    ) -> std::result::Result<Self, ValidationErrors> {
+        let mut reader = FieldReader::new(value)?;
+        let request = Self {
+            before: reader.series("before"),
+            after: reader.series("after"),
+            percentiles: reader
+                .optional(
+                    "percentiles",
+                    "invalid_type",
+                    "Percentiles must be an array of numbers",
+                )
+                .unwrap_or_else(default_percentiles),
+            method: reader
+                .optional(
+                    "method",
+                    "invalid_value",
+                    "Method must be one of: linear, nearest_rank, lower, upper, midpoint, nearest_even",
+                )
+                .unwrap_or_default(),
+        };
+        reader.finish(request, Self::validate)
+    }
+}
+
+/// Response structure for the paired compare API endpoint
+#[cfg_attr(feature = "server", derive(ToSchema))]
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PairedCompareResponse {
+    /// Number of (before, after) pairs
+    pub count: usize,
+    /// The interpolation method used
+    pub method: PercentileMethod,
+    /// Percentiles of `after - before`
+    pub difference: Vec<PercentileValue>,
+    /// Percentiles of `after / before` (null when any `before` value is zero)
+    pub ratio: Option<Vec<PercentileValue>>,
+}
+
+/// Element-wise `after - before`
+///
+/// # Examples
+/// ```
+/// use outlier::paired_differences;
+///
+/// let diffs = paired_differences(&[10.0, 20.0], &[12.0, 15.0]).unwrap();
+/// assert_eq!(diffs, vec![2.0, -5.0]);
+/// ```
+pub fn paired_differences(before: &[f64], after: &[f64]) -> Result<Vec<f64>> {
+    check_paired(before, after)?;
+    Ok(before.iter().zip(after).map(|(b, a)| a - b).collect())
+}
+
+/// Element-wise `after / before`, or `None` when any `before` value is zero
+pub fn paired_ratios(before: &[f64], after: &[f64]) -> Result<Option<Vec<f64>>> {
+    check_paired(before, after)?;
+    if before.contains(&0.0) {
+        return Ok(None);
+    }
+    Ok(Some(before.iter().zip(after).map(|(b, a)| a / b).collect()))
+}
+
+/// Percentiles of the per-pair differences and ratios between two aligned series
+pub fn compare_paired(
+    before: &[f64],
+    after: &[f64],
+    percentiles: &[f64],
+    method: PercentileMethod,
+) -> Result<PairedCompareResponse> {
+    let difference =
+        calculate_percentiles_with(&paired_differences(before, after)?, percentiles, method)?;
+    let ratio = paired_ratios(before, after)?
+        .map(|ratios| calculate_percentiles_with(&ratios, percentiles, method))
+        .transpose()?;
+
+    Ok(PairedCompareResponse {
+        count: before.len(),
+        method,
+        difference,
+        ratio,
+    })
+}
+
+fn check_paired(before: &[f64], after: &[f64]) -> Result<()> {
+    if before.len() != after.len() {
+        anyhow::bail!(
+            "Series must be the same length (before has {}, after has {})",
+            before.len(),
+            after.len()
+        );
+    }
+    if before.is_empty() {
+        anyhow::bail!("Cannot compare empty datasets");
+    }
+    Ok(())
+}
@@ -1,6 +1,10 @@
-use serde::Deserialize;
-use std::net::IpAddr;
-use std::path::PathBuf;
+use crate::EngineKind;
+use ipnet::IpNet;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::net::{IpAddr, SocketAddr};
+use std::path::{Path, PathBuf};
+use utoipa::ToSchema;
 
 /// Logging verbosity levels
 #[derive(Debug, Clone, Copy, Deserialize, Default, PartialEq)]
@@ -87,6 +91,35 @@ pub struct ServerConfig {
     pub port: u16,
     #[serde(default = "default_bind_ip")]
     pub bind_ip: IpAddr,
+    /// Reject mutating requests (e.g. admin key changes) while calculations stay live
+    #[serde(default)]
+    pub read_only: bool,
+    /// Cancel calculations still running after this many seconds
+    #[serde(default)]
+    pub compute_timeout_secs: Option<u64>,
+    /// Keep data values out of error messages, replacing them with hashes
+    #[serde(default)]
+    pub redact_values: bool,
+    /// Snapshot from `POST /admin/snapshot` to load sketches from at startup
+    #[serde(default)]
+    pub restore: Option<PathBuf>,
+    /// Tokio runtime sizing, applied at startup
+    #[serde(default)]
+    pub runtime: RuntimeConfig,
+}
+
+/// Tokio runtime configuration section (`[server.runtime]`)
+///
+/// Unset sizes keep Tokio's defaults: a worker per CPU the host reports and
+/// up to 512 blocking threads.
+#[derive(Debug, Clone, Deserialize, Default)]
+pub struct RuntimeConfig {
+    /// Threads driving async tasks and requests
+    #[serde(default)]
+    pub worker_threads: Option<usize>,
+    /// Upper bound on threads running calculations off the async workers
+    #[serde(default)]
+    pub max_blocking_threads: Option<usize>,
 }
 
 fn default_port() -> u16 {
@@ -102,6 +135,11 @@ impl Default for ServerConfig {
         Self {
             port: default_port(),
             bind_ip: default_bind_ip(),
+            read_only: false,
+            compute_timeout_secs: None,
+            redact_values: false,
+            restore: None,
+            runtime: RuntimeConfig::default(),
         }
     }
 }
@@ -125,6 +163,8 @@ pub struct JwtConfig {
     pub audience: String,
     #[serde(default)]
     pub jwks_url: Option<String>,
+    #[serde(default)]
+    pub discovery: bool,
     #[serde(default = "default_jwks_cache_ttl")]
     pub jwks_cache_ttl_secs: u64,
     #[serde(default = "default_algorithms")]
@@ -137,6 +177,7 @@ impl Default for JwtConfig {
             issuer: String::new(),
             audience: String::new(),
             jwks_url: None,
+            discovery: false,
             jwks_cache_ttl_secs: default_jwks_cache_ttl(),
             algorithms: default_algorithms(),
         }
@@ -151,6 +192,28 @@ fn default_algorithms() -> Vec<String> {
     vec!["RS256".to_string()]
 }
 
+/// Permission carried by an API key and required by a route
+#[derive(Debug, Clone, Copy, Deserialize, Serialize, PartialEq, Eq, ToSchema)]
+pub enum Scope {
+    /// Run percentile and correlation calculations
+    #[serde(rename = "calculate")]
+    Calculate,
+    /// Create or modify stored datasets
+    #[serde(rename = "datasets:write")]
+    DatasetsWrite,
+    /// Manage API keys; implies every other scope
+    #[serde(rename = "admin")]
+    Admin,
+}
+
+/// API key with an explicit set of scopes
+#[derive(Debug, Clone, Deserialize)]
+pub struct ScopedApiKeyConfig {
+    pub id: String,
+    pub key: String,
+    pub scopes: Vec<Scope>,
+}
+
 /// Authentication configuration section
 #[derive(Debug, Clone, Deserialize, Default)]
 pub struct AuthConfig {
@@ -161,6 +224,8 @@ pub struct AuthConfig {
     #[serde(default)]
     pub api_keys: Vec<String>,
     #[serde(default)]
+    pub scoped_keys: Vec<ScopedApiKeyConfig>,
+    #[serde(default)]
     pub jwt: JwtConfig,
 }
 
@@ -207,6 +272,375 @@ impl Default for RateLimitConfig {
     }
 }
 
+/// IP allow/deny list configuration section
+#[derive(Debug, Clone, Deserialize, Default)]
+pub struct SecurityConfig {
+    #[serde(default)]
+    pub allow_cidrs: Vec<IpNet>,
+    #[serde(default)]
+    pub deny_cidrs: Vec<IpNet>,
+    #[serde(default)]
+    pub admin_allow_cidrs: Vec<IpNet>,
+    #[serde(default)]
+    pub trusted_proxies: Vec<IpNet>,
+}
+
+/// Outbound TLS configuration section
+#[derive(Debug, Clone, Deserialize, Default)]
+pub struct TlsConfig {
+    /// Cipher suites to offer, by IANA name (empty = the provider's defaults)
+    #[serde(default)]
+    pub cipher_suites: Vec<String>,
+    /// Refuse to start unless crypto comes from the FIPS-validated module
+    #[serde(default)]
+    pub require_fips: bool,
+}
+
+/// Record/replay fixture configuration section
+///
+/// At most one of the two may be set.
+#[derive(Debug, Clone, Deserialize, Default)]
+pub struct FixturesConfig {
+    /// Append every request and its response to this JSON Lines file
+    #[serde(default)]
+    pub record: Option<PathBuf>,
+    /// Answer requests from this recording instead of calculating
+    #[serde(default)]
+    pub replay: Option<PathBuf>,
+}
+
+/// StatsD listener configuration section
+#[derive(Debug, Clone, Deserialize, Default)]
+pub struct StatsdConfig {
+    /// UDP address to receive StatsD timings on, e.g. `0.0.0.0:8125`
+    /// (unset = no listener)
+    #[serde(default)]
+    pub bind: Option<SocketAddr>,
+}
+
+/// MQTT subscription configuration section (needs the `mqtt` feature)
+#[derive(Debug, Clone, Deserialize)]
+pub struct MqttConfig {
+    /// Broker host name or address (unset = no subscription)
+    #[serde(default)]
+    pub host: Option<String>,
+    #[serde(default = "default_mqtt_port")]
+    pub port: u16,
+    #[serde(default = "default_mqtt_client_id")]
+    pub client_id: String,
+    #[serde(default)]
+    pub username: Option<String>,
+    #[serde(default)]
+    pub password: Option<String>,
+    /// Topic filters to subscribe to; `+` and `#` wildcards allowed
+    #[serde(default)]
+    pub topics: Vec<String>,
+    /// Payload field holding the measurement, dotted for nested objects
+    #[serde(default = "default_mqtt_field")]
+    pub field: String,
+}
+
+fn default_mqtt_port() -> u16 {
+    1883
+}
+
+fn default_mqtt_client_id() -> String {
+    "outlier".to_string()
+}
+
+fn default_mqtt_field() -> String {
+    "value".to_string()
+}
+
+impl Default for MqttConfig {
+    fn default() -> Self {
+        Self {
+            host: None,
+            port: default_mqtt_port(),
+            client_id: default_mqtt_client_id(),
+            username: None,
+            password: None,
+            topics: Vec::new(),
+            field: default_mqtt_field(),
+        }
+    }
+}
+
+/// What happens to a value outside its dataset's expected range
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum RangeAction {
+    /// Leave the value out of the dataset
+    #[default]
+    Reject,
+    /// Keep the value, only counting it as a violation
+    Count,
+}
+
+/// Expected values of the datasets a rule names, e.g. latencies in
+/// milliseconds between 0 and 600000
+#[derive(Debug, Clone, Deserialize)]
+pub struct RangeRule {
+    /// Dataset name, or a prefix ending in `*` such as `api.*`
+    pub dataset: String,
+    #[serde(default)]
+    pub min: Option<f64>,
+    #[serde(default)]
+    pub max: Option<f64>,
+    #[serde(default)]
+    pub action: RangeAction,
+}
+
+impl RangeRule {
+    /// Whether the rule applies to the dataset called `name`
+    pub fn matches(&self, name: &str) -> bool {
+        match self.dataset.strip_suffix('*') {
+            Some(prefix) => name.starts_with(prefix),
+            None => name == self.dataset,
+        }
+    }
+
+    pub fn contains(&self, value: f64) -> bool {
+        self.min.is_none_or(|min| value >= min) && self.max.is_none_or(|max| value <= max)
+    }
+
+    /// The bounds in words, e.g. `0 to 600000` or `at least 0`
+    pub fn bounds(&self) -> String {
+        match (self.min, self.max) {
+            (Some(min), Some(max)) => format!("{min} to {max}"),
+            (Some(min), None) => format!("at least {min}"),
+            (None, Some(max)) => format!("at most {max}"),
+            (None, None) => "any value".to_string(),
+        }
+    }
+
+    /// Reject rules that could never match or never be broken
+    pub fn validate(&self) -> anyhow::Result<()> {
+        let name = &self.dataset;
+        anyhow::ensure!(!name.is_empty(), "datasets.ranges entry has no dataset");
+        anyhow::ensure!(
+            self.min.is_some() || self.max.is_some(),
+            "datasets.ranges entry for {name} needs min, max or both"
+        );
+        anyhow::ensure!(
+            self.min.is_none_or(f64::is_finite) && self.max.is_none_or(f64::is_finite),
+            "datasets.ranges entry for {name} has a non-finite bound"
+        );
+        if let (Some(min), Some(max)) = (self.min, self.max) {
+            anyhow::ensure!(
+                min <= max,
+                "datasets.ranges entry for {name} has min above max"
+            );
+        }
+        Ok(())
+    }
+}
+
+/// A registered metric: what its values mean, who owns it and how it is
+/// read by default
+#[derive(Debug, Clone, Deserialize)]
+pub struct MetricSpec {
+    /// Dataset the metric is ingested into
+    pub name: String,
+    /// Unit of the values, e.g. `ms`
+    #[serde(default)]
+    pub unit: Option<String>,
+    /// Team or person to ask about the metric
+    #[serde(default)]
+    pub owner: Option<String>,
+    #[serde(default)]
+    pub description: Option<String>,
+    /// Expected range, checked like a `datasets.ranges` rule and taking
+    /// precedence over them
+    #[serde(default)]
+    pub min: Option<f64>,
+    #[serde(default)]
+    pub max: Option<f64>,
+    #[serde(default)]
+    pub action: RangeAction,
+    /// Percentiles a query reports unless it asks for others
+    #[serde(default)]
+    pub percentiles: Vec<f64>,
+}
+
+impl MetricSpec {
+    /// The metric's expected range as a rule, if it has one
+    pub fn range(&self) -> Option<RangeRule> {
+        (self.min.is_some() || self.max.is_some()).then(|| RangeRule {
+            dataset: self.name.clone(),
+            min: self.min,
+            max: self.max,
+            action: self.action,
+        })
+    }
+
+    pub fn validate(&self) -> anyhow::Result<()> {
+        let name = &self.name;
+        crate::sketches::validate_name(name)
+            .map_err(|e| anyhow::anyhow!("datasets.metrics entry {name:?}: {e}"))?;
+        if let Some(range) = self.range() {
+            range.validate()?;
+        }
+        anyhow::ensure!(
+            self.percentiles.iter().all(|p| (0.0..=100.0).contains(p)),
+            "datasets.metrics entry for {name} has percentiles outside 0 to 100"
+        );
+        Ok(())
+    }
+}
+
+/// Dataset configuration section
+#[derive(Debug, Clone, Deserialize, Default)]
+pub struct DatasetsConfig {
+    /// Expected ranges, checked as values are ingested; the first rule
+    /// matching a dataset applies
+    #[serde(default)]
+    pub ranges: Vec<RangeRule>,
+    /// Registry of known metrics, one per dataset name
+    #[serde(default)]
+    pub metrics: Vec<MetricSpec>,
+}
+
+impl DatasetsConfig {
+    pub fn validate(&self) -> anyhow::Result<()> {
+        for range in &self.ranges {
+            range.validate()?;
+        }
+        for (i, metric) in self.metrics.iter().enumerate() {
+            metric.validate()?;
+            anyhow::ensure!(
+                !self.metrics[..i].iter().any(|m| m.name == metric.name),
+                "datasets.metrics registers {} twice",
+                metric.name
+            );
+        }
+        Ok(())
+    }
+
+    /// The registered metric called `name`
+    pub fn metric(&self, name: &str) -> Option<&MetricSpec> {
+        self.metrics.iter().find(|m| m.name == name)
+    }
+
+    /// The range rule for the dataset called `name`: the registered
+    /// metric's, else the first matching `ranges` rule
+    pub fn range(&self, name: &str) -> Option<RangeRule> {
+        self.metric(name)
+            .and_then(MetricSpec::range)
+            .or_else(|| self.ranges.iter().find(|r| r.matches(name)).cloned())
+    }
+}
+
+/// Percentile engine configuration section
+#[derive(Debug, Clone, Deserialize, Default)]
+pub struct EngineConfig {
+    /// Engine used when a request doesn't choose one
+    #[serde(default)]
+    pub default: EngineKind,
+    /// Command line for the `external` engine, e.g. `["python3", "engine.py"]`
+    #[serde(default)]
+    pub external_command: Vec<String>,
+}
+
+/// Fields of a calculation response that `defaults.result_field` can't reuse
+const RESPONSE_FIELDS: [&str; 7] = [
+    "count",
+    "percentile",
+    "results",
+    "method",
+    "transformed",
+    "engine",
+    "summary",
+];
+
+/// Request and response defaults the server applies to `/calculate` and
+/// `/calculate/file`
+#[derive(Debug, Clone, Deserialize)]
+pub struct DefaultsConfig {
+    /// Percentile calculated when a request doesn't name one
+    #[serde(default = "default_percentile")]
+    pub percentile: f64,
+    /// Name the result is reported under, e.g. `value`
+    #[serde(default = "default_result_field")]
+    pub result_field: String,
+    /// Report the result under `result` as well as `result_field`, so
+    /// clients can move over one at a time
+    #[serde(default)]
+    pub keep_result: bool,
+}
+
+fn default_percentile() -> f64 {
+    95.0
+}
+
+fn default_result_field() -> String {
+    "result".to_string()
+}
+
+impl Default for DefaultsConfig {
+    fn default() -> Self {
+        Self {
+            percentile: default_percentile(),
+            result_field: default_result_field(),
+            keep_result: false,
+        }
+    }
+}
+
+impl DefaultsConfig {
+    pub fn validate(&self) -> anyhow::Result<()> {
+        anyhow::ensure!(
+            (0.0..=100.0).contains(&self.percentile),
+            "defaults.percentile must be between 0 and 100"
+        );
+        let field = &self.result_field;
+        anyhow::ensure!(!field.is_empty(), "defaults.result_field is empty");
+        anyhow::ensure!(
+            !RESPONSE_FIELDS.contains(&field.as_str()),
+            "defaults.result_field {field:?} would replace another response field"
+        );
+        Ok(())
+    }
+}
+
+/// HMAC request signature configuration section
+#[derive(Debug, Clone, Deserialize, Default)]
+pub struct SignatureConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default)]
+    pub secret: Option<String>,
+}
+
+/// Idempotency key configuration section
+#[derive(Debug, Clone, Deserialize)]
+pub struct IdempotencyConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default = "default_idempotency_ttl")]
+    pub ttl_secs: u64,
+    #[serde(default = "default_idempotency_max_entries")]
+    pub max_entries: usize,
+}
+
+fn default_idempotency_ttl() -> u64 {
+    86400
+}
+
+fn default_idempotency_max_entries() -> usize {
+    10_000
+}
+
+impl Default for IdempotencyConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            ttl_secs: default_idempotency_ttl(),
+            max_entries: default_idempotency_max_entries(),
+        }
+    }
+}
+
 /// Main configuration structure
 #[derive(Debug, Clone, Deserialize, Default)]
 pub struct Config {
@@ -218,41 +652,367 @@ pub struct Config {
     pub auth: AuthConfig,
     #[serde(default)]
     pub rate_limit: RateLimitConfig,
+    #[serde(default)]
+    pub idempotency: IdempotencyConfig,
+    #[serde(default)]
+    pub signature: SignatureConfig,
+    #[serde(default)]
+    pub security: SecurityConfig,
+    #[serde(default)]
+    pub defaults: DefaultsConfig,
+    #[serde(default)]
+    pub engine: EngineConfig,
+    #[serde(default)]
+    pub tls: TlsConfig,
+    #[serde(default)]
+    pub fixtures: FixturesConfig,
+    #[serde(default)]
+    pub statsd: StatsdConfig,
+    #[serde(default)]
+    pub mqtt: MqttConfig,
+    #[serde(default)]
+    pub datasets: DatasetsConfig,
 }
 
+/// Where an effective configuration value came from
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ConfigSource {
+    /// Built-in default
+    Default,
+    /// Set in the config file at this path
+    File(PathBuf),
+    /// Overridden by an environment variable
+    Env(&'static str),
+    /// Overridden by a command-line flag
+    Cli(&'static str),
+}
+
+impl std::fmt::Display for ConfigSource {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ConfigSource::Default => write!(f, "default"),
+            ConfigSource::File(path) => write!(f, "file {}", path.display()),
+            ConfigSource::Env(var) => write!(f, "env {var}"),
+            ConfigSource::Cli(flag) => write!(f, "flag {flag}"),
+        }
+    }
+}
+
+/// Source of each configuration value, keyed by dotted path (e.g. `server.port`)
+///
+/// Keys that were never set report [`ConfigSource::Default`].
+#[derive(Debug, Clone, Default)]
+pub struct Provenance(BTreeMap<String, ConfigSource>);
+
+impl Provenance {
+    /// Where the value at `key` came from
+    pub fn source(&self, key: &str) -> &ConfigSource {
+        self.0.get(key).unwrap_or(&ConfigSource::Default)
+    }
+
+    /// Record that `key` was set by `source`, replacing any earlier source
+    pub fn set(&mut self, key: impl Into<String>, source: ConfigSource) {
+        self.0.insert(key.into(), source);
+    }
+
+    /// Record every leaf of a parsed TOML table as coming from `path`
+    fn record_file(&mut self, table: &toml::Table, prefix: &str, path: &Path) {
+        for (name, value) in table {
+            let key = if prefix.is_empty() {
+                name.clone()
+            } else {
+                format!("{prefix}.{name}")
+            };
+            match value {
+                toml::Value::Table(inner) => self.record_file(inner, &key, path),
+                _ => self.set(key, ConfigSource::File(path.to_path_buf())),
+            }
+        }
+    }
+}
+
+/// Environment variables that override config file values
+const ENV_OVERRIDES: [(&str, &str); 6] = [
+    ("OUTLIER_API_KEYS", "auth.api_keys"),
+    ("OUTLIER_JWT_ISSUER", "auth.jwt.issuer"),
+    ("OUTLIER_JWT_AUDIENCE", "auth.jwt.audience"),
+    ("OUTLIER_JWT_JWKS_URL", "auth.jwt.jwks_url"),
+    ("OUTLIER_SIGNATURE_SECRET", "signature.secret"),
+    ("OUTLIER_MQTT_PASSWORD", "mqtt.password"),
+];
+
 impl Config {
     /// Load configuration with the following priority (highest to lowest):
-    /// 1. CLI-specified config file path
-    /// 2. CONFIG_FILE environment variable
-    /// 3. Default values
+    /// 1. `OUTLIER_*` environment variable overrides
+    /// 2. CLI-specified config file path
+    /// 3. CONFIG_FILE environment variable
+    /// 4. Default values
     pub fn load(cli_config_path: Option<&PathBuf>) -> anyhow::Result<Self> {
-        // Try CLI path first
-        if let Some(path) = cli_config_path {
-            return Self::load_from_file(path);
-        }
+        Self::load_with_provenance(cli_config_path).map(|(config, _)| config)
+    }
 
-        // Try CONFIG_FILE environment variable
-        if let Ok(env_path) = std::env::var("CONFIG_FILE") {
-            let path = PathBuf::from(env_path);
-            return Self::load_from_file(&path);
-        }
+    /// Load configuration as [`Config::load`] does, also reporting where each
+    /// value came from
+    pub fn load_with_provenance(
+        cli_config_path: Option<&PathBuf>,
+    ) -> anyhow::Result<(Self, Provenance)> {
+        let mut provenance = Provenance::default();
+
+        // Try CLI path first, then the CONFIG_FILE environment variable
+        let path = cli_config_path
+            .cloned()
+            .or_else(|| std::env::var("CONFIG_FILE").ok().map(PathBuf::from));
+        let mut config = match path {
+            Some(path) => Self::load_from_file(&path, &mut provenance)?,
+            None => Self::default(),
+        };
 
-        // Return defaults
-        Ok(Self::default())
+        config.apply_env_overrides(&mut provenance, |var| std::env::var(var).ok());
+        Ok((config, provenance))
     }
 
     /// Load configuration from a specific file
-    fn load_from_file(path: &PathBuf) -> anyhow::Result<Self> {
+    fn load_from_file(path: &PathBuf, provenance: &mut Provenance) -> anyhow::Result<Self> {
         let contents = std::fs::read_to_string(path).map_err(|e| {
             anyhow::anyhow!("Failed to read config file '{}': {}", path.display(), e)
         })?;
 
-        let config: Config = toml::from_str(&contents).map_err(|e| {
+        let table: toml::Table = toml::from_str(&contents).map_err(|e| {
+            anyhow::anyhow!("Failed to parse config file '{}': {}", path.display(), e)
+        })?;
+        provenance.record_file(&table, "", path);
+
+        let config: Config = table.try_into().map_err(|e| {
             anyhow::anyhow!("Failed to parse config file '{}': {}", path.display(), e)
         })?;
 
         Ok(config)
     }
+
+    /// Apply non-empty `OUTLIER_*` overrides, the same ones the server honours
+    fn apply_env_overrides(
+        &mut self,
+        provenance: &mut Provenance,
+        env: impl Fn(&str) -> Option<String>,
+    ) {
+        for (var, key) in ENV_OVERRIDES {
+            let Some(value) = env(var).filter(|v| !v.is_empty()) else {
+                continue;
+            };
+            match key {
+                "auth.api_keys" => {
+                    let keys: Vec<String> = value
+                        .split(',')
+                        .map(|k| k.trim().to_string())
+                        .filter(|k| !k.is_empty())
+                        .collect();
+                    if keys.is_empty() {
+                        continue;
+                    }
+                    self.auth.api_keys = keys;
+                }
+                "auth.jwt.issuer" => self.auth.jwt.issuer = value,
+                "auth.jwt.audience" => self.auth.jwt.audience = value,
+                "auth.jwt.jwks_url" => self.auth.jwt.jwks_url = Some(value),
+                "signature.secret" => self.signature.secret = Some(value),
+                "mqtt.password" => self.mqtt.password = Some(value),
+                _ => unreachable!("unknown override {key}"),
+            }
+            provenance.set(key, ConfigSource::Env(var));
+        }
+    }
+
+    /// Every setting as a dotted key and display value, secrets redacted
+    pub fn entries(&self) -> Vec<(&'static str, String)> {
+        fn list<T: std::fmt::Display>(items: &[T]) -> String {
+            let items: Vec<String> = items.iter().map(|i| format!("\"{i}\"")).collect();
+            format!("[{}]", items.join(", "))
+        }
+        let path = |path: &Option<PathBuf>| {
+            path.as_ref().map_or("(unset)".to_string(), |p| {
+                format!("{:?}", p.display().to_string())
+            })
+        };
+        let count = |count: Option<usize>| count.map_or("(unset)".to_string(), |n| n.to_string());
+        let redacted = |set: bool| if set { "\"<redacted>\"" } else { "(unset)" }.to_string();
+
+        let output = match &self.logging.output {
+            LogOutput::Stdout => "\"stdout\"".to_string(),
+            LogOutput::Stderr => "\"stderr\"".to_string(),
+            LogOutput::File(path) => format!("{:?}", path.display().to_string()),
+        };
+        let format = match self.logging.format {
+            LogFormat::Compact => "compact",
+            LogFormat::Pretty => "pretty",
+            LogFormat::Json => "json",
+        };
+        let mode = match self.auth.mode {
+            AuthMode::ApiKey => "api_key",
+            AuthMode::Jwt => "jwt",
+            AuthMode::Both => "both",
+        };
+        let scoped_ids: Vec<&str> = self
+            .auth
+            .scoped_keys
+            .iter()
+            .map(|k| k.id.as_str())
+            .collect();
+        let range_datasets: Vec<&str> = self
+            .datasets
+            .ranges
+            .iter()
+            .map(|r| r.dataset.as_str())
+            .collect();
+        let metric_names: Vec<&str> = self
+            .datasets
+            .metrics
+            .iter()
+            .map(|m| m.name.as_str())
+            .collect();
+
+        vec![
+            ("logging.level", format!("\"{}\"", self.logging.level)),
+            ("logging.output", output),
+            ("logging.format", format!("\"{format}\"")),
+            ("server.port", self.server.port.to_string()),
+            ("server.bind_ip", format!("\"{}\"", self.server.bind_ip)),
+            ("server.read_only", self.server.read_only.to_string()),
+            (
+                "server.compute_timeout_secs",
+                self.server
+                    .compute_timeout_secs
+                    .map_or("(unset)".to_string(), |s| s.to_string()),
+            ),
+            (
+                "server.redact_values",
+                self.server.redact_values.to_string(),
+            ),
+            ("server.restore", path(&self.server.restore)),
+            (
+                "server.runtime.worker_threads",
+                count(self.server.runtime.worker_threads),
+            ),
+            (
+                "server.runtime.max_blocking_threads",
+                count(self.server.runtime.max_blocking_threads),
+            ),
+            ("auth.enabled", self.auth.enabled.to_string()),
+            ("auth.mode", format!("\"{mode}\"")),
+            (
+                "auth.api_keys",
+                format!("{} key(s), redacted", self.auth.api_keys.len()),
+            ),
+            ("auth.scoped_keys", format!("ids {}", list(&scoped_ids))),
+            ("auth.jwt.issuer", format!("{:?}", self.auth.jwt.issuer)),
+            ("auth.jwt.audience", format!("{:?}", self.auth.jwt.audience)),
+            (
+                "auth.jwt.jwks_url",
+                self.auth
+                    .jwt
+                    .jwks_url
+                    .as_ref()
+                    .map_or("(unset)".to_string(), |u| format!("{u:?}")),
+            ),
+            ("auth.jwt.discovery", self.auth.jwt.discovery.to_string()),
+            (
+                "auth.jwt.jwks_cache_ttl_secs",
+                self.auth.jwt.jwks_cache_ttl_secs.to_string(),
+            ),
+            ("auth.jwt.algorithms", list(&self.auth.jwt.algorithms)),
+            ("rate_limit.enabled", self.rate_limit.enabled.to_string()),
+            (
+                "rate_limit.per_ip_per_second",
+                self.rate_limit.per_ip_per_second.to_string(),
+            ),
+            (
+                "rate_limit.per_ip_burst",
+                self.rate_limit.per_ip_burst.to_string(),
+            ),
+            (
+                "rate_limit.global_per_second",
+                self.rate_limit.global_per_second.to_string(),
+            ),
+            (
+                "rate_limit.global_burst",
+                self.rate_limit.global_burst.to_string(),
+            ),
+            ("idempotency.enabled", self.idempotency.enabled.to_string()),
+            (
+                "idempotency.ttl_secs",
+                self.idempotency.ttl_secs.to_string(),
+            ),
+            (
+                "idempotency.max_entries",
+                self.idempotency.max_entries.to_string(),
+            ),
+            ("signature.enabled", self.signature.enabled.to_string()),
+            (
+                "signature.secret",
+                redacted(self.signature.secret.is_some()),
+            ),
+            ("security.allow_cidrs", list(&self.security.allow_cidrs)),
+            ("security.deny_cidrs", list(&self.security.deny_cidrs)),
+            (
+                "security.admin_allow_cidrs",
+                list(&self.security.admin_allow_cidrs),
+            ),
+            (
+                "security.trusted_proxies",
+                list(&self.security.trusted_proxies),
+            ),
+            ("defaults.percentile", self.defaults.percentile.to_string()),
+            (
+                "defaults.result_field",
+                format!("{:?}", self.defaults.result_field),
+            ),
+            (
+                "defaults.keep_result",
+                self.defaults.keep_result.to_string(),
+            ),
+            ("engine.default", format!("\"{}\"", self.engine.default)),
+            (
+                "engine.external_command",
+                list(&self.engine.external_command),
+            ),
+            ("tls.cipher_suites", list(&self.tls.cipher_suites)),
+            ("tls.require_fips", self.tls.require_fips.to_string()),
+            ("fixtures.record", path(&self.fixtures.record)),
+            ("fixtures.replay", path(&self.fixtures.replay)),
+            (
+                "statsd.bind",
+                self.statsd
+                    .bind
+                    .map_or("(unset)".to_string(), |a| format!("\"{a}\"")),
+            ),
+            (
+                "mqtt.host",
+                self.mqtt
+                    .host
+                    .as_ref()
+                    .map_or("(unset)".to_string(), |h| format!("{h:?}")),
+            ),
+            ("mqtt.port", self.mqtt.port.to_string()),
+            ("mqtt.client_id", format!("{:?}", self.mqtt.client_id)),
+            (
+                "mqtt.username",
+                self.mqtt
+                    .username
+                    .as_ref()
+                    .map_or("(unset)".to_string(), |u| format!("{u:?}")),
+            ),
+            ("mqtt.password", redacted(self.mqtt.password.is_some())),
+            ("mqtt.topics", list(&self.mqtt.topics)),
+            ("mqtt.field", format!("{:?}", self.mqtt.field)),
+            (
+                "datasets.ranges",
+                format!("datasets {}", list(&range_datasets)),
+            ),
+            (
+                "datasets.metrics",
+                format!("metrics {}", list(&metric_names)),
+            ),
+        ]
+    }
 }
 
 #[cfg(test)]
@@ -394,6 +1154,27 @@ port = 3000
         assert!(!config.rate_limit.enabled);
     }
 
+    #[test]
+    fn test_default_idempotency_config() {
+        let config = IdempotencyConfig::default();
+        assert!(!config.enabled);
+        assert_eq!(config.ttl_secs, 86400);
+        assert_eq!(config.max_entries, 10_000);
+    }
+
+    #[test]
+    fn test_parse_idempotency_config() {
+        let toml_str = r#"
+[idempotency]
+enabled = true
+ttl_secs = 600
+"#;
+        let config: Config = toml::from_str(toml_str).unwrap();
+        assert!(config.idempotency.enabled);
+        assert_eq!(config.idempotency.ttl_secs, 600);
+        assert_eq!(config.idempotency.max_entries, 10_000);
+    }
+
     #[test]
     fn test_parse_jwt_config() {
         let toml_str = r#"
@@ -463,4 +1244,355 @@ jwks_url = "https://custom.example.com/keys"
             Some("https://custom.example.com/keys")
         );
     }
+
+    #[test]
+    fn test_jwt_config_with_discovery() {
+        let toml_str = r#"
+[auth]
+enabled = true
+mode = "jwt"
+
+[auth.jwt]
+issuer = "https://login.example.com/"
+audience = "https://api.outlier.dev"
+discovery = true
+"#;
+        let config: Config = toml::from_str(toml_str).unwrap();
+        assert!(config.auth.jwt.discovery);
+        assert!(config.auth.jwt.jwks_url.is_none());
+    }
+
+    #[test]
+    fn test_parse_scoped_keys() {
+        let toml_str = r#"
+[auth]
+enabled = true
+
+[[auth.scoped_keys]]
+id = "ci"
+key = "ci-secret"
+scopes = ["calculate", "datasets:write"]
+
+[[auth.scoped_keys]]
+id = "ops"
+key = "ops-secret"
+scopes = ["admin"]
+"#;
+        let config: Config = toml::from_str(toml_str).unwrap();
+        assert_eq!(config.auth.scoped_keys.len(), 2);
+        assert_eq!(
+            config.auth.scoped_keys[0].scopes,
+            vec![Scope::Calculate, Scope::DatasetsWrite]
+        );
+        assert_eq!(config.auth.scoped_keys[1].scopes, vec![Scope::Admin]);
+    }
+
+    #[test]
+    fn test_unknown_scope_rejected() {
+        let toml_str = r#"
+[[auth.scoped_keys]]
+id = "bad"
+key = "secret"
+scopes = ["everything"]
+"#;
+        assert!(toml::from_str::<Config>(toml_str).is_err());
+    }
+
+    #[test]
+    fn test_parse_signature_config() {
+        let toml_str = r#"
+[signature]
+enabled = true
+secret = "shared"
+"#;
+        let config: Config = toml::from_str(toml_str).unwrap();
+        assert!(config.signature.enabled);
+        assert_eq!(config.signature.secret.as_deref(), Some("shared"));
+        assert!(!Config::default().signature.enabled);
+    }
+
+    #[test]
+    fn test_parse_security_config() {
+        let toml_str = r#"
+[security]
+allow_cidrs = ["10.0.0.0/8", "2001:db8::/32"]
+deny_cidrs = ["10.0.5.0/24"]
+admin_allow_cidrs = ["192.168.10.0/24"]
+trusted_proxies = ["127.0.0.1/32"]
+"#;
+        let config: Config = toml::from_str(toml_str).unwrap();
+        assert_eq!(config.security.allow_cidrs.len(), 2);
+        assert_eq!(config.security.deny_cidrs[0].to_string(), "10.0.5.0/24");
+        assert_eq!(config.security.admin_allow_cidrs.len(), 1);
+        assert_eq!(config.security.trusted_proxies.len(), 1);
+    }
+
+    #[test]
+    fn test_parse_engine_config() {
+        let toml_str = r#"
+[engine]
+default = "quick_select"
+external_command = ["python3", "engine.py"]
+"#;
+        let config: Config = toml::from_str(toml_str).unwrap();
+        assert_eq!(config.engine.default, EngineKind::QuickSelect);
+        assert_eq!(config.engine.external_command, vec!["python3", "engine.py"]);
+        assert_eq!(Config::default().engine.default, EngineKind::ExactSort);
+        assert!(
+            config
+                .entries()
+                .contains(&("engine.default", "\"quick_select\"".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_parse_runtime_config() {
+        let toml_str = r#"
+[server.runtime]
+worker_threads = 2
+"#;
+        let config: Config = toml::from_str(toml_str).unwrap();
+        assert_eq!(config.server.runtime.worker_threads, Some(2));
+        assert_eq!(config.server.runtime.max_blocking_threads, None);
+        assert!(
+            config
+                .entries()
+                .contains(&("server.runtime.worker_threads", "2".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_parse_compute_timeout() {
+        let config: Config = toml::from_str("[server]\ncompute_timeout_secs = 30\n").unwrap();
+        assert_eq!(config.server.compute_timeout_secs, Some(30));
+        assert_eq!(Config::default().server.compute_timeout_secs, None);
+    }
+
+    #[test]
+    fn test_parse_redact_values() {
+        let config: Config = toml::from_str("[server]\nredact_values = true\n").unwrap();
+        assert!(config.server.redact_values);
+        assert!(!Config::default().server.redact_values);
+    }
+
+    #[test]
+    fn test_parse_tls_config() {
+        let toml_str = r#"
+[tls]
+cipher_suites = ["TLS13_AES_256_GCM_SHA384"]
+require_fips = true
+"#;
+        let config: Config = toml::from_str(toml_str).unwrap();
+        assert_eq!(config.tls.cipher_suites, vec!["TLS13_AES_256_GCM_SHA384"]);
+        assert!(config.tls.require_fips);
+        assert!(Config::default().tls.cipher_suites.is_empty());
+    }
+
+    #[test]
+    fn test_parse_fixtures_config() {
+        let toml_str = r#"
+[fixtures]
+record = "fixtures/outlier.jsonl"
+"#;
+        let config: Config = toml::from_str(toml_str).unwrap();
+        assert_eq!(
+            config.fixtures.record,
+            Some(PathBuf::from("fixtures/outlier.jsonl"))
+        );
+        assert!(config.fixtures.replay.is_none());
+    }
+
+    #[test]
+    fn test_parse_statsd_config() {
+        let toml_str = r#"
+[statsd]
+bind = "127.0.0.1:8125"
+"#;
+        let config: Config = toml::from_str(toml_str).unwrap();
+        assert_eq!(config.statsd.bind, Some("127.0.0.1:8125".parse().unwrap()));
+        assert!(Config::default().statsd.bind.is_none());
+        assert!(toml::from_str::<Config>("[statsd]\nbind = \"8125\"").is_err());
+    }
+
+    #[test]
+    fn test_parse_mqtt_config() {
+        let toml_str = r#"
+[mqtt]
+host = "gateway.local"
+topics = ["sensors/+/temperature"]
+field = "reading.celsius"
+"#;
+        let config: Config = toml::from_str(toml_str).unwrap();
+        assert_eq!(config.mqtt.host.as_deref(), Some("gateway.local"));
+        assert_eq!(config.mqtt.port, 1883);
+        assert_eq!(config.mqtt.client_id, "outlier");
+        assert_eq!(config.mqtt.topics, vec!["sensors/+/temperature"]);
+        assert_eq!(config.mqtt.field, "reading.celsius");
+        assert_eq!(Config::default().mqtt.field, "value");
+    }
+
+    #[test]
+    fn test_parse_dataset_ranges() {
+        let toml_str = r#"
+[[datasets.ranges]]
+dataset = "api.*"
+min = 0
+max = 600000
+
+[[datasets.ranges]]
+dataset = "queue.depth"
+min = 0
+action = "count"
+"#;
+        let config: Config = toml::from_str(toml_str).unwrap();
+        let [api, queue] = &config.datasets.ranges[..] else {
+            panic!("expected two rules");
+        };
+        assert!(api.matches("api.latency") && !api.matches("db.latency"));
+        assert!(queue.matches("queue.depth") && !queue.matches("queue.depth2"));
+        assert_eq!(
+            (api.action, queue.action),
+            (RangeAction::Reject, RangeAction::Count)
+        );
+        assert!(api.contains(0.0) && api.contains(600000.0) && !api.contains(600000.5));
+        assert!(queue.contains(1e12) && !queue.contains(-1.0));
+        assert_eq!(api.bounds(), "0 to 600000");
+        assert_eq!(queue.bounds(), "at least 0");
+        assert!(api.validate().is_ok() && queue.validate().is_ok());
+
+        let invalid = |toml_str: &str| {
+            let config: Config = toml::from_str(toml_str).unwrap();
+            config.datasets.ranges[0].validate().is_err()
+        };
+        assert!(invalid("[[datasets.ranges]]\ndataset = \"a\"\n"));
+        assert!(invalid(
+            "[[datasets.ranges]]\ndataset = \"a\"\nmin = 2\nmax = 1\n"
+        ));
+        assert!(invalid("[[datasets.ranges]]\ndataset = \"\"\nmin = 0\n"));
+    }
+
+    #[test]
+    fn test_parse_metric_registry() {
+        let toml_str = r#"
+[[datasets.ranges]]
+dataset = "api.*"
+max = 1000
+
+[[datasets.metrics]]
+name = "api.latency"
+unit = "ms"
+owner = "platform"
+min = 0
+max = 600000
+percentiles = [50, 99, 99.9]
+
+[[datasets.metrics]]
+name = "api.errors"
+"#;
+        let config: Config = toml::from_str(toml_str).unwrap();
+        let datasets = &config.datasets;
+        assert!(datasets.validate().is_ok());
+        let latency = datasets.metric("api.latency").unwrap();
+        assert_eq!(latency.unit.as_deref(), Some("ms"));
+        assert_eq!(latency.percentiles, vec![50.0, 99.0, 99.9]);
+        // A registered range wins over the rules; otherwise they apply
+        assert_eq!(datasets.range("api.latency").unwrap().max, Some(600000.0));
+        assert_eq!(datasets.range("api.errors").unwrap().max, Some(1000.0));
+        assert!(datasets.range("db.latency").is_none());
+
+        let invalid = |toml_str: &str| {
+            let config: Config = toml::from_str(toml_str).unwrap();
+            config.datasets.validate().is_err()
+        };
+        assert!(invalid(
+            "[[datasets.metrics]]\nname = \"a\"\n[[datasets.metrics]]\nname = \"a\"\n"
+        ));
+        assert!(invalid("[[datasets.metrics]]\nname = \"a b\"\n"));
+        assert!(invalid(
+            "[[datasets.metrics]]\nname = \"a\"\npercentiles = [101]\n"
+        ));
+    }
+
+    #[test]
+    fn test_parse_defaults() {
+        let toml_str = r#"
+[defaults]
+percentile = 99.0
+result_field = "value"
+keep_result = true
+"#;
+        let config: Config = toml::from_str(toml_str).unwrap();
+        assert_eq!(config.defaults.percentile, 99.0);
+        assert_eq!(config.defaults.result_field, "value");
+        assert!(config.defaults.keep_result);
+        assert!(config.defaults.validate().is_ok());
+        assert_eq!(Config::default().defaults.result_field, "result");
+
+        let invalid = |toml_str: &str| {
+            let config: Config = toml::from_str(toml_str).unwrap();
+            config.defaults.validate().is_err()
+        };
+        assert!(invalid("[defaults]\npercentile = 101\n"));
+        assert!(invalid("[defaults]\nresult_field = \"\"\n"));
+        assert!(invalid("[defaults]\nresult_field = \"count\"\n"));
+    }
+
+    #[test]
+    fn test_invalid_cidr_rejected() {
+        let toml_str = r#"
+[security]
+allow_cidrs = ["10.0.0.0/33"]
+"#;
+        assert!(toml::from_str::<Config>(toml_str).is_err());
+    }
+
+    #[test]
+    fn test_provenance_tracks_file_keys() {
+        let path =
+            std::env::temp_dir().join(format!("outlier-provenance-{}.toml", std::process::id()));
+        std::fs::write(
+            &path,
+            "[server]\nport = 8080\n\n[auth.jwt]\nissuer = \"idp\"\n",
+        )
+        .unwrap();
+
+        let mut provenance = Provenance::default();
+        let config = Config::load_from_file(&path, &mut provenance).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(config.server.port, 8080);
+        assert_eq!(
+            provenance.source("server.port"),
+            &ConfigSource::File(path.clone())
+        );
+        assert_eq!(
+            provenance.source("auth.jwt.issuer"),
+            &ConfigSource::File(path)
+        );
+        assert_eq!(provenance.source("server.bind_ip"), &ConfigSource::Default);
+    }
+
+    #[test]
+    fn test_env_overrides_recorded() {
+        let mut config = Config::default();
+        let mut provenance = Provenance::default();
+        config.apply_env_overrides(&mut provenance, |var| match var {
+            "OUTLIER_API_KEYS" => Some("a, b".to_string()),
+            "OUTLIER_JWT_ISSUER" => Some(String::new()),
+            _ => None,
+        });
+
+        assert_eq!(config.auth.api_keys, vec!["a", "b"]);
+        assert_eq!(
+            provenance.source("auth.api_keys"),
+            &ConfigSource::Env("OUTLIER_API_KEYS")
+        );
+        // Empty overrides are ignored, as in the server
+        assert_eq!(provenance.source("auth.jwt.issuer"), &ConfigSource::Default);
+        assert!(
+            config
+                .entries()
+                .contains(&("auth.api_keys", "2 key(s), redacted".to_string()))
+        );
+    }
 }
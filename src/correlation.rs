@@ -0,0 +1,312 @@
+use crate::{CsvOptions, FieldError, FieldReader, ValidationErrors, parse_localized_number};
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+
+#[cfg(feature = "server")]
+use utoipa::ToSchema;
+
+/// Maximum number of buckets per axis in the scatter matrix
+pub const MAX_BUCKETS: usize = 100;
+
+/// Request structure for correlate API endpoint
+#[cfg_attr(feature = "server", derive(ToSchema))]
+#[derive(Debug, Deserialize, Serialize)]
+pub struct CorrelateRequest {
+    /// First series
+    pub x: Vec<f64>,
+    /// Second series, aligned index-by-index with `x`
+    pub y: Vec<f64>,
+    /// Number of buckets per axis in the scatter matrix (defaults to 10)
+    #[serde(default = "default_buckets")]
+    pub buckets: usize,
+}
+
+fn default_buckets() -> usize {
+    10
+}
+
+impl CorrelateRequest {
+    /// Validate the request, collecting every problem rather than stopping at the first
+    pub fn validate(&self) -> std::result::Result<(), ValidationErrors> {
+        let mut errors = Vec::new();
+
+        for (name, series) in [("x", &self.x), ("y", &self.y)] {
+            if series.len() < 2 {
+                errors.push(FieldError::new(
+                    name,
+                    "too_few",
+                    "At least 2 values are required for correlation",
+                ));
+            } else if series.iter().any(|v| !v.is_finite()) {
+                errors.push(FieldError::new(
+                    name,
+                    "non_finite",
+                    "Values must be finite numbers",
+                ));
+            }
+        }
+        if self.x.len() != self.y.len() && !errors.iter().any(|e| e.field == "y") {
+            errors.push(FieldError::new(
+                "y",
+                "length_mismatch",
+                format!(
+                    "Series must be the same length (x has {}, y has {})",
+                    self.x.len(),
+                    self.y.len()
+                ),
+            ));
+        }
+        if !(1..=MAX_BUCKETS).contains(&self.buckets) {
+            errors.push(FieldError::new(
+                "buckets",
+                "out_of_range",
+                format!("Buckets must be between 1 and {MAX_BUCKETS}"),
+            ));
+        }
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(ValidationErrors(errors))
+        }
+    }
+
+    /// Build a request from a JSON object, reporting type errors for every field
+    /// together with the semantic checks from [`CorrelateRequest::validate`]
+    pub fn from_json_value(
+        value: serde_json::Value,
+    ) -> std::result::Result<Self, ValidationErrors> {
+        let mut reader = FieldReader::new(value)?;
+        let request = Self {
+            x: reader.series("x"),
+            y: reader.series("y"),
+            buckets: reader
+                .optional(
+                    "buckets",
+                    "invalid_type",
+                    "Buckets must be a positive integer",
+                )
+                .unwrap_or_else(default_buckets),
+        };
+        reader.finish(request, Self::validate)
+    }
+}
+
+/// Response structure for correlate API endpoint
+#[cfg_attr(feature = "server", derive(ToSchema))]
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CorrelateResponse {
+    /// Number of (x, y) pairs
+    pub count: usize,
+    /// Pearson correlation coefficient (null when a series is constant)
+    pub pearson: Option<f64>,
+    /// Spearman rank correlation coefficient (null when a series is constant)
+    pub spearman: Option<f64>,
+    /// Joint distribution of the pairs over equal-width buckets
+    pub scatter: ScatterMatrix,
+}
+
+/// Counts of (x, y) pairs falling in each pair of equal-width buckets
+#[cfg_attr(feature = "server", derive(ToSchema))]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ScatterMatrix {
+    /// Bucket boundaries along x (`buckets + 1` entries)
+    pub x_edges: Vec<f64>,
+    /// Bucket boundaries along y (`buckets + 1` entries)
+    pub y_edges: Vec<f64>,
+    /// `counts[i][j]` is the number of pairs in x bucket `i` and y bucket `j`
+    pub counts: Vec<Vec<usize>>,
+}
+
+/// Pearson correlation coefficient of two aligned series
+///
+/// Returns `Ok(None)` when either series has zero variance.
+///
+/// # Examples
+/// ```
+/// use outlier::pearson_correlation;
+///
+/// let r = pearson_correlation(&[1.0, 2.0, 3.0], &[2.0, 4.0, 6.0]).unwrap();
+/// assert!((r.unwrap() - 1.0).abs() < 1e-12);
+/// ```
+pub fn pearson_correlation(x: &[f64], y: &[f64]) -> Result<Option<f64>> {
+    check_aligned(x, y)?;
+
+    let n = x.len() as f64;
+    let mean_x = x.iter().sum::<f64>() / n;
+    let mean_y = y.iter().sum::<f64>() / n;
+
+    let (mut cov, mut var_x, mut var_y) = (0.0, 0.0, 0.0);
+    for (a, b) in x.iter().zip(y) {
+        let (dx, dy) = (a - mean_x, b - mean_y);
+        cov += dx * dy;
+        var_x += dx * dx;
+        var_y += dy * dy;
+    }
+
+    let r = cov / (var_x * var_y).sqrt();
+    // Float noise can push perfectly correlated data just past +/-1
+    Ok(r.is_finite().then(|| r.clamp(-1.0, 1.0)))
+}
+
+/// Spearman rank correlation coefficient of two aligned series
+///
+/// Tied values share the average of their ranks. Returns `Ok(None)` when
+/// either series is constant.
+///
+/// # Examples
+/// ```
+/// use outlier::spearman_correlation;
+///
+/// // Monotonic but non-linear
+/// let r = spearman_correlation(&[1.0, 2.0, 3.0, 4.0], &[1.0, 8.0, 27.0, 64.0]).unwrap();
+/// assert_eq!(r, Some(1.0));
+/// ```
+pub fn spearman_correlation(x: &[f64], y: &[f64]) -> Result<Option<f64>> {
+    check_aligned(x, y)?;
+    pearson_correlation(&ranks(x), &ranks(y))
+}
+
+/// Bucket aligned series into a `buckets` x `buckets` matrix of pair counts
+///
+/// Buckets are equal-width between each series' minimum and maximum; the
+/// maximum falls in the last bucket.
+pub fn scatter_buckets(x: &[f64], y: &[f64], buckets: usize) -> Result<ScatterMatrix> {
+    check_aligned(x, y)?;
+    if !(1..=MAX_BUCKETS).contains(&buckets) {
+        anyhow::bail!("Buckets must be between 1 and {}", MAX_BUCKETS);
+    }
+
+    let x_edges = bucket_edges(x, buckets);
+    let y_edges = bucket_edges(y, buckets);
+    let mut counts = vec![vec![0; buckets]; buckets];
+    for (a, b) in x.iter().zip(y) {
+        counts[bucket_of(*a, &x_edges)][bucket_of(*b, &y_edges)] += 1;
+    }
+
+    Ok(ScatterMatrix {
+        x_edges,
+        y_edges,
+        counts,
+    })
+}
+
+/// Compute Pearson, Spearman and the scatter matrix for two aligned series
+pub fn correlate(x: &[f64], y: &[f64], buckets: usize) -> Result<CorrelateResponse> {
+    Ok(CorrelateResponse {
+        count: x.len(),
+        pearson: pearson_correlation(x, y)?,
+        spearman: spearman_correlation(x, y)?,
+        scatter: scatter_buckets(x, y, buckets)?,
+    })
+}
+
+fn check_aligned(x: &[f64], y: &[f64]) -> Result<()> {
+    if x.len() != y.len() {
+        anyhow::bail!(
+            "Series must be the same length (x has {}, y has {})",
+            x.len(),
+            y.len()
+        );
+    }
+    if x.len() < 2 {
+        anyhow::bail!("At least 2 values are required for correlation");
+    }
+    Ok(())
+}
+
+/// 1-based ranks, with ties given the average of the ranks they span
+fn ranks(values: &[f64]) -> Vec<f64> {
+    let mut order: Vec<usize> = (0..values.len()).collect();
+    order.sort_by(|&a, &b| values[a].total_cmp(&values[b]));
+
+    let mut ranks = vec![0.0; values.len()];
+    let mut start = 0;
+    while start < order.len() {
+        let mut end = start + 1;
+        while end < order.len() && values[order[end]] == values[order[start]] {
+            end += 1;
+        }
+        let rank = (start + end + 1) as f64 / 2.0;
+        for &i in &order[start..end] {
+            ranks[i] = rank;
+        }
+        start = end;
+    }
+    ranks
+}
+
+fn bucket_edges(values: &[f64], buckets: usize) -> Vec<f64> {
+    let min = values.iter().copied().fold(f64::INFINITY, f64::min);
+    let max = values.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+    let width = (max - min) / buckets as f64;
+    (0..=buckets)
+        .map(|i| {
+            if i == buckets {
+                max
+            } else {
+                min + width * i as f64
+            }
+        })
+        .collect()
+}
+
+fn bucket_of(value: f64, edges: &[f64]) -> usize {
+    let last = edges.len() - 2;
+    // First edge strictly greater than the value, minus one
+    edges[1..=last].partition_point(|&edge| edge <= value)
+}
+
+/// Read two aligned series from a CSV upload
+///
+/// Uses the `x` and `y` columns when both headers are present, otherwise the
+/// first two columns.
+pub fn read_pairs_from_bytes_with_options(
+    bytes: &[u8],
+    filename: &str,
+    options: &CsvOptions,
+) -> Result<(Vec<f64>, Vec<f64>)> {
+    let extension = filename.split('.').next_back().unwrap_or("");
+    if !extension.eq_ignore_ascii_case("csv") {
+        anyhow::bail!("Unsupported file format. Use .csv with two columns");
+    }
+
+    let mut reader = csv::ReaderBuilder::new()
+        .delimiter(options.delimiter)
+        .from_reader(bytes);
+    let headers = reader.headers().context("Failed to parse CSV record")?;
+    let (x_col, y_col) = match (
+        headers.iter().position(|h| h == "x"),
+        headers.iter().position(|h| h == "y"),
+    ) {
+        (Some(x), Some(y)) => (x, y),
+        _ if headers.len() >= 2 => (0, 1),
+        _ => anyhow::bail!("Failed to parse CSV record: expected two columns"),
+    };
+
+    let (mut x, mut y) = (Vec::new(), Vec::new());
+    const MAX_VALUES: usize = 10_000_000; // 10 million
+
+    for (row, result) in reader.records().enumerate() {
+        if x.len() >= MAX_VALUES {
+            anyhow::bail!(
+                "Input dataset exceeds the limit of {} values. Aborting.",
+                MAX_VALUES
+            );
+        }
+        let record = result.context("Failed to parse CSV record")?;
+        for (column, series) in [(x_col, &mut x), (y_col, &mut y)] {
+            let field = record.get(column).unwrap_or_default();
+            let value = parse_localized_number(field, options).with_context(|| {
+                format!(
+                    "Failed to parse CSV record {}: invalid number {:?}",
+                    row + 1,
+                    field
+                )
+            })?;
+            series.push(value);
+        }
+    }
+
+    Ok((x, y))
+}
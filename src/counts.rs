@@ -0,0 +1,98 @@
+use crate::{PercentileMethod, percentile_from_ranks};
+use anyhow::Result;
+
+/// A dataset stored as its distinct values and how often each occurs
+///
+/// For low-cardinality data such as status codes or bucketed sizes this
+/// takes memory proportional to the number of distinct values rather than
+/// the number of observations. Percentiles are read off the cumulative
+/// counts and match [`calculate_percentile`](crate::calculate_percentile)
+/// on the expanded values.
+///
+/// # Examples
+/// ```
+/// use outlier::{PercentileMethod, ValueCounts};
+///
+/// let statuses = ValueCounts::from_counts([(200.0, 9_000), (404.0, 900), (500.0, 100)]);
+/// assert_eq!(statuses.len(), 10_000);
+/// assert_eq!(statuses.percentile(95.0, PercentileMethod::Lower).unwrap(), 404.0);
+/// assert_eq!(statuses.percentile(99.5, PercentileMethod::Lower).unwrap(), 500.0);
+/// ```
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ValueCounts {
+    /// Distinct values in ascending order
+    values: Vec<f64>,
+    /// Observations up to and including each value
+    cumulative: Vec<u64>,
+}
+
+impl ValueCounts {
+    /// Count the distinct values of a dataset
+    pub fn from_values(values: &[f64]) -> Self {
+        Self::from_counts(values.iter().map(|&v| (v, 1)))
+    }
+
+    /// Build from `(value, count)` pairs in any order; repeated values are
+    /// merged and zero counts dropped
+    pub fn from_counts(counts: impl IntoIterator<Item = (f64, u64)>) -> Self {
+        let mut counts: Vec<(f64, u64)> = counts.into_iter().filter(|&(_, n)| n > 0).collect();
+        counts.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+        let mut result = Self::default();
+        let mut total = 0;
+        for (value, count) in counts {
+            total += count;
+            if result.values.last() == Some(&value) {
+                *result.cumulative.last_mut().expect("pushed with the value") = total;
+            } else {
+                result.values.push(value);
+                result.cumulative.push(total);
+            }
+        }
+        result
+    }
+
+    /// Number of observations
+    pub fn len(&self) -> u64 {
+        self.cumulative.last().copied().unwrap_or(0)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+
+    /// Number of distinct values
+    pub fn distinct(&self) -> usize {
+        self.values.len()
+    }
+
+    /// Each distinct value with its count, in ascending order
+    pub fn counts(&self) -> impl Iterator<Item = (f64, u64)> + '_ {
+        let previous = std::iter::once(0).chain(self.cumulative.iter().copied());
+        self.values
+            .iter()
+            .zip(self.cumulative.iter().zip(previous))
+            .map(|(&value, (&total, before))| (value, total - before))
+    }
+
+    /// The value at `rank`, the 0-based position in ascending order
+    fn at(&self, rank: usize) -> f64 {
+        self.values[self
+            .cumulative
+            .partition_point(|&total| total <= rank as u64)]
+    }
+
+    /// Calculate a percentile from the cumulative counts
+    pub fn percentile(&self, percentile: f64, method: PercentileMethod) -> Result<f64> {
+        if self.is_empty() {
+            anyhow::bail!("Cannot calculate percentile of empty dataset");
+        }
+        if !(0.0..=100.0).contains(&percentile) {
+            anyhow::bail!("Percentile must be between 0 and 100");
+        }
+        let len = usize::try_from(self.len())?;
+        Ok(percentile_from_ranks(len, percentile, method, |rank| {
+            self.at(rank)
+        }))
+    }
+}
@@ -0,0 +1,47 @@
+use sha2::{Digest, Sha256};
+
+/// Response header carrying the SHA-256 of the uploaded dataset a result was
+/// computed from
+pub const DATASET_HASH_HEADER: &str = "dataset-sha256";
+
+/// SHA-256 of a dataset's raw bytes, fed in whatever chunks they arrive in
+///
+/// The digest only depends on the bytes, so an upload hashes the same however
+/// the client or proxy split it, and matches `sha256sum` of the file.
+#[derive(Clone, Default)]
+pub struct DatasetHasher(Sha256);
+
+impl DatasetHasher {
+    pub fn update(&mut self, chunk: &[u8]) {
+        self.0.update(chunk);
+    }
+
+    /// The digest as lowercase hex
+    pub fn finish(self) -> String {
+        hex::encode(self.0.finalize())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn chunking_does_not_change_the_hash() {
+        let data = b"value\n1\n2\n3\n";
+        let mut whole = DatasetHasher::default();
+        whole.update(data);
+        let mut chunked = DatasetHasher::default();
+        for chunk in data.chunks(3) {
+            chunked.update(chunk);
+        }
+        assert_eq!(whole.finish(), chunked.finish());
+
+        let mut abc = DatasetHasher::default();
+        abc.update(b"abc");
+        assert_eq!(
+            abc.finish(),
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+    }
+}
@@ -0,0 +1,187 @@
+use axum::{
+    Json, Router,
+    extract::{Query, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    routing::get,
+};
+use serde::Deserialize;
+use serde_json::json;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::Semaphore;
+
+/// Longest delay `/debug/sleep` will honor
+const MAX_SLEEP_MS: u64 = 300_000;
+
+/// Largest allocation `/debug/oom-sim` will make
+const MAX_OOM_SIM_MB: usize = 4096;
+
+/// Longest time `/debug/oom-sim` will hold its allocation
+const MAX_OOM_HOLD_MS: u64 = 60_000;
+
+/// Allocations `/debug/oom-sim` will hold at once
+const MAX_CONCURRENT_OOM_SIMS: usize = 1;
+
+#[derive(Deserialize)]
+struct SleepParams {
+    #[serde(default)]
+    ms: u64,
+}
+
+#[derive(Deserialize)]
+struct ErrorParams {
+    #[serde(default = "default_error_status")]
+    status: u16,
+}
+
+fn default_error_status() -> u16 {
+    500
+}
+
+#[derive(Deserialize)]
+struct OomSimParams {
+    #[serde(default = "default_oom_mb")]
+    mb: usize,
+    #[serde(default = "default_oom_hold_ms")]
+    hold_ms: u64,
+}
+
+fn default_oom_mb() -> usize {
+    256
+}
+
+fn default_oom_hold_ms() -> u64 {
+    1000
+}
+
+/// Chaos routes for resilience testing: `/debug/sleep`, `/debug/error`, `/debug/oom-sim`
+///
+/// Only compiled with the `test-endpoints` feature; never enable it in production builds.
+/// The server mounts them behind authentication with the `admin` scope.
+pub fn routes<S: Clone + Send + Sync + 'static>() -> Router<S> {
+    Router::new()
+        .route("/debug/sleep", get(sleep))
+        .route("/debug/error", get(error))
+        .route("/debug/oom-sim", get(oom_sim))
+        .with_state(Arc::new(Semaphore::new(MAX_CONCURRENT_OOM_SIMS)))
+}
+
+/// Respond after `ms` milliseconds, to exercise client timeouts
+async fn sleep(Query(params): Query<SleepParams>) -> Json<serde_json::Value> {
+    let ms = params.ms.min(MAX_SLEEP_MS);
+    tokio::time::sleep(Duration::from_millis(ms)).await;
+    Json(json!({ "slept_ms": ms }))
+}
+
+/// Respond with the requested status, to exercise client retry policies
+async fn error(Query(params): Query<ErrorParams>) -> Response {
+    match StatusCode::from_u16(params.status) {
+        Ok(status) if status.is_client_error() || status.is_server_error() => (
+            status,
+            Json(json!({ "error": format!("Simulated {} error", status.as_u16()) })),
+        )
+            .into_response(),
+        _ => (
+            StatusCode::BAD_REQUEST,
+            Json(json!({ "error": "status must be between 400 and 599" })),
+        )
+            .into_response(),
+    }
+}
+
+/// Allocate and touch `mb` megabytes for `hold_ms`, to exercise memory limits
+///
+/// Only `MAX_CONCURRENT_OOM_SIMS` allocations are held at once; further
+/// requests get `429` rather than queueing up more memory.
+async fn oom_sim(
+    State(permits): State<Arc<Semaphore>>,
+    Query(params): Query<OomSimParams>,
+) -> Response {
+    let Ok(_permit) = permits.try_acquire() else {
+        return (
+            StatusCode::TOO_MANY_REQUESTS,
+            Json(json!({ "error": "Another oom-sim allocation is already held" })),
+        )
+            .into_response();
+    };
+    let mb = params.mb.min(MAX_OOM_SIM_MB);
+    let hold_ms = params.hold_ms.min(MAX_OOM_HOLD_MS);
+
+    // Writing every byte forces the pages to be resident, not just reserved;
+    // gigabytes of it would stall an async worker
+    let Ok(ballast) = tokio::task::spawn_blocking(move || vec![1u8; mb * 1024 * 1024]).await else {
+        return (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({ "error": "Allocation failed" })),
+        )
+            .into_response();
+    };
+    tokio::time::sleep(Duration::from_millis(hold_ms)).await;
+    drop(ballast);
+
+    Json(json!({ "allocated_mb": mb, "held_ms": hold_ms })).into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::body::Body;
+    use axum::http::Request;
+    use http_body_util::BodyExt;
+    use tower::ServiceExt;
+
+    async fn get_json(uri: &str) -> (StatusCode, serde_json::Value) {
+        let response = routes::<()>()
+            .oneshot(Request::get(uri).body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        let status = response.status();
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        (status, serde_json::from_slice(&body).unwrap())
+    }
+
+    #[tokio::test]
+    async fn sleep_reports_delay() {
+        let (status, json) = get_json("/debug/sleep?ms=5").await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(json["slept_ms"], 5);
+    }
+
+    #[tokio::test]
+    async fn error_returns_requested_status() {
+        let (status, _) = get_json("/debug/error?status=503").await;
+        assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+
+        let (status, _) = get_json("/debug/error?status=200").await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn oom_sim_caps_allocation() {
+        let (status, json) = get_json("/debug/oom-sim?mb=1&hold_ms=1").await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(json["allocated_mb"], 1);
+    }
+
+    #[tokio::test]
+    async fn oom_sim_holds_one_allocation_at_a_time() {
+        let app = routes::<()>();
+        let request = || {
+            Request::get("/debug/oom-sim?mb=1&hold_ms=200")
+                .body(Body::empty())
+                .unwrap()
+        };
+        let (first, second) = tokio::join!(
+            app.clone().oneshot(request()),
+            app.clone().oneshot(request())
+        );
+        let mut statuses = [first.unwrap().status(), second.unwrap().status()];
+        statuses.sort();
+        assert_eq!(statuses, [StatusCode::OK, StatusCode::TOO_MANY_REQUESTS]);
+
+        // The permit is released once the allocation is dropped
+        let response = app.oneshot(request()).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+}
@@ -0,0 +1,159 @@
+use crate::{PercentileMethod, calculate_percentile};
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+#[cfg(feature = "server")]
+use utoipa::ToSchema;
+
+/// The statistics pandas' `Series.describe()` reports, under the same names
+///
+/// Serializes to the same JSON as `Series.describe().to_json()`; `std` is
+/// `null` for a single value, where pandas reports `NaN`.
+#[cfg_attr(feature = "server", derive(ToSchema))]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Describe {
+    /// Number of values
+    pub count: usize,
+    /// Arithmetic mean
+    pub mean: f64,
+    /// Sample standard deviation (`ddof=1`)
+    pub std: Option<f64>,
+    /// Smallest value
+    pub min: f64,
+    /// 25th percentile (linear interpolation)
+    #[serde(rename = "25%")]
+    pub p25: f64,
+    /// Median (linear interpolation)
+    #[serde(rename = "50%")]
+    pub p50: f64,
+    /// 75th percentile (linear interpolation)
+    #[serde(rename = "75%")]
+    pub p75: f64,
+    /// Largest value
+    pub max: f64,
+}
+
+impl Describe {
+    /// Render as `DataFrame.describe().to_csv()` does for a single `column`
+    ///
+    /// Numbers use the shortest round-trip form with a trailing `.0` for whole
+    /// values, matching Python's float repr.
+    pub fn to_csv(&self, column: &str) -> String {
+        let cell = |v: Option<f64>| v.map(|v| format!("{v:?}")).unwrap_or_default();
+        let rows = [
+            ("count", Some(self.count as f64)),
+            ("mean", Some(self.mean)),
+            ("std", self.std),
+            ("min", Some(self.min)),
+            ("25%", Some(self.p25)),
+            ("50%", Some(self.p50)),
+            ("75%", Some(self.p75)),
+            ("max", Some(self.max)),
+        ];
+
+        let mut csv = format!(",{column}\n");
+        for (label, value) in rows {
+            csv.push_str(&format!("{label},{}\n", cell(value)));
+        }
+        csv
+    }
+}
+
+/// Compute the columns of pandas' `describe()` for a dataset
+///
+/// # Examples
+/// ```
+/// use outlier::describe;
+///
+/// let d = describe(&[1.0, 2.0, 3.0, 4.0]).unwrap();
+/// assert_eq!(d.mean, 2.5);
+/// assert_eq!(d.p25, 1.75);
+/// ```
+pub fn describe(values: &[f64]) -> Result<Describe> {
+    if values.is_empty() {
+        anyhow::bail!("Cannot describe an empty dataset");
+    }
+
+    let count = values.len();
+    let mean = values.iter().sum::<f64>() / count as f64;
+    let std = (count > 1).then(|| {
+        let sum_sq: f64 = values.iter().map(|v| (v - mean).powi(2)).sum();
+        (sum_sq / (count - 1) as f64).sqrt()
+    });
+    let quantile = |p| calculate_percentile(values, p, PercentileMethod::Linear);
+
+    Ok(Describe {
+        count,
+        mean,
+        std,
+        min: quantile(0.0)?,
+        p25: quantile(25.0)?,
+        p50: quantile(50.0)?,
+        p75: quantile(75.0)?,
+        max: quantile(100.0)?,
+    })
+}
+
+/// The whole picture of a dataset in one call
+#[cfg_attr(feature = "server", derive(ToSchema))]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SummaryStats {
+    /// Number of values
+    pub count: usize,
+    /// Smallest value
+    pub min: f64,
+    /// Largest value
+    pub max: f64,
+    /// Total of every value
+    pub sum: f64,
+    /// Arithmetic mean
+    pub mean: f64,
+    /// Median (linear interpolation)
+    pub median: f64,
+    /// Sample variance (`ddof=1`; 0 for a single value)
+    pub variance: f64,
+    /// Sample standard deviation, the square root of `variance`
+    pub std_dev: f64,
+}
+
+/// Count, range, sum, mean, median and spread of a dataset
+///
+/// # Examples
+/// ```
+/// use outlier::summary_stats;
+///
+/// let s = summary_stats(&[2.0, 4.0, 4.0, 6.0]).unwrap();
+/// assert_eq!(s.sum, 16.0);
+/// assert_eq!(s.median, 4.0);
+/// assert_eq!(s.variance, 8.0 / 3.0);
+/// ```
+pub fn summary_stats(values: &[f64]) -> Result<SummaryStats> {
+    if values.is_empty() {
+        anyhow::bail!("Cannot summarize an empty dataset");
+    }
+
+    let count = values.len();
+    let sum: f64 = values.iter().sum();
+    let mean = sum / count as f64;
+    let variance = if count > 1 {
+        values.iter().map(|v| (v - mean).powi(2)).sum::<f64>() / (count - 1) as f64
+    } else {
+        0.0
+    };
+    let (min, max) = values
+        .iter()
+        .fold((f64::INFINITY, f64::NEG_INFINITY), |(lo, hi), &v| {
+            (lo.min(v), hi.max(v))
+        });
+
+    Ok(SummaryStats {
+        count,
+        min,
+        max,
+        sum,
+        mean,
+        median: calculate_percentile(values, 50.0, PercentileMethod::Linear)?,
+        variance,
+        std_dev: variance.sqrt(),
+    })
+}
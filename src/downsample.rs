@@ -0,0 +1,124 @@
+use crate::{FieldError, FieldReader, ValidationErrors};
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+#[cfg(feature = "server")]
+use utoipa::ToSchema;
+
+/// Maximum number of points a dataset can be reduced to
+pub const MAX_POINTS: usize = 100_000;
+
+/// Request structure for downsample API endpoint
+#[cfg_attr(feature = "server", derive(ToSchema))]
+#[derive(Debug, Deserialize, Serialize)]
+pub struct DownsampleRequest {
+    /// Array of numerical values
+    pub values: Vec<f64>,
+    /// Number of representative points to return
+    pub points: usize,
+}
+
+impl DownsampleRequest {
+    /// Validate the request, collecting every problem rather than stopping at the first
+    pub fn validate(&self) -> std::result::Result<(), ValidationErrors> {
+        let mut errors = Vec::new();
+
+        if self.values.is_empty() {
+            errors.push(FieldError::new(
+                "values",
+                "empty",
+                "Cannot downsample an empty dataset",
+            ));
+        } else if self.values.iter().any(|v| !v.is_finite()) {
+            errors.push(FieldError::new(
+                "values",
+                "non_finite",
+                "Values must be finite numbers",
+            ));
+        }
+        if !(2..=MAX_POINTS).contains(&self.points) {
+            errors.push(FieldError::new(
+                "points",
+                "out_of_range",
+                format!("Points must be between 2 and {MAX_POINTS}"),
+            ));
+        }
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(ValidationErrors(errors))
+        }
+    }
+
+    /// Build a request from a JSON object, reporting type errors for every field
+    /// together with the semantic checks from [`DownsampleRequest::validate`]
+    pub fn from_json_value(
+        value: serde_json::Value,
+    ) -> std::result::Result<Self, ValidationErrors> {
+        let mut reader = FieldReader::new(value)?;
+        let request = Self {
+            values: reader
+                .required(
+                    "values",
+                    "invalid_type",
+                    "Values must be an array of numbers",
+                    "Values are required",
+                )
+                .unwrap_or_default(),
+            points: reader
+                .required(
+                    "points",
+                    "invalid_type",
+                    "Points must be a positive integer",
+                    "Points are required",
+                )
+                .unwrap_or_default(),
+        };
+        reader.finish(request, Self::validate)
+    }
+}
+
+/// Response structure for downsample API endpoint
+#[cfg_attr(feature = "server", derive(ToSchema))]
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DownsampleResponse {
+    /// Number of values in the original dataset
+    pub count: usize,
+    /// Representative points in ascending order
+    pub values: Vec<f64>,
+}
+
+/// Reduce a dataset to at most `points` values that preserve its distribution
+///
+/// Returns the values at evenly spaced ranks of the sorted data, always
+/// including the minimum and maximum, so a histogram or CDF of the result has
+/// the same shape as the original. Datasets with no more than `points` values
+/// are returned whole, sorted.
+///
+/// # Examples
+/// ```
+/// use outlier::downsample;
+///
+/// let values: Vec<f64> = (0..=100).map(f64::from).collect();
+/// assert_eq!(downsample(&values, 5).unwrap(), vec![0.0, 25.0, 50.0, 75.0, 100.0]);
+/// ```
+pub fn downsample(values: &[f64], points: usize) -> Result<Vec<f64>> {
+    if values.is_empty() {
+        anyhow::bail!("Cannot downsample an empty dataset");
+    }
+    if points < 2 {
+        anyhow::bail!("Points must be at least 2");
+    }
+
+    let mut sorted = values.to_vec();
+    sorted.sort_by(|a, b| a.total_cmp(b));
+    if sorted.len() <= points {
+        return Ok(sorted);
+    }
+
+    let last = (sorted.len() - 1) as f64;
+    Ok((0..points)
+        .map(|i| sorted[(i as f64 * last / (points - 1) as f64).round() as usize])
+        .collect())
+}
@@ -0,0 +1,130 @@
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use tokio::sync::Notify;
+use utoipa::ToSchema;
+
+/// Request body for `POST /admin/drain`
+#[derive(Debug, Default, Deserialize, ToSchema)]
+pub struct DrainRequest {
+    /// Shut the server down once the last in-flight request finishes
+    #[serde(default)]
+    pub exit_when_drained: bool,
+}
+
+/// Drain progress as reported by the admin and readiness endpoints
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct DrainStatus {
+    /// Whether the server has stopped accepting new work
+    pub draining: bool,
+    /// Calculation requests still running
+    pub in_flight: usize,
+    /// Whether the server will exit once `in_flight` reaches zero
+    pub exit_when_drained: bool,
+}
+
+/// Tracks in-flight work and whether the server is draining
+///
+/// Draining is one-way: once started, the server only becomes ready again
+/// after a restart.
+#[derive(Default)]
+pub struct DrainState {
+    draining: AtomicBool,
+    exit_when_drained: AtomicBool,
+    in_flight: AtomicUsize,
+    drained: Notify,
+}
+
+/// Marks a request as in flight until dropped
+pub struct InFlightGuard<'a>(&'a DrainState);
+
+impl Drop for InFlightGuard<'_> {
+    fn drop(&mut self) {
+        if self.0.in_flight.fetch_sub(1, Ordering::SeqCst) == 1 {
+            self.0.notify_if_drained();
+        }
+    }
+}
+
+impl DrainState {
+    /// Stop accepting new work; later calls can only turn auto-exit on
+    pub fn begin(&self, exit_when_drained: bool) -> DrainStatus {
+        if exit_when_drained {
+            self.exit_when_drained.store(true, Ordering::SeqCst);
+        }
+        self.draining.store(true, Ordering::SeqCst);
+        self.notify_if_drained();
+        self.status()
+    }
+
+    pub fn is_draining(&self) -> bool {
+        self.draining.load(Ordering::SeqCst)
+    }
+
+    pub fn status(&self) -> DrainStatus {
+        DrainStatus {
+            draining: self.is_draining(),
+            in_flight: self.in_flight.load(Ordering::SeqCst),
+            exit_when_drained: self.exit_when_drained.load(Ordering::SeqCst),
+        }
+    }
+
+    /// Count a request as in flight, or `None` when draining
+    pub fn track(&self) -> Option<InFlightGuard<'_>> {
+        self.in_flight.fetch_add(1, Ordering::SeqCst);
+        let guard = InFlightGuard(self);
+        // Checked after incrementing so `begin` can't miss a request that
+        // slipped in between the check and the increment
+        (!self.is_draining()).then_some(guard)
+    }
+
+    /// Resolve once draining with auto-exit has no work left
+    pub async fn drained(&self) {
+        self.drained.notified().await;
+    }
+
+    fn notify_if_drained(&self) {
+        if self.is_draining()
+            && self.exit_when_drained.load(Ordering::SeqCst)
+            && self.in_flight.load(Ordering::SeqCst) == 0
+        {
+            // notify_one stores a permit, so a later `drained()` still wakes
+            self.drained.notify_one();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn draining_refuses_new_work_and_counts_in_flight() {
+        let state = DrainState::default();
+        let guard = state.track().unwrap();
+        assert_eq!(state.status().in_flight, 1);
+
+        let status = state.begin(false);
+        assert!(status.draining);
+        assert_eq!(status.in_flight, 1);
+        assert!(state.track().is_none());
+        assert_eq!(state.status().in_flight, 1);
+
+        drop(guard);
+        assert_eq!(state.status().in_flight, 0);
+    }
+
+    #[tokio::test]
+    async fn exits_once_last_request_finishes() {
+        let state = DrainState::default();
+        let guard = state.track().unwrap();
+        state.begin(true);
+
+        let waiter = tokio::time::timeout(std::time::Duration::from_millis(50), state.drained());
+        assert!(waiter.await.is_err());
+
+        drop(guard);
+        tokio::time::timeout(std::time::Duration::from_secs(1), state.drained())
+            .await
+            .unwrap();
+    }
+}
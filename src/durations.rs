@@ -0,0 +1,185 @@
+use crate::CsvOptions;
+use anyhow::{Context, Result};
+use chrono::{DateTime, FixedOffset, NaiveDateTime, TimeZone};
+use chrono_tz::Tz;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// Layouts tried, in order, for timestamps without an explicit `time_format`
+/// or UTC offset
+const NAIVE_FORMATS: [&str; 2] = ["%Y-%m-%d %H:%M:%S%.f", "%Y-%m-%dT%H:%M:%S%.f"];
+
+/// Unit durations are reported in
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize, Default)]
+#[serde(rename_all = "snake_case")]
+#[cfg_attr(feature = "cli", clap(rename_all = "snake_case"))]
+pub enum DurationUnit {
+    /// Microseconds
+    Us,
+    /// Milliseconds
+    #[default]
+    Ms,
+    /// Seconds
+    S,
+}
+
+impl std::fmt::Display for DurationUnit {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DurationUnit::Us => write!(f, "us"),
+            DurationUnit::Ms => write!(f, "ms"),
+            DurationUnit::S => write!(f, "s"),
+        }
+    }
+}
+
+impl DurationUnit {
+    fn convert_seconds(self, seconds: f64) -> f64 {
+        match self {
+            DurationUnit::Us => seconds * 1_000_000.0,
+            DurationUnit::Ms => seconds * 1000.0,
+            DurationUnit::S => seconds,
+        }
+    }
+}
+
+/// How to turn a CSV of start and end timestamps into durations
+#[derive(Debug, Clone, PartialEq)]
+pub struct DurationOptions {
+    /// Header of the column holding each event's start
+    pub start_column: String,
+    /// Header of the column holding each event's end
+    pub end_column: String,
+    /// strftime-style layout of the timestamps; when unset, RFC 3339,
+    /// `YYYY-MM-DD HH:MM:SS[.fff]` and Unix seconds are accepted
+    pub time_format: Option<String>,
+    /// Zone for timestamps that carry no UTC offset
+    pub timezone: Tz,
+    /// Unit of the resulting durations
+    pub unit: DurationUnit,
+}
+
+impl Default for DurationOptions {
+    fn default() -> Self {
+        Self {
+            start_column: "start".to_string(),
+            end_column: "end".to_string(),
+            time_format: None,
+            timezone: Tz::UTC,
+            unit: DurationUnit::default(),
+        }
+    }
+}
+
+/// Parse an IANA timezone name such as `Europe/Berlin` or `UTC`
+pub fn parse_timezone(name: &str) -> Result<Tz> {
+    name.parse().map_err(|_| {
+        anyhow::anyhow!("Unknown timezone '{name}'; use an IANA name such as Europe/Berlin")
+    })
+}
+
+/// Parse a timestamp to an instant
+fn parse_timestamp(text: &str, options: &DurationOptions) -> Result<DateTime<FixedOffset>> {
+    let text = text.trim();
+    let localize = |naive: NaiveDateTime| {
+        options
+            .timezone
+            .from_local_datetime(&naive)
+            .single()
+            .map(|dt| dt.fixed_offset())
+            .with_context(|| {
+                format!(
+                    "{text:?} is ambiguous or doesn't exist in {}; include a UTC offset",
+                    options.timezone
+                )
+            })
+    };
+
+    if let Some(ref format) = options.time_format {
+        if let Ok(dt) = DateTime::parse_from_str(text, format) {
+            return Ok(dt);
+        }
+        let naive = NaiveDateTime::parse_from_str(text, format)
+            .with_context(|| format!("{text:?} doesn't match time format {format:?}"))?;
+        return localize(naive);
+    }
+
+    if let Ok(dt) = DateTime::parse_from_rfc3339(text) {
+        return Ok(dt);
+    }
+    if let Some(naive) = NAIVE_FORMATS
+        .iter()
+        .find_map(|format| NaiveDateTime::parse_from_str(text, format).ok())
+    {
+        return localize(naive);
+    }
+    text.parse::<f64>()
+        .ok()
+        .filter(|epoch| epoch.is_finite())
+        .and_then(|epoch| {
+            let secs = epoch.floor();
+            let nanos = ((epoch - secs) * 1e9).round() as u32;
+            DateTime::from_timestamp(secs as i64, nanos.min(999_999_999))
+        })
+        .map(|dt| dt.fixed_offset())
+        .with_context(|| {
+            format!("Unrecognised timestamp {text:?}; set a time format for non-ISO timestamps")
+        })
+}
+
+/// Read a CSV of start and end timestamps and return each row's duration
+///
+/// Rows where either timestamp is empty are skipped, as unfinished events
+/// usually are in exports. An end before its start is an error.
+pub fn read_durations_from_bytes(
+    bytes: &[u8],
+    options: &DurationOptions,
+    csv_options: &CsvOptions,
+) -> Result<Vec<f64>> {
+    let mut reader = csv::ReaderBuilder::new()
+        .delimiter(csv_options.delimiter)
+        .from_reader(bytes);
+    let headers = reader.headers().context("Failed to parse CSV record")?;
+    let column = |name: &str| {
+        headers
+            .iter()
+            .position(|h| h.trim() == name)
+            .with_context(|| format!("CSV has no '{name}' column"))
+    };
+    let start = column(&options.start_column)?;
+    let end = column(&options.end_column)?;
+
+    let mut durations = Vec::new();
+    for (index, record) in reader.records().enumerate() {
+        let record = record.context("Failed to parse CSV record")?;
+        // Data rows are numbered from 2, after the header
+        let row = index + 2;
+        let (Some(from), Some(to)) = (record.get(start), record.get(end)) else {
+            continue;
+        };
+        if from.trim().is_empty() || to.trim().is_empty() {
+            continue;
+        }
+        let from = parse_timestamp(from, options).with_context(|| format!("Row {row}"))?;
+        let to = parse_timestamp(to, options).with_context(|| format!("Row {row}"))?;
+        if to < from {
+            anyhow::bail!("Row {row}: end is before start");
+        }
+        let elapsed = (to - from)
+            .num_nanoseconds()
+            .with_context(|| format!("Row {row}: duration is too long"))?;
+        durations.push(options.unit.convert_seconds(elapsed as f64 / 1e9));
+    }
+    Ok(durations)
+}
+
+/// Read durations from a CSV file of start and end timestamps
+pub fn read_durations_from_file(
+    path: &Path,
+    options: &DurationOptions,
+    csv_options: &CsvOptions,
+) -> Result<Vec<f64>> {
+    let bytes = std::fs::read(path).context("Failed to open CSV file")?;
+    read_durations_from_bytes(&bytes, options, csv_options)
+}
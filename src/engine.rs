@@ -0,0 +1,551 @@
+use crate::{PercentileMethod, percentile_from_ranks, percentile_of_sorted, sort_values};
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+#[cfg(feature = "server")]
+use utoipa::ToSchema;
+
+/// Centroid budget of the t-digest engine; higher is more accurate and slower
+pub const DEFAULT_COMPRESSION: f64 = 100.0;
+
+/// An algorithm for calculating percentiles
+///
+/// Implementations must accept any non-empty dataset and percentiles in
+/// `[0, 100]`, returning one result per requested percentile in order.
+pub trait PercentileEngine: Send + Sync {
+    fn percentiles(
+        &self,
+        values: &[f64],
+        percentiles: &[f64],
+        method: PercentileMethod,
+    ) -> Result<Vec<f64>>;
+
+    /// Calculate a single percentile
+    fn percentile(&self, values: &[f64], percentile: f64, method: PercentileMethod) -> Result<f64> {
+        Ok(self.percentiles(values, &[percentile], method)?[0])
+    }
+}
+
+/// Which [`PercentileEngine`] to calculate with
+#[cfg_attr(feature = "server", derive(ToSchema))]
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize, Default)]
+#[serde(rename_all = "snake_case")]
+#[cfg_attr(feature = "cli", clap(rename_all = "snake_case"))]
+pub enum EngineKind {
+    /// Sort a copy of the values (exact)
+    #[default]
+    ExactSort,
+    /// Select only the ranks needed, in linear time (exact)
+    QuickSelect,
+    /// Summarize the values in a t-digest (approximate, ignores the method)
+    TDigest,
+    /// Delegate to an external command speaking JSON over stdin/stdout
+    External,
+}
+
+impl fmt::Display for EngineKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EngineKind::ExactSort => write!(f, "exact_sort"),
+            EngineKind::QuickSelect => write!(f, "quick_select"),
+            EngineKind::TDigest => write!(f, "t_digest"),
+            EngineKind::External => write!(f, "external"),
+        }
+    }
+}
+
+impl EngineKind {
+    /// The engine for this kind; `external` is the configured command, which
+    /// the `External` kind requires
+    pub fn build(self, external: Option<&External>) -> Result<Box<dyn PercentileEngine>> {
+        Ok(match self {
+            EngineKind::ExactSort => Box::new(ExactSort),
+            EngineKind::QuickSelect => Box::new(QuickSelect),
+            EngineKind::TDigest => Box::new(TDigest::default()),
+            EngineKind::External => Box::new(
+                external
+                    .cloned()
+                    .context("The external engine needs a command to run")?,
+            ),
+        })
+    }
+}
+
+fn check_input(values: &[f64], percentiles: &[f64]) -> Result<()> {
+    if values.is_empty() {
+        anyhow::bail!("Cannot calculate percentile of empty dataset");
+    }
+    if percentiles.iter().any(|p| !(0.0..=100.0).contains(p)) {
+        anyhow::bail!("Percentile must be between 0 and 100");
+    }
+    Ok(())
+}
+
+/// Sorts a copy of the values once, then reads each percentile off it
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ExactSort;
+
+impl PercentileEngine for ExactSort {
+    fn percentiles(
+        &self,
+        values: &[f64],
+        percentiles: &[f64],
+        method: PercentileMethod,
+    ) -> Result<Vec<f64>> {
+        check_input(values, percentiles)?;
+        let sorted = sort_values(values);
+        percentiles
+            .iter()
+            .map(|&p| percentile_of_sorted(&sorted, p, method))
+            .collect()
+    }
+}
+
+/// Finds only the order statistics each percentile needs with
+/// `select_nth_unstable`, which is linear rather than `n log n`
+///
+/// Matches [`ExactSort`] for every method.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct QuickSelect;
+
+impl PercentileEngine for QuickSelect {
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            level = "debug",
+            name = "quick_select",
+            skip_all,
+            fields(value_count = values.len(), percentile_count = percentiles.len())
+        )
+    )]
+    fn percentiles(
+        &self,
+        values: &[f64],
+        percentiles: &[f64],
+        method: PercentileMethod,
+    ) -> Result<Vec<f64>> {
+        check_input(values, percentiles)?;
+        let mut scratch = values.to_vec();
+        let mut rank = |k: usize| *scratch.select_nth_unstable_by(k, f64::total_cmp).1;
+        Ok(percentiles
+            .iter()
+            .map(|&p| percentile_from_ranks(values.len(), p, method, &mut rank))
+            .collect())
+    }
+}
+
+/// Approximates percentiles from a merging t-digest
+///
+/// Values are merged in batches into at most about `compression` centroids,
+/// kept small near the tails so extreme percentiles stay accurate.
+/// Estimates interpolate between centroids, so `method` is ignored.
+#[derive(Debug, Clone, Copy)]
+pub struct TDigest {
+    pub compression: f64,
+}
+
+impl Default for TDigest {
+    fn default() -> Self {
+        Self {
+            compression: DEFAULT_COMPRESSION,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Centroid {
+    mean: f64,
+    weight: f64,
+}
+
+impl TDigest {
+    /// Scale function k1: maps a quantile to a centroid index
+    fn k(&self, q: f64) -> f64 {
+        self.compression / (2.0 * std::f64::consts::PI) * (2.0 * q - 1.0).asin()
+    }
+
+    /// Inverse of [`TDigest::k`]
+    fn q(&self, k: f64) -> f64 {
+        let limit = self.compression / 4.0;
+        ((2.0 * std::f64::consts::PI * k.clamp(-limit, limit) / self.compression).sin() + 1.0) / 2.0
+    }
+
+    /// Merge centroids sorted by mean so each spans at most one unit of k
+    fn compress(&self, sorted: Vec<Centroid>) -> Vec<Centroid> {
+        let total: f64 = sorted.iter().map(|c| c.weight).sum();
+        let mut merged = Vec::new();
+        let mut centroids = sorted.into_iter();
+        let Some(mut current) = centroids.next() else {
+            return merged;
+        };
+        let mut before = 0.0;
+        let mut limit = total * self.q(self.k(0.0) + 1.0);
+        for next in centroids {
+            if before + current.weight + next.weight <= limit {
+                let weight = current.weight + next.weight;
+                current.mean += (next.mean - current.mean) * next.weight / weight;
+                current.weight = weight;
+            } else {
+                before += current.weight;
+                merged.push(current);
+                limit = total * self.q(self.k(before / total) + 1.0);
+                current = next;
+            }
+        }
+        merged.push(current);
+        merged
+    }
+
+    /// Values merged into the centroids at a time
+    fn batch(&self) -> usize {
+        (self.compression * 5.0).max(1.0) as usize
+    }
+
+    /// Fold `values` into `centroids` as single-value centroids and compress
+    fn absorb(&self, mut centroids: Vec<Centroid>, values: &[f64]) -> Vec<Centroid> {
+        centroids.extend(values.iter().map(|&mean| Centroid { mean, weight: 1.0 }));
+        centroids.sort_by(|a, b| a.mean.total_cmp(&b.mean));
+        self.compress(centroids)
+    }
+
+    fn digest(&self, values: &[f64]) -> Vec<Centroid> {
+        let mut centroids = Vec::new();
+        for chunk in values.chunks(self.batch()) {
+            centroids = self.absorb(centroids, chunk);
+        }
+        centroids
+    }
+
+    fn check_compression(&self) -> Result<()> {
+        if !(self.compression.is_finite() && self.compression >= 1.0) {
+            anyhow::bail!("t-digest compression must be at least 1");
+        }
+        Ok(())
+    }
+}
+
+/// Percentiles of `total` values spanning `min..=max`, interpolated between
+/// centroids sorted by mean
+fn estimate(
+    centroids: &[Centroid],
+    min: f64,
+    max: f64,
+    total: f64,
+    percentiles: &[f64],
+) -> Vec<f64> {
+    // Each centroid sits at the middle of the ranks it covers; with
+    // single-value centroids this reduces to linear interpolation
+    let mut centers = Vec::with_capacity(centroids.len());
+    let mut before = 0.0;
+    for c in centroids {
+        centers.push((before + c.weight / 2.0, c.mean));
+        before += c.weight;
+    }
+    let (first, last) = (centers[0], centers[centers.len() - 1]);
+
+    percentiles
+        .iter()
+        .map(|&p| {
+            let target = p / 100.0 * (total - 1.0) + 0.5;
+            let lerp = |(x0, y0): (f64, f64), (x1, y1): (f64, f64)| {
+                if x1 <= x0 {
+                    y0
+                } else {
+                    y0 + (y1 - y0) * ((target - x0) / (x1 - x0)).clamp(0.0, 1.0)
+                }
+            };
+            // Later batches can sort beside a merged tail centroid, so
+            // the extremes come from the tracked min and max
+            if p == 0.0 {
+                min
+            } else if p == 100.0 {
+                max
+            } else if target <= first.0 {
+                lerp((0.5, min), first)
+            } else if target >= last.0 {
+                lerp(last, (total - 0.5, max))
+            } else {
+                let i = centers.partition_point(|(x, _)| *x <= target);
+                lerp(centers[i - 1], centers[i])
+            }
+        })
+        .collect()
+}
+
+impl PercentileEngine for TDigest {
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            level = "debug",
+            name = "t_digest",
+            skip_all,
+            fields(value_count = values.len(), compression = self.compression)
+        )
+    )]
+    fn percentiles(
+        &self,
+        values: &[f64],
+        percentiles: &[f64],
+        _method: PercentileMethod,
+    ) -> Result<Vec<f64>> {
+        check_input(values, percentiles)?;
+        self.check_compression()?;
+        let min = values.iter().copied().fold(f64::INFINITY, f64::min);
+        let max = values.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+        Ok(estimate(
+            &self.digest(values),
+            min,
+            max,
+            values.len() as f64,
+            percentiles,
+        ))
+    }
+}
+
+/// A t-digest fed one value at a time, for streams too long to keep
+///
+/// Values are buffered and merged in the batches [`TDigest`] uses, so memory
+/// stays bounded by the compression however many values arrive.
+#[derive(Debug, Clone)]
+pub struct TDigestSketch {
+    digest: TDigest,
+    centroids: Vec<Centroid>,
+    buffer: Vec<f64>,
+    count: u64,
+    min: f64,
+    max: f64,
+}
+
+impl Default for TDigestSketch {
+    fn default() -> Self {
+        Self::new(TDigest::default())
+    }
+}
+
+impl TDigestSketch {
+    pub fn new(digest: TDigest) -> Self {
+        Self {
+            digest,
+            centroids: Vec::new(),
+            buffer: Vec::new(),
+            count: 0,
+            min: f64::INFINITY,
+            max: f64::NEG_INFINITY,
+        }
+    }
+
+    /// Add a value; rejecting NaN and infinities is up to the caller
+    pub fn insert(&mut self, value: f64) {
+        self.count += 1;
+        self.min = self.min.min(value);
+        self.max = self.max.max(value);
+        self.buffer.push(value);
+        if self.buffer.len() >= self.digest.batch() {
+            self.flush();
+        }
+    }
+
+    /// Values inserted so far
+    pub fn count(&self) -> u64 {
+        self.count
+    }
+
+    /// Smallest and largest values inserted, `None` while empty
+    pub fn range(&self) -> Option<(f64, f64)> {
+        (self.count > 0).then_some((self.min, self.max))
+    }
+
+    /// Estimated percentiles of every value inserted so far
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(level = "debug", name = "sketch_percentiles", skip_all, fields(count = self.count))
+    )]
+    pub fn percentiles(&mut self, percentiles: &[f64]) -> Result<Vec<f64>> {
+        if self.count == 0 {
+            anyhow::bail!("Cannot calculate percentile of empty dataset");
+        }
+        check_input(&[self.min], percentiles)?;
+        self.digest.check_compression()?;
+        self.flush();
+        Ok(estimate(
+            &self.centroids,
+            self.min,
+            self.max,
+            self.count as f64,
+            percentiles,
+        ))
+    }
+
+    /// Merge any buffered values and copy the sketch out for saving
+    pub fn save(&mut self) -> SavedSketch {
+        self.flush();
+        SavedSketch {
+            compression: self.digest.compression,
+            centroids: self.centroids.iter().map(|c| [c.mean, c.weight]).collect(),
+            count: self.count,
+            min: self.range().map(|(min, _)| min),
+            max: self.range().map(|(_, max)| max),
+        }
+    }
+
+    /// Rebuild a saved sketch, rejecting one whose centroids don't account
+    /// for its count
+    pub fn restore(saved: SavedSketch) -> Result<Self> {
+        let digest = TDigest {
+            compression: saved.compression,
+        };
+        digest.check_compression()?;
+        let mut sketch = Self::new(digest);
+        if saved.count == 0 {
+            if !saved.centroids.is_empty() {
+                anyhow::bail!("Saved sketch has centroids but no values");
+            }
+            return Ok(sketch);
+        }
+        let (Some(min), Some(max)) = (saved.min, saved.max) else {
+            anyhow::bail!("Saved sketch has values but no min or max");
+        };
+        if !(min.is_finite() && max.is_finite() && min <= max) {
+            anyhow::bail!("Saved sketch has an invalid range {min}..{max}");
+        }
+        let mut weight = 0.0;
+        for &[mean, w] in &saved.centroids {
+            if !(mean.is_finite() && w.is_finite() && w > 0.0) {
+                anyhow::bail!("Saved sketch has an invalid centroid ({mean}, {w})");
+            }
+            weight += w;
+        }
+        if (weight - saved.count as f64).abs() > 0.5 {
+            anyhow::bail!(
+                "Saved sketch's centroids hold {weight} values, not {}",
+                saved.count
+            );
+        }
+        sketch.centroids = saved
+            .centroids
+            .iter()
+            .map(|&[mean, weight]| Centroid { mean, weight })
+            .collect();
+        sketch.centroids.sort_by(|a, b| a.mean.total_cmp(&b.mean));
+        sketch.count = saved.count;
+        sketch.min = min;
+        sketch.max = max;
+        Ok(sketch)
+    }
+
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(level = "trace", name = "merge", skip_all, fields(buffered = self.buffer.len()))
+    )]
+    fn flush(&mut self) {
+        if !self.buffer.is_empty() {
+            let centroids = std::mem::take(&mut self.centroids);
+            self.centroids = self.digest.absorb(centroids, &self.buffer);
+            self.buffer.clear();
+        }
+    }
+}
+
+/// A [`TDigestSketch`] as written to a snapshot
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "server", derive(ToSchema))]
+pub struct SavedSketch {
+    pub compression: f64,
+    /// `[mean, weight]` of each centroid
+    pub centroids: Vec<[f64; 2]>,
+    pub count: u64,
+    /// Smallest value inserted, `None` while empty
+    pub min: Option<f64>,
+    /// Largest value inserted, `None` while empty
+    pub max: Option<f64>,
+}
+
+/// Runs a command that reads `{"values", "percentiles", "method"}` as JSON on
+/// stdin and writes a JSON array with one result per percentile to stdout
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct External {
+    pub program: String,
+    pub args: Vec<String>,
+}
+
+impl External {
+    /// From a command line split into words, e.g. `["python3", "engine.py"]`
+    pub fn from_command(command: &[String]) -> Option<Self> {
+        let (program, args) = command.split_first()?;
+        Some(Self {
+            program: program.clone(),
+            args: args.to_vec(),
+        })
+    }
+}
+
+impl PercentileEngine for External {
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            level = "debug",
+            name = "external_engine",
+            skip_all,
+            fields(program = %self.program, value_count = values.len())
+        )
+    )]
+    fn percentiles(
+        &self,
+        values: &[f64],
+        percentiles: &[f64],
+        method: PercentileMethod,
+    ) -> Result<Vec<f64>> {
+        check_input(values, percentiles)?;
+        let input = serde_json::to_vec(&serde_json::json!({
+            "values": values,
+            "percentiles": percentiles,
+            "method": method,
+        }))?;
+
+        let mut child = Command::new(&self.program)
+            .args(&self.args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .with_context(|| format!("Failed to start external engine '{}'", self.program))?;
+        let mut stdin = child.stdin.take().context("External engine has no stdin")?;
+        // Write from another thread so an engine that answers before reading
+        // everything can't deadlock against a full pipe
+        let output = std::thread::scope(|scope| {
+            scope.spawn(move || stdin.write_all(&input));
+            child.wait_with_output()
+        })
+        .context("Failed to run external engine")?;
+
+        // stderr can echo the input or environment, so it's only logged
+        if !output.status.success() {
+            let status = output.status.code().map_or_else(
+                || output.status.to_string(),
+                |code| format!("exit status {code}"),
+            );
+            #[cfg(feature = "tracing")]
+            tracing::warn!(
+                program = %self.program,
+                %status,
+                stderr = %String::from_utf8_lossy(&output.stderr).trim(),
+                "External engine failed"
+            );
+            anyhow::bail!("External engine failed ({status})");
+        }
+        let results: Vec<f64> = serde_json::from_slice(&output.stdout)
+            .context("External engine must print a JSON array of numbers")?;
+        if results.len() != percentiles.len() {
+            anyhow::bail!(
+                "External engine returned {} results for {} percentiles",
+                results.len(),
+                percentiles.len()
+            );
+        }
+        Ok(results)
+    }
+}
@@ -0,0 +1,263 @@
+use anyhow::{Context, Result};
+use reqwest::{StatusCode, header};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// Delay before the first retry; doubled for each one after
+const INITIAL_BACKOFF: Duration = Duration::from_millis(500);
+
+/// Longest `Retry-After` a server can make us wait
+const MAX_RETRY_AFTER: Duration = Duration::from_secs(30);
+
+/// How to download a remote input file
+#[derive(Debug, Clone)]
+pub struct FetchOptions {
+    /// Retries after the first attempt for connection errors, 429 and 5xx
+    pub retries: u32,
+    /// Directory for cached downloads, revalidated with conditional GETs
+    pub cache_dir: Option<PathBuf>,
+    /// Largest body accepted, in bytes
+    pub max_bytes: u64,
+}
+
+/// Validators stored next to a cached body
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct CacheEntry {
+    url: String,
+    etag: Option<String>,
+    last_modified: Option<String>,
+}
+
+/// Whether an input path is an `http://` or `https://` URL
+pub fn is_url(path: &Path) -> bool {
+    path.to_str()
+        .is_some_and(|p| p.starts_with("http://") || p.starts_with("https://"))
+}
+
+/// The file name at the end of a URL's path, for detecting its format
+pub fn file_name(url: &str) -> &str {
+    let path = url.split(['?', '#']).next().unwrap_or(url);
+    path.rsplit('/').next().unwrap_or(path)
+}
+
+/// Download `url`, retrying transient failures with exponential backoff
+///
+/// With a cache directory, a previous download is revalidated with
+/// `If-None-Match` / `If-Modified-Since` and reused on `304 Not Modified`.
+pub async fn fetch(url: &str, options: &FetchOptions) -> Result<Vec<u8>> {
+    let client = reqwest::Client::builder()
+        .user_agent(concat!("outlier/", env!("CARGO_PKG_VERSION")))
+        .build()?;
+    let cache = options
+        .cache_dir
+        .as_deref()
+        .map(|dir| cache_paths(dir, url));
+    let cached = cache.as_ref().and_then(|(body, meta)| {
+        let entry: CacheEntry = serde_json::from_slice(&std::fs::read(meta).ok()?).ok()?;
+        (entry.url == url && body.exists()).then_some(entry)
+    });
+
+    let mut attempt = 0;
+    let response = loop {
+        let mut request = client.get(url);
+        if let Some(ref entry) = cached {
+            if let Some(ref etag) = entry.etag {
+                request = request.header(header::IF_NONE_MATCH, etag);
+            }
+            if let Some(ref modified) = entry.last_modified {
+                request = request.header(header::IF_MODIFIED_SINCE, modified);
+            }
+        }
+
+        let (retryable, delay) = match request.send().await {
+            Ok(response)
+                if response.status() == StatusCode::TOO_MANY_REQUESTS
+                    || response.status().is_server_error() =>
+            {
+                (
+                    format!("{url} returned {}", response.status()),
+                    retry_after(&response),
+                )
+            }
+            Ok(response) => break response,
+            Err(e) if e.is_connect() || e.is_timeout() || e.is_request() => {
+                (format!("Failed to fetch {url}: {e}"), None)
+            }
+            Err(e) => return Err(e).with_context(|| format!("Failed to fetch {url}")),
+        };
+        if attempt >= options.retries {
+            anyhow::bail!("{retryable} (gave up after {} attempts)", attempt + 1);
+        }
+        tokio::time::sleep(delay.unwrap_or(INITIAL_BACKOFF * 2u32.pow(attempt))).await;
+        attempt += 1;
+    };
+
+    if response.status() == StatusCode::NOT_MODIFIED
+        && let Some((body, _)) = &cache
+    {
+        return std::fs::read(body)
+            .with_context(|| format!("Failed to read cached {}", body.display()));
+    }
+    let response = response
+        .error_for_status()
+        .with_context(|| format!("Failed to fetch {url}"))?;
+    let entry = CacheEntry {
+        url: url.to_string(),
+        etag: header_text(&response, header::ETAG),
+        last_modified: header_text(&response, header::LAST_MODIFIED),
+    };
+    let bytes = read_limited(response, options.max_bytes, url).await?;
+
+    if let Some((body, meta)) = cache
+        && (entry.etag.is_some() || entry.last_modified.is_some())
+    {
+        // A cache that can't be written only costs a full download next time
+        let stored = std::fs::create_dir_all(body.parent().unwrap_or(Path::new(".")))
+            .and_then(|()| std::fs::write(&body, &bytes))
+            .and_then(|()| std::fs::write(&meta, serde_json::to_vec(&entry)?));
+        if let Err(e) = stored {
+            tracing::warn!("Failed to cache {url}: {e}");
+        }
+    }
+    Ok(bytes)
+}
+
+/// Read a body, stopping as soon as it exceeds `max_bytes`
+async fn read_limited(
+    mut response: reqwest::Response,
+    max_bytes: u64,
+    url: &str,
+) -> Result<Vec<u8>> {
+    let too_large = || anyhow::anyhow!("{url} is larger than the {max_bytes}-byte download limit");
+    if response.content_length().is_some_and(|len| len > max_bytes) {
+        return Err(too_large());
+    }
+    let mut bytes = Vec::new();
+    while let Some(chunk) = response
+        .chunk()
+        .await
+        .with_context(|| format!("Failed to download {url}"))?
+    {
+        if (bytes.len() + chunk.len()) as u64 > max_bytes {
+            return Err(too_large());
+        }
+        bytes.extend_from_slice(&chunk);
+    }
+    Ok(bytes)
+}
+
+/// Body and metadata paths for `url` in the cache directory
+fn cache_paths(dir: &Path, url: &str) -> (PathBuf, PathBuf) {
+    let key = hex::encode(Sha256::digest(url.as_bytes()));
+    (
+        dir.join(format!("{key}.body")),
+        dir.join(format!("{key}.json")),
+    )
+}
+
+fn header_text(response: &reqwest::Response, name: header::HeaderName) -> Option<String> {
+    response
+        .headers()
+        .get(name)?
+        .to_str()
+        .ok()
+        .map(str::to_string)
+}
+
+/// A `Retry-After` given in seconds, capped at [`MAX_RETRY_AFTER`]
+fn retry_after(response: &reqwest::Response) -> Option<Duration> {
+    let seconds: u64 = header_text(response, header::RETRY_AFTER)?
+        .trim()
+        .parse()
+        .ok()?;
+    Some(Duration::from_secs(seconds).min(MAX_RETRY_AFTER))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    /// Serve canned responses in order, recording each request's headers
+    async fn serve(responses: Vec<&'static str>) -> (String, Arc<std::sync::Mutex<Vec<String>>>) {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/data.json", listener.local_addr().unwrap());
+        let requests = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let seen = requests.clone();
+        let served = Arc::new(AtomicUsize::new(0));
+        tokio::spawn(async move {
+            loop {
+                let (mut socket, _) = listener.accept().await.unwrap();
+                let mut buf = vec![0; 4096];
+                let n = socket.read(&mut buf).await.unwrap();
+                seen.lock()
+                    .unwrap()
+                    .push(String::from_utf8_lossy(&buf[..n]).to_lowercase());
+                let response = responses[served.fetch_add(1, Ordering::SeqCst)];
+                socket.write_all(response.as_bytes()).await.unwrap();
+                socket.shutdown().await.unwrap();
+            }
+        });
+        (url, requests)
+    }
+
+    fn options(cache_dir: Option<PathBuf>) -> FetchOptions {
+        FetchOptions {
+            retries: 2,
+            cache_dir,
+            max_bytes: 1024,
+        }
+    }
+
+    #[tokio::test]
+    async fn retries_then_revalidates_from_cache() {
+        let (url, requests) = serve(vec![
+            "HTTP/1.1 503 Service Unavailable\r\nRetry-After: 0\r\nContent-Length: 0\r\nConnection: close\r\n\r\n",
+            "HTTP/1.1 200 OK\r\nETag: \"v1\"\r\nContent-Length: 9\r\nConnection: close\r\n\r\n[1, 2, 3]",
+            "HTTP/1.1 304 Not Modified\r\nConnection: close\r\n\r\n",
+        ])
+        .await;
+        let dir = std::env::temp_dir().join(format!("outlier-fetch-{}", std::process::id()));
+
+        let first = fetch(&url, &options(Some(dir.clone()))).await.unwrap();
+        assert_eq!(first, b"[1, 2, 3]");
+        let second = fetch(&url, &options(Some(dir.clone()))).await.unwrap();
+        assert_eq!(second, b"[1, 2, 3]");
+
+        let requests = requests.lock().unwrap();
+        assert_eq!(requests.len(), 3);
+        assert!(!requests[1].contains("if-none-match"));
+        assert!(requests[2].contains("if-none-match: \"v1\""));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn rejects_oversized_downloads_and_gives_up_after_retries() {
+        let (url, _) = serve(vec![
+            "HTTP/1.1 200 OK\r\nContent-Length: 2048\r\nConnection: close\r\n\r\n",
+            "HTTP/1.1 500 Internal Server Error\r\nRetry-After: 0\r\nContent-Length: 0\r\nConnection: close\r\n\r\n",
+            "HTTP/1.1 500 Internal Server Error\r\nRetry-After: 0\r\nContent-Length: 0\r\nConnection: close\r\n\r\n",
+            "HTTP/1.1 500 Internal Server Error\r\nRetry-After: 0\r\nContent-Length: 0\r\nConnection: close\r\n\r\n",
+        ])
+        .await;
+
+        let err = fetch(&url, &options(None)).await.unwrap_err();
+        assert!(err.to_string().contains("download limit"));
+        let err = fetch(&url, &options(None)).await.unwrap_err();
+        assert!(err.to_string().contains("gave up after 3 attempts"));
+    }
+
+    #[test]
+    fn only_http_urls_are_remote() {
+        assert!(is_url(Path::new("https://example.com/data.csv")));
+        assert!(!is_url(Path::new("data/https.csv")));
+        assert_eq!(
+            file_name("https://bucket.s3.amazonaws.com/runs/latency.csv?X-Amz-Signature=abc"),
+            "latency.csv"
+        );
+    }
+}
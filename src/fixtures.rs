@@ -0,0 +1,218 @@
+use anyhow::Context;
+use axum::body::Body;
+use axum::http::{HeaderValue, StatusCode, header};
+use axum::response::Response;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::io::{BufRead, Write};
+use std::path::Path;
+use std::sync::Mutex;
+
+use crate::config::FixturesConfig;
+
+/// Header set on responses served from a recording
+pub const REPLAYED_HEADER: &str = "fixture-replayed";
+
+/// What identifies a request in a recording
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct RequestKey {
+    pub method: String,
+    /// Path and query string
+    pub path: String,
+    /// SHA-256 of the body, hex; see [`body_hash`]
+    pub body_sha256: String,
+}
+
+/// One line of a fixture file
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Recording {
+    #[serde(flatten)]
+    pub request: RequestKey,
+    pub status: u16,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub content_type: Option<String>,
+    pub body: String,
+}
+
+impl Recording {
+    pub fn to_response(&self) -> Response {
+        let mut response = Response::new(Body::from(self.body.clone()));
+        *response.status_mut() =
+            StatusCode::from_u16(self.status).unwrap_or(StatusCode::INTERNAL_SERVER_ERROR);
+        if let Some(content_type) = self
+            .content_type
+            .as_deref()
+            .and_then(|c| HeaderValue::from_str(c).ok())
+        {
+            response
+                .headers_mut()
+                .insert(header::CONTENT_TYPE, content_type);
+        }
+        response
+            .headers_mut()
+            .insert(REPLAYED_HEADER, HeaderValue::from_static("true"));
+        response
+    }
+}
+
+/// Record/replay mode, from the `[fixtures]` config section
+pub enum Fixtures {
+    /// Append each exchange to the file
+    Record(Mutex<std::fs::File>),
+    /// Serve the last recording of each request
+    Replay(HashMap<RequestKey, Recording>),
+}
+
+impl Fixtures {
+    pub fn from_config(config: &FixturesConfig) -> anyhow::Result<Option<Self>> {
+        match (&config.record, &config.replay) {
+            (Some(_), Some(_)) => {
+                anyhow::bail!("fixtures.record and fixtures.replay can't both be set")
+            }
+            (Some(path), None) => {
+                let file = std::fs::OpenOptions::new()
+                    .create(true)
+                    .append(true)
+                    .open(path)
+                    .with_context(|| format!("Failed to open fixture file {}", path.display()))?;
+                Ok(Some(Self::Record(Mutex::new(file))))
+            }
+            (None, Some(path)) => Ok(Some(Self::Replay(load(path)?))),
+            (None, None) => Ok(None),
+        }
+    }
+
+    /// Append `recording` as one JSON line
+    pub fn record(&self, recording: &Recording) -> anyhow::Result<()> {
+        let Self::Record(file) = self else {
+            return Ok(());
+        };
+        let mut line = serde_json::to_string(recording)?;
+        line.push('\n');
+        let mut file = file.lock().unwrap_or_else(|e| e.into_inner());
+        file.write_all(line.as_bytes())?;
+        Ok(())
+    }
+}
+
+fn load(path: &Path) -> anyhow::Result<HashMap<RequestKey, Recording>> {
+    let file = std::fs::File::open(path)
+        .with_context(|| format!("Failed to open fixture file {}", path.display()))?;
+    let mut recordings = HashMap::new();
+    for (i, line) in std::io::BufReader::new(file).lines().enumerate() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let recording: Recording = serde_json::from_str(&line).with_context(|| {
+            format!("Invalid recording on line {} of {}", i + 1, path.display())
+        })?;
+        recordings.insert(recording.request.clone(), recording);
+    }
+    Ok(recordings)
+}
+
+/// SHA-256 of a request body, hex
+///
+/// Multipart boundaries are chosen at random by most clients, so the
+/// boundary named in `content_type` is removed before hashing; the same form
+/// then hashes the same on every run.
+pub fn body_hash(content_type: Option<&str>, body: &[u8]) -> String {
+    let boundary = content_type
+        .filter(|c| c.starts_with("multipart/"))
+        .and_then(|c| {
+            c.split(';')
+                .find_map(|p| p.trim().strip_prefix("boundary="))
+        })
+        .map(|b| b.trim_matches('"'))
+        .filter(|b| !b.is_empty());
+
+    let mut hasher = Sha256::new();
+    match boundary {
+        Some(boundary) => {
+            let boundary = boundary.as_bytes();
+            let mut rest = body;
+            while let Some(at) = rest.windows(boundary.len()).position(|w| w == boundary) {
+                hasher.update(&rest[..at]);
+                rest = &rest[at + boundary.len()..];
+            }
+            hasher.update(rest);
+        }
+        None => hasher.update(body),
+    }
+    hex::encode(hasher.finalize())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn multipart_boundaries_do_not_change_the_hash() {
+        let form = |boundary: &str| {
+            format!(
+                "--{boundary}\r\nContent-Disposition: form-data; name=\"file\"; filename=\"a.csv\"\r\n\r\n1\n2\r\n--{boundary}--\r\n"
+            )
+        };
+        let hash = |boundary: &str| {
+            body_hash(
+                Some(&format!("multipart/form-data; boundary={boundary}")),
+                form(boundary).as_bytes(),
+            )
+        };
+        assert_eq!(hash("abc123"), hash("zz-999"));
+        assert_ne!(
+            body_hash(Some("application/json"), b"[1]"),
+            body_hash(Some("application/json"), b"[2]")
+        );
+    }
+
+    #[test]
+    fn recordings_round_trip_through_the_file() {
+        let path =
+            std::env::temp_dir().join(format!("outlier-fixtures-{}.jsonl", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let request = RequestKey {
+            method: "POST".to_string(),
+            path: "/calculate".to_string(),
+            body_sha256: body_hash(None, b"{}"),
+        };
+        let recorder = Fixtures::from_config(&FixturesConfig {
+            record: Some(path.clone()),
+            replay: None,
+        })
+        .unwrap()
+        .unwrap();
+        for result in ["1", "2"] {
+            recorder
+                .record(&Recording {
+                    request: request.clone(),
+                    status: 200,
+                    content_type: Some("application/json".to_string()),
+                    body: result.to_string(),
+                })
+                .unwrap();
+        }
+
+        let Some(Fixtures::Replay(recordings)) = Fixtures::from_config(&FixturesConfig {
+            record: None,
+            replay: Some(path.clone()),
+        })
+        .unwrap() else {
+            panic!("expected replay mode");
+        };
+        // The last recording of a request wins
+        assert_eq!(recordings[&request].body, "2");
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn record_and_replay_are_exclusive() {
+        let both = FixturesConfig {
+            record: Some("a.jsonl".into()),
+            replay: Some("b.jsonl".into()),
+        };
+        assert!(Fixtures::from_config(&both).is_err());
+    }
+}
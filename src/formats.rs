@@ -0,0 +1,387 @@
+use crate::{CsvOptions, InputFormat, loadtest, otel, read_csv_values};
+use anyhow::{Context, Result};
+use std::io::Read;
+use std::sync::{Arc, LazyLock};
+
+const MAX_VALUES: usize = 10_000_000; // 10 million
+
+/// Bytes of an input passed to [`Format::sniff`]
+pub const SNIFF_BYTES: usize = 4096;
+
+/// The built-in formats, shared by every `InputFormat` lookup
+static BUILTIN: LazyLock<FormatRegistry> = LazyLock::new(FormatRegistry::builtin);
+
+/// A file format values can be read from
+///
+/// Implement this to read a format outlier doesn't know about and
+/// [`register`](FormatRegistry::register) it alongside the built-in ones.
+pub trait Format: Send + Sync {
+    /// Name used to choose the format explicitly, e.g. `csv`
+    fn name(&self) -> &str;
+
+    /// Lowercase file extensions, without the dot, that identify the format
+    fn extensions(&self) -> &[&str] {
+        &[]
+    }
+
+    /// Whether the first [`SNIFF_BYTES`] of an input look like this format
+    fn sniff(&self, _head: &[u8]) -> bool {
+        false
+    }
+
+    /// Parse every value from an input in this format
+    fn parse_stream(&self, reader: &mut dyn Read, options: &CsvOptions) -> Result<Vec<f64>>;
+}
+
+/// Formats that inputs are detected as and parsed with
+///
+/// Detection tries every format's [`Format::sniff`] in registration order,
+/// then falls back to the file extension, so a Gatling log is recognised
+/// whatever it is named.
+///
+/// # Examples
+/// ```
+/// use outlier::{CsvOptions, Format, FormatRegistry};
+///
+/// /// One value per line
+/// struct Lines;
+///
+/// impl Format for Lines {
+///     fn name(&self) -> &str {
+///         "lines"
+///     }
+///     fn extensions(&self) -> &[&str] {
+///         &["txt"]
+///     }
+///     fn parse_stream(
+///         &self,
+///         reader: &mut dyn std::io::Read,
+///         _options: &CsvOptions,
+///     ) -> anyhow::Result<Vec<f64>> {
+///         let mut text = String::new();
+///         reader.read_to_string(&mut text)?;
+///         Ok(text.lines().map(str::parse).collect::<Result<_, _>>()?)
+///     }
+/// }
+///
+/// let mut registry = FormatRegistry::builtin();
+/// registry.register(Lines);
+/// let values = registry
+///     .read(b"3\n1\n2\n", "data.txt", None, &CsvOptions::default())
+///     .unwrap();
+/// assert_eq!(values, vec![3.0, 1.0, 2.0]);
+/// ```
+#[derive(Clone, Default)]
+pub struct FormatRegistry {
+    formats: Vec<Arc<dyn Format>>,
+}
+
+impl FormatRegistry {
+    /// A registry with no formats
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// A registry with every format this build supports
+    pub fn builtin() -> Self {
+        let mut registry = Self::new();
+        registry
+            .register(Otel)
+            .register(Gatling)
+            .register(Jmeter)
+            .register(K6)
+            .register(Vegeta)
+            .register(Wrk)
+            .register(Json)
+            .register(Csv);
+        #[cfg(feature = "hdr")]
+        registry.register(Hdr);
+        registry
+    }
+
+    /// Add a format, replacing any registered under the same name
+    pub fn register(&mut self, format: impl Format + 'static) -> &mut Self {
+        self.formats.retain(|f| f.name() != format.name());
+        self.formats.push(Arc::new(format));
+        self
+    }
+
+    /// The format registered under `name`
+    pub fn get(&self, name: &str) -> Option<&dyn Format> {
+        self.formats
+            .iter()
+            .find(|f| f.name() == name)
+            .map(|f| f.as_ref())
+    }
+
+    /// Names of the registered formats, in registration order
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.formats.iter().map(|f| f.name())
+    }
+
+    /// Detect the format of an input from its contents, then its filename
+    pub fn sniff(&self, bytes: &[u8], filename: &str) -> Option<&dyn Format> {
+        let head = &bytes[..bytes.len().min(SNIFF_BYTES)];
+        let extension = filename
+            .rsplit_once('.')
+            .map(|(_, ext)| ext.to_lowercase())
+            .unwrap_or_default();
+        self.formats
+            .iter()
+            .find(|f| f.sniff(head))
+            .or_else(|| {
+                self.formats
+                    .iter()
+                    .find(|f| f.extensions().contains(&extension.as_str()))
+            })
+            .map(|f| f.as_ref())
+    }
+
+    /// Parse values from bytes in the named format, detecting it when `None`
+    pub fn read(
+        &self,
+        bytes: &[u8],
+        filename: &str,
+        format: Option<&str>,
+        options: &CsvOptions,
+    ) -> Result<Vec<f64>> {
+        let format = match format {
+            Some(name) => self
+                .get(name)
+                .with_context(|| format!("Unknown input format {name:?}"))?,
+            None => self
+                .sniff(bytes, filename)
+                .ok_or_else(crate::unsupported_format)?,
+        };
+        format.parse_stream(&mut &bytes[..], options)
+    }
+}
+
+impl InputFormat {
+    /// Detect a built-in format from an input's contents, then its filename
+    ///
+    /// Returns `None` when neither is recognised.
+    pub fn sniff(bytes: &[u8], filename: &str) -> Option<InputFormat> {
+        let format = BUILTIN.sniff(bytes, filename)?;
+        serde_json::from_value(serde_json::Value::String(format.name().to_string())).ok()
+    }
+
+    /// Parse values in this format; `Auto` must be resolved first
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(level = "debug", name = "parse", skip_all, fields(format = %self))
+    )]
+    pub fn parse_stream(self, reader: &mut dyn Read, options: &CsvOptions) -> Result<Vec<f64>> {
+        if self == InputFormat::Auto {
+            anyhow::bail!("Detect the input format before parsing");
+        }
+        let Some(format) = BUILTIN.get(&self.to_string()) else {
+            // Only feature-gated formats can be missing from the registry
+            anyhow::bail!("HDR histogram input requires the `hdr` feature");
+        };
+        format.parse_stream(reader, options)
+    }
+}
+
+/// The first non-blank line of an input's head
+fn first_line(head: &[u8]) -> String {
+    String::from_utf8_lossy(head)
+        .lines()
+        .map(str::trim)
+        .find(|l| !l.is_empty())
+        .unwrap_or_default()
+        .to_string()
+}
+
+fn read_all(reader: &mut dyn Read) -> Result<Vec<u8>> {
+    let mut bytes = Vec::new();
+    reader
+        .read_to_end(&mut bytes)
+        .context("Failed to read input")?;
+    Ok(bytes)
+}
+
+/// JSON array of numbers
+struct Json;
+
+impl Format for Json {
+    fn name(&self) -> &str {
+        "json"
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["json"]
+    }
+
+    fn parse_stream(&self, reader: &mut dyn Read, _options: &CsvOptions) -> Result<Vec<f64>> {
+        let values: Vec<f64> = serde_json::from_reader(reader)
+            .context("Failed to parse JSON. Expected array of numbers.")?;
+        if values.len() > MAX_VALUES {
+            anyhow::bail!(
+                "Input dataset exceeds the limit of {} values. Aborting.",
+                MAX_VALUES
+            );
+        }
+        Ok(values)
+    }
+}
+
+/// CSV with a `value` column
+struct Csv;
+
+impl Format for Csv {
+    fn name(&self) -> &str {
+        "csv"
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["csv"]
+    }
+
+    fn parse_stream(&self, reader: &mut dyn Read, options: &CsvOptions) -> Result<Vec<f64>> {
+        read_csv_values(reader, options)
+    }
+}
+
+/// Compressed base64 HdrHistogram
+#[cfg(feature = "hdr")]
+struct Hdr;
+
+#[cfg(feature = "hdr")]
+impl Format for Hdr {
+    fn name(&self) -> &str {
+        "hdr"
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["hdr"]
+    }
+
+    fn parse_stream(&self, reader: &mut dyn Read, _options: &CsvOptions) -> Result<Vec<f64>> {
+        let bytes = read_all(reader)?;
+        crate::hdr::decode_hdr_histogram(
+            std::str::from_utf8(&bytes).context("Failed to parse HDR histogram: invalid UTF-8")?,
+        )
+    }
+}
+
+/// Gatling `simulation.log`
+struct Gatling;
+
+impl Format for Gatling {
+    fn name(&self) -> &str {
+        "gatling"
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["log"]
+    }
+
+    fn sniff(&self, head: &[u8]) -> bool {
+        let line = first_line(head);
+        line.starts_with("RUN\t") || line.starts_with("REQUEST\t")
+    }
+
+    fn parse_stream(&self, reader: &mut dyn Read, _options: &CsvOptions) -> Result<Vec<f64>> {
+        loadtest::read_gatling_log(&read_all(reader)?)
+    }
+}
+
+/// JMeter JTL results, CSV or XML
+struct Jmeter;
+
+impl Format for Jmeter {
+    fn name(&self) -> &str {
+        "jmeter"
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["jtl"]
+    }
+
+    fn sniff(&self, head: &[u8]) -> bool {
+        let line = first_line(head);
+        let has_header = |name: &str| line.split(',').any(|h| h == name);
+        line.starts_with("<?xml")
+            || line.starts_with("<testResults")
+            || (has_header("elapsed") && has_header("timeStamp"))
+    }
+
+    fn parse_stream(&self, reader: &mut dyn Read, _options: &CsvOptions) -> Result<Vec<f64>> {
+        loadtest::read_jmeter_jtl(&read_all(reader)?)
+    }
+}
+
+/// k6 `--out json` results
+struct K6;
+
+impl Format for K6 {
+    fn name(&self) -> &str {
+        "k6"
+    }
+
+    fn sniff(&self, head: &[u8]) -> bool {
+        let line = first_line(head);
+        line.starts_with('{') && line.contains("\"metric\"")
+    }
+
+    fn parse_stream(&self, reader: &mut dyn Read, _options: &CsvOptions) -> Result<Vec<f64>> {
+        loadtest::read_k6_json(&read_all(reader)?)
+    }
+}
+
+/// vegeta JSON-lines results
+struct Vegeta;
+
+impl Format for Vegeta {
+    fn name(&self) -> &str {
+        "vegeta"
+    }
+
+    fn sniff(&self, head: &[u8]) -> bool {
+        let line = first_line(head);
+        line.starts_with('{') && line.contains("\"latency\"")
+    }
+
+    fn parse_stream(&self, reader: &mut dyn Read, _options: &CsvOptions) -> Result<Vec<f64>> {
+        loadtest::read_vegeta_json(&read_all(reader)?)
+    }
+}
+
+/// wrk2 `--latency` report
+struct Wrk;
+
+impl Format for Wrk {
+    fn name(&self) -> &str {
+        "wrk"
+    }
+
+    fn sniff(&self, head: &[u8]) -> bool {
+        let line = first_line(head);
+        line.starts_with("Running ") && line.contains(" test @ ")
+    }
+
+    fn parse_stream(&self, reader: &mut dyn Read, _options: &CsvOptions) -> Result<Vec<f64>> {
+        loadtest::read_wrk_latency(&read_all(reader)?)
+    }
+}
+
+/// OpenTelemetry OTLP/JSON metrics holding exponential histograms
+struct Otel;
+
+impl Format for Otel {
+    fn name(&self) -> &str {
+        "otel"
+    }
+
+    // Checked before k6 and vegeta, whose sniffing a metric named `latency`
+    // could satisfy
+    fn sniff(&self, head: &[u8]) -> bool {
+        let head = String::from_utf8_lossy(head);
+        head.trim_start().starts_with('{') && head.contains("\"resourceMetrics\"")
+    }
+
+    fn parse_stream(&self, reader: &mut dyn Read, _options: &CsvOptions) -> Result<Vec<f64>> {
+        otel::read_exponential_histograms(&read_all(reader)?)
+    }
+}
@@ -0,0 +1,68 @@
+use anyhow::{Context, Result};
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD;
+use hdrhistogram::Histogram;
+use hdrhistogram::serialization::{Deserializer, Serializer, V2DeflateSerializer};
+
+/// Significant decimal digits kept when encoding values into a histogram
+const SIGNIFICANT_DIGITS: u8 = 3;
+
+/// Maximum number of values expanded from an imported histogram
+const MAX_VALUES: u64 = 10_000_000; // 10 million
+
+/// Encode values as an HdrHistogram in the compressed base64 interchange format
+///
+/// This is the format produced by `encodeIntoCompressedByteBuffer` in the Java
+/// implementation and understood by wrk2, Gatling and HdrHistogram log tools.
+/// Values are rounded to the nearest integer (use integral units such as
+/// microseconds) and must be non-negative.
+///
+/// # Examples
+/// ```
+/// use outlier::{decode_hdr_histogram, encode_hdr_histogram};
+///
+/// let encoded = encode_hdr_histogram(&[1.0, 2.0, 2.0, 1000.0]).unwrap();
+/// assert_eq!(decode_hdr_histogram(&encoded).unwrap(), vec![1.0, 2.0, 2.0, 1000.0]);
+/// ```
+pub fn encode_hdr_histogram(values: &[f64]) -> Result<String> {
+    let mut histogram = Histogram::<u64>::new(SIGNIFICANT_DIGITS)?;
+    for &value in values {
+        if !value.is_finite() || value < 0.0 {
+            anyhow::bail!("HDR histograms can only hold non-negative values, got `{value}`");
+        }
+        histogram.record(value.round() as u64)?;
+    }
+
+    let mut bytes = Vec::new();
+    V2DeflateSerializer::new()
+        .serialize(&histogram, &mut bytes)
+        .map_err(|e| anyhow::anyhow!("Failed to encode HDR histogram: {e:?}"))?;
+    Ok(STANDARD.encode(bytes))
+}
+
+/// Decode a compressed base64 HdrHistogram back into a value stream
+///
+/// Each recorded value is repeated by its count, at the histogram's
+/// highest-equivalent value for its bucket.
+pub fn decode_hdr_histogram(encoded: &str) -> Result<Vec<f64>> {
+    let bytes = STANDARD
+        .decode(encoded.trim())
+        .context("Failed to parse HDR histogram: invalid base64")?;
+    let histogram: Histogram<u64> = Deserializer::new()
+        .deserialize(&mut bytes.as_slice())
+        .map_err(|e| anyhow::anyhow!("Failed to parse HDR histogram: {e:?}"))?;
+
+    if histogram.len() > MAX_VALUES {
+        anyhow::bail!(
+            "Input dataset exceeds the limit of {} values. Aborting.",
+            MAX_VALUES
+        );
+    }
+
+    let mut values = Vec::with_capacity(histogram.len() as usize);
+    for bucket in histogram.iter_recorded() {
+        let value = bucket.value_iterated_to() as f64;
+        values.extend(std::iter::repeat_n(value, bucket.count_at_value() as usize));
+    }
+    Ok(values)
+}
@@ -0,0 +1,274 @@
+use axum::body::{Body, Bytes};
+use axum::http::{HeaderMap, HeaderValue, Method, StatusCode, header};
+use axum::response::Response;
+use std::collections::HashMap;
+use std::hash::{DefaultHasher, Hash, Hasher};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// Header clients send to make a request safely retryable
+pub const IDEMPOTENCY_KEY_HEADER: &str = "idempotency-key";
+
+/// Header set on responses replayed from the idempotency store
+pub const REPLAYED_HEADER: &str = "idempotent-replayed";
+
+/// Maximum accepted length of an idempotency key
+pub const MAX_KEY_LEN: usize = 255;
+
+/// Largest response body stored for replay; bigger responses release their key
+pub const MAX_STORED_BYTES: usize = 1024 * 1024;
+
+/// A response captured for replay
+#[derive(Clone)]
+pub struct StoredResponse {
+    pub status: StatusCode,
+    pub content_type: Option<HeaderValue>,
+    pub body: Bytes,
+}
+
+impl StoredResponse {
+    /// Rebuild the response, marking it as a replay
+    pub fn to_response(&self) -> Response {
+        let mut response = Response::new(Body::from(self.body.clone()));
+        *response.status_mut() = self.status;
+        if let Some(ref content_type) = self.content_type {
+            response
+                .headers_mut()
+                .insert(header::CONTENT_TYPE, content_type.clone());
+        }
+        response
+            .headers_mut()
+            .insert(REPLAYED_HEADER, HeaderValue::from_static("true"));
+        response
+    }
+}
+
+enum EntryState {
+    InFlight,
+    Completed(StoredResponse),
+}
+
+struct Entry {
+    fingerprint: u64,
+    state: EntryState,
+    created_at: Instant,
+}
+
+/// Outcome of reserving an idempotency key for a request
+pub enum Reservation<'a> {
+    /// First time this key is seen; the caller must run the request and `complete` it
+    New(Pending<'a>),
+    /// The same request already completed; replay its response
+    Replay(StoredResponse),
+    /// The same key is still being processed by another request
+    InFlight,
+    /// The key was used before with a different request
+    Mismatch,
+    /// The store is full of unexpired entries; run the request without caching
+    Full,
+}
+
+/// In-memory store of responses keyed by client-supplied idempotency keys
+pub struct IdempotencyStore {
+    entries: Mutex<HashMap<String, Entry>>,
+    ttl: Duration,
+    max_entries: usize,
+}
+
+impl IdempotencyStore {
+    pub fn new(ttl: Duration, max_entries: usize) -> Self {
+        Self {
+            entries: Mutex::new(HashMap::new()),
+            ttl,
+            max_entries,
+        }
+    }
+
+    /// Reserve `key` for a request with the given fingerprint
+    pub fn reserve(&self, key: &str, fingerprint: u64) -> Reservation<'_> {
+        let mut entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+
+        if let Some(entry) = entries.get(key) {
+            if entry.created_at.elapsed() <= self.ttl {
+                if entry.fingerprint != fingerprint {
+                    return Reservation::Mismatch;
+                }
+                return match entry.state {
+                    EntryState::InFlight => Reservation::InFlight,
+                    EntryState::Completed(ref stored) => Reservation::Replay(stored.clone()),
+                };
+            }
+            entries.remove(key);
+        }
+
+        if entries.len() >= self.max_entries {
+            let ttl = self.ttl;
+            entries.retain(|_, e| e.created_at.elapsed() <= ttl);
+            if entries.len() >= self.max_entries {
+                return Reservation::Full;
+            }
+        }
+
+        entries.insert(
+            key.to_string(),
+            Entry {
+                fingerprint,
+                state: EntryState::InFlight,
+                created_at: Instant::now(),
+            },
+        );
+        Reservation::New(Pending {
+            store: self,
+            key: key.to_string(),
+            completed: false,
+        })
+    }
+
+    /// Store the response for a reserved key, or release it if the response should not be replayed
+    fn complete(&self, key: &str, response: Option<StoredResponse>) {
+        let mut entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+        match response {
+            Some(stored) => {
+                if let Some(entry) = entries.get_mut(key) {
+                    entry.state = EntryState::Completed(stored);
+                }
+            }
+            None => {
+                entries.remove(key);
+            }
+        }
+    }
+}
+
+/// A key reserved for a running request
+///
+/// Dropping it without [`complete`](Self::complete), as happens when the
+/// client disconnects or the request times out, releases the key, so a retry
+/// runs instead of getting a conflict until the key expires.
+pub struct Pending<'a> {
+    store: &'a IdempotencyStore,
+    key: String,
+    completed: bool,
+}
+
+impl Pending<'_> {
+    /// Store the response for replay, or release the key when it is `None`
+    pub fn complete(mut self, response: Option<StoredResponse>) {
+        self.store.complete(&self.key, response);
+        self.completed = true;
+    }
+}
+
+impl Drop for Pending<'_> {
+    fn drop(&mut self) {
+        if !self.completed {
+            self.store.complete(&self.key, None);
+        }
+    }
+}
+
+/// Fingerprint a request so a reused key with a different payload is detected
+pub fn fingerprint(method: &Method, path: &str, body: &[u8]) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    method.as_str().hash(&mut hasher);
+    path.hash(&mut hasher);
+    body.hash(&mut hasher);
+    hasher.finish()
+}
+
+/// Scope the client key by credentials so different clients can't collide
+pub fn scoped_key(key: &str, headers: &HeaderMap) -> String {
+    let mut hasher = DefaultHasher::new();
+    for name in ["x-api-key", "authorization"] {
+        headers
+            .get(name)
+            .map(|v| v.as_bytes())
+            .unwrap_or_default()
+            .hash(&mut hasher);
+    }
+    format!("{:016x}:{key}", hasher.finish())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stored(body: &'static str) -> StoredResponse {
+        StoredResponse {
+            status: StatusCode::OK,
+            content_type: None,
+            body: Bytes::from_static(body.as_bytes()),
+        }
+    }
+
+    #[test]
+    fn first_reservation_is_new_then_in_flight() {
+        let store = IdempotencyStore::new(Duration::from_secs(60), 10);
+        let pending = store.reserve("k", 1);
+        assert!(matches!(pending, Reservation::New(_)));
+        assert!(matches!(store.reserve("k", 1), Reservation::InFlight));
+    }
+
+    #[test]
+    fn dropped_reservation_releases_the_key() {
+        let store = IdempotencyStore::new(Duration::from_secs(60), 10);
+        let pending = store.reserve("k", 1);
+        drop(pending);
+        assert!(matches!(store.reserve("k", 1), Reservation::New(_)));
+    }
+
+    fn new(reservation: Reservation<'_>) -> Pending<'_> {
+        match reservation {
+            Reservation::New(pending) => pending,
+            _ => panic!("expected a new reservation"),
+        }
+    }
+
+    #[test]
+    fn completed_reservation_replays() {
+        let store = IdempotencyStore::new(Duration::from_secs(60), 10);
+        new(store.reserve("k", 1)).complete(Some(stored("hello")));
+        match store.reserve("k", 1) {
+            Reservation::Replay(r) => assert_eq!(r.body, "hello"),
+            _ => panic!("expected replay"),
+        }
+    }
+
+    #[test]
+    fn reused_key_with_different_fingerprint_is_mismatch() {
+        let store = IdempotencyStore::new(Duration::from_secs(60), 10);
+        new(store.reserve("k", 1)).complete(Some(stored("hello")));
+        assert!(matches!(store.reserve("k", 2), Reservation::Mismatch));
+    }
+
+    #[test]
+    fn released_key_can_be_retried() {
+        let store = IdempotencyStore::new(Duration::from_secs(60), 10);
+        new(store.reserve("k", 1)).complete(None);
+        assert!(matches!(store.reserve("k", 1), Reservation::New(_)));
+    }
+
+    #[test]
+    fn expired_entries_are_replaced() {
+        let store = IdempotencyStore::new(Duration::ZERO, 10);
+        new(store.reserve("k", 1)).complete(Some(stored("old")));
+        std::thread::sleep(Duration::from_millis(2));
+        assert!(matches!(store.reserve("k", 2), Reservation::New(_)));
+    }
+
+    #[test]
+    fn full_store_skips_caching() {
+        let store = IdempotencyStore::new(Duration::from_secs(60), 1);
+        let _pending = store.reserve("a", 1);
+        assert!(matches!(store.reserve("b", 1), Reservation::Full));
+    }
+
+    #[test]
+    fn scoped_key_differs_per_credential() {
+        let mut a = HeaderMap::new();
+        a.insert("x-api-key", HeaderValue::from_static("one"));
+        let mut b = HeaderMap::new();
+        b.insert("x-api-key", HeaderValue::from_static("two"));
+        assert_ne!(scoped_key("k", &a), scoped_key("k", &b));
+    }
+}
@@ -0,0 +1,211 @@
+use crate::coerce::{looks_like_timestamp, parse_duration_ms};
+use crate::{
+    CsvOptions, InputFormat, parse_localized_number, read_values_from_bytes_with_format,
+    resolve_input_format,
+};
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+
+#[cfg(feature = "server")]
+use utoipa::ToSchema;
+
+/// Inferred type of an input column
+#[cfg_attr(feature = "server", derive(ToSchema))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ColumnKind {
+    /// Whole numbers
+    Int,
+    /// Numbers with a fractional part, possibly mixed with whole numbers
+    Float,
+    /// Numbers with a time unit, e.g. `250ms` or `1.5s`
+    Duration,
+    /// Dates or date-times, e.g. `2024-03-01T10:00:00Z`
+    Timestamp,
+    /// Anything else
+    String,
+    /// Cells of more than one kind
+    Mixed,
+    /// Every cell is empty
+    Empty,
+}
+
+impl std::fmt::Display for ColumnKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ColumnKind::Int => write!(f, "int"),
+            ColumnKind::Float => write!(f, "float"),
+            ColumnKind::Duration => write!(f, "duration"),
+            ColumnKind::Timestamp => write!(f, "timestamp"),
+            ColumnKind::String => write!(f, "string"),
+            ColumnKind::Mixed => write!(f, "mixed"),
+            ColumnKind::Empty => write!(f, "empty"),
+        }
+    }
+}
+
+impl ColumnKind {
+    /// Infer the kind of a single cell
+    fn of_cell(cell: &str, options: &CsvOptions) -> ColumnKind {
+        let cell = cell.trim();
+        if cell.is_empty() {
+            ColumnKind::Empty
+        } else if let Some(value) = parse_localized_number(cell, options) {
+            let fractional = cell.contains(options.decimal_separator)
+                || cell.contains(['e', 'E'])
+                || !value.is_finite();
+            if fractional {
+                ColumnKind::Float
+            } else {
+                ColumnKind::Int
+            }
+        } else if parse_duration_ms(cell, options).is_some() {
+            ColumnKind::Duration
+        } else if looks_like_timestamp(cell) {
+            ColumnKind::Timestamp
+        } else {
+            ColumnKind::String
+        }
+    }
+
+    fn merge(self, cell: ColumnKind) -> ColumnKind {
+        match (self, cell) {
+            (ColumnKind::Empty, kind) | (kind, ColumnKind::Empty) => kind,
+            (a, b) if a == b => a,
+            (ColumnKind::Int, ColumnKind::Float) | (ColumnKind::Float, ColumnKind::Int) => {
+                ColumnKind::Float
+            }
+            _ => ColumnKind::Mixed,
+        }
+    }
+}
+
+/// A column of the input and its inferred type
+#[cfg_attr(feature = "server", derive(ToSchema))]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ColumnSummary {
+    /// Column name (`value` for formats without a header)
+    pub name: String,
+    /// Inferred type of the raw cells
+    pub kind: ColumnKind,
+    /// Cells that aren't plain numbers but that the enabled coercion rules
+    /// turn into one
+    pub coerced: usize,
+}
+
+impl ColumnSummary {
+    /// A column of plain numbers, for formats without typed columns
+    fn numbers(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            kind: ColumnKind::Float,
+            coerced: 0,
+        }
+    }
+}
+
+/// What a run over an input would see, without computing anything
+#[cfg_attr(feature = "server", derive(ToSchema))]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct InputReport {
+    /// The format the input was read as (detected when `auto`)
+    pub format: InputFormat,
+    /// Number of values that would be used
+    pub count: usize,
+    /// Columns of the input; CSV reports every header, other formats a single column
+    pub columns: Vec<ColumnSummary>,
+    /// Problems that won't stop a run but may skew its results
+    pub warnings: Vec<String>,
+}
+
+/// Parse an input exactly as a calculation would and report on it
+///
+/// Parse errors are returned as errors, the same ones a real run would hit.
+pub fn inspect_input(
+    bytes: &[u8],
+    filename: &str,
+    format: InputFormat,
+    options: &CsvOptions,
+) -> Result<InputReport> {
+    let format = resolve_input_format(bytes, filename, format)?;
+    let values = read_values_from_bytes_with_format(bytes, filename, format, options)?;
+
+    let columns = match format {
+        InputFormat::Csv => csv_columns(bytes, options)?,
+        InputFormat::Json | InputFormat::Hdr => vec![ColumnSummary::numbers("value")],
+        _ => vec![ColumnSummary::numbers("latency_ms")],
+    };
+
+    let mut report = inspect_values(&values);
+    report
+        .warnings
+        .extend(columns.iter().filter(|c| c.coerced > 0).map(|c| {
+            format!(
+                "{} cell(s) in column '{}' were coerced to numbers",
+                c.coerced, c.name
+            )
+        }));
+    Ok(InputReport {
+        columns,
+        format,
+        ..report
+    })
+}
+
+/// Report on values that have already been parsed, e.g. from a JSON request body
+pub fn inspect_values(values: &[f64]) -> InputReport {
+    InputReport {
+        format: InputFormat::Json,
+        count: values.len(),
+        columns: vec![ColumnSummary::numbers("value")],
+        warnings: value_warnings(values),
+    }
+}
+
+fn csv_columns(bytes: &[u8], options: &CsvOptions) -> Result<Vec<ColumnSummary>> {
+    let mut reader = csv::ReaderBuilder::new()
+        .delimiter(options.delimiter)
+        .from_reader(bytes);
+    let mut columns: Vec<ColumnSummary> = reader
+        .headers()
+        .context("Failed to parse CSV record")?
+        .iter()
+        .map(|name| ColumnSummary {
+            name: name.to_string(),
+            kind: ColumnKind::Empty,
+            coerced: 0,
+        })
+        .collect();
+
+    for record in reader.records() {
+        let record = record.context("Failed to parse CSV record")?;
+        for (column, cell) in columns.iter_mut().zip(record.iter()) {
+            let kind = ColumnKind::of_cell(cell, options);
+            if !matches!(
+                kind,
+                ColumnKind::Int | ColumnKind::Float | ColumnKind::Empty
+            ) && options.coerce.apply(cell, options).is_some()
+            {
+                column.coerced += 1;
+            }
+            column.kind = column.kind.merge(kind);
+        }
+    }
+    Ok(columns)
+}
+
+fn value_warnings(values: &[f64]) -> Vec<String> {
+    let mut warnings = Vec::new();
+    if values.is_empty() {
+        warnings.push("No values found; a calculation would fail".to_string());
+    }
+    let non_finite = values.iter().filter(|v| !v.is_finite()).count();
+    if non_finite > 0 {
+        warnings.push(format!("{non_finite} value(s) are NaN or infinite"));
+    }
+    let negative = values.iter().filter(|v| **v < 0.0).count();
+    if negative > 0 {
+        warnings.push(format!("{negative} value(s) are negative"));
+    }
+    warnings
+}
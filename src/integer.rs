@@ -0,0 +1,159 @@
+use crate::{CsvOptions, InputFormat, PercentileMethod, bankers_round, percentile_index};
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+const MAX_VALUES: usize = 10_000_000; // 10 million
+
+/// A percentile of integer data such as queue depths or request counts
+///
+/// Methods that pick one of the values (`lower`, `upper`, `nearest_rank`,
+/// `nearest_even`) always give `Exact`, so choosing one disables
+/// interpolation. `linear` and `midpoint` give `Exact` whenever the result is
+/// a whole number, so equal neighbours never come out as `2.9999999`.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum IntegerPercentile {
+    Exact(i64),
+    /// Falls between two values
+    Interpolated(f64),
+}
+
+impl IntegerPercentile {
+    /// The result if it is a whole number
+    pub fn exact(self) -> Option<i64> {
+        match self {
+            IntegerPercentile::Exact(value) => Some(value),
+            IntegerPercentile::Interpolated(_) => None,
+        }
+    }
+
+    pub fn as_f64(self) -> f64 {
+        match self {
+            IntegerPercentile::Exact(value) => value as f64,
+            IntegerPercentile::Interpolated(value) => value,
+        }
+    }
+}
+
+impl fmt::Display for IntegerPercentile {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            IntegerPercentile::Exact(value) => write!(f, "{value}"),
+            IntegerPercentile::Interpolated(value) => value.fmt(f),
+        }
+    }
+}
+
+/// Calculate a percentile of integers without converting them to floats
+///
+/// Uses the same ranks as [`calculate_percentile`](crate::calculate_percentile);
+/// only results that fall between two values are floats.
+///
+/// # Examples
+/// ```
+/// use outlier::{IntegerPercentile, PercentileMethod, calculate_integer_percentile};
+///
+/// let depths = [3, 3, 3, 3, 7];
+/// let p = calculate_integer_percentile(&depths, 30.0, PercentileMethod::Linear).unwrap();
+/// assert_eq!(p, IntegerPercentile::Exact(3));
+/// let p = calculate_integer_percentile(&depths, 90.0, PercentileMethod::Linear).unwrap();
+/// assert_eq!(p, IntegerPercentile::Interpolated(5.4));
+/// ```
+pub fn calculate_integer_percentile(
+    values: &[i64],
+    percentile: f64,
+    method: PercentileMethod,
+) -> Result<IntegerPercentile> {
+    if values.is_empty() {
+        anyhow::bail!("Cannot calculate percentile of empty dataset");
+    }
+    if !(0.0..=100.0).contains(&percentile) {
+        anyhow::bail!("Percentile must be between 0 and 100");
+    }
+
+    let mut sorted = values.to_vec();
+    sorted.sort_unstable();
+    let index = percentile_index(percentile, sorted.len());
+    let lower = sorted[index.floor() as usize];
+    let upper = sorted[index.ceil() as usize];
+
+    Ok(match method {
+        PercentileMethod::Linear => {
+            // Weigh in hundredths of a rank, so whole percentiles give offsets
+            // like 5 * 60 / 100 that come out exact
+            let hundredths = percentile * (sorted.len() - 1) as f64 - index.floor() * 100.0;
+            let offset = (upper as i128 - lower as i128) as f64 * hundredths / 100.0;
+            if lower == upper {
+                IntegerPercentile::Exact(lower)
+            } else if offset.fract() == 0.0 {
+                IntegerPercentile::Exact((lower as i128 + offset as i128) as i64)
+            } else {
+                IntegerPercentile::Interpolated(lower as f64 + offset)
+            }
+        }
+        PercentileMethod::Midpoint => {
+            let sum = lower as i128 + upper as i128;
+            if sum % 2 == 0 {
+                IntegerPercentile::Exact((sum / 2) as i64)
+            } else {
+                IntegerPercentile::Interpolated(sum as f64 / 2.0)
+            }
+        }
+        PercentileMethod::NearestRank => IntegerPercentile::Exact(sorted[index.round() as usize]),
+        PercentileMethod::Lower => IntegerPercentile::Exact(lower),
+        PercentileMethod::Upper => IntegerPercentile::Exact(upper),
+        PercentileMethod::NearestEven => {
+            IntegerPercentile::Exact(sorted[bankers_round(index) as usize])
+        }
+    })
+}
+
+/// Parse integers from a JSON array or the "value" column of a CSV
+///
+/// Values must be plain integers; anything with a fraction or exponent is
+/// rejected rather than rounded. Only the CSV delimiter is taken from `options`.
+pub fn read_integers_from_bytes(
+    bytes: &[u8],
+    filename: &str,
+    options: &CsvOptions,
+) -> Result<Vec<i64>> {
+    let values: Vec<i64> = match crate::resolve_input_format(bytes, filename, InputFormat::Auto)? {
+        InputFormat::Json => serde_json::from_slice(bytes)
+            .context("Failed to parse JSON. Expected array of integers.")?,
+        InputFormat::Csv => {
+            let mut reader = csv::ReaderBuilder::new()
+                .delimiter(options.delimiter)
+                .from_reader(bytes);
+            let column = reader
+                .headers()
+                .context("Failed to parse CSV record")?
+                .iter()
+                .position(|h| h == "value")
+                .context("Failed to parse CSV record: missing \"value\" header")?;
+            reader
+                .records()
+                .enumerate()
+                .map(|(row, record)| {
+                    let record = record.context("Failed to parse CSV record")?;
+                    let field = record.get(column).unwrap_or_default();
+                    field.trim().parse().with_context(|| {
+                        format!(
+                            "Failed to parse CSV record {}: invalid integer {:?}",
+                            row + 1,
+                            field
+                        )
+                    })
+                })
+                .collect::<Result<_>>()?
+        }
+        format => anyhow::bail!("Integer input must be JSON or CSV, not {format}"),
+    };
+    if values.len() > MAX_VALUES {
+        anyhow::bail!(
+            "Input dataset exceeds the limit of {} values. Aborting.",
+            MAX_VALUES
+        );
+    }
+    Ok(values)
+}
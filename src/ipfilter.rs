@@ -0,0 +1,130 @@
+use axum::http::HeaderMap;
+use ipnet::IpNet;
+use std::net::IpAddr;
+
+/// CIDR-based allow/deny lists with trusted-proxy aware client IP resolution
+#[derive(Debug, Clone, Default)]
+pub struct IpFilter {
+    allow: Vec<IpNet>,
+    deny: Vec<IpNet>,
+    trusted_proxies: Vec<IpNet>,
+}
+
+impl IpFilter {
+    pub fn new(allow: Vec<IpNet>, deny: Vec<IpNet>, trusted_proxies: Vec<IpNet>) -> Self {
+        Self {
+            allow,
+            deny,
+            trusted_proxies,
+        }
+    }
+
+    /// Whether the filter has any allow or deny rules
+    pub fn is_active(&self) -> bool {
+        !self.allow.is_empty() || !self.deny.is_empty()
+    }
+
+    /// Resolve the client address for a connection from `peer`
+    ///
+    /// `X-Forwarded-For` is only honoured when the peer is a trusted proxy. The
+    /// header is walked right to left, skipping further trusted proxies, so a
+    /// client can't spoof its address by prepending entries.
+    pub fn client_ip(&self, peer: IpAddr, headers: &HeaderMap) -> IpAddr {
+        let peer = peer.to_canonical();
+        if !self.is_trusted_proxy(peer) {
+            return peer;
+        }
+
+        let forwarded = headers
+            .get_all("x-forwarded-for")
+            .iter()
+            .filter_map(|v| v.to_str().ok())
+            .flat_map(|v| v.split(','))
+            .map(str::trim)
+            .collect::<Vec<_>>();
+
+        let mut client = peer;
+        for hop in forwarded.iter().rev() {
+            let Ok(ip) = hop.parse::<IpAddr>() else {
+                break;
+            };
+            client = ip.to_canonical();
+            if !self.is_trusted_proxy(client) {
+                break;
+            }
+        }
+        client
+    }
+
+    /// Deny rules win; an empty allow list allows everything not denied
+    pub fn is_allowed(&self, ip: IpAddr) -> bool {
+        let ip = ip.to_canonical();
+        if self.deny.iter().any(|net| net.contains(&ip)) {
+            return false;
+        }
+        self.allow.is_empty() || self.allow.iter().any(|net| net.contains(&ip))
+    }
+
+    fn is_trusted_proxy(&self, ip: IpAddr) -> bool {
+        self.trusted_proxies.iter().any(|net| net.contains(&ip))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::http::HeaderValue;
+
+    fn nets(cidrs: &[&str]) -> Vec<IpNet> {
+        cidrs.iter().map(|c| c.parse().unwrap()).collect()
+    }
+
+    fn ip(s: &str) -> IpAddr {
+        s.parse().unwrap()
+    }
+
+    fn forwarded(value: &'static str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert("x-forwarded-for", HeaderValue::from_static(value));
+        headers
+    }
+
+    #[test]
+    fn empty_filter_allows_everything() {
+        let filter = IpFilter::default();
+        assert!(!filter.is_active());
+        assert!(filter.is_allowed(ip("203.0.113.7")));
+    }
+
+    #[test]
+    fn deny_wins_over_allow() {
+        let filter = IpFilter::new(nets(&["10.0.0.0/8"]), nets(&["10.0.5.0/24"]), vec![]);
+        assert!(filter.is_allowed(ip("10.1.2.3")));
+        assert!(!filter.is_allowed(ip("10.0.5.9")));
+        assert!(!filter.is_allowed(ip("192.168.1.1")));
+    }
+
+    #[test]
+    fn ipv4_mapped_ipv6_matches_ipv4_rules() {
+        let filter = IpFilter::new(nets(&["10.0.0.0/8"]), vec![], vec![]);
+        assert!(filter.is_allowed(ip("::ffff:10.1.2.3")));
+    }
+
+    #[test]
+    fn forwarded_for_ignored_from_untrusted_peer() {
+        let filter = IpFilter::new(vec![], vec![], nets(&["127.0.0.1/32"]));
+        let client = filter.client_ip(ip("198.51.100.4"), &forwarded("10.0.0.1"));
+        assert_eq!(client, ip("198.51.100.4"));
+    }
+
+    #[test]
+    fn forwarded_for_walks_past_trusted_proxies() {
+        let filter = IpFilter::new(vec![], vec![], nets(&["127.0.0.1/32", "172.16.0.0/12"]));
+        // Spoofed first entry is ignored; the nearest untrusted hop is the client
+        let client = filter.client_ip(
+            ip("127.0.0.1"),
+            &forwarded("10.9.9.9, 203.0.113.50, 172.16.0.2"),
+        );
+        assert_eq!(client, ip("203.0.113.50"));
+    }
+}
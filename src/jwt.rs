@@ -13,6 +13,7 @@ pub enum JwtError {
     FetchError(String),
     ValidationError(String),
     InvalidHeader(String),
+    DiscoveryError(String),
 }
 
 impl std::fmt::Display for JwtError {
@@ -23,6 +24,7 @@ impl std::fmt::Display for JwtError {
             JwtError::FetchError(msg) => write!(f, "JWKS fetch error: {msg}"),
             JwtError::ValidationError(msg) => write!(f, "JWT validation error: {msg}"),
             JwtError::InvalidHeader(msg) => write!(f, "Invalid JWT header: {msg}"),
+            JwtError::DiscoveryError(msg) => write!(f, "OIDC discovery error: {msg}"),
         }
     }
 }
@@ -36,6 +38,32 @@ pub struct Claims {
     pub iss: Option<String>,
     pub exp: Option<u64>,
     pub iat: Option<u64>,
+    /// Space-separated OAuth scopes
+    pub scope: Option<String>,
+}
+
+/// Where the JWKS signing keys are fetched from
+#[derive(Debug, Clone)]
+pub enum JwksSource {
+    /// Fixed JWKS endpoint
+    Url(String),
+    /// OIDC discovery document whose `jwks_uri` points at the JWKS endpoint
+    Discovery(String),
+}
+
+impl JwksSource {
+    /// Discovery document location for an issuer (`{issuer}/.well-known/openid-configuration`)
+    pub fn discovery_for_issuer(issuer: &str) -> Self {
+        let issuer = issuer.trim_end_matches('/');
+        Self::Discovery(format!("{issuer}/.well-known/openid-configuration"))
+    }
+}
+
+/// The subset of an OIDC discovery document used to locate signing keys
+#[derive(Debug, serde::Deserialize)]
+struct OidcDiscovery {
+    issuer: String,
+    jwks_uri: String,
 }
 
 struct CachedKeys {
@@ -45,7 +73,7 @@ struct CachedKeys {
 }
 
 pub struct JwksCache {
-    jwks_url: String,
+    source: JwksSource,
     issuer: String,
     audience: String,
     algorithms: Vec<Algorithm>,
@@ -56,14 +84,14 @@ pub struct JwksCache {
 
 impl JwksCache {
     pub fn new(
-        jwks_url: String,
+        source: JwksSource,
         issuer: String,
         audience: String,
         algorithms: Vec<Algorithm>,
         ttl: Duration,
     ) -> Self {
         Self {
-            jwks_url,
+            source,
             issuer,
             audience,
             algorithms,
@@ -139,11 +167,12 @@ impl JwksCache {
     }
 
     pub async fn refresh_keys(&self) -> Result<(), JwtError> {
-        debug!(jwks_url = %self.jwks_url, "Refreshing JWKS keys");
+        let jwks_url = self.resolve_jwks_url().await?;
+        debug!(jwks_url = %jwks_url, "Refreshing JWKS keys");
 
         let response = self
             .http_client
-            .get(&self.jwks_url)
+            .get(&jwks_url)
             .send()
             .await
             .map_err(|e| JwtError::FetchError(e.to_string()))?;
@@ -164,12 +193,43 @@ impl JwksCache {
     }
 }
 
+impl JwksCache {
+    /// Resolve the JWKS endpoint, re-reading the discovery document on every
+    /// refresh so a provider moving its `jwks_uri` is picked up with the keys
+    async fn resolve_jwks_url(&self) -> Result<String, JwtError> {
+        let discovery_url = match &self.source {
+            JwksSource::Url(url) => return Ok(url.clone()),
+            JwksSource::Discovery(url) => url,
+        };
+        debug!(discovery_url = %discovery_url, "Fetching OIDC discovery document");
+
+        let discovery: OidcDiscovery = self
+            .http_client
+            .get(discovery_url)
+            .send()
+            .await
+            .map_err(|e| JwtError::DiscoveryError(e.to_string()))?
+            .json()
+            .await
+            .map_err(|e| JwtError::DiscoveryError(e.to_string()))?;
+
+        if discovery.issuer.trim_end_matches('/') != self.issuer.trim_end_matches('/') {
+            return Err(JwtError::DiscoveryError(format!(
+                "issuer mismatch: expected {}, discovery document has {}",
+                self.issuer, discovery.issuer
+            )));
+        }
+
+        Ok(discovery.jwks_uri)
+    }
+}
+
 #[cfg(test)]
 impl JwksCache {
     pub fn with_test_jwks(jwks: JwkSet, issuer: String, audience: String) -> Self {
         let now = Instant::now();
         Self {
-            jwks_url: String::new(),
+            source: JwksSource::Url(String::new()),
             issuer,
             audience,
             algorithms: vec![Algorithm::RS256],
@@ -334,4 +394,63 @@ M9LEGJLcpr1rIhS7lm02vRk=
         let cache = test_cache();
         assert!(cache.validate_token("not.a.jwt").await.is_err());
     }
+
+    /// Serve a discovery document and JWKS from a local server, returning the issuer URL
+    async fn spawn_oidc_provider(issuer_override: Option<&'static str>) -> String {
+        use axum::{Json, Router, routing::get};
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let base = format!("http://{}", listener.local_addr().unwrap());
+        let issuer = issuer_override.map(str::to_string).unwrap_or(base.clone());
+        let jwks_uri = format!("{base}/keys");
+        let app = Router::new()
+            .route(
+                "/.well-known/openid-configuration",
+                get(move || async move {
+                    Json(serde_json::json!({ "issuer": issuer, "jwks_uri": jwks_uri }))
+                }),
+            )
+            .route(
+                "/keys",
+                get(|| async {
+                    serde_json::from_str::<serde_json::Value>(TEST_JWKS_JSON)
+                        .map(Json)
+                        .unwrap()
+                }),
+            );
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+        base
+    }
+
+    #[tokio::test]
+    async fn discovery_resolves_jwks_uri_and_validates() {
+        let issuer = spawn_oidc_provider(None).await;
+        let cache = JwksCache::new(
+            JwksSource::discovery_for_issuer(&issuer),
+            issuer.clone(),
+            TEST_AUDIENCE.to_string(),
+            vec![Algorithm::RS256],
+            Duration::from_secs(3600),
+        );
+        cache.refresh_keys().await.unwrap();
+
+        let mut claims = valid_claims();
+        claims["iss"] = serde_json::json!(issuer);
+        let token = make_test_jwt(&claims, "test-key-1");
+        assert!(cache.validate_token(&token).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn discovery_rejects_issuer_mismatch() {
+        let issuer = spawn_oidc_provider(Some("https://other.example.com/")).await;
+        let cache = JwksCache::new(
+            JwksSource::discovery_for_issuer(&issuer),
+            issuer,
+            TEST_AUDIENCE.to_string(),
+            vec![Algorithm::RS256],
+            Duration::from_secs(3600),
+        );
+        let err = cache.refresh_keys().await.unwrap_err();
+        assert!(matches!(err, JwtError::DiscoveryError(_)));
+    }
 }
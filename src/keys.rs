@@ -0,0 +1,274 @@
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use std::sync::RwLock;
+use utoipa::ToSchema;
+
+use crate::config::{Scope, ScopedApiKeyConfig};
+
+/// Prefix on generated keys so they are recognisable in logs and secret scanners
+const GENERATED_KEY_PREFIX: &str = "olk_";
+
+/// Constant-time comparison to prevent timing attacks on API key validation
+pub fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    if a.len() != b.len() {
+        return false;
+    }
+    let mut result = 0u8;
+    for (x, y) in a.iter().zip(b.iter()) {
+        result |= x ^ y;
+    }
+    result == 0
+}
+
+/// Scopes granted to the authenticated caller, stored in request extensions
+#[derive(Debug, Clone)]
+pub struct GrantedScopes(pub Vec<Scope>);
+
+impl GrantedScopes {
+    /// Scopes granted when authentication is disabled
+    ///
+    /// Admin is left out: draining, key management and the debug routes
+    /// always need a credential.
+    pub fn unauthenticated() -> Self {
+        Self(vec![Scope::Calculate, Scope::DatasetsWrite])
+    }
+
+    /// Scopes for a JWT: `calculate` plus any known scopes in its `scope` claim
+    pub fn from_jwt_scope(scope: Option<&str>) -> Self {
+        let mut scopes = vec![Scope::Calculate];
+        scopes.extend(
+            scope
+                .unwrap_or_default()
+                .split_whitespace()
+                .filter_map(|s| {
+                    serde_json::from_value::<Scope>(serde_json::Value::String(s.to_string())).ok()
+                }),
+        );
+        Self(scopes)
+    }
+
+    pub fn allows(&self, scope: Scope) -> bool {
+        self.0.contains(&scope) || self.0.contains(&Scope::Admin)
+    }
+}
+
+/// Where a key was defined
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum KeySource {
+    /// `auth.api_keys`, `auth.scoped_keys` or `OUTLIER_API_KEYS`
+    Config,
+    /// Created through `POST /admin/keys`
+    Runtime,
+}
+
+/// Key metadata returned by the management API (never includes the secret)
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct ApiKeyInfo {
+    pub id: String,
+    pub scopes: Vec<Scope>,
+    pub source: KeySource,
+}
+
+/// Request body for creating a key at runtime
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct CreateKeyRequest {
+    /// Scopes to grant the new key
+    pub scopes: Vec<Scope>,
+}
+
+/// A newly created key; the secret is only ever returned here
+#[derive(Debug, Serialize, ToSchema)]
+pub struct CreatedKey {
+    pub id: String,
+    pub key: String,
+    pub scopes: Vec<Scope>,
+}
+
+struct ApiKey {
+    info: ApiKeyInfo,
+    secret: String,
+}
+
+/// In-memory set of API keys with their scopes
+///
+/// Runtime changes are not persisted and are lost on restart.
+#[derive(Default)]
+pub struct ApiKeyStore {
+    keys: RwLock<Vec<ApiKey>>,
+}
+
+impl ApiKeyStore {
+    /// Build the store from unscoped keys (granted `calculate`) and scoped keys
+    pub fn from_config(plain: &[String], scoped: &[ScopedApiKeyConfig]) -> Self {
+        let plain = plain.iter().enumerate().map(|(i, key)| ApiKey {
+            info: ApiKeyInfo {
+                id: format!("key-{}", i + 1),
+                scopes: vec![Scope::Calculate],
+                source: KeySource::Config,
+            },
+            secret: key.clone(),
+        });
+        let scoped = scoped.iter().map(|k| ApiKey {
+            info: ApiKeyInfo {
+                id: k.id.clone(),
+                scopes: k.scopes.clone(),
+                source: KeySource::Config,
+            },
+            secret: k.key.clone(),
+        });
+        Self {
+            keys: RwLock::new(plain.chain(scoped).collect()),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.keys.read().unwrap_or_else(|e| e.into_inner()).len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Scopes for `candidate`, or `None` if it matches no key
+    pub fn authenticate(&self, candidate: &str) -> Option<GrantedScopes> {
+        let keys = self.keys.read().unwrap_or_else(|e| e.into_inner());
+        // Compare against every key so timing doesn't reveal which one matched
+        let mut granted = None;
+        for key in keys.iter() {
+            if constant_time_eq(candidate.as_bytes(), key.secret.as_bytes()) {
+                granted = Some(GrantedScopes(key.info.scopes.clone()));
+            }
+        }
+        granted
+    }
+
+    pub fn list(&self) -> Vec<ApiKeyInfo> {
+        let keys = self.keys.read().unwrap_or_else(|e| e.into_inner());
+        keys.iter().map(|k| k.info.clone()).collect()
+    }
+
+    /// Generate a new random key with the given scopes
+    pub fn create(&self, scopes: Vec<Scope>) -> CreatedKey {
+        let mut rng = rand::rng();
+        let id = format!("rk-{}", hex(&rng.random::<[u8; 6]>()));
+        let key = format!("{GENERATED_KEY_PREFIX}{}", hex(&rng.random::<[u8; 32]>()));
+
+        self.keys
+            .write()
+            .unwrap_or_else(|e| e.into_inner())
+            .push(ApiKey {
+                info: ApiKeyInfo {
+                    id: id.clone(),
+                    scopes: scopes.clone(),
+                    source: KeySource::Runtime,
+                },
+                secret: key.clone(),
+            });
+
+        CreatedKey { id, key, scopes }
+    }
+
+    /// Remove the key with `id`, returning whether it existed
+    pub fn revoke(&self, id: &str) -> bool {
+        let mut keys = self.keys.write().unwrap_or_else(|e| e.into_inner());
+        let before = keys.len();
+        keys.retain(|k| k.info.id != id);
+        keys.len() != before
+    }
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{b:02x}")).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // --- constant_time_eq tests ---
+
+    #[test]
+    fn constant_time_eq_equal_strings() {
+        assert!(constant_time_eq(b"hello", b"hello"));
+    }
+
+    #[test]
+    fn constant_time_eq_different_strings() {
+        assert!(!constant_time_eq(b"hello", b"world"));
+    }
+
+    #[test]
+    fn constant_time_eq_different_lengths() {
+        assert!(!constant_time_eq(b"short", b"longer"));
+    }
+
+    #[test]
+    fn constant_time_eq_empty_strings() {
+        assert!(constant_time_eq(b"", b""));
+    }
+
+    // --- ApiKeyStore tests ---
+
+    fn store() -> ApiKeyStore {
+        ApiKeyStore::from_config(
+            &["plain".to_string()],
+            &[ScopedApiKeyConfig {
+                id: "ops".to_string(),
+                key: "ops-secret".to_string(),
+                scopes: vec![Scope::Admin],
+            }],
+        )
+    }
+
+    #[test]
+    fn plain_keys_get_calculate_scope() {
+        let granted = store().authenticate("plain").unwrap();
+        assert!(granted.allows(Scope::Calculate));
+        assert!(!granted.allows(Scope::Admin));
+    }
+
+    #[test]
+    fn admin_implies_every_scope() {
+        let granted = store().authenticate("ops-secret").unwrap();
+        assert!(granted.allows(Scope::Calculate));
+        assert!(granted.allows(Scope::DatasetsWrite));
+    }
+
+    #[test]
+    fn unknown_key_is_rejected() {
+        assert!(store().authenticate("nope").is_none());
+    }
+
+    #[test]
+    fn created_keys_authenticate_until_revoked() {
+        let store = store();
+        let created = store.create(vec![Scope::DatasetsWrite]);
+        assert!(created.key.starts_with(GENERATED_KEY_PREFIX));
+        assert!(
+            store
+                .authenticate(&created.key)
+                .unwrap()
+                .allows(Scope::DatasetsWrite)
+        );
+
+        assert!(store.revoke(&created.id));
+        assert!(store.authenticate(&created.key).is_none());
+        assert!(!store.revoke(&created.id));
+    }
+
+    #[test]
+    fn jwt_scope_claim_is_parsed() {
+        let granted = GrantedScopes::from_jwt_scope(Some("openid datasets:write"));
+        assert!(granted.allows(Scope::Calculate));
+        assert!(granted.allows(Scope::DatasetsWrite));
+        assert!(!granted.allows(Scope::Admin));
+    }
+
+    #[test]
+    fn list_omits_secrets() {
+        let json = serde_json::to_string(&store().list()).unwrap();
+        assert!(!json.contains("ops-secret"));
+        assert!(json.contains("\"ops\""));
+    }
+}
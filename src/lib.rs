@@ -4,16 +4,142 @@ use std::fmt;
 use std::fs::File;
 use std::io::BufReader;
 use std::path::Path;
+#[cfg(feature = "tracing")]
 use tracing::instrument;
 
 #[cfg(feature = "server")]
 use utoipa::ToSchema;
 
+pub mod accuracy;
+pub mod annotate;
+pub mod cancel;
+#[cfg(feature = "charts")]
+pub mod charts;
+pub mod coerce;
+pub mod compare;
+pub mod correlation;
+pub mod counts;
+pub mod describe;
+pub mod downsample;
+#[cfg(feature = "durations")]
+pub mod durations;
+pub mod engine;
+pub mod formats;
+pub mod inspect;
+pub mod integer;
+pub mod loadtest;
+pub mod otel;
+pub mod outliers;
+pub mod p2;
+#[cfg(feature = "pipeline")]
+pub mod pipeline;
+#[cfg(feature = "privacy")]
+pub mod privacy;
+pub mod report;
+pub mod stratify;
+pub mod timeseries;
+pub mod transform;
+#[cfg(feature = "webhook")]
+pub mod webhook;
+
+#[cfg(feature = "server")]
+mod array_stream;
+#[cfg(feature = "server")]
+pub mod config;
+#[cfg(feature = "server")]
+mod dataset_hash;
+#[cfg(feature = "test-endpoints")]
+mod debug;
+#[cfg(feature = "server")]
+mod drain;
+#[cfg(feature = "server")]
+mod fixtures;
+#[cfg(feature = "hdr")]
+mod hdr;
+#[cfg(feature = "server")]
+mod idempotency;
+#[cfg(feature = "server")]
+mod ipfilter;
+#[cfg(feature = "server")]
+mod jwt;
+#[cfg(feature = "server")]
+mod keys;
+#[cfg(feature = "mqtt")]
+mod mqtt;
+#[cfg(feature = "server")]
+mod redact;
+#[cfg(feature = "server")]
+pub mod runtime;
+#[cfg(feature = "server")]
+pub mod server;
+#[cfg(feature = "server")]
+mod signature;
+#[cfg(feature = "server")]
+mod sketches;
+#[cfg(feature = "server")]
+mod stats;
+#[cfg(feature = "server")]
+mod statsd;
+#[cfg(feature = "test-util")]
+pub mod test_support;
+#[cfg(feature = "server")]
+mod tls;
+#[cfg(feature = "ui")]
+mod ui;
+
+pub use annotate::{ANNOTATION_COLUMNS, Annotation, annotate, annotate_csv, tukey_fences};
+pub use cancel::{CancellableReader, CancellationToken, Cancelled, DropGuard};
+pub use coerce::{CoercionRule, Coercions, parse_duration_ms};
+pub use compare::{
+    PairedCompareRequest, PairedCompareResponse, compare_paired, paired_differences, paired_ratios,
+};
+pub use correlation::{
+    CorrelateRequest, CorrelateResponse, ScatterMatrix, correlate, pearson_correlation,
+    scatter_buckets, spearman_correlation,
+};
+pub use counts::ValueCounts;
+pub use describe::{Describe, SummaryStats, describe, summary_stats};
+pub use downsample::{DownsampleRequest, DownsampleResponse, downsample};
+#[cfg(feature = "durations")]
+pub use durations::{
+    DurationOptions, DurationUnit, parse_timezone, read_durations_from_bytes,
+    read_durations_from_file,
+};
+pub use engine::{
+    EngineKind, ExactSort, External, PercentileEngine, QuickSelect, TDigest, TDigestSketch,
+};
+pub use formats::{Format, FormatRegistry};
+#[cfg(feature = "hdr")]
+pub use hdr::{decode_hdr_histogram, encode_hdr_histogram};
+pub use inspect::{ColumnKind, ColumnSummary, InputReport, inspect_input, inspect_values};
+pub use integer::{IntegerPercentile, calculate_integer_percentile, read_integers_from_bytes};
+pub use outliers::{
+    DEFAULT_MAD_CUTOFF, DEFAULT_ZSCORE_THRESHOLD, OutlierMethod, OutlierRequest, OutlierSplit,
+    detect_outliers_iqr, detect_outliers_mad, detect_outliers_zscore,
+};
+pub use p2::{P2Estimator, StreamEstimate, estimate_percentiles};
+#[cfg(feature = "pipeline")]
+pub use pipeline::{Filter, Pipeline, PipelineResult, Sink, Source, Statistic, Transform};
+#[cfg(feature = "privacy")]
+pub use privacy::{DEFAULT_MIN_COUNT, PrivacyOptions, PrivateReport, private_percentiles};
+pub use stratify::{StratifyRequest, StratifyResponse, Stratum, stratify};
+pub use timeseries::{
+    BAND_PERCENTILES, PercentileBand, TimeBucket, TimeseriesRequest, TimeseriesResponse,
+    percentiles_over_time, skip_warmup,
+};
+pub use transform::{
+    FittedTransform, TransformedResult, ValueTransform, box_cox, estimate_box_cox_lambda,
+    inverse_box_cox,
+};
+#[cfg(feature = "webhook")]
+pub use webhook::{WebhookKey, sign_webhook, verify_webhook};
+
 /// Percentile interpolation method
 #[cfg_attr(feature = "server", derive(ToSchema))]
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize, Default, clap::ValueEnum)]
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize, Default)]
 #[serde(rename_all = "snake_case")]
-#[clap(rename_all = "snake_case")]
+#[cfg_attr(feature = "cli", clap(rename_all = "snake_case"))]
 pub enum PercentileMethod {
     /// Linear interpolation between adjacent values (default)
     #[default]
@@ -43,6 +169,86 @@ impl fmt::Display for PercentileMethod {
     }
 }
 
+/// Rounding mode applied when a result precision is requested
+#[cfg_attr(feature = "server", derive(ToSchema))]
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize, Default)]
+#[serde(rename_all = "snake_case")]
+#[cfg_attr(feature = "cli", clap(rename_all = "snake_case"))]
+pub enum RoundingMode {
+    /// Round half to even (banker's rounding, default)
+    #[default]
+    HalfEven,
+    /// Always round toward negative infinity
+    Floor,
+    /// Always round toward positive infinity
+    Ceil,
+}
+
+impl fmt::Display for RoundingMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RoundingMode::HalfEven => write!(f, "half_even"),
+            RoundingMode::Floor => write!(f, "floor"),
+            RoundingMode::Ceil => write!(f, "ceil"),
+        }
+    }
+}
+
+/// Input file format
+#[cfg_attr(feature = "server", derive(ToSchema))]
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize, Default)]
+#[serde(rename_all = "snake_case")]
+#[cfg_attr(feature = "cli", clap(rename_all = "snake_case"))]
+pub enum InputFormat {
+    /// Detect from the contents, then the file extension (default)
+    #[default]
+    Auto,
+    /// JSON array of numbers
+    Json,
+    /// CSV with a `value` column
+    Csv,
+    /// Compressed base64 HdrHistogram (requires the `hdr` feature)
+    Hdr,
+    /// Gatling `simulation.log` (response times in ms)
+    Gatling,
+    /// JMeter JTL results, CSV or XML (elapsed times in ms)
+    Jmeter,
+    /// k6 `--out json` results (`http_req_duration` samples in ms)
+    K6,
+    /// vegeta JSON-lines results (latencies converted from ns to ms)
+    Vegeta,
+    /// wrk2 `--latency` report, expanded from its percentile spectrum (ms)
+    Wrk,
+    /// OpenTelemetry OTLP/JSON metrics, expanded from exponential histogram
+    /// buckets
+    Otel,
+}
+
+impl fmt::Display for InputFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            InputFormat::Auto => write!(f, "auto"),
+            InputFormat::Json => write!(f, "json"),
+            InputFormat::Csv => write!(f, "csv"),
+            InputFormat::Hdr => write!(f, "hdr"),
+            InputFormat::Gatling => write!(f, "gatling"),
+            InputFormat::Jmeter => write!(f, "jmeter"),
+            InputFormat::K6 => write!(f, "k6"),
+            InputFormat::Vegeta => write!(f, "vegeta"),
+            InputFormat::Wrk => write!(f, "wrk"),
+            InputFormat::Otel => write!(f, "otel"),
+        }
+    }
+}
+
+/// Maximum number of decimal places accepted for result rounding
+pub const MAX_PRECISION: u32 = 15;
+
+/// Percentiles reported when a request doesn't ask for specific ones
+pub const DEFAULT_PERCENTILES: [f64; 4] = [50.0, 90.0, 95.0, 99.0];
+
 /// CSV record structure for parsing
 #[derive(Debug, Deserialize)]
 pub struct ValueRecord {
@@ -55,16 +261,337 @@ pub struct ValueRecord {
 pub struct CalculateRequest {
     /// Array of numerical values
     pub values: Vec<f64>,
-    /// Percentile to calculate (0-100)
-    #[serde(default = "default_percentile")]
-    pub percentile: f64,
+    /// Percentile to calculate (0-100), fractional values such as 99.95 are
+    /// allowed; 95, or the server's configured default, when omitted
+    #[cfg_attr(
+        feature = "server",
+        schema(example = 99.95, minimum = 0.0, maximum = 100.0)
+    )]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub percentile: Option<f64>,
+    /// Several percentiles to calculate from one sort, in place of
+    /// `percentile`; the response lists every result in `percentiles`
+    #[cfg_attr(feature = "server", schema(example = json!([50.0, 90.0, 99.0])))]
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub percentiles: Vec<f64>,
     /// Interpolation method (defaults to linear)
     #[serde(default)]
     pub method: PercentileMethod,
+    /// Number of decimal places to round the result to (unrounded if omitted)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub precision: Option<u32>,
+    /// Rounding mode used when `precision` is set (defaults to half_even)
+    #[serde(default)]
+    pub rounding: RoundingMode,
+    /// Number of leading values to drop as warm-up before calculating
+    #[serde(default)]
+    pub skip_first: usize,
+    /// Transform applied to the values before calculating; the result is
+    /// reported in both transformed and original units
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub transform: Option<ValueTransform>,
+    /// Percentile engine to calculate with (defaults to the server's configured engine)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub engine: Option<EngineKind>,
+    /// Also report count, min, max, sum, mean, median, variance and
+    /// standard deviation of the measured values
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub summary: bool,
+    /// The values are already in ascending order, so exact engines skip
+    /// their sort; checked in one pass, see [`is_sorted_ascending`]
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub sorted: bool,
 }
 
-fn default_percentile() -> f64 {
-    95.0
+/// Percentile calculated when a request names neither `percentile` nor `percentiles`
+pub const DEFAULT_PERCENTILE: f64 = 95.0;
+
+impl CalculateRequest {
+    /// Start building a request; `values` is required, every other field has a default
+    ///
+    /// # Examples
+    /// ```
+    /// use outlier::{CalculateRequest, PercentileMethod};
+    ///
+    /// let request = CalculateRequest::builder()
+    ///     .values(vec![1.0, 2.0, 3.0])
+    ///     .percentile(99.0)
+    ///     .method(PercentileMethod::NearestRank)
+    ///     .build()
+    ///     .unwrap();
+    /// assert_eq!(request.percentile, Some(99.0));
+    /// ```
+    pub fn builder() -> CalculateRequestBuilder {
+        CalculateRequestBuilder::default()
+    }
+
+    /// The values left after dropping the first `skip_first` as warm-up
+    pub fn measured_values(&self) -> &[f64] {
+        &self.values[self.skip_first.min(self.values.len())..]
+    }
+
+    /// `percentiles` when given, otherwise the single `percentile` or
+    /// [`DEFAULT_PERCENTILE`]
+    pub fn requested_percentiles(&self) -> &[f64] {
+        if self.percentiles.is_empty() {
+            std::slice::from_ref(self.percentile.as_ref().unwrap_or(&DEFAULT_PERCENTILE))
+        } else {
+            &self.percentiles
+        }
+    }
+
+    /// Validate the request, collecting every problem rather than stopping at the first
+    pub fn validate(&self) -> std::result::Result<(), ValidationErrors> {
+        let mut errors = Vec::new();
+
+        if self.values.is_empty() {
+            errors.push(FieldError::new(
+                "values",
+                "empty",
+                "Cannot calculate percentile of empty dataset",
+            ));
+        }
+        if let Some(percentile) = self.percentile
+            && !(0.0..=100.0).contains(&percentile)
+        {
+            errors.push(FieldError::new(
+                "percentile",
+                "out_of_range",
+                "Percentile must be between 0 and 100",
+            ));
+        }
+        if self.percentiles.iter().any(|p| !(0.0..=100.0).contains(p)) {
+            errors.push(FieldError::new(
+                "percentiles",
+                "out_of_range",
+                "Percentiles must be between 0 and 100",
+            ));
+        }
+        if self.transform.is_some() && self.percentiles.len() > 1 {
+            errors.push(FieldError::new(
+                "transform",
+                "invalid_value",
+                "A transform applies to a single percentile",
+            ));
+        }
+        if let Some(precision) = self.precision
+            && precision > MAX_PRECISION
+        {
+            errors.push(FieldError::new(
+                "precision",
+                "out_of_range",
+                format!("Precision must be between 0 and {MAX_PRECISION}"),
+            ));
+        }
+        if !self.values.is_empty() && self.skip_first >= self.values.len() {
+            errors.push(FieldError::new(
+                "skip_first",
+                "out_of_range",
+                format!(
+                    "skip_first must leave at least one of the {} values",
+                    self.values.len()
+                ),
+            ));
+        }
+        if self.transform.is_some()
+            && !self.values.is_empty()
+            && self.measured_values().iter().any(|v| *v <= 0.0)
+        {
+            errors.push(FieldError::new(
+                "transform",
+                "non_positive",
+                "Log and Box-Cox transforms need positive values",
+            ));
+        }
+        if self.sorted && !is_sorted_ascending(self.measured_values()) {
+            errors.push(FieldError::new(
+                "sorted",
+                "unsorted",
+                "Values marked sorted are not in ascending order",
+            ));
+        }
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(ValidationErrors(errors))
+        }
+    }
+
+    /// Build a request from a JSON object, reporting type errors for every field
+    /// together with the semantic checks from [`CalculateRequest::validate`]
+    pub fn from_json_value(
+        value: serde_json::Value,
+    ) -> std::result::Result<Self, ValidationErrors> {
+        let mut reader = FieldReader::new(value)?;
+        let request = Self {
+            values: reader
+                .required(
+                    "values",
+                    "invalid_type",
+                    "Values must be an array of numbers",
+                    "Values are required",
+                )
+                .unwrap_or_default(),
+            percentile: reader.optional("percentile", "invalid_type", "Percentile must be a number"),
+            percentiles: reader
+                .optional(
+                    "percentiles",
+                    "invalid_type",
+                    "Percentiles must be an array of numbers",
+                )
+                .unwrap_or_default(),
+            method: reader
+                .optional(
+                    "method",
+                    "invalid_value",
+                    "Method must be one of: linear, nearest_rank, lower, upper, midpoint, nearest_even",
+                )
+                .unwrap_or_default(),
+            precision: reader.optional(
+                "precision",
+                "invalid_type",
+                "Precision must be a non-negative integer",
+            ),
+            rounding: reader
+                .optional(
+                    "rounding",
+                    "invalid_value",
+                    "Rounding must be one of: half_even, floor, ceil",
+                )
+                .unwrap_or_default(),
+            skip_first: reader
+                .optional(
+                    "skip_first",
+                    "invalid_type",
+                    "skip_first must be a non-negative integer",
+                )
+                .unwrap_or_default(),
+            transform: reader.optional(
+                "transform",
+                "invalid_value",
+                "Transform must be one of: log10, ln, box_cox",
+            ),
+            engine: reader.optional(
+                "engine",
+                "invalid_value",
+                "Engine must be one of: exact_sort, quick_select, t_digest, external",
+            ),
+            summary: reader
+                .optional("summary", "invalid_type", "summary must be a boolean")
+                .unwrap_or_default(),
+            sorted: reader
+                .optional("sorted", "invalid_type", "sorted must be a boolean")
+                .unwrap_or_default(),
+        };
+        reader.finish(request, Self::validate)
+    }
+}
+
+/// Builder for [`CalculateRequest`] that validates on [`build`](Self::build)
+#[derive(Debug, Clone, Default)]
+pub struct CalculateRequestBuilder {
+    values: Option<Vec<f64>>,
+    percentile: Option<f64>,
+    percentiles: Vec<f64>,
+    method: PercentileMethod,
+    precision: Option<u32>,
+    rounding: RoundingMode,
+    skip_first: usize,
+    transform: Option<ValueTransform>,
+    engine: Option<EngineKind>,
+    summary: bool,
+    sorted: bool,
+}
+
+impl CalculateRequestBuilder {
+    pub fn values(mut self, values: impl Into<Vec<f64>>) -> Self {
+        self.values = Some(values.into());
+        self
+    }
+
+    pub fn percentile(mut self, percentile: f64) -> Self {
+        self.percentile = Some(percentile);
+        self
+    }
+
+    pub fn percentiles(mut self, percentiles: impl Into<Vec<f64>>) -> Self {
+        self.percentiles = percentiles.into();
+        self
+    }
+
+    pub fn method(mut self, method: PercentileMethod) -> Self {
+        self.method = method;
+        self
+    }
+
+    pub fn precision(mut self, precision: u32) -> Self {
+        self.precision = Some(precision);
+        self
+    }
+
+    pub fn rounding(mut self, rounding: RoundingMode) -> Self {
+        self.rounding = rounding;
+        self
+    }
+
+    pub fn skip_first(mut self, skip_first: usize) -> Self {
+        self.skip_first = skip_first;
+        self
+    }
+
+    pub fn transform(mut self, transform: ValueTransform) -> Self {
+        self.transform = Some(transform);
+        self
+    }
+
+    pub fn engine(mut self, engine: EngineKind) -> Self {
+        self.engine = Some(engine);
+        self
+    }
+
+    pub fn summary(mut self, summary: bool) -> Self {
+        self.summary = summary;
+        self
+    }
+
+    pub fn sorted(mut self, sorted: bool) -> Self {
+        self.sorted = sorted;
+        self
+    }
+
+    /// Build the request, reporting every validation failure at once
+    pub fn build(self) -> std::result::Result<CalculateRequest, ValidationErrors> {
+        let missing_values = self.values.is_none();
+        let request = CalculateRequest {
+            values: self.values.unwrap_or_default(),
+            percentile: self.percentile,
+            percentiles: self.percentiles,
+            method: self.method,
+            precision: self.precision,
+            rounding: self.rounding,
+            skip_first: self.skip_first,
+            transform: self.transform,
+            engine: self.engine,
+            summary: self.summary,
+            sorted: self.sorted,
+        };
+
+        let mut errors = request.validate().err().unwrap_or_default().0;
+        if missing_values {
+            errors.retain(|e| e.field != "values");
+            errors.insert(
+                0,
+                FieldError::new("values", "required", "Values are required"),
+            );
+        }
+
+        if errors.is_empty() {
+            Ok(request)
+        } else {
+            Err(ValidationErrors(errors))
+        }
+    }
 }
 
 /// Response structure for calculate API endpoint
@@ -73,21 +600,312 @@ fn default_percentile() -> f64 {
 pub struct CalculateResponse {
     /// Number of values in the dataset
     pub count: usize,
-    /// The requested percentile value
+    /// The requested percentile value, or the first of `percentiles`
     pub percentile: f64,
     /// The calculated result
     pub result: f64,
+    /// Every result, labelled and in request order, when the request listed
+    /// `percentiles`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub results: Vec<PercentileResult>,
     /// The interpolation method used
     #[serde(default)]
     pub method: PercentileMethod,
+    /// The result in transformed space, when a transform was requested;
+    /// `result` is then back-transformed to the original units
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub transformed: Option<TransformedResult>,
+    /// The percentile engine that calculated the result
+    #[serde(default)]
+    pub engine: EngineKind,
+    /// Summary statistics of the values, unrounded, when the request asked
+    /// for `summary`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub summary: Option<SummaryStats>,
+}
+
+/// Response structure for the health endpoint
+#[cfg_attr(feature = "server", derive(ToSchema))]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HealthResponse {
+    /// Service health (`healthy`)
+    pub status: String,
+    /// Service name
+    pub service: String,
+    /// Service version
+    pub version: String,
+}
+
+impl HealthResponse {
+    /// Health response for this build of outlier
+    pub fn healthy() -> Self {
+        Self {
+            status: "healthy".to_string(),
+            service: "outlier".to_string(),
+            version: env!("CARGO_PKG_VERSION").to_string(),
+        }
+    }
+}
+
+/// A single percentile and its value
+#[cfg_attr(feature = "server", derive(ToSchema))]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PercentileValue {
+    /// The requested percentile (0-100)
+    pub percentile: f64,
+    /// The calculated value
+    pub value: f64,
+}
+
+/// One of several requested percentiles and its value, labelled for display
+#[cfg_attr(feature = "server", derive(ToSchema))]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PercentileResult {
+    /// The requested percentile (0-100)
+    pub percentile: f64,
+    /// Lowercase label such as `p95` or `p99.9`
+    pub label: String,
+    /// The calculated value
+    pub value: f64,
+}
+
+impl PercentileResult {
+    /// Label `percentile` the way [`format_percentile_label`] does, in lowercase
+    ///
+    /// # Examples
+    /// ```
+    /// use outlier::PercentileResult;
+    ///
+    /// assert_eq!(PercentileResult::new(99.9, 12.5).label, "p99.9");
+    /// ```
+    pub fn new(percentile: f64, value: f64) -> Self {
+        Self {
+            percentile,
+            label: format_percentile_label(percentile).to_lowercase(),
+            value,
+        }
+    }
+}
+
+/// Descriptive statistics for a dataset
+#[cfg_attr(feature = "server", derive(ToSchema))]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SummaryResponse {
+    /// Number of values in the dataset
+    pub count: usize,
+    /// Smallest value
+    pub min: f64,
+    /// Largest value
+    pub max: f64,
+    /// Arithmetic mean
+    pub mean: f64,
+    /// Median (linear interpolation)
+    pub median: f64,
+    /// Sample standard deviation (0 for a single value)
+    pub std_dev: f64,
+    /// Requested percentiles, in request order
+    pub percentiles: Vec<PercentileValue>,
+    /// The interpolation method used for `percentiles`
+    #[serde(default)]
+    pub method: PercentileMethod,
+}
+
+/// One bucket of a histogram, covering `[lower, upper)` (the last bucket includes `upper`)
+#[cfg_attr(feature = "server", derive(ToSchema))]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HistogramBucket {
+    /// Inclusive lower bound
+    pub lower: f64,
+    /// Exclusive upper bound
+    pub upper: f64,
+    /// Number of values in the bucket
+    pub count: usize,
+}
+
+/// Distribution of a dataset over contiguous buckets
+#[cfg_attr(feature = "server", derive(ToSchema))]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HistogramResponse {
+    /// Number of values in the dataset
+    pub count: usize,
+    /// Buckets in ascending order
+    pub buckets: Vec<HistogramBucket>,
+}
+
+/// Summary statistics, percentiles and histogram of an uploaded file
+#[cfg_attr(feature = "server", derive(ToSchema))]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AnalyzeResponse {
+    /// pandas-style `describe()` statistics
+    pub summary: Describe,
+    /// Requested percentiles, in request order
+    pub percentiles: Vec<PercentileValue>,
+    /// The interpolation method used for `percentiles`
+    pub method: PercentileMethod,
+    pub histogram: HistogramResponse,
+}
+
+/// A value flagged as an outlier
+#[cfg_attr(feature = "server", derive(ToSchema))]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OutlierValue {
+    /// Position of the value in the input
+    pub index: usize,
+    /// The value itself
+    pub value: f64,
+}
+
+/// Values falling outside the bounds of an outlier test
+#[cfg_attr(feature = "server", derive(ToSchema))]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OutlierResponse {
+    /// Number of values in the dataset
+    pub count: usize,
+    /// Values below this bound are outliers
+    pub lower_bound: f64,
+    /// Values above this bound are outliers
+    pub upper_bound: f64,
+    /// Flagged values, in input order
+    pub outliers: Vec<OutlierValue>,
 }
 
 /// Error response structure
 #[cfg_attr(feature = "server", derive(ToSchema))]
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, Deserialize)]
 pub struct ErrorResponse {
     /// Error message
     pub error: String,
+    /// Every validation failure found in the request (omitted for other errors)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub errors: Vec<FieldError>,
+}
+
+/// A single request validation failure
+#[cfg_attr(feature = "server", derive(ToSchema))]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FieldError {
+    /// Name of the offending request field
+    pub field: String,
+    /// Machine-readable failure code (e.g. `empty`, `out_of_range`, `invalid_value`)
+    pub code: String,
+    /// Human-readable description
+    pub message: String,
+}
+
+impl FieldError {
+    pub fn new(field: &str, code: &str, message: impl Into<String>) -> Self {
+        Self {
+            field: field.to_string(),
+            code: code.to_string(),
+            message: message.into(),
+        }
+    }
+}
+
+/// All validation failures found in a request
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct ValidationErrors(pub Vec<FieldError>);
+
+impl fmt::Display for ValidationErrors {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let messages: Vec<&str> = self.0.iter().map(|e| e.message.as_str()).collect();
+        write!(f, "{}", messages.join("; "))
+    }
+}
+
+impl std::error::Error for ValidationErrors {}
+
+/// Reads the fields of a JSON request body one by one, so a request reports a
+/// type error for every bad field instead of serde's first one
+///
+/// A `null` field counts as absent. Each request's `from_json_value` reads its
+/// fields and hands the result to [`finish`](Self::finish) with its semantic checks.
+pub(crate) struct FieldReader {
+    object: serde_json::Map<String, serde_json::Value>,
+    errors: Vec<FieldError>,
+}
+
+impl FieldReader {
+    /// Start reading `value`, which must be a JSON object
+    pub(crate) fn new(value: serde_json::Value) -> std::result::Result<Self, ValidationErrors> {
+        let serde_json::Value::Object(object) = value else {
+            return Err(ValidationErrors(vec![FieldError::new(
+                "body",
+                "invalid_type",
+                "Request body must be a JSON object",
+            )]));
+        };
+        Ok(Self {
+            object,
+            errors: Vec::new(),
+        })
+    }
+
+    /// An optional field, or `None` when absent or, after recording `code`
+    /// and `message` against it, when it doesn't parse as `T`
+    pub(crate) fn optional<T: serde::de::DeserializeOwned>(
+        &mut self,
+        name: &str,
+        code: &str,
+        message: impl Into<String>,
+    ) -> Option<T> {
+        let value = self.object.remove(name).filter(|v| !v.is_null())?;
+        serde_json::from_value(value)
+            .map_err(|_| self.errors.push(FieldError::new(name, code, message)))
+            .ok()
+    }
+
+    /// A required field, additionally recording `missing` as a `required`
+    /// error when it is absent
+    pub(crate) fn required<T: serde::de::DeserializeOwned>(
+        &mut self,
+        name: &str,
+        code: &str,
+        message: impl Into<String>,
+        missing: impl Into<String>,
+    ) -> Option<T> {
+        if self.object.get(name).is_none_or(serde_json::Value::is_null) {
+            self.errors.push(FieldError::new(name, "required", missing));
+            return None;
+        }
+        self.optional(name, code, message)
+    }
+
+    /// A required array of numbers, with errors that name the field
+    pub(crate) fn series(&mut self, name: &str) -> Vec<f64> {
+        self.required(
+            name,
+            "invalid_type",
+            format!("{name} must be an array of numbers"),
+            format!("{name} is required"),
+        )
+        .unwrap_or_default()
+    }
+
+    /// Return `request` unless a field failed to parse or `validate` rejects it
+    ///
+    /// Only semantic problems for fields that parsed are reported, so a
+    /// malformed field isn't also blamed for being empty.
+    pub(crate) fn finish<T>(
+        self,
+        request: T,
+        validate: impl FnOnce(&T) -> std::result::Result<(), ValidationErrors>,
+    ) -> std::result::Result<T, ValidationErrors> {
+        let mut errors = self.errors;
+        if let Err(ValidationErrors(semantic)) = validate(&request) {
+            for error in semantic {
+                if !errors.iter().any(|e| e.field == error.field) {
+                    errors.push(error);
+                }
+            }
+        }
+
+        if errors.is_empty() {
+            Ok(request)
+        } else {
+            Err(ValidationErrors(errors))
+        }
+    }
 }
 
 /// Calculate percentile from a slice of values
@@ -112,7 +930,10 @@ pub struct ErrorResponse {
 /// let p50 = calculate_percentile(&values, 50.0, PercentileMethod::Linear).unwrap();
 /// assert_eq!(p50, 3.0);
 /// ```
-#[instrument(skip(values), fields(value_count = values.len(), percentile = %percentile, method = %method))]
+#[cfg_attr(
+    feature = "tracing",
+    instrument(skip(values), fields(value_count = values.len(), percentile = %percentile, method = %method))
+)]
 pub fn calculate_percentile(
     values: &[f64],
     percentile: f64,
@@ -126,33 +947,406 @@ pub fn calculate_percentile(
         anyhow::bail!("Percentile must be between 0 and 100");
     }
 
+    percentile_of_sorted(&sort_values(values), percentile, method)
+}
+
+/// Calculate a percentile by sorting `values` in place, leaving them in
+/// ascending order
+///
+/// Unlike [`calculate_percentile`] this needs no copy of the values, which
+/// halves peak memory for large slices. Sorting is unstable, so equal values
+/// may swap places.
+///
+/// # Examples
+/// ```
+/// use outlier::{calculate_percentile_mut, PercentileMethod};
+///
+/// let mut values = vec![5.0, 1.0, 3.0, 2.0, 4.0];
+/// let p50 = calculate_percentile_mut(&mut values, 50.0, PercentileMethod::Linear).unwrap();
+/// assert_eq!(p50, 3.0);
+/// assert_eq!(values, [1.0, 2.0, 3.0, 4.0, 5.0]);
+/// ```
+#[cfg_attr(
+    feature = "tracing",
+    instrument(skip(values), fields(value_count = values.len(), percentile = %percentile, method = %method))
+)]
+pub fn calculate_percentile_mut(
+    values: &mut [f64],
+    percentile: f64,
+    method: PercentileMethod,
+) -> Result<f64> {
+    if values.is_empty() {
+        anyhow::bail!("Cannot calculate percentile of empty dataset");
+    }
+
+    if !(0.0..=100.0).contains(&percentile) {
+        anyhow::bail!("Percentile must be between 0 and 100");
+    }
+
+    sort_values_in_place(values);
+    percentile_of_sorted(values, percentile, method)
+}
+
+/// Calculate a percentile of values the caller no longer needs, sorting
+/// them in place as [`calculate_percentile_mut`] does
+///
+/// # Examples
+/// ```
+/// use outlier::{calculate_percentile_owned, PercentileMethod};
+///
+/// let values: Vec<f64> = (1..=100).map(f64::from).collect();
+/// assert_eq!(calculate_percentile_owned(values, 100.0, PercentileMethod::Linear).unwrap(), 100.0);
+/// ```
+pub fn calculate_percentile_owned(
+    mut values: Vec<f64>,
+    percentile: f64,
+    method: PercentileMethod,
+) -> Result<f64> {
+    calculate_percentile_mut(&mut values, percentile, method)
+}
+
+/// Sort values into ascending order without allocating
+#[cfg_attr(
+    feature = "tracing",
+    instrument(level = "debug", name = "sort", skip_all, fields(value_count = values.len()))
+)]
+fn sort_values_in_place(values: &mut [f64]) {
+    values.sort_unstable_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
+}
+
+/// Copy values into ascending order, the sort step of [`calculate_percentile`]
+#[cfg_attr(
+    feature = "tracing",
+    instrument(level = "debug", name = "sort", skip_all, fields(value_count = values.len()))
+)]
+pub fn sort_values(values: &[f64]) -> Vec<f64> {
     let mut sorted = values.to_vec();
     sorted.sort_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
+    sorted
+}
+
+/// [`sort_values`], checking `token` between chunks so a cancelled sort of a
+/// large dataset stops early
+#[cfg_attr(
+    feature = "tracing",
+    instrument(level = "debug", name = "sort", skip_all, fields(value_count = values.len()))
+)]
+pub fn sort_values_cancellable(values: &[f64], token: &CancellationToken) -> Result<Vec<f64>> {
+    const CHUNK: usize = 1 << 16;
+    let compare = |a: &f64, b: &f64| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal);
+    let mut sorted = values.to_vec();
+    for chunk in sorted.chunks_mut(CHUNK) {
+        token.check()?;
+        chunk.sort_by(compare);
+    }
+    token.check()?;
+    // The stable sort detects the sorted chunks as runs and only merges them
+    sorted.sort_by(compare);
+    Ok(sorted)
+}
+
+/// Calculate a percentile of values already in ascending order
+///
+/// Lets callers sort once (see [`sort_values`]) and time or reuse that step
+/// separately; results match [`calculate_percentile`] on the unsorted input.
+///
+/// # Examples
+/// ```
+/// use outlier::{percentile_of_sorted, sort_values, PercentileMethod};
+///
+/// let sorted = sort_values(&[5.0, 1.0, 3.0, 2.0, 4.0]);
+/// assert_eq!(percentile_of_sorted(&sorted, 50.0, PercentileMethod::Linear).unwrap(), 3.0);
+/// ```
+#[cfg_attr(
+    feature = "tracing",
+    instrument(level = "debug", name = "interpolate", skip(sorted), fields(method = %method))
+)]
+pub fn percentile_of_sorted(
+    sorted: &[f64],
+    percentile: f64,
+    method: PercentileMethod,
+) -> Result<f64> {
+    if sorted.is_empty() {
+        anyhow::bail!("Cannot calculate percentile of empty dataset");
+    }
+
+    if !(0.0..=100.0).contains(&percentile) {
+        anyhow::bail!("Percentile must be between 0 and 100");
+    }
+
+    Ok(percentile_from_ranks(
+        sorted.len(),
+        percentile,
+        method,
+        |rank| sorted[rank],
+    ))
+}
 
-    let index = (percentile / 100.0) * (sorted.len() - 1) as f64;
+/// Calculate a percentile of values the caller keeps in ascending order,
+/// without copying or sorting them
+///
+/// The order is checked with [`is_sorted_ascending`] first, a single pass
+/// that is still far cheaper than the sort it replaces.
+///
+/// # Examples
+/// ```
+/// use outlier::{calculate_percentile_sorted, PercentileMethod};
+///
+/// let sorted = [1.0, 2.0, 3.0, 4.0, 5.0];
+/// let p50 = calculate_percentile_sorted(&sorted, 50.0, PercentileMethod::Linear).unwrap();
+/// assert_eq!(p50, 3.0);
+/// assert!(calculate_percentile_sorted(&[3.0, 1.0], 50.0, PercentileMethod::Linear).is_err());
+/// ```
+pub fn calculate_percentile_sorted(
+    sorted: &[f64],
+    percentile: f64,
+    method: PercentileMethod,
+) -> Result<f64> {
+    if !is_sorted_ascending(sorted) {
+        anyhow::bail!("Values are not in ascending order");
+    }
+    percentile_of_sorted(sorted, percentile, method)
+}
+
+/// Whether `values` are in ascending order, comparing every adjacent pair
+///
+/// NaN is never in order, so any slice holding one fails.
+///
+/// # Examples
+/// ```
+/// use outlier::is_sorted_ascending;
+///
+/// assert!(is_sorted_ascending(&[1.0, 1.0, 2.0]));
+/// assert!(!is_sorted_ascending(&[1.0, f64::NAN, 2.0]));
+/// ```
+pub fn is_sorted_ascending(values: &[f64]) -> bool {
+    match values {
+        [value] => !value.is_nan(),
+        _ => values.windows(2).all(|pair| pair[0] <= pair[1]),
+    }
+}
+
+/// Apply `method` given a way to look up the value at each rank (0-based
+/// position in ascending order) of a dataset of `len` values
+///
+/// Shared by [`percentile_of_sorted`] and engines that find ranks without
+/// sorting everything. `len` must be non-zero and `percentile` in range.
+pub(crate) fn percentile_from_ranks(
+    len: usize,
+    percentile: f64,
+    method: PercentileMethod,
+    mut at: impl FnMut(usize) -> f64,
+) -> f64 {
+    let index = percentile_index(percentile, len);
     let lower = index.floor() as usize;
     let upper = index.ceil() as usize;
 
     match method {
         PercentileMethod::Linear => {
             if lower == upper {
-                Ok(sorted[lower])
+                at(lower)
             } else {
                 let weight = index - lower as f64;
-                Ok(sorted[lower] * (1.0 - weight) + sorted[upper] * weight)
+                at(lower) * (1.0 - weight) + at(upper) * weight
             }
         }
-        PercentileMethod::NearestRank => Ok(sorted[index.round() as usize]),
-        PercentileMethod::Lower => Ok(sorted[lower]),
-        PercentileMethod::Upper => Ok(sorted[upper]),
-        PercentileMethod::Midpoint => Ok((sorted[lower] + sorted[upper]) / 2.0),
-        PercentileMethod::NearestEven => {
-            let rounded = bankers_round(index) as usize;
-            Ok(sorted[rounded])
-        }
+        PercentileMethod::NearestRank => at(index.round() as usize),
+        PercentileMethod::Lower => at(lower),
+        PercentileMethod::Upper => at(upper),
+        PercentileMethod::Midpoint => (at(lower) + at(upper)) / 2.0,
+        PercentileMethod::NearestEven => at(bankers_round(index) as usize),
     }
 }
 
+/// Calculate several percentiles of the same dataset with `method`, sorting it
+/// once, and label each result with its percentile
+///
+/// # Examples
+/// ```
+/// use outlier::{calculate_percentiles_with, PercentileMethod};
+///
+/// let result = calculate_percentiles_with(&[1.0, 2.0, 3.0], &[0.0, 100.0], PercentileMethod::Linear).unwrap();
+/// assert_eq!(result[1].value, 3.0);
+/// ```
+#[cfg_attr(
+    feature = "tracing",
+    instrument(skip_all, fields(value_count = values.len(), percentile_count = percentiles.len(), method = %method))
+)]
+pub fn calculate_percentiles_with(
+    values: &[f64],
+    percentiles: &[f64],
+    method: PercentileMethod,
+) -> Result<Vec<PercentileValue>> {
+    let results = ExactSort.percentiles(values, percentiles, method)?;
+    Ok(percentiles
+        .iter()
+        .zip(results)
+        .map(|(&percentile, value)| PercentileValue { percentile, value })
+        .collect())
+}
+
+/// Calculate several percentiles of the same dataset with linear
+/// interpolation, sorting it once
+///
+/// The results are in the order of `percentiles`; [`calculate_percentiles_with`]
+/// takes a method and labels each result with its percentile.
+///
+/// # Examples
+/// ```
+/// use outlier::calculate_percentiles;
+///
+/// let result = calculate_percentiles(&[1.0, 2.0, 3.0, 4.0], &[50.0, 100.0]).unwrap();
+/// assert_eq!(result, vec![2.5, 4.0]);
+/// ```
+pub fn calculate_percentiles(values: &[f64], percentiles: &[f64]) -> Result<Vec<f64>> {
+    ExactSort.percentiles(values, percentiles, PercentileMethod::Linear)
+}
+
+/// Count values into `buckets` equal-width buckets spanning the data
+///
+/// Every bucket covers `[lower, upper)` except the last, which includes the
+/// maximum. A dataset of identical values yields a single bucket.
+///
+/// # Examples
+/// ```
+/// use outlier::histogram;
+///
+/// let result = histogram(&[1.0, 2.0, 2.5, 4.0, 5.0], 2).unwrap();
+/// assert_eq!(result.buckets[0].count, 3);
+/// assert_eq!(result.buckets[1].count, 2);
+/// ```
+pub fn histogram(values: &[f64], buckets: usize) -> Result<HistogramResponse> {
+    if values.is_empty() {
+        anyhow::bail!("Cannot build a histogram of an empty dataset");
+    }
+    if buckets == 0 {
+        anyhow::bail!("Buckets must be at least 1");
+    }
+    if values.iter().any(|v| !v.is_finite()) {
+        anyhow::bail!("Values must be finite numbers");
+    }
+
+    let min = values.iter().copied().fold(f64::INFINITY, f64::min);
+    let max = values.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+    let buckets = if min == max { 1 } else { buckets };
+    let width = (max - min) / buckets as f64;
+
+    let mut counts = vec![0; buckets];
+    for &v in values {
+        let index = if width == 0.0 {
+            0
+        } else {
+            (((v - min) / width) as usize).min(buckets - 1)
+        };
+        counts[index] += 1;
+    }
+
+    Ok(HistogramResponse {
+        count: values.len(),
+        buckets: counts
+            .into_iter()
+            .enumerate()
+            .map(|(i, count)| HistogramBucket {
+                lower: min + i as f64 * width,
+                upper: if i + 1 == buckets {
+                    max
+                } else {
+                    min + (i + 1) as f64 * width
+                },
+                count,
+            })
+            .collect(),
+    })
+}
+
+/// Fractional index of a percentile within a sorted dataset of `len` values
+///
+/// Multiplying before dividing keeps exact ranks exact for fractional percentiles
+/// (P99.99 of 10001 values is index 9999, not 9998.999...). Indices within float
+/// noise of an integer are snapped so `lower`/`upper` pick the intended element.
+fn percentile_index(percentile: f64, len: usize) -> f64 {
+    let index = percentile * (len - 1) as f64 / 100.0;
+    let nearest = index.round();
+    if (index - nearest).abs() <= index.max(1.0) * 1e-12 {
+        nearest
+    } else {
+        index
+    }
+}
+
+/// Format a percentile as a label, e.g. `P95`, `P99.95`, `P99.999`
+///
+/// Uses the shortest representation that round-trips, so fractional
+/// percentiles are never truncated.
+///
+/// # Examples
+/// ```
+/// use outlier::format_percentile_label;
+///
+/// assert_eq!(format_percentile_label(95.0), "P95");
+/// assert_eq!(format_percentile_label(99.95), "P99.95");
+/// ```
+pub fn format_percentile_label(percentile: f64) -> String {
+    format!("P{percentile}")
+}
+
+/// Round a value to `precision` decimal places using the given rounding mode
+///
+/// Scaled values within a few ULPs of an integer or of a half are treated as
+/// exact, so `2.675` rounds like the decimal it was written as rather than
+/// its binary approximation. Anything further away is genuinely off the
+/// boundary, so `Floor` never rounds up and `Ceil` never rounds down.
+///
+/// # Examples
+/// ```
+/// use outlier::{round_to_precision, RoundingMode};
+///
+/// assert_eq!(round_to_precision(2.345, 2, RoundingMode::HalfEven).unwrap(), 2.34);
+/// assert_eq!(round_to_precision(2.341, 2, RoundingMode::Ceil).unwrap(), 2.35);
+/// ```
+pub fn round_to_precision(value: f64, precision: u32, mode: RoundingMode) -> Result<f64> {
+    if precision > MAX_PRECISION {
+        anyhow::bail!("Precision must be between 0 and {}", MAX_PRECISION);
+    }
+    if !value.is_finite() {
+        return Ok(value);
+    }
+
+    let scale = 10f64.powi(precision as i32);
+    let scaled = value * scale;
+    // Scaling can overflow a huge value, and from 2^52 up every f64 is a
+    // whole number anyway, so there is nothing to round
+    if !scaled.is_finite() || scaled.abs() >= 2f64.powi(52) {
+        return Ok(value);
+    }
+    // Scaling is off by at most an ULP or so of `scaled`
+    let tolerance = 4.0 * f64::EPSILON * scaled.abs().max(1.0);
+    let nearest = scaled.round();
+
+    let rounded = if (scaled - nearest).abs() <= tolerance {
+        nearest
+    } else {
+        match mode {
+            RoundingMode::HalfEven => {
+                let floor = scaled.floor();
+                if (scaled - floor - 0.5).abs() <= tolerance {
+                    if floor.rem_euclid(2.0) == 0.0 {
+                        floor
+                    } else {
+                        floor + 1.0
+                    }
+                } else {
+                    nearest
+                }
+            }
+            RoundingMode::Floor => scaled.floor(),
+            RoundingMode::Ceil => scaled.ceil(),
+        }
+    };
+
+    Ok(rounded / scale)
+}
+
 /// Banker's rounding: round half to even
 fn bankers_round(value: f64) -> f64 {
     let rounded = value.round();
@@ -169,19 +1363,219 @@ fn bankers_round(value: f64) -> f64 {
     }
 }
 
+/// Number formatting conventions used when parsing CSV input
+#[cfg_attr(feature = "server", derive(ToSchema))]
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize, Default)]
+#[serde(rename_all = "snake_case")]
+#[cfg_attr(feature = "cli", clap(rename_all = "snake_case"))]
+pub enum NumberLocale {
+    /// `1,234.56` with comma-delimited fields (default)
+    #[default]
+    En,
+    /// `1.234,56` with semicolon-delimited fields
+    De,
+    /// `1 234,56` with semicolon-delimited fields
+    Fr,
+}
+
+impl fmt::Display for NumberLocale {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            NumberLocale::En => write!(f, "en"),
+            NumberLocale::De => write!(f, "de"),
+            NumberLocale::Fr => write!(f, "fr"),
+        }
+    }
+}
+
+/// Options controlling how CSV input is parsed
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CsvOptions {
+    /// Field delimiter
+    pub delimiter: u8,
+    /// Character separating the integer and fractional parts
+    pub decimal_separator: char,
+    /// Optional digit grouping character (e.g. `.` in `1.234,56`)
+    pub thousands_separator: Option<char>,
+    /// Only accept plain decimals: no padding, exponents, infinities, or hex floats
+    pub strict: bool,
+    /// Rules for reading cells that aren't plain numbers, such as `$1,234.50`
+    pub coerce: Coercions,
+}
+
+impl Default for CsvOptions {
+    fn default() -> Self {
+        Self {
+            delimiter: b',',
+            decimal_separator: '.',
+            thousands_separator: None,
+            strict: false,
+            coerce: Coercions::default(),
+        }
+    }
+}
+
+impl CsvOptions {
+    /// Options matching the number conventions of a locale
+    pub fn for_locale(locale: NumberLocale) -> Self {
+        match locale {
+            NumberLocale::En => Self {
+                thousands_separator: Some(','),
+                ..Self::default()
+            },
+            NumberLocale::De => Self {
+                delimiter: b';',
+                decimal_separator: ',',
+                thousands_separator: Some('.'),
+                ..Self::default()
+            },
+            NumberLocale::Fr => Self {
+                delimiter: b';',
+                decimal_separator: ',',
+                thousands_separator: Some(' '),
+                ..Self::default()
+            },
+        }
+    }
+}
+
+/// Parse a number written with the given decimal and thousands separators
+///
+/// Thousands separators are only accepted in the integer part and in groups of
+/// three digits, so `1,234.56` is rejected under `de` instead of misparsing.
+///
+/// Unless `options.strict` is set, surrounding whitespace is ignored and
+/// scientific notation (`1.2e-3`), infinities (`+inf`) and hex floats
+/// (`0x1.8p3`) are accepted. NaN is always rejected.
+pub fn parse_localized_number(text: &str, options: &CsvOptions) -> Option<f64> {
+    let text = if options.strict { text } else { text.trim() };
+    if !options.strict
+        && let Some(value) = parse_hex_float(text)
+    {
+        return Some(value);
+    }
+
+    let (int_part, frac_part) = match text.split_once(options.decimal_separator) {
+        Some((int_part, frac_part)) => (int_part, Some(frac_part)),
+        None => (text, None),
+    };
+
+    let mut normalized = String::with_capacity(text.len());
+    match options.thousands_separator {
+        Some(sep) if int_part.contains(sep) => {
+            let mut groups = int_part.split(sep);
+            let first = groups.next().unwrap_or_default();
+            let first_digits = first.trim_start_matches(['+', '-']);
+            if first_digits.is_empty()
+                || first_digits.len() > 3
+                || !first_digits.chars().all(|c| c.is_ascii_digit())
+            {
+                return None;
+            }
+            normalized.push_str(first);
+            for group in groups {
+                if group.len() != 3 || !group.chars().all(|c| c.is_ascii_digit()) {
+                    return None;
+                }
+                normalized.push_str(group);
+            }
+        }
+        _ => normalized.push_str(int_part),
+    }
+
+    if let Some(frac_part) = frac_part {
+        if options
+            .thousands_separator
+            .is_some_and(|sep| frac_part.contains(sep))
+            || frac_part.contains(options.decimal_separator)
+        {
+            return None;
+        }
+        normalized.push('.');
+        normalized.push_str(frac_part);
+    } else if options.decimal_separator != '.' && normalized.contains('.') {
+        return None;
+    }
+
+    if options.strict && !is_plain_decimal(&normalized) {
+        return None;
+    }
+
+    normalized.parse().ok().filter(|v: &f64| !v.is_nan())
+}
+
+/// Check for `[+-]digits[.digits]` with no exponent, padding, or special values
+fn is_plain_decimal(text: &str) -> bool {
+    let digits = text.strip_prefix(['+', '-']).unwrap_or(text);
+    let (int_part, frac_part) = digits.split_once('.').unwrap_or((digits, "0"));
+    !int_part.is_empty()
+        && !frac_part.is_empty()
+        && int_part.chars().all(|c| c.is_ascii_digit())
+        && frac_part.chars().all(|c| c.is_ascii_digit())
+}
+
+/// Parse a C99-style hex float such as `0x1.8p3` or `-0xAp-2`
+fn parse_hex_float(text: &str) -> Option<f64> {
+    let (negative, rest) = match text.as_bytes().first() {
+        Some(b'-') => (true, &text[1..]),
+        Some(b'+') => (false, &text[1..]),
+        _ => (false, text),
+    };
+    let rest = rest
+        .strip_prefix("0x")
+        .or_else(|| rest.strip_prefix("0X"))?;
+
+    let (mantissa, exponent) = match rest.split_once(['p', 'P']) {
+        Some((mantissa, exponent)) => (mantissa, exponent.parse::<i32>().ok()?),
+        None => (rest, 0),
+    };
+    let (int_digits, frac_digits) = mantissa.split_once('.').unwrap_or((mantissa, ""));
+    if int_digits.is_empty() && frac_digits.is_empty() {
+        return None;
+    }
+
+    let mut value = 0.0f64;
+    for c in int_digits.chars() {
+        value = value * 16.0 + c.to_digit(16)? as f64;
+    }
+    let mut scale = 1.0 / 16.0;
+    for c in frac_digits.chars() {
+        value += c.to_digit(16)? as f64 * scale;
+        scale /= 16.0;
+    }
+
+    let value = value * 2f64.powi(exponent);
+    Some(if negative { -value } else { value })
+}
+
+/// Error for an input whose format couldn't be detected
+fn unsupported_format() -> anyhow::Error {
+    anyhow::anyhow!(
+        "Unsupported file format. Use .json, .csv, .hdr, a Gatling/JMeter/k6/vegeta/wrk results file, OpenTelemetry metrics, or set the format explicitly"
+    )
+}
+
 /// Read values from a file (JSON or CSV format)
-#[instrument(fields(path = %path.display()))]
+#[cfg_attr(feature = "tracing", instrument(fields(path = %path.display())))]
 pub fn read_values_from_file(path: &Path) -> Result<Vec<f64>> {
-    let extension = path
-        .extension()
-        .and_then(|s| s.to_str())
-        .context("Unable to determine file extension")?;
+    read_values_from_file_with_options(path, &CsvOptions::default())
+}
 
-    match extension.to_lowercase().as_str() {
-        "json" => read_json_file(path),
-        "csv" => read_csv_file(path),
-        _ => anyhow::bail!("Unsupported file format. Use .json or .csv"),
-    }
+/// Read values from a file (JSON or CSV format) using the given CSV options
+pub fn read_values_from_file_with_options(path: &Path, options: &CsvOptions) -> Result<Vec<f64>> {
+    read_values_from_file_with_format(path, InputFormat::Auto, options)
+}
+
+/// Read values from a file in the given format, detecting it when `Auto`
+pub fn read_values_from_file_with_format(
+    path: &Path,
+    format: InputFormat,
+    options: &CsvOptions,
+) -> Result<Vec<f64>> {
+    let bytes = std::fs::read(path)
+        .with_context(|| format!("Failed to open input file {}", path.display()))?;
+    read_values_from_bytes_with_format(&bytes, &path.to_string_lossy(), format, options)
 }
 
 /// Read values from a JSON file (expects array of numbers)
@@ -204,64 +1598,112 @@ pub fn read_json_file(path: &Path) -> Result<Vec<f64>> {
 
 /// Read values from a CSV file (expects header row "value")
 pub fn read_csv_file(path: &Path) -> Result<Vec<f64>> {
+    read_csv_file_with_options(path, &CsvOptions::default())
+}
+
+/// Read values from a CSV file (expects header row "value") using the given options
+pub fn read_csv_file_with_options(path: &Path, options: &CsvOptions) -> Result<Vec<f64>> {
     let file = File::open(path).context("Failed to open CSV file")?;
-    let mut reader = csv::Reader::from_reader(file);
+    read_csv_values(file, options)
+}
+
+/// Parse the "value" column of a CSV stream
+fn read_csv_values<R: std::io::Read>(reader: R, options: &CsvOptions) -> Result<Vec<f64>> {
+    let mut reader = csv::ReaderBuilder::new()
+        .delimiter(options.delimiter)
+        .from_reader(reader);
+    let column = reader
+        .headers()
+        .context("Failed to parse CSV record")?
+        .iter()
+        .position(|h| h == "value")
+        .context("Failed to parse CSV record: missing \"value\" header")?;
+
     let mut values = Vec::new();
     const MAX_VALUES: usize = 10_000_000; // 10 million
 
-    for result in reader.deserialize() {
+    for (row, result) in reader.records().enumerate() {
         if values.len() >= MAX_VALUES {
             anyhow::bail!(
                 "Input dataset exceeds the limit of {} values. Aborting.",
                 MAX_VALUES
             );
         }
-        let record: ValueRecord = result.context("Failed to parse CSV record")?;
-        values.push(record.value);
+        let record = result.context("Failed to parse CSV record")?;
+        let field = record.get(column).unwrap_or_default();
+        let value = parse_localized_number(field, options)
+            .or_else(|| options.coerce.apply(field, options))
+            .with_context(|| {
+                format!(
+                    "Failed to parse CSV record {}: invalid number {:?}",
+                    row + 1,
+                    field
+                )
+            })?;
+        values.push(value);
     }
 
     Ok(values)
 }
 
 /// Parse values from bytes (JSON or CSV)
-#[instrument(skip(bytes), fields(filename = %filename, byte_count = bytes.len()))]
+#[cfg_attr(
+    feature = "tracing",
+    instrument(skip(bytes), fields(filename = %filename, byte_count = bytes.len()))
+)]
 pub fn read_values_from_bytes(bytes: &[u8], filename: &str) -> Result<Vec<f64>> {
-    let extension = filename.split('.').next_back().unwrap_or("");
-
-    match extension.to_lowercase().as_str() {
-        "json" => {
-            let values: Vec<f64> = serde_json::from_slice(bytes)
-                .context("Failed to parse JSON. Expected array of numbers.")?;
-            const MAX_VALUES: usize = 10_000_000; // 10 million
-            if values.len() > MAX_VALUES {
-                anyhow::bail!(
-                    "Input dataset exceeds the limit of {} values. Aborting.",
-                    MAX_VALUES
-                );
-            }
-            Ok(values)
-        }
-        "csv" => {
-            let mut reader = csv::Reader::from_reader(bytes);
-            let mut values = Vec::new();
-            const MAX_VALUES: usize = 10_000_000; // 10 million
-
-            for result in reader.deserialize() {
-                if values.len() >= MAX_VALUES {
-                    anyhow::bail!(
-                        "Input dataset exceeds the limit of {} values. Aborting.",
-                        MAX_VALUES
-                    );
-                }
-                let record: ValueRecord = result.context("Failed to parse CSV record")?;
-                values.push(record.value);
-            }
+    read_values_from_bytes_with_options(bytes, filename, &CsvOptions::default())
+}
 
-            Ok(values)
-        }
-        _ => anyhow::bail!("Unsupported file format. Use .json or .csv"),
+/// Parse values from bytes (JSON or CSV) using the given CSV options
+pub fn read_values_from_bytes_with_options(
+    bytes: &[u8],
+    filename: &str,
+    options: &CsvOptions,
+) -> Result<Vec<f64>> {
+    read_values_from_bytes_with_format(bytes, filename, InputFormat::Auto, options)
+}
+
+/// The concrete format `format` refers to, detecting it from the contents
+/// and filename when `Auto`
+pub fn resolve_input_format(
+    bytes: &[u8],
+    filename: &str,
+    format: InputFormat,
+) -> Result<InputFormat> {
+    match format {
+        InputFormat::Auto => InputFormat::sniff(bytes, filename).ok_or_else(unsupported_format),
+        format => Ok(format),
     }
 }
 
+/// Parse values from bytes in the given format, detecting it when `Auto`
+pub fn read_values_from_bytes_with_format(
+    bytes: &[u8],
+    filename: &str,
+    format: InputFormat,
+    options: &CsvOptions,
+) -> Result<Vec<f64>> {
+    resolve_input_format(bytes, filename, format)?.parse_stream(&mut &bytes[..], options)
+}
+
+/// [`read_values_from_bytes_with_format`], failing with [`Cancelled`] at the
+/// parser's next read once `token` is cancelled
+pub fn read_values_from_bytes_cancellable(
+    bytes: &[u8],
+    filename: &str,
+    format: InputFormat,
+    options: &CsvOptions,
+    token: &CancellationToken,
+) -> Result<Vec<f64>> {
+    let format = resolve_input_format(bytes, filename, format)?;
+    format
+        .parse_stream(&mut BufReader::new(token.reader(bytes)), options)
+        .map_err(|e| match token.check() {
+            Err(cancelled) => cancelled.into(),
+            Ok(()) => e,
+        })
+}
+
 #[cfg(test)]
 mod tests;
@@ -0,0 +1,275 @@
+use anyhow::{Context, Result};
+use serde::Deserialize;
+
+use crate::InputFormat;
+
+const MAX_VALUES: usize = 10_000_000; // 10 million
+
+/// Guess the format of an input from its contents, falling back to its extension
+///
+/// Load-test outputs are recognised by their first line, so a Gatling log or
+/// k6 results file is detected whatever it is named. Returns `None` when
+/// neither the contents nor the extension are recognised. Equivalent to
+/// [`InputFormat::sniff`].
+pub fn sniff_format(bytes: &[u8], filename: &str) -> Option<InputFormat> {
+    InputFormat::sniff(bytes, filename)
+}
+
+/// Response times in milliseconds from a Gatling `simulation.log`
+///
+/// Every `REQUEST` record is used, whether it succeeded (`OK`) or failed (`KO`).
+pub fn read_gatling_log(bytes: &[u8]) -> Result<Vec<f64>> {
+    let text = std::str::from_utf8(bytes).context("Failed to parse Gatling log: invalid UTF-8")?;
+
+    let mut values = Vec::new();
+    for (line_no, line) in text.lines().enumerate() {
+        let fields: Vec<&str> = line.split('\t').collect();
+        if fields.first() != Some(&"REQUEST") {
+            continue;
+        }
+        if values.len() >= MAX_VALUES {
+            anyhow::bail!(
+                "Input dataset exceeds the limit of {} values. Aborting.",
+                MAX_VALUES
+            );
+        }
+        // Field layout varies between Gatling versions, but start and end
+        // timestamps always precede the OK/KO status
+        let parsed = fields
+            .iter()
+            .position(|f| *f == "OK" || *f == "KO")
+            .filter(|&status| status >= 2)
+            .and_then(|status| {
+                let start = fields[status - 2].parse::<f64>().ok()?;
+                let end = fields[status - 1].parse::<f64>().ok()?;
+                Some(end - start)
+            });
+        let value = parsed.with_context(|| {
+            format!(
+                "Failed to parse Gatling log line {}: expected start and end timestamps before OK/KO",
+                line_no + 1
+            )
+        })?;
+        values.push(value);
+    }
+
+    if values.is_empty() {
+        anyhow::bail!("No REQUEST records found in Gatling log");
+    }
+    Ok(values)
+}
+
+/// Elapsed times in milliseconds from a JMeter JTL file (CSV or XML)
+pub fn read_jmeter_jtl(bytes: &[u8]) -> Result<Vec<f64>> {
+    let text = std::str::from_utf8(bytes).context("Failed to parse JTL: invalid UTF-8")?;
+    let values = if text.trim_start().starts_with('<') {
+        read_jmeter_xml(text)?
+    } else {
+        read_jmeter_csv(bytes)?
+    };
+
+    if values.is_empty() {
+        anyhow::bail!("No samples found in JTL file");
+    }
+    Ok(values)
+}
+
+fn read_jmeter_csv(bytes: &[u8]) -> Result<Vec<f64>> {
+    let mut reader = csv::Reader::from_reader(bytes);
+    let column = reader
+        .headers()
+        .context("Failed to parse JTL record")?
+        .iter()
+        .position(|h| h == "elapsed")
+        .context("Failed to parse JTL record: missing \"elapsed\" header")?;
+
+    let mut values = Vec::new();
+    for (row, result) in reader.records().enumerate() {
+        if values.len() >= MAX_VALUES {
+            anyhow::bail!(
+                "Input dataset exceeds the limit of {} values. Aborting.",
+                MAX_VALUES
+            );
+        }
+        let record = result.context("Failed to parse JTL record")?;
+        let field = record.get(column).unwrap_or_default();
+        let value = field.trim().parse::<f64>().with_context(|| {
+            format!(
+                "Failed to parse JTL record {}: invalid elapsed time {:?}",
+                row + 1,
+                field
+            )
+        })?;
+        values.push(value);
+    }
+    Ok(values)
+}
+
+fn read_jmeter_xml(text: &str) -> Result<Vec<f64>> {
+    let mut values = Vec::new();
+    let mut rest = text;
+    while let Some(start) = rest.find('<') {
+        rest = &rest[start + 1..];
+        let end = rest.find('>').unwrap_or(rest.len());
+        let tag = &rest[..end];
+        rest = &rest[end..];
+
+        if !(tag.starts_with("httpSample ") || tag.starts_with("sample ")) {
+            continue;
+        }
+        if values.len() >= MAX_VALUES {
+            anyhow::bail!(
+                "Input dataset exceeds the limit of {} values. Aborting.",
+                MAX_VALUES
+            );
+        }
+        let elapsed = tag
+            .split_once(" t=\"")
+            .and_then(|(_, attr)| attr.split_once('"'))
+            .and_then(|(value, _)| value.parse::<f64>().ok())
+            .with_context(|| {
+                format!("Failed to parse JTL sample: missing elapsed time in <{tag}>")
+            })?;
+        values.push(elapsed);
+    }
+    Ok(values)
+}
+
+#[derive(Deserialize)]
+struct K6Line {
+    #[serde(rename = "type")]
+    kind: String,
+    metric: String,
+    data: Option<K6Data>,
+}
+
+#[derive(Deserialize)]
+struct K6Data {
+    // Absent on `Metric` declaration lines
+    value: Option<f64>,
+}
+
+/// `http_req_duration` samples in milliseconds from `k6 run --out json=...` output
+pub fn read_k6_json(bytes: &[u8]) -> Result<Vec<f64>> {
+    let text = std::str::from_utf8(bytes).context("Failed to parse k6 output: invalid UTF-8")?;
+
+    if serde_json::from_str::<serde_json::Value>(text).is_ok_and(|v| v.get("metrics").is_some()) {
+        anyhow::bail!(
+            "k6 summary exports only contain aggregates; record samples with `k6 run --out json=results.json`"
+        );
+    }
+
+    let mut values = Vec::new();
+    for (line_no, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+        let parsed: K6Line = serde_json::from_str(line)
+            .with_context(|| format!("Failed to parse k6 output line {}", line_no + 1))?;
+        if parsed.kind != "Point" || parsed.metric != "http_req_duration" {
+            continue;
+        }
+        if values.len() >= MAX_VALUES {
+            anyhow::bail!(
+                "Input dataset exceeds the limit of {} values. Aborting.",
+                MAX_VALUES
+            );
+        }
+        let value = parsed.data.and_then(|d| d.value).with_context(|| {
+            format!(
+                "Failed to parse k6 output line {}: missing value",
+                line_no + 1
+            )
+        })?;
+        values.push(value);
+    }
+
+    if values.is_empty() {
+        anyhow::bail!("No http_req_duration samples found in k6 output");
+    }
+    Ok(values)
+}
+
+#[derive(Deserialize)]
+struct VegetaResult {
+    /// Nanoseconds
+    latency: u64,
+}
+
+/// Latencies in milliseconds from vegeta's JSON-lines results
+/// (`vegeta encode --to json`)
+pub fn read_vegeta_json(bytes: &[u8]) -> Result<Vec<f64>> {
+    let text =
+        std::str::from_utf8(bytes).context("Failed to parse vegeta results: invalid UTF-8")?;
+
+    let mut values = Vec::new();
+    for (line_no, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+        if values.len() >= MAX_VALUES {
+            anyhow::bail!(
+                "Input dataset exceeds the limit of {} values. Aborting.",
+                MAX_VALUES
+            );
+        }
+        let parsed: VegetaResult = serde_json::from_str(line)
+            .with_context(|| format!("Failed to parse vegeta result line {}", line_no + 1))?;
+        values.push(parsed.latency as f64 / 1_000_000.0);
+    }
+
+    if values.is_empty() {
+        anyhow::bail!("No results found in vegeta output");
+    }
+    Ok(values)
+}
+
+/// Latencies in milliseconds reconstructed from a wrk2 `--latency` report
+///
+/// Only the "Detailed Percentile spectrum" carries per-sample counts; each row
+/// contributes its value once per request added since the previous row. The
+/// four-line "Latency Distribution" summary from plain wrk can't be expanded.
+pub fn read_wrk_latency(bytes: &[u8]) -> Result<Vec<f64>> {
+    let text = std::str::from_utf8(bytes).context("Failed to parse wrk output: invalid UTF-8")?;
+    let spectrum = text
+        .split_once("Detailed Percentile spectrum:")
+        .map(|(_, rest)| rest)
+        .context(
+            "No \"Detailed Percentile spectrum\" found in wrk output; run wrk2 with --latency",
+        )?;
+
+    let mut values = Vec::new();
+    let mut previous = 0u64;
+    for line in spectrum.lines() {
+        let line = line.trim();
+        if line.starts_with('#') || line.starts_with("----") {
+            break;
+        }
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        // Value, Percentile, TotalCount, 1/(1-Percentile); skips the header row
+        let (Some(value), Some(total)) = (
+            fields.first().and_then(|f| f.parse::<f64>().ok()),
+            fields.get(2).and_then(|f| f.parse::<u64>().ok()),
+        ) else {
+            continue;
+        };
+        if total > MAX_VALUES as u64 {
+            anyhow::bail!(
+                "Input dataset exceeds the limit of {} values. Aborting.",
+                MAX_VALUES
+            );
+        }
+        let added = total.checked_sub(previous).with_context(|| {
+            format!("Failed to parse wrk percentile spectrum: TotalCount decreased at {line:?}")
+        })?;
+        values.extend(std::iter::repeat_n(value, added as usize));
+        previous = total;
+    }
+
+    if values.is_empty() {
+        anyhow::bail!("No samples found in wrk percentile spectrum");
+    }
+    Ok(values)
+}
@@ -1,21 +1,48 @@
 use anyhow::Result;
-use clap::Parser;
+use clap::{Parser, Subcommand};
 use std::path::PathBuf;
 
+use cache::ValueCache;
+
+mod cache;
+mod fetch;
+mod plot;
+mod remote;
+mod scheduling;
+#[cfg(feature = "self-update")]
+mod self_update;
+mod sinks;
+mod table;
 mod telemetry;
 
 #[cfg(feature = "server")]
-mod config;
-#[cfg(feature = "server")]
-mod jwt;
-#[cfg(feature = "server")]
-mod server;
+use outlier::{config, server};
+
+#[cfg(feature = "jemalloc")]
+#[global_allocator]
+static ALLOCATOR: tikv_jemallocator::Jemalloc = tikv_jemallocator::Jemalloc;
+
+#[cfg(all(feature = "mimalloc", not(feature = "jemalloc")))]
+#[global_allocator]
+static ALLOCATOR: outlier::runtime::CountingAllocator<mimalloc::MiMalloc> =
+    outlier::runtime::CountingAllocator(mimalloc::MiMalloc);
+
+#[cfg(all(
+    feature = "alloc-stats",
+    not(any(feature = "jemalloc", feature = "mimalloc"))
+))]
+#[global_allocator]
+static ALLOCATOR: outlier::runtime::CountingAllocator =
+    outlier::runtime::CountingAllocator(std::alloc::System);
 
 #[derive(Parser, Debug)]
 #[command(name = "outlier")]
 #[command(version)]
 #[command(about = "Calculate percentiles from numerical datasets", long_about = None)]
 struct Args {
+    #[command(subcommand)]
+    command: Option<Command>,
+
     /// Start API server mode
     #[cfg(feature = "server")]
     #[arg(long)]
@@ -24,35 +51,519 @@ struct Args {
     /// Path to configuration file (TOML format)
     /// Can also be set via CONFIG_FILE environment variable
     #[cfg(feature = "server")]
-    #[arg(short = 'c', long = "config")]
+    #[arg(short = 'c', long = "config", global = true)]
     config_file: Option<PathBuf>,
 
     /// Port for API server (only with --serve, overrides config file)
     #[cfg(feature = "server")]
-    #[arg(long)]
+    #[arg(long, global = true)]
     port: Option<u16>,
 
-    /// Percentile to calculate (e.g., 95, 99)
-    #[arg(short = 'p', long, default_value = "95")]
-    percentile: f64,
+    /// Load sketches from a snapshot taken by POST /admin/snapshot (only with --serve)
+    #[cfg(feature = "server")]
+    #[arg(long, value_name = "PATH", requires = "serve")]
+    restore: Option<PathBuf>,
+
+    /// Run at this nice level (-20 to 19; higher yields more CPU to others)
+    #[arg(
+        long,
+        global = true,
+        allow_negative_numbers = true,
+        value_parser = clap::value_parser!(i32).range(-20..=19)
+    )]
+    nice: Option<i32>,
+
+    /// Use at most this many threads for computation and I/O (overrides
+    /// `[server.runtime]` with --serve)
+    #[arg(long, global = true, value_name = "N")]
+    max_threads: Option<std::num::NonZeroUsize>,
+
+    /// Only run on these CPUs, e.g. `0-3,6` (Linux)
+    #[arg(long, global = true, value_name = "LIST")]
+    cpus: Option<scheduling::CpuList>,
+
+    /// Percentiles to calculate, comma-separated (e.g., 95 or 50,99,99.95)
+    #[arg(
+        short = 'p',
+        long = "percentile",
+        value_delimiter = ',',
+        default_value = "95"
+    )]
+    percentiles: Vec<f64>,
+
+    /// Number of decimal places in the printed result
+    #[arg(long, default_value = "2")]
+    precision: u32,
+
+    /// Rounding mode applied at the given precision
+    #[arg(long, default_value = "half_even", value_enum)]
+    rounding: outlier::RoundingMode,
 
     /// Interpolation method
     #[arg(short = 'm', long, default_value = "linear", value_enum)]
     method: outlier::PercentileMethod,
 
-    /// Input file (JSON or CSV format)
+    /// Number locale for CSV input (e.g. `de` for `1.234,56` with `;` delimiters)
+    #[arg(long, default_value = "en", value_enum)]
+    locale: outlier::NumberLocale,
+
+    /// Reject CSV values with padding, exponents, infinities, or hex floats
+    #[arg(long)]
+    strict: bool,
+
+    /// Rules for reading CSV cells that aren't plain numbers, e.g. `currency,percent,duration`
+    #[arg(long, value_enum, value_delimiter = ',')]
+    coerce: Vec<outlier::CoercionRule>,
+
+    /// Input file (JSON, CSV, compressed base64 HdrHistogram `.hdr`, Gatling/JMeter/k6/vegeta/wrk results
+    /// or OpenTelemetry metrics),
+    /// or an http(s) URL to download it from
     #[arg(short = 'f', long)]
     file: Option<PathBuf>,
 
+    /// Retries for a URL that fails to connect or returns 429 or 5xx
+    #[arg(long, default_value = "3")]
+    retries: u32,
+
+    /// Cache URL downloads here and revalidate them with conditional GETs
+    #[arg(long)]
+    cache_dir: Option<PathBuf>,
+
+    /// Parse local files afresh instead of reusing values cached by earlier runs
+    #[arg(long)]
+    no_cache: bool,
+
+    /// Refuse URL downloads larger than this many MiB
+    #[arg(long, default_value = "100")]
+    max_download_mb: u64,
+
+    /// Input file format (detected from contents and extension by default)
+    #[arg(long, default_value = "auto", value_enum)]
+    format: outlier::InputFormat,
+
+    /// Read the file as a CSV of start and end timestamps and use each row's duration
+    #[arg(long, requires = "file", conflicts_with_all = ["format", "dry_run"])]
+    durations: bool,
+
+    /// Column holding each event's start time (with --durations)
+    #[arg(long, default_value = "start")]
+    start_column: String,
+
+    /// Column holding each event's end time (with --durations)
+    #[arg(long, default_value = "end")]
+    end_column: String,
+
+    /// strftime layout of the timestamps, e.g. `%d/%m/%Y %H:%M:%S` (default: ISO 8601 or Unix seconds)
+    #[arg(long)]
+    time_format: Option<String>,
+
+    /// IANA timezone for timestamps without a UTC offset (with --durations)
+    #[arg(long, default_value = "UTC")]
+    timezone: String,
+
+    /// Unit of the computed durations (with --durations)
+    #[arg(long, default_value = "ms", value_enum)]
+    duration_unit: outlier::DurationUnit,
+
     /// Direct values from command line (comma-separated)
-    #[arg(short = 'v', long, value_delimiter = ',')]
-    values: Option<Vec<f64>>,
+    #[arg(short = 'v', long, value_delimiter = ',', value_parser = parse_number)]
+    values: Option<Vec<String>>,
+
+    /// Report the percentile from a histogram with Laplace noise for this
+    /// privacy budget, so it can be shared (differential privacy; needs --dp-bounds)
+    #[arg(long, requires = "dp_bounds")]
+    dp_epsilon: Option<f64>,
+
+    /// Range values are clamped to with --dp-epsilon, e.g. `0,5000`
+    #[arg(long, value_delimiter = ',', value_name = "LOWER,UPPER")]
+    dp_bounds: Option<Vec<f64>>,
+
+    /// Suppress the result when the noisy count is below this (with --dp-epsilon)
+    #[arg(long, default_value_t = outlier::DEFAULT_MIN_COUNT)]
+    dp_min_count: u64,
+
+    /// Treat the values as integers (e.g. queue depths), printing exact ranks
+    /// as integers rather than floats
+    #[arg(long)]
+    integer: bool,
+
+    /// Drop this many leading values as warm-up before calculating
+    #[arg(long, default_value = "0")]
+    skip_first: usize,
+
+    /// Calculate in transformed space and report the result in both spaces (text output)
+    #[arg(long, value_enum)]
+    transform: Option<outlier::ValueTransform>,
+
+    /// Also print min, max, sum, mean, median, variance and standard deviation (text output)
+    #[arg(long, conflicts_with_all = ["dp_epsilon", "integer", "remote"])]
+    summary: bool,
+
+    /// Also list the outliers found with this test: iqr (default), zscore or
+    /// mad (text output)
+    #[arg(
+        long,
+        value_enum,
+        value_name = "METHOD",
+        num_args = 0..=1,
+        default_missing_value = "iqr",
+        conflicts_with_all = ["dp_epsilon", "integer", "remote"]
+    )]
+    outliers: Option<outlier::OutlierMethod>,
+
+    /// Threshold for `--outliers`: the fence k for iqr (1.5), standard
+    /// deviations for zscore (3.0) or the modified z-score for mad (3.5)
+    #[arg(long, value_name = "THRESHOLD", requires = "outliers")]
+    outlier_threshold: Option<f64>,
+
+    /// Percentile engine: exact_sort, quick_select, t_digest (approximate) or external
+    #[arg(long, default_value = "exact_sort", value_enum)]
+    engine: outlier::EngineKind,
+
+    /// Command for `--engine external`, which reads JSON on stdin and prints a JSON array
+    #[arg(long, num_args = 1.., allow_hyphen_values = true, value_name = "COMMAND")]
+    engine_command: Vec<String>,
+
+    /// Also write the values as a compressed base64 HdrHistogram to this path
+    #[arg(long)]
+    hdr_out: Option<PathBuf>,
+
+    /// Also write the input back out as CSV to this path, with
+    /// `percentile_rank`, `is_outlier` and `zscore` columns added to each row
+    #[arg(
+        long,
+        value_name = "PATH",
+        requires = "file",
+        conflicts_with_all = ["durations", "dry_run", "integer"]
+    )]
+    annotate: Option<PathBuf>,
+
+    /// Also write the results as JSON to this path (`-` for stdout)
+    #[arg(long, value_name = "PATH")]
+    out: Option<PathBuf>,
+
+    /// Calculate on an outlier server at this URL instead, streaming `--file`
+    /// to it rather than loading it here (API key from `OUTLIER_API_KEY`)
+    #[arg(
+        long,
+        value_name = "URL",
+        requires = "file",
+        conflicts_with_all = ["values", "durations", "dry_run", "integer", "hdr_out", "annotate", "dp_epsilon", "plot", "engine_command"]
+    )]
+    remote: Option<String>,
+
+    /// Also POST the results as JSON to this URL
+    #[arg(long, value_name = "URL")]
+    post: Option<String>,
+
+    /// Also PUT the results as Prometheus metrics to this Pushgateway group URL
+    #[arg(long, value_name = "URL")]
+    push_metrics: Option<String>,
+
+    /// Output mode
+    #[arg(short = 'o', long, default_value = "text", value_enum)]
+    output: OutputMode,
+
+    /// Unit suffix for values in table output (e.g. `ms`)
+    #[arg(long)]
+    unit: Option<String>,
+
+    /// Color table values at or above this yellow
+    #[arg(long)]
+    warn_above: Option<f64>,
+
+    /// Color table values at or above this red
+    #[arg(long)]
+    crit_above: Option<f64>,
+
+    /// Also draw a sparkline and histogram of the distribution
+    #[arg(long)]
+    plot: bool,
+
+    /// Parse and report on the input file without calculating anything
+    #[arg(long)]
+    dry_run: bool,
+}
+
+impl Args {
+    /// The percentile of a mode that reports a single result
+    fn single_percentile(&self, mode: &str) -> Result<f64> {
+        match self.percentiles[..] {
+            [percentile] => Ok(percentile),
+            _ => anyhow::bail!("{mode} takes a single --percentile"),
+        }
+    }
+}
+
+/// How `outlier` prints its result
+#[derive(clap::ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+#[clap(rename_all = "snake_case")]
+enum OutputMode {
+    /// The requested percentiles as plain text
+    Text,
+    /// P50, P90, P95, P99 and the requested percentiles as a table, colored
+    /// by `--warn-above` / `--crit-above` (respects `NO_COLOR`)
+    Table,
+    /// pandas `Series.describe().to_json()` columns (ignores `--percentile`)
+    DescribeJson,
+    /// pandas `DataFrame.describe().to_csv()` rows (ignores `--percentile`)
+    DescribeCsv,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Report percentiles of one CSV column within quantile buckets of another
+    Stratify(StratifyArgs),
+    /// Report percentiles over fixed-width time buckets of a timestamped CSV
+    Timeseries(TimeseriesArgs),
+    /// Write a self-contained report directory: JSON and CSV results plus an HTML page with charts
+    Report(ReportArgs),
+    /// Draw a histogram or percentile curve of a file as an SVG or PNG image
+    Histogram(HistogramArgs),
+    /// Estimate percentiles of one number per line in constant memory (P² algorithm)
+    Stream(StreamArgs),
+    /// Run pipeline definitions (TOML or JSON)
+    Pipeline {
+        #[command(subcommand)]
+        action: PipelineAction,
+    },
+    /// Inspect or clear the cache of parsed input files
+    Cache {
+        #[command(subcommand)]
+        action: CacheAction,
+    },
+    /// Print the effective server configuration and where each value came from
+    #[cfg(feature = "server")]
+    ExplainConfig,
+    /// Replace this binary with the latest GitHub release
+    #[cfg(feature = "self-update")]
+    SelfUpdate {
+        /// Only report whether an update is available
+        #[arg(long)]
+        check: bool,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+enum PipelineAction {
+    /// Run a pipeline, print its results as JSON and deliver them to its sinks
+    Run {
+        /// Pipeline definition; `.json` files are read as JSON, anything else as TOML
+        file: PathBuf,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+enum CacheAction {
+    /// Print where the cache is and how much it holds
+    Info,
+    /// Delete every cached entry
+    Clear,
+}
+
+#[derive(clap::Args, Debug)]
+struct StratifyArgs {
+    /// Two-column CSV: the column to bucket by, then the column to report on
+    #[arg(short = 'f', long)]
+    file: PathBuf,
+
+    /// Number of quantile buckets (4 = quartiles)
+    #[arg(short = 's', long, default_value = "4")]
+    strata: usize,
+
+    /// Percentiles to report per bucket (comma-separated)
+    #[arg(
+        short = 'p',
+        long,
+        value_delimiter = ',',
+        default_value = "50,90,95,99"
+    )]
+    percentiles: Vec<f64>,
+
+    /// Number of decimal places in the printed results
+    #[arg(long, default_value = "2")]
+    precision: u32,
+
+    /// Interpolation method
+    #[arg(short = 'm', long, default_value = "linear", value_enum)]
+    method: outlier::PercentileMethod,
+
+    /// Number locale for CSV input
+    #[arg(long, default_value = "en", value_enum)]
+    locale: outlier::NumberLocale,
+
+    /// Reject CSV values with padding, exponents, infinities, or hex floats
+    #[arg(long)]
+    strict: bool,
 }
 
-#[tokio::main]
-async fn main() -> Result<()> {
+#[derive(clap::Args, Debug)]
+struct ReportArgs {
+    /// Input file (JSON, CSV, HdrHistogram or load-test results)
+    #[arg(short = 'f', long)]
+    file: PathBuf,
+
+    /// Directory to write the report into, created if missing
+    #[arg(long, value_name = "DIR")]
+    out: PathBuf,
+
+    /// Percentiles to report (comma-separated)
+    #[arg(
+        short = 'p',
+        long,
+        value_delimiter = ',',
+        default_value = "50,90,95,99,99.9"
+    )]
+    percentiles: Vec<f64>,
+
+    /// Interpolation method
+    #[arg(short = 'm', long, default_value = "linear", value_enum)]
+    method: outlier::PercentileMethod,
+
+    /// Number of histogram buckets
+    #[arg(long, default_value = "20")]
+    buckets: usize,
+
+    /// Input format
+    #[arg(long, default_value = "auto", value_enum)]
+    format: outlier::InputFormat,
+
+    /// Number locale for CSV input
+    #[arg(long, default_value = "en", value_enum)]
+    locale: outlier::NumberLocale,
+
+    /// Reject CSV values with padding, exponents, infinities, or hex floats
+    #[arg(long)]
+    strict: bool,
+}
+
+#[derive(clap::Args, Debug)]
+struct HistogramArgs {
+    /// Input file (JSON, CSV, HdrHistogram or load-test results)
+    #[arg(short = 'f', long)]
+    file: PathBuf,
+
+    /// SVG or PNG file to write; PNG needs the charts feature
+    #[arg(long, value_name = "PATH")]
+    out: PathBuf,
+
+    /// Draw the percentile curve instead of the histogram
+    #[arg(long)]
+    curve: bool,
+
+    /// Percentiles to mark on the curve (comma-separated)
+    #[arg(
+        short = 'p',
+        long,
+        value_delimiter = ',',
+        default_value = "50,90,95,99"
+    )]
+    percentiles: Vec<f64>,
+
+    /// Interpolation method
+    #[arg(short = 'm', long, default_value = "linear", value_enum)]
+    method: outlier::PercentileMethod,
+
+    /// Number of histogram buckets
+    #[arg(long, default_value = "20")]
+    buckets: usize,
+
+    /// Input format
+    #[arg(long, default_value = "auto", value_enum)]
+    format: outlier::InputFormat,
+
+    /// Number locale for CSV input
+    #[arg(long, default_value = "en", value_enum)]
+    locale: outlier::NumberLocale,
+
+    /// Reject CSV values with padding, exponents, infinities, or hex floats
+    #[arg(long)]
+    strict: bool,
+}
+
+#[derive(clap::Args, Debug)]
+struct StreamArgs {
+    /// File with one number per line; reads standard input when omitted or `-`
+    #[arg(short = 'f', long)]
+    file: Option<PathBuf>,
+
+    /// Percentiles to estimate (comma-separated)
+    #[arg(
+        short = 'p',
+        long,
+        value_delimiter = ',',
+        default_value = "50,90,95,99"
+    )]
+    percentiles: Vec<f64>,
+
+    /// Number of decimal places in the printed results
+    #[arg(long, default_value = "2")]
+    precision: u32,
+}
+
+#[derive(clap::Args, Debug)]
+struct TimeseriesArgs {
+    /// Two-column CSV: timestamp in seconds, then the measured value
+    #[arg(short = 'f', long)]
+    file: PathBuf,
+
+    /// Width of each time bucket in seconds
+    #[arg(short = 'b', long)]
+    bucket_secs: f64,
+
+    /// Report the running percentile of all values up to each bucket's end
+    #[arg(long)]
+    cumulative: bool,
+
+    /// Drop this many leading rows as warm-up
+    #[arg(long, default_value = "0")]
+    skip_first: usize,
+
+    /// Drop rows within this long of the earliest timestamp as warm-up (e.g. 30s, 2m, 500ms)
+    #[arg(long, value_parser = parse_duration_secs)]
+    skip_duration: Option<f64>,
+
+    /// Percentiles to report per bucket (comma-separated)
+    #[arg(
+        short = 'p',
+        long,
+        value_delimiter = ',',
+        default_value = "50,90,95,99"
+    )]
+    percentiles: Vec<f64>,
+
+    /// Number of decimal places in the printed results
+    #[arg(long, default_value = "2")]
+    precision: u32,
+
+    /// Interpolation method
+    #[arg(short = 'm', long, default_value = "linear", value_enum)]
+    method: outlier::PercentileMethod,
+
+    /// Number locale for CSV input
+    #[arg(long, default_value = "en", value_enum)]
+    locale: outlier::NumberLocale,
+
+    /// Reject CSV values with padding, exponents, infinities, or hex floats
+    #[arg(long)]
+    strict: bool,
+
+    /// Also draw a sparkline of each percentile over time
+    #[arg(long)]
+    plot: bool,
+}
+
+fn main() -> Result<()> {
     let args = Args::parse();
 
+    // Before any thread starts, so every thread inherits them
+    if let Some(nice) = args.nice {
+        scheduling::set_nice(nice)?;
+    }
+    if let Some(cpus) = &args.cpus {
+        scheduling::pin_to_cpus(cpus)?;
+    }
+
     #[cfg(feature = "server")]
     if args.serve {
         // Load configuration
@@ -62,9 +573,85 @@ async fn main() -> Result<()> {
         if let Some(port) = args.port {
             config.server.port = port;
         }
+        if let Some(path) = &args.restore {
+            config.server.restore = Some(path.clone());
+        }
+        if let Some(threads) = args.max_threads {
+            config.server.runtime.worker_threads = Some(threads.get());
+            config.server.runtime.max_blocking_threads = Some(threads.get());
+        }
 
         // Start API server (server has its own logging via init_logging)
-        return server::serve(config).await;
+        return server_runtime(&config.server.runtime)?.block_on(server::serve(config));
+    }
+
+    let mut runtime = tokio::runtime::Builder::new_multi_thread();
+    runtime.enable_all();
+    if let Some(threads) = args.max_threads {
+        runtime
+            .worker_threads(threads.get())
+            .max_blocking_threads(threads.get());
+    }
+    runtime.build()?.block_on(run(args))
+}
+
+/// A Tokio runtime sized by `[server.runtime]`
+#[cfg(feature = "server")]
+fn server_runtime(config: &config::RuntimeConfig) -> Result<tokio::runtime::Runtime> {
+    let mut builder = tokio::runtime::Builder::new_multi_thread();
+    builder.enable_all();
+    if let Some(threads) = config.worker_threads {
+        anyhow::ensure!(
+            threads > 0,
+            "server.runtime.worker_threads must be at least 1"
+        );
+        builder.worker_threads(threads);
+    }
+    if let Some(threads) = config.max_blocking_threads {
+        anyhow::ensure!(
+            threads > 0,
+            "server.runtime.max_blocking_threads must be at least 1"
+        );
+        builder.max_blocking_threads(threads);
+    }
+    Ok(builder.build()?)
+}
+
+async fn run(args: Args) -> Result<()> {
+    #[cfg(feature = "server")]
+    if let Some(Command::ExplainConfig) = args.command {
+        return explain_config(args.config_file.as_ref(), args.port);
+    }
+
+    #[cfg(feature = "self-update")]
+    if let Some(Command::SelfUpdate { check }) = args.command {
+        return self_update::run(check).await;
+    }
+
+    if let Some(Command::Pipeline {
+        action: PipelineAction::Run { ref file },
+    }) = args.command
+    {
+        return run_pipeline(file).await;
+    }
+
+    if let Some(command) = args.command {
+        telemetry::init_telemetry();
+        let result = match command {
+            Command::Stratify(stratify_args) => run_stratify(stratify_args),
+            Command::Timeseries(timeseries_args) => run_timeseries(timeseries_args),
+            Command::Report(report_args) => run_report(report_args),
+            Command::Histogram(histogram_args) => run_histogram(histogram_args),
+            Command::Stream(stream_args) => run_stream(stream_args),
+            Command::Cache { action } => run_cache(action),
+            Command::Pipeline { .. } => unreachable!("handled above"),
+            #[cfg(feature = "server")]
+            Command::ExplainConfig => unreachable!("handled above"),
+            #[cfg(feature = "self-update")]
+            Command::SelfUpdate { .. } => unreachable!("handled above"),
+        };
+        telemetry::shutdown_telemetry();
+        return result;
     }
 
     // Show help if no input provided
@@ -77,40 +664,983 @@ async fn main() -> Result<()> {
     // Initialize Honeycomb telemetry only for CLI mode
     telemetry::init_telemetry();
 
+    if let Some(ref server) = args.remote {
+        let outputs = output_sinks(&args);
+        let result = match run_remote(&args, server).await {
+            Ok(calculation) => sinks::deliver(&calculation, &outputs).await,
+            Err(e) => Err(e),
+        };
+        telemetry::shutdown_telemetry();
+        return result;
+    }
+
+    // Download a remote input up front so the calculation itself stays synchronous
+    let remote = match args.file {
+        Some(ref file) if fetch::is_url(file) => {
+            let options = fetch::FetchOptions {
+                retries: args.retries,
+                cache_dir: args.cache_dir.clone(),
+                max_bytes: args.max_download_mb.saturating_mul(1024 * 1024),
+            };
+            match fetch::fetch(&file.to_string_lossy(), &options).await {
+                Ok(bytes) => Some(bytes),
+                Err(e) => {
+                    telemetry::shutdown_telemetry();
+                    return Err(e);
+                }
+            }
+        }
+        _ => None,
+    };
+
     // Run CLI mode
-    let result = run_cli(args);
+    let outputs = output_sinks(&args);
+    let result = match run_cli(args, remote) {
+        Ok(Some(calculation)) => sinks::deliver(&calculation, &outputs).await,
+        Ok(None) => Ok(()),
+        Err(e) => Err(e),
+    };
     telemetry::shutdown_telemetry();
     result
 }
 
-#[tracing::instrument(skip_all, fields(percentile = %args.percentile, method = %args.method))]
-fn run_cli(args: Args) -> Result<()> {
-    use outlier::{calculate_percentile, read_values_from_file};
+/// The sinks `--out`, `--post` and `--push-metrics` ask for
+fn output_sinks(args: &Args) -> Vec<Box<dyn sinks::OutputSink>> {
+    let mut outputs: Vec<Box<dyn sinks::OutputSink>> = Vec::new();
+    match args.out {
+        Some(ref path) if path.as_os_str() == "-" => outputs.push(Box::new(sinks::Stdout)),
+        Some(ref path) => outputs.push(Box::new(sinks::JsonFile { path: path.clone() })),
+        None => {}
+    }
+    if let Some(ref url) = args.post {
+        outputs.push(Box::new(sinks::HttpPost {
+            url: url.clone(),
+            key: outlier::WebhookKey::from_env(),
+        }));
+    }
+    if let Some(ref url) = args.push_metrics {
+        outputs.push(Box::new(sinks::MetricsPush {
+            url: url.clone(),
+            prefix: sinks::DEFAULT_PREFIX.to_string(),
+            key: outlier::WebhookKey::from_env(),
+        }));
+    }
+    outputs
+}
+
+/// Print each effective config value annotated with its source
+#[cfg(feature = "server")]
+fn explain_config(config_file: Option<&PathBuf>, port: Option<u16>) -> Result<()> {
+    let (mut config, mut provenance) = config::Config::load_with_provenance(config_file)?;
+    if let Some(port) = port {
+        config.server.port = port;
+        provenance.set("server.port", config::ConfigSource::Cli("--port"));
+    }
+
+    let lines: Vec<(String, &str)> = config
+        .entries()
+        .into_iter()
+        .map(|(key, value)| (format!("{key} = {value}"), key))
+        .collect();
+    let width = lines.iter().map(|(line, _)| line.len()).max().unwrap_or(0);
+    for (line, key) in lines {
+        println!("{line:<width$}  # {}", provenance.source(key));
+    }
+    Ok(())
+}
+
+/// Run the calculation on the `--remote` server and print its result like a local run
+#[tracing::instrument(skip_all, fields(percentiles = ?args.percentiles, server = %server))]
+async fn run_remote(args: &Args, server: &str) -> Result<sinks::Calculation> {
+    let Some(ref file) = args.file else {
+        anyhow::bail!("--remote requires --file");
+    };
+    if fetch::is_url(file) {
+        anyhow::bail!("--remote uploads a local --file; pass the URL's data to the server instead");
+    }
+    if args.output != OutputMode::Text {
+        anyhow::bail!("--remote only applies to text output");
+    }
+    let percentile = args.single_percentile("--remote")?;
+
+    let mut upload = remote::RemoteCalculation {
+        file,
+        fields: vec![
+            ("percentile", percentile.to_string()),
+            ("precision", args.precision.to_string()),
+            ("strict", args.strict.to_string()),
+            ("skip_first", args.skip_first.to_string()),
+        ],
+    };
+    upload.option("method", args.method)?;
+    upload.option("rounding", args.rounding)?;
+    upload.option("locale", args.locale)?;
+    upload.option("format", args.format)?;
+    upload.option("engine", args.engine)?;
+    upload.options("coerce", &args.coerce)?;
+    if let Some(transform) = args.transform {
+        upload.option("transform", transform)?;
+    }
+    let api_key = std::env::var(remote::API_KEY_VAR)
+        .ok()
+        .filter(|k| !k.is_empty());
+    let response = upload.send(server, api_key.as_deref()).await?;
 
-    // Validate percentile
-    if args.percentile < 0.0 || args.percentile > 100.0 {
+    let label = outlier::format_percentile_label(response.percentile);
+    let precision = args.precision as usize;
+    println!("Number of values: {}", response.count);
+    println!("Method: {}", response.method);
+    if let Some(ref transformed) = response.transformed {
+        match transformed.lambda {
+            Some(lambda) => println!(
+                "Transform: {} (lambda = {lambda:.4})",
+                transformed.transform
+            ),
+            None => println!("Transform: {}", transformed.transform),
+        }
+    }
+    println!("Percentile ({label}): {:.precision$}", response.result);
+    if let Some(ref transformed) = response.transformed {
+        println!(
+            "Percentile ({label}, transformed): {:.precision$}",
+            transformed.result
+        );
+    }
+
+    Ok(sinks::Calculation {
+        count: response.count,
+        method: response.method,
+        engine: response.engine,
+        percentiles: vec![outlier::PercentileValue {
+            percentile: response.percentile,
+            value: response.result,
+        }],
+        transformed: response.transformed,
+    })
+}
+
+#[tracing::instrument(skip_all, fields(percentiles = ?args.percentiles, method = %args.method))]
+fn run_cli(mut args: Args, remote: Option<Vec<u8>>) -> Result<Option<sinks::Calculation>> {
+    use outlier::{
+        CsvOptions, External, PercentileValue, format_percentile_label,
+        read_values_from_bytes_with_format, read_values_from_file_with_format, round_to_precision,
+    };
+
+    // Validate percentiles
+    if args.percentiles.iter().any(|p| !(0.0..=100.0).contains(p)) {
         anyhow::bail!("Percentile must be between 0 and 100");
     }
+    let external = External::from_command(&args.engine_command);
+    if args.engine == outlier::EngineKind::External && external.is_none() {
+        anyhow::bail!("--engine external needs --engine-command");
+    }
+    let engine = args.engine.build(external.as_ref())?;
+    if args.integer {
+        return run_integer(&args, remote);
+    }
 
     // Collect values from either file or CLI
     let values = if let Some(ref file_path) = args.file {
-        read_values_from_file(file_path)?
-    } else if let Some(values) = args.values {
+        let csv_options = CsvOptions {
+            strict: args.strict,
+            coerce: outlier::Coercions::from_rules(&args.coerce),
+            ..CsvOptions::for_locale(args.locale)
+        };
+        let path = file_path.to_string_lossy();
+        let name = if remote.is_some() {
+            fetch::file_name(&path)
+        } else {
+            &path
+        };
+        if args.dry_run {
+            let bytes = match remote {
+                Some(bytes) => bytes,
+                None => std::fs::read(file_path).map_err(|e| {
+                    anyhow::anyhow!("Failed to open input file {}: {e}", file_path.display())
+                })?,
+            };
+            return print_input_report(&bytes, name, args.format, &csv_options).map(|()| None);
+        }
+        if let Some(ref annotate_path) = args.annotate {
+            let local;
+            let bytes = match remote.as_deref() {
+                Some(bytes) => bytes,
+                None => {
+                    local = std::fs::read(file_path).map_err(|e| {
+                        anyhow::anyhow!("Failed to open input file {}: {e}", file_path.display())
+                    })?;
+                    &local
+                }
+            };
+            let annotated = outlier::annotate_csv(
+                bytes,
+                name,
+                args.format,
+                &csv_options,
+                outlier::annotate::DEFAULT_FENCE,
+            )?;
+            std::fs::write(annotate_path, annotated)
+                .map_err(|e| anyhow::anyhow!("Failed to write {}: {e}", annotate_path.display()))?;
+        }
+        // Everything that changes how the file parses, so other options miss
+        let cache_key = format!(
+            "{:?}",
+            (
+                args.format,
+                args.locale,
+                args.strict,
+                &args.coerce,
+                args.durations.then_some((
+                    &args.start_column,
+                    &args.end_column,
+                    &args.time_format,
+                    &args.timezone,
+                    args.duration_unit,
+                )),
+            )
+        );
+        let durations = if args.durations {
+            Some(outlier::DurationOptions {
+                start_column: args.start_column.clone(),
+                end_column: args.end_column.clone(),
+                time_format: args.time_format.clone(),
+                timezone: outlier::parse_timezone(&args.timezone)?,
+                unit: args.duration_unit,
+            })
+        } else {
+            None
+        };
+        let parse_local = || match durations {
+            Some(ref options) => {
+                outlier::read_durations_from_file(file_path, options, &csv_options)
+            }
+            None => read_values_from_file_with_format(file_path, args.format, &csv_options),
+        };
+        match (remote, ValueCache::default_location()) {
+            (Some(bytes), _) => match durations {
+                Some(ref options) => {
+                    outlier::read_durations_from_bytes(&bytes, options, &csv_options)?
+                }
+                None => {
+                    read_values_from_bytes_with_format(&bytes, name, args.format, &csv_options)?
+                }
+            },
+            (None, Some(cache)) if !args.no_cache => {
+                cache.values(file_path, &cache_key, parse_local)?
+            }
+            (None, _) => parse_local()?,
+        }
+    } else if let Some(values) = args.values.take() {
         values
+            .iter()
+            .map(|v| v.parse())
+            .collect::<Result<Vec<f64>, _>>()?
     } else {
         anyhow::bail!("Must provide either --file or --values");
     };
+    if args.dry_run {
+        anyhow::bail!("--dry-run requires --file");
+    }
+
+    if values.is_empty() {
+        anyhow::bail!("No values provided");
+    }
+    if args.skip_first >= values.len() {
+        anyhow::bail!(
+            "--skip-first {} leaves none of the {} values",
+            args.skip_first,
+            values.len()
+        );
+    }
+    let values = &values[args.skip_first..];
+
+    if let Some(ref hdr_path) = args.hdr_out {
+        std::fs::write(hdr_path, outlier::encode_hdr_histogram(values)?)?;
+    }
+
+    if args.transform.is_some() && args.output != OutputMode::Text {
+        anyhow::bail!("--transform only applies to text output");
+    }
+    if args.summary && args.output != OutputMode::Text {
+        anyhow::bail!("--summary only applies to text output");
+    }
+    if args.outliers.is_some() && args.output != OutputMode::Text {
+        anyhow::bail!("--outliers only applies to text output");
+    }
+    if args.dp_epsilon.is_some()
+        && (args.output != OutputMode::Text || args.transform.is_some() || args.plot)
+    {
+        anyhow::bail!("--dp-epsilon only applies to text output without --transform or --plot");
+    }
+    let delivered = args.out.is_some() || args.post.is_some() || args.push_metrics.is_some();
+    if delivered && !matches!(args.output, OutputMode::Text | OutputMode::Table) {
+        anyhow::bail!("--out, --post and --push-metrics only apply to text and table output");
+    }
+
+    match args.output {
+        OutputMode::Text => {}
+        OutputMode::Table => {
+            let percentiles = print_table(&args, values, engine.as_ref())?;
+            if args.plot {
+                print_distribution_plot(values, args.precision as usize)?;
+            }
+            return Ok(Some(sinks::Calculation {
+                count: values.len(),
+                method: args.method,
+                engine: args.engine,
+                percentiles,
+                transformed: None,
+            }));
+        }
+        OutputMode::DescribeJson => {
+            println!("{}", serde_json::to_string(&outlier::describe(values)?)?);
+            return Ok(None);
+        }
+        OutputMode::DescribeCsv => {
+            print!("{}", outlier::describe(values)?.to_csv("value"));
+            return Ok(None);
+        }
+    }
+
+    if let Some(epsilon) = args.dp_epsilon {
+        return print_private(&args, values, epsilon);
+    }
+
+    // Calculate percentiles, sorting once for all of them
+    let precision = args.precision as usize;
+    let Some(transform) = args.transform else {
+        let results = engine.percentiles(values, &args.percentiles, args.method)?;
+        println!("Number of values: {}", values.len());
+        println!("Method: {}", args.method);
+        let mut percentiles = Vec::with_capacity(results.len());
+        for (&percentile, result) in args.percentiles.iter().zip(results) {
+            let value = round_to_precision(result, args.precision, args.rounding)?;
+            let label = format_percentile_label(percentile);
+            println!("Percentile ({label}): {value:.precision$}");
+            percentiles.push(PercentileValue { percentile, value });
+        }
+        if args.summary {
+            print_summary(&args, values)?;
+        }
+        if let Some(method) = args.outliers {
+            print_outliers(&args, values, method)?;
+        }
+        if args.plot {
+            print_distribution_plot(values, precision)?;
+        }
+        return Ok(Some(sinks::Calculation {
+            count: values.len(),
+            method: args.method,
+            engine: args.engine,
+            percentiles,
+            transformed: None,
+        }));
+    };
+
+    let percentile = args.single_percentile("--transform")?;
+    let label = format_percentile_label(percentile);
+    let fitted = outlier::FittedTransform::fit(transform, values)?;
+    let transformed: Vec<f64> = values.iter().map(|v| fitted.apply(*v)).collect();
+    let result = engine.percentile(&transformed, percentile, args.method)?;
+    let back = round_to_precision(fitted.invert(result), args.precision, args.rounding)?;
+    let result = round_to_precision(result, args.precision, args.rounding)?;
+
+    println!("Number of values: {}", values.len());
+    println!("Method: {}", args.method);
+    match fitted.lambda() {
+        Some(lambda) => println!("Transform: {transform} (lambda = {lambda:.4})"),
+        None => println!("Transform: {transform}"),
+    }
+    println!("Percentile ({label}): {back:.precision$}");
+    println!("Percentile ({label}, transformed): {result:.precision$}");
+    if args.summary {
+        print_summary(&args, values)?;
+    }
+    if let Some(method) = args.outliers {
+        print_outliers(&args, values, method)?;
+    }
+    if args.plot {
+        print_distribution_plot(values, precision)?;
+    }
+
+    Ok(Some(sinks::Calculation {
+        count: values.len(),
+        method: args.method,
+        engine: args.engine,
+        percentiles: vec![PercentileValue {
+            percentile,
+            value: back,
+        }],
+        transformed: Some(outlier::TransformedResult {
+            transform,
+            lambda: fitted.lambda(),
+            result,
+        }),
+    }))
+}
+
+fn print_input_report(
+    bytes: &[u8],
+    name: &str,
+    format: outlier::InputFormat,
+    csv_options: &outlier::CsvOptions,
+) -> Result<()> {
+    use sha2::Digest;
+
+    let report = outlier::inspect_input(bytes, name, format, csv_options)?;
+
+    let columns: Vec<String> = report
+        .columns
+        .iter()
+        .map(|c| format!("{} ({})", c.name, c.kind))
+        .collect();
+    println!("Format: {}", report.format);
+    println!("SHA-256: {}", hex::encode(sha2::Sha256::digest(bytes)));
+    println!("Values: {}", report.count);
+    println!("Columns: {}", columns.join(", "));
+    if report.warnings.is_empty() {
+        println!("Warnings: none");
+    }
+    for warning in &report.warnings {
+        println!("Warning: {warning}");
+    }
+    Ok(())
+}
+
+/// `--summary`: summary statistics of the values, rounded like the percentiles
+fn print_summary(args: &Args, values: &[f64]) -> Result<()> {
+    let summary = outlier::summary_stats(values)?;
+    let precision = args.precision as usize;
+    for (label, value) in [
+        ("Min", summary.min),
+        ("Max", summary.max),
+        ("Sum", summary.sum),
+        ("Mean", summary.mean),
+        ("Median", summary.median),
+        ("Variance", summary.variance),
+        ("Std dev", summary.std_dev),
+    ] {
+        let value = outlier::round_to_precision(value, args.precision, args.rounding)?;
+        println!("{label}: {value:.precision$}");
+    }
+    Ok(())
+}
+
+/// Most outliers `--outliers` lists one by one
+const MAX_LISTED_OUTLIERS: usize = 20;
+
+/// `--outliers`: the bounds and the values outside them, numbered by their
+/// position in the input
+fn print_outliers(args: &Args, values: &[f64], method: outlier::OutlierMethod) -> Result<()> {
+    let threshold = args
+        .outlier_threshold
+        .unwrap_or_else(|| method.default_threshold());
+    let response = method.detect(values, Some(threshold))?;
+    let precision = args.precision as usize;
+    let round = |value| outlier::round_to_precision(value, args.precision, args.rounding);
+    println!(
+        "Outliers ({method}, threshold {threshold}): {} of {} outside [{:.precision$}, {:.precision$}]",
+        response.outliers.len(),
+        response.count,
+        round(response.lower_bound)?,
+        round(response.upper_bound)?
+    );
+    for outlier in response.outliers.iter().take(MAX_LISTED_OUTLIERS) {
+        println!(
+            "  #{}: {:.precision$}",
+            outlier.index + args.skip_first,
+            round(outlier.value)?
+        );
+    }
+    if response.outliers.len() > MAX_LISTED_OUTLIERS {
+        println!(
+            "  ... and {} more",
+            response.outliers.len() - MAX_LISTED_OUTLIERS
+        );
+    }
+    Ok(())
+}
+
+/// Number of histogram columns drawn by `--plot`
+const PLOT_BUCKETS: usize = 40;
+
+/// Height in rows of the histogram drawn by `--plot`
+const PLOT_HEIGHT: usize = 8;
+
+fn print_distribution_plot(values: &[f64], precision: usize) -> Result<()> {
+    let histogram = outlier::histogram(values, PLOT_BUCKETS.min(values.len()))?;
+    let counts: Vec<Option<f64>> = histogram
+        .buckets
+        .iter()
+        .map(|b| Some(b.count as f64))
+        .collect();
+
+    println!();
+    println!("Distribution: {}", plot::sparkline(&counts));
+    print!(
+        "{}",
+        plot::vertical_histogram(&histogram, PLOT_HEIGHT, precision)
+    );
+    Ok(())
+}
+
+/// `--dp-epsilon`: print a noisy percentile, or nothing when too few values
+fn print_private(args: &Args, values: &[f64], epsilon: f64) -> Result<Option<sinks::Calculation>> {
+    let bounds = args.dp_bounds.as_deref().unwrap_or_default();
+    let [lower, upper] = bounds else {
+        anyhow::bail!("--dp-bounds takes a lower and an upper bound");
+    };
+    let options = outlier::PrivacyOptions {
+        epsilon,
+        lower: *lower,
+        upper: *upper,
+        min_count: args.dp_min_count,
+    };
+    let percentile = args.single_percentile("--dp-epsilon")?;
+    let report = outlier::private_percentiles(values, &[percentile], &options, &mut rand::rng())?;
+
+    let (Some(count), [result]) = (report.count, &report.percentiles[..]) else {
+        println!(
+            "Suppressed: fewer than {} values (differential privacy)",
+            args.dp_min_count
+        );
+        return Ok(None);
+    };
+    let label = outlier::format_percentile_label(percentile);
+    let value = outlier::round_to_precision(result.value, args.precision, args.rounding)?;
+    let precision = args.precision as usize;
+    println!("Number of values: ~{count} (noisy)");
+    println!("Privacy: epsilon = {epsilon}, values clamped to [{lower}, {upper}]");
+    println!("Percentile ({label}): {value:.precision$}");
+    Ok(Some(sinks::Calculation {
+        count: count as usize,
+        method: args.method,
+        engine: args.engine,
+        percentiles: vec![outlier::PercentileValue { percentile, value }],
+        transformed: None,
+    }))
+}
+
+/// Validate a `--values` entry as a number, keeping its text so `--integer`
+/// can parse it without going through a float
+fn parse_number(text: &str) -> Result<String, String> {
+    let text = text.trim();
+    text.parse::<f64>()
+        .map(|_| text.to_string())
+        .map_err(|e| e.to_string())
+}
+
+/// `--integer`: a percentile of whole numbers, never converted to floats
+fn run_integer(args: &Args, remote: Option<Vec<u8>>) -> Result<Option<sinks::Calculation>> {
+    use outlier::IntegerPercentile;
 
+    if args.output != OutputMode::Text || args.transform.is_some() || args.durations {
+        anyhow::bail!("--integer only applies to text output without --transform or --durations");
+    }
+    if args.dry_run || args.hdr_out.is_some() || args.plot {
+        anyhow::bail!("--integer can't be combined with --dry-run, --hdr-out or --plot");
+    }
+    let percentile = args.single_percentile("--integer")?;
+    let values: Vec<i64> = if let Some(ref file_path) = args.file {
+        let path = file_path.to_string_lossy();
+        let (bytes, name) = match remote {
+            Some(bytes) => (bytes, fetch::file_name(&path)),
+            None => (
+                std::fs::read(file_path).map_err(|e| {
+                    anyhow::anyhow!("Failed to open input file {}: {e}", file_path.display())
+                })?,
+                &*path,
+            ),
+        };
+        outlier::read_integers_from_bytes(
+            &bytes,
+            name,
+            &outlier::CsvOptions::for_locale(args.locale),
+        )?
+    } else if let Some(ref values) = args.values {
+        values
+            .iter()
+            .map(|v| {
+                v.parse()
+                    .map_err(|_| anyhow::anyhow!("--integer needs whole numbers, got {v:?}"))
+            })
+            .collect::<Result<_>>()?
+    } else {
+        anyhow::bail!("Must provide either --file or --values");
+    };
     if values.is_empty() {
         anyhow::bail!("No values provided");
     }
+    if args.skip_first >= values.len() {
+        anyhow::bail!(
+            "--skip-first {} leaves none of the {} values",
+            args.skip_first,
+            values.len()
+        );
+    }
+    let values = &values[args.skip_first..];
 
-    // Calculate percentile
-    let result = calculate_percentile(&values, args.percentile, args.method)?;
+    let label = outlier::format_percentile_label(percentile);
+    let result = outlier::calculate_integer_percentile(values, percentile, args.method)?;
+    let result = match result {
+        IntegerPercentile::Exact(_) => result,
+        IntegerPercentile::Interpolated(value) => IntegerPercentile::Interpolated(
+            outlier::round_to_precision(value, args.precision, args.rounding)?,
+        ),
+    };
+    println!("Number of values: {}", values.len());
+    println!("Method: {}", args.method);
+    match result {
+        IntegerPercentile::Exact(value) => println!("Percentile ({label}): {value}"),
+        IntegerPercentile::Interpolated(value) => {
+            let precision = args.precision as usize;
+            println!("Percentile ({label}): {value:.precision$}")
+        }
+    }
+    Ok(Some(sinks::Calculation {
+        count: values.len(),
+        method: args.method,
+        engine: args.engine,
+        percentiles: vec![outlier::PercentileValue {
+            percentile,
+            value: result.as_f64(),
+        }],
+        transformed: None,
+    }))
+}
 
+/// `outlier cache info` and `outlier cache clear`
+fn run_cache(action: CacheAction) -> Result<()> {
+    let cache = ValueCache::default_location()
+        .ok_or_else(|| anyhow::anyhow!("No cache directory: set OUTLIER_CACHE_DIR or HOME"))?;
+    match action {
+        CacheAction::Info => {
+            let (entries, bytes) = cache.usage()?;
+            println!("Cache directory: {}", cache.dir().display());
+            println!("Entries: {entries}");
+            println!("Size: {:.1} MiB", bytes as f64 / (1024.0 * 1024.0));
+        }
+        CacheAction::Clear => {
+            let (entries, bytes) = cache.clear()?;
+            println!(
+                "Removed {entries} entries ({:.1} MiB) from {}",
+                bytes as f64 / (1024.0 * 1024.0),
+                cache.dir().display()
+            );
+        }
+    }
+    Ok(())
+}
+
+/// Print the percentile table, returning its rows
+fn print_table(
+    args: &Args,
+    values: &[f64],
+    engine: &dyn outlier::PercentileEngine,
+) -> Result<Vec<outlier::PercentileValue>> {
+    use outlier::{DEFAULT_PERCENTILES, PercentileValue, round_to_precision};
+
+    if let (Some(warn), Some(crit)) = (args.warn_above, args.crit_above)
+        && warn > crit
+    {
+        anyhow::bail!("--warn-above must not be greater than --crit-above");
+    }
+
+    let mut percentiles = DEFAULT_PERCENTILES.to_vec();
+    for percentile in &args.percentiles {
+        if !percentiles.contains(percentile) {
+            percentiles.push(*percentile);
+        }
+    }
+    percentiles.sort_by(f64::total_cmp);
+    let rows = engine
+        .percentiles(values, &percentiles, args.method)?
+        .into_iter()
+        .zip(&percentiles)
+        .map(|(value, &percentile)| {
+            Ok(PercentileValue {
+                percentile,
+                value: round_to_precision(value, args.precision, args.rounding)?,
+            })
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    let thresholds = table::Thresholds {
+        warn: args.warn_above,
+        crit: args.crit_above,
+    };
     println!("Number of values: {}", values.len());
     println!("Method: {}", args.method);
-    println!("Percentile (P{}): {:.2}", args.percentile, result);
+    println!(
+        "{}",
+        table::percentile_table(
+            &rows,
+            args.precision as usize,
+            args.unit.as_deref(),
+            thresholds
+        )
+    );
+    Ok(rows)
+}
+
+/// Load a pipeline definition, run it, print the results and deliver them to its sinks
+async fn run_pipeline(file: &std::path::Path) -> Result<()> {
+    let text = std::fs::read_to_string(file)
+        .map_err(|e| anyhow::anyhow!("Failed to open pipeline {}: {e}", file.display()))?;
+    let pipeline = outlier::Pipeline::parse(&text, &file.to_string_lossy())?;
+    pipeline.validate()?;
+
+    let base_dir = file.parent().unwrap_or(std::path::Path::new("."));
+    let values = pipeline.load_values(base_dir, &outlier::CsvOptions::default())?;
+    let result = pipeline.run(values)?;
+    println!("{}", serde_json::to_string_pretty(&result)?);
+    sinks::deliver(
+        &result,
+        &sinks::pipeline_sinks(
+            &pipeline.sinks,
+            base_dir,
+            outlier::WebhookKey::from_env().as_ref(),
+        ),
+    )
+    .await
+}
+
+#[tracing::instrument(skip_all, fields(percentiles = ?args.percentiles))]
+fn run_stream(args: StreamArgs) -> Result<()> {
+    let estimate = match args.file.as_deref() {
+        Some(path) if path != std::path::Path::new("-") => {
+            let file = std::fs::File::open(path)
+                .map_err(|e| anyhow::anyhow!("Failed to open {}: {e}", path.display()))?;
+            outlier::estimate_percentiles(std::io::BufReader::new(file), &args.percentiles)?
+        }
+        _ => outlier::estimate_percentiles(std::io::stdin().lock(), &args.percentiles)?,
+    };
 
+    let precision = args.precision as usize;
+    println!("Number of values: {}", estimate.count);
+    println!("Method: p2 (estimated)");
+    for p in &estimate.percentiles {
+        let label = outlier::format_percentile_label(p.percentile);
+        println!("Percentile ({label}): {:.precision$}", p.value);
+    }
     Ok(())
 }
+
+#[tracing::instrument(skip_all, fields(strata = args.strata, method = %args.method))]
+fn run_stratify(args: StratifyArgs) -> Result<()> {
+    use outlier::stratify;
+
+    let (by, values) = read_csv_pairs(&args.file, args.locale, args.strict)?;
+
+    let result = stratify(&by, &values, args.strata, &args.percentiles, args.method)?;
+
+    let precision = args.precision as usize;
+    println!("Number of values: {}", result.count);
+    println!("Method: {}", result.method);
+    for (i, stratum) in result.strata.iter().enumerate() {
+        println!(
+            "Stratum {} [{:.*}, {:.*}{}: n={} {}",
+            i + 1,
+            precision,
+            stratum.lower,
+            precision,
+            stratum.upper,
+            if i + 1 == result.strata.len() {
+                "]"
+            } else {
+                ")"
+            },
+            stratum.count,
+            format_percentiles(&stratum.percentiles, precision)
+        );
+    }
+
+    Ok(())
+}
+
+#[tracing::instrument(skip_all, fields(percentiles = ?args.percentiles))]
+fn run_report(args: ReportArgs) -> Result<()> {
+    let values = read_report_input(&args.file, args.format, args.locale, args.strict)?;
+    if args.percentiles.iter().any(|p| !(0.0..=100.0).contains(p)) {
+        anyhow::bail!("Percentile must be between 0 and 100");
+    }
+
+    let report =
+        outlier::report::Report::build(&values, &args.percentiles, args.method, args.buckets)?;
+    let name = args
+        .file
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_else(|| args.file.display().to_string());
+    report.write(&args.out, &name)?;
+    println!(
+        "Wrote report of {} values to {}",
+        report.analysis.summary.count,
+        args.out.join("index.html").display()
+    );
+    Ok(())
+}
+
+#[tracing::instrument(skip_all, fields(curve = args.curve))]
+fn run_histogram(args: HistogramArgs) -> Result<()> {
+    let extension = |name: &str| {
+        args.out
+            .extension()
+            .is_some_and(|e| e.eq_ignore_ascii_case(name))
+    };
+    let png = extension("png");
+    if !png && !extension("svg") {
+        anyhow::bail!("Charts are drawn as SVG or PNG; give --out a .svg or .png path");
+    }
+    if png && !cfg!(feature = "charts") {
+        anyhow::bail!("PNG charts need a build with the charts feature");
+    }
+    let values = read_report_input(&args.file, args.format, args.locale, args.strict)?;
+    if args.percentiles.iter().any(|p| !(0.0..=100.0).contains(p)) {
+        anyhow::bail!("Percentile must be between 0 and 100");
+    }
+
+    let report =
+        outlier::report::Report::build(&values, &args.percentiles, args.method, args.buckets)?;
+    if png {
+        #[cfg(feature = "charts")]
+        outlier::charts::write_png(&report, args.curve, &args.out)?;
+    } else {
+        let svg = if args.curve {
+            report.curve_svg()
+        } else {
+            report.histogram_svg()
+        };
+        std::fs::write(&args.out, svg)
+            .map_err(|e| anyhow::anyhow!("Failed to write {}: {e}", args.out.display()))?;
+    }
+    println!("Wrote {}", args.out.display());
+    Ok(())
+}
+
+/// Values of a `report` or `histogram` input file
+fn read_report_input(
+    file: &std::path::Path,
+    format: outlier::InputFormat,
+    locale: outlier::NumberLocale,
+    strict: bool,
+) -> Result<Vec<f64>> {
+    let csv_options = outlier::CsvOptions {
+        strict,
+        ..outlier::CsvOptions::for_locale(locale)
+    };
+    outlier::read_values_from_file_with_format(file, format, &csv_options)
+}
+
+#[tracing::instrument(skip_all, fields(bucket_secs = args.bucket_secs, cumulative = args.cumulative))]
+fn run_timeseries(args: TimeseriesArgs) -> Result<()> {
+    use outlier::{percentiles_over_time, skip_warmup};
+
+    let (timestamps, values) = read_csv_pairs(&args.file, args.locale, args.strict)?;
+    let (timestamps, values) = skip_warmup(
+        &timestamps,
+        &values,
+        args.skip_first,
+        args.skip_duration.unwrap_or(0.0),
+    );
+    if timestamps.is_empty() {
+        anyhow::bail!("Warm-up exclusion leaves no values");
+    }
+    let result = percentiles_over_time(
+        &timestamps,
+        &values,
+        args.bucket_secs,
+        args.cumulative,
+        &args.percentiles,
+        args.method,
+    )?;
+
+    let precision = args.precision as usize;
+    println!("Number of values: {}", result.count);
+    println!("Method: {}", result.method);
+    if result.cumulative {
+        println!("Cumulative: yes");
+    }
+    for bucket in &result.buckets {
+        println!(
+            "[{:.*}, {:.*}): n={} {}",
+            precision,
+            bucket.start,
+            precision,
+            bucket.end,
+            bucket.count,
+            format_percentiles(&bucket.percentiles, precision)
+        );
+    }
+    if args.plot {
+        println!();
+        for (i, &percentile) in args.percentiles.iter().enumerate() {
+            let series: Vec<Option<f64>> = result
+                .buckets
+                .iter()
+                .map(|b| b.percentiles.get(i).map(|p| p.value))
+                .collect();
+            println!(
+                "{:>8} {}",
+                outlier::format_percentile_label(percentile),
+                plot::sparkline(&series)
+            );
+        }
+    }
+
+    Ok(())
+}
+
+/// Parse a duration such as `30s`, `2m`, `1h` or `500ms` (bare numbers are seconds)
+fn parse_duration_secs(text: &str) -> Result<f64, String> {
+    let text = text.trim();
+    let (number, scale) = if let Some(n) = text.strip_suffix("ms") {
+        (n, 0.001)
+    } else if let Some(n) = text.strip_suffix('s') {
+        (n, 1.0)
+    } else if let Some(n) = text.strip_suffix('m') {
+        (n, 60.0)
+    } else if let Some(n) = text.strip_suffix('h') {
+        (n, 3600.0)
+    } else {
+        (text, 1.0)
+    };
+    match number.trim().parse::<f64>() {
+        Ok(n) if n.is_finite() && n >= 0.0 => Ok(n * scale),
+        _ => Err(format!(
+            "invalid duration {text:?} (expected e.g. 30s, 2m, 1h, 500ms)"
+        )),
+    }
+}
+
+/// Read a two-column CSV file into aligned series
+fn read_csv_pairs(
+    path: &std::path::Path,
+    locale: outlier::NumberLocale,
+    strict: bool,
+) -> Result<(Vec<f64>, Vec<f64>)> {
+    use outlier::{CsvOptions, correlation::read_pairs_from_bytes_with_options};
+
+    let csv_options = CsvOptions {
+        strict,
+        ..CsvOptions::for_locale(locale)
+    };
+    let bytes = std::fs::read(path)?;
+    read_pairs_from_bytes_with_options(&bytes, &path.to_string_lossy(), &csv_options)
+}
+
+/// Format percentile results as `P50=1.00 P99=2.00`
+fn format_percentiles(percentiles: &[outlier::PercentileValue], precision: usize) -> String {
+    percentiles
+        .iter()
+        .map(|p| {
+            format!(
+                "{}={:.*}",
+                outlier::format_percentile_label(p.percentile),
+                precision,
+                p.value
+            )
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
@@ -0,0 +1,172 @@
+use rumqttc::{AsyncClient, Event, EventLoop, MqttOptions, Packet, QoS, SubscribeFilter};
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::{debug, info, warn};
+
+use crate::config::MqttConfig;
+use crate::sketches::{SketchStore, validate_name};
+
+/// Wait between reconnection attempts
+const RETRY_DELAY: Duration = Duration::from_secs(5);
+
+/// Requests the client can queue for the event loop
+const CLIENT_CAPACITY: usize = 16;
+
+/// Subscribe to the configured topics and feed each publish into the sketch
+/// of its topic, reconnecting whenever the broker goes away
+///
+/// Must be called from within a Tokio runtime; connecting happens in the
+/// background, so an unreachable broker doesn't stop the server starting.
+pub fn start(config: &MqttConfig, sketches: Arc<SketchStore>) -> anyhow::Result<()> {
+    let Some(host) = &config.host else {
+        return Ok(());
+    };
+    if config.topics.is_empty() {
+        anyhow::bail!("mqtt.host is set but mqtt.topics is empty");
+    }
+    let mut options = MqttOptions::new(&config.client_id, host, config.port);
+    options.set_keep_alive(Duration::from_secs(30));
+    if let Some(username) = &config.username {
+        options.set_credentials(username, config.password.clone().unwrap_or_default());
+    }
+    let (client, events) = AsyncClient::new(options, CLIENT_CAPACITY);
+    info!(
+        "Subscribing to {} MQTT topic filter(s) on {host}:{}",
+        config.topics.len(),
+        config.port
+    );
+    tokio::spawn(run(
+        client,
+        events,
+        config.topics.clone(),
+        config.field.clone(),
+        sketches,
+    ));
+    Ok(())
+}
+
+async fn run(
+    client: AsyncClient,
+    mut events: EventLoop,
+    topics: Vec<String>,
+    field: String,
+    sketches: Arc<SketchStore>,
+) {
+    loop {
+        match events.poll().await {
+            Ok(Event::Incoming(Packet::ConnAck(_))) => {
+                info!("Connected to MQTT broker");
+                // A clean session forgets subscriptions, so renew them on
+                // every connect
+                let filters = topics
+                    .iter()
+                    .map(|topic| SubscribeFilter::new(topic.clone(), QoS::AtMostOnce));
+                if let Err(e) = client.subscribe_many(filters).await {
+                    warn!("MQTT subscribe failed: {e}");
+                }
+            }
+            Ok(Event::Incoming(Packet::Publish(publish))) => {
+                if let Err(reason) = record(&sketches, &publish.topic, &publish.payload, &field) {
+                    debug!(topic = %publish.topic, "Dropped MQTT message: {reason}");
+                }
+            }
+            Ok(_) => {}
+            Err(e) => {
+                warn!("MQTT connection error: {e}; retrying in {RETRY_DELAY:?}");
+                tokio::time::sleep(RETRY_DELAY).await;
+            }
+        }
+    }
+}
+
+fn record(
+    sketches: &SketchStore,
+    topic: &str,
+    payload: &[u8],
+    field: &str,
+) -> Result<(), &'static str> {
+    let name = sketch_name(topic).ok_or("topic can't be a sketch name")?;
+    let value = extract_value(payload, field)?;
+    let sketch = sketches.get_or_create(&name).ok_or("too many sketches")?;
+    let mut dataset = sketch.lock().unwrap_or_else(|e| e.into_inner());
+    if !dataset.insert(value) {
+        return Err("value outside the expected range");
+    }
+    Ok(())
+}
+
+/// The sketch a topic feeds: its levels joined with `.` instead of `/`
+fn sketch_name(topic: &str) -> Option<String> {
+    let name = topic.trim_matches('/').replace('/', ".");
+    validate_name(&name).ok().map(|()| name)
+}
+
+/// The number at `field` (dotted for nested objects) of a JSON payload, or
+/// the payload itself when it is a bare number
+fn extract_value(payload: &[u8], field: &str) -> Result<f64, &'static str> {
+    let json: serde_json::Value =
+        serde_json::from_slice(payload).map_err(|_| "payload isn't JSON")?;
+    let value = if json.is_number() {
+        &json
+    } else {
+        field
+            .split('.')
+            .try_fold(&json, |value, key| value.get(key))
+            .ok_or("payload has no such field")?
+    };
+    value
+        .as_f64()
+        .filter(|v| v.is_finite())
+        .ok_or("field isn't a number")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn extracts_numeric_fields() {
+        assert_eq!(extract_value(br#"{"value": 21.5}"#, "value"), Ok(21.5));
+        assert_eq!(
+            extract_value(br#"{"reading": {"celsius": -3}}"#, "reading.celsius"),
+            Ok(-3.0)
+        );
+        assert_eq!(extract_value(b"17", "value"), Ok(17.0));
+        assert_eq!(
+            extract_value(br#"{"temp": 1}"#, "value"),
+            Err("payload has no such field")
+        );
+        assert_eq!(
+            extract_value(br#"{"value": "21.5"}"#, "value"),
+            Err("field isn't a number")
+        );
+        assert_eq!(extract_value(b"on", "value"), Err("payload isn't JSON"));
+    }
+
+    #[test]
+    fn topics_map_to_sketch_names() {
+        assert_eq!(
+            sketch_name("sensors/kitchen/temp").as_deref(),
+            Some("sensors.kitchen.temp")
+        );
+        assert_eq!(sketch_name("/gw/1/").as_deref(), Some("gw.1"));
+        assert_eq!(sketch_name("sensors/living room"), None);
+
+        let sketches = SketchStore::default();
+        record(&sketches, "sensors/a", br#"{"value": 2}"#, "value").unwrap();
+        record(&sketches, "sensors/a", b"4", "value").unwrap();
+        assert_eq!(sketches.list()[0].name, "sensors.a");
+        assert_eq!(sketches.list()[0].count, 2);
+    }
+
+    #[tokio::test]
+    async fn start_needs_topics() {
+        let sketches = Arc::new(SketchStore::default());
+        assert!(start(&MqttConfig::default(), Arc::clone(&sketches)).is_ok());
+        let config = MqttConfig {
+            host: Some("localhost".to_string()),
+            ..MqttConfig::default()
+        };
+        assert!(start(&config, sketches).is_err());
+    }
+}
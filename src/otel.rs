@@ -0,0 +1,249 @@
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use std::collections::BTreeMap;
+
+const MAX_VALUES: u64 = 10_000_000; // 10 million
+
+/// Scales the OpenTelemetry specification allows
+const SCALES: std::ops::RangeInclusive<i32> = -10..=20;
+
+/// `AGGREGATION_TEMPORALITY_CUMULATIVE` in OTLP
+const CUMULATIVE: i64 = 2;
+
+/// Values reconstructed from the exponential histograms in OTLP/JSON metrics,
+/// as written by the collector's `file` exporter or sent to `/v1/metrics`
+///
+/// Each bucket's count is spread evenly across the bucket on a log scale, so
+/// percentiles interpolate within buckets rather than snapping to their edges,
+/// and the results are clamped to each data point's `min` and `max` when
+/// present. Zero-bucket counts become zeros. Delta data points are summed;
+/// for cumulative ones only the latest point of each attribute set counts.
+///
+/// The input may be one request or several, one per line, but must hold a
+/// single metric, as values of different metrics can't be mixed.
+pub fn read_exponential_histograms(bytes: &[u8]) -> Result<Vec<f64>> {
+    let mut metric_name: Option<String> = None;
+    let mut delta = Vec::new();
+    let mut cumulative: BTreeMap<String, DataPoint> = BTreeMap::new();
+    for request in serde_json::Deserializer::from_slice(bytes).into_iter::<ExportRequest>() {
+        let request = request.context("Failed to parse OpenTelemetry metrics JSON")?;
+        let metrics = request
+            .resource_metrics
+            .into_iter()
+            .flat_map(|r| r.scope_metrics)
+            .flat_map(|s| s.metrics);
+        for metric in metrics {
+            let Some(histogram) = metric.exponential_histogram else {
+                continue;
+            };
+            match &metric_name {
+                Some(name) if *name != metric.name => anyhow::bail!(
+                    "OpenTelemetry input holds exponential histograms of several metrics \
+                     ({name:?} and {:?}); export one metric per file",
+                    metric.name
+                ),
+                Some(_) => {}
+                None => metric_name = Some(metric.name),
+            }
+            if !histogram.aggregation_temporality.is_cumulative() {
+                delta.extend(histogram.data_points);
+                continue;
+            }
+            for point in histogram.data_points {
+                let series = point.attributes.to_string();
+                let newer = cumulative
+                    .get(&series)
+                    .is_none_or(|kept| point.time_unix_nano.0 >= kept.time_unix_nano.0);
+                if newer {
+                    cumulative.insert(series, point);
+                }
+            }
+        }
+    }
+
+    let points: Vec<DataPoint> = delta.into_iter().chain(cumulative.into_values()).collect();
+    let total = points
+        .iter()
+        .map(DataPoint::count)
+        .fold(0u64, u64::saturating_add);
+    if total > MAX_VALUES {
+        anyhow::bail!(
+            "Input dataset exceeds the limit of {} values. Aborting.",
+            MAX_VALUES
+        );
+    }
+    if total == 0 {
+        anyhow::bail!("No exponential histogram data points found in OpenTelemetry metrics");
+    }
+
+    let mut values = Vec::with_capacity(total as usize);
+    for point in &points {
+        point.expand(&mut values)?;
+    }
+    Ok(values)
+}
+
+/// `ExportMetricsServiceRequest`
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ExportRequest {
+    #[serde(default)]
+    resource_metrics: Vec<ResourceMetrics>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ResourceMetrics {
+    #[serde(default)]
+    scope_metrics: Vec<ScopeMetrics>,
+}
+
+#[derive(Deserialize)]
+struct ScopeMetrics {
+    #[serde(default)]
+    metrics: Vec<Metric>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Metric {
+    #[serde(default)]
+    name: String,
+    exponential_histogram: Option<ExponentialHistogram>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ExponentialHistogram {
+    #[serde(default)]
+    data_points: Vec<DataPoint>,
+    #[serde(default)]
+    aggregation_temporality: Temporality,
+}
+
+/// Aggregation temporality, which OTLP/JSON encoders write as the enum's
+/// number or its name
+#[derive(Deserialize, Default)]
+#[serde(untagged)]
+enum Temporality {
+    #[default]
+    Unspecified,
+    Number(i64),
+    Name(String),
+}
+
+impl Temporality {
+    fn is_cumulative(&self) -> bool {
+        match self {
+            Temporality::Number(n) => *n == CUMULATIVE,
+            Temporality::Name(name) => name == "AGGREGATION_TEMPORALITY_CUMULATIVE",
+            Temporality::Unspecified => false,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct DataPoint {
+    #[serde(default)]
+    attributes: serde_json::Value,
+    #[serde(default)]
+    time_unix_nano: Uint64,
+    #[serde(default)]
+    scale: i32,
+    #[serde(default)]
+    zero_count: Uint64,
+    #[serde(default)]
+    positive: Buckets,
+    #[serde(default)]
+    negative: Buckets,
+    min: Option<f64>,
+    max: Option<f64>,
+}
+
+impl DataPoint {
+    /// Values the point's buckets hold
+    fn count(&self) -> u64 {
+        [&self.positive, &self.negative]
+            .iter()
+            .flat_map(|b| &b.bucket_counts)
+            .fold(self.zero_count.0, |sum, c| sum.saturating_add(c.0))
+    }
+
+    fn expand(&self, values: &mut Vec<f64>) -> Result<()> {
+        if !SCALES.contains(&self.scale) {
+            anyhow::bail!(
+                "Failed to parse OpenTelemetry exponential histogram: scale {} is outside {}..={}",
+                self.scale,
+                SCALES.start(),
+                SCALES.end()
+            );
+        }
+        let lowest = self.min.unwrap_or(f64::NEG_INFINITY);
+        let highest = self.max.unwrap_or(f64::INFINITY);
+        let start = values.len();
+        values.extend(std::iter::repeat_n(0.0, self.zero_count.0 as usize));
+        self.positive.expand(self.scale, 1.0, values);
+        self.negative.expand(self.scale, -1.0, values);
+        if lowest <= highest {
+            for value in &mut values[start..] {
+                *value = value.clamp(lowest, highest);
+            }
+        }
+        Ok(())
+    }
+}
+
+#[derive(Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+struct Buckets {
+    #[serde(default)]
+    offset: i32,
+    #[serde(default)]
+    bucket_counts: Vec<Uint64>,
+}
+
+impl Buckets {
+    /// Spread each bucket's count across it on a log scale
+    ///
+    /// Bucket `index` holds magnitudes in `(base^index, base^(index + 1)]`
+    /// where `base = 2^(2^-scale)`.
+    fn expand(&self, scale: i32, sign: f64, values: &mut Vec<f64>) {
+        let width = 2f64.powi(-scale);
+        for (i, count) in self.bucket_counts.iter().enumerate() {
+            let index = f64::from(self.offset) + i as f64;
+            let count = count.0;
+            values.extend((0..count).map(|k| {
+                let position = (k as f64 + 0.5) / count as f64;
+                sign * ((index + position) * width).exp2()
+            }));
+        }
+    }
+}
+
+/// A 64-bit integer, which OTLP/JSON writes as a string but some encoders
+/// write as a number
+#[derive(Deserialize, Default, Clone, Copy)]
+#[serde(try_from = "Uint64Repr")]
+struct Uint64(u64);
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum Uint64Repr {
+    Number(u64),
+    Text(String),
+}
+
+impl TryFrom<Uint64Repr> for Uint64 {
+    type Error = String;
+
+    fn try_from(repr: Uint64Repr) -> Result<Self, String> {
+        match repr {
+            Uint64Repr::Number(n) => Ok(Uint64(n)),
+            Uint64Repr::Text(text) => text
+                .parse()
+                .map(Uint64)
+                .map_err(|_| format!("invalid 64-bit integer {text:?}")),
+        }
+    }
+}
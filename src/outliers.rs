@@ -0,0 +1,296 @@
+use crate::annotate::{DEFAULT_FENCE, tukey_fences};
+use crate::{
+    FieldError, FieldReader, OutlierResponse, OutlierValue, PercentileMethod, ValidationErrors,
+    percentile_of_sorted, sort_values,
+};
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+#[cfg(feature = "server")]
+use utoipa::ToSchema;
+
+/// Standard deviations from the mean beyond which [`detect_outliers_zscore`]
+/// flags a value by default
+pub const DEFAULT_ZSCORE_THRESHOLD: f64 = 3.0;
+
+/// Modified z-score beyond which [`detect_outliers_mad`] flags a value by
+/// default, as recommended by Iglewicz and Hoaglin
+pub const DEFAULT_MAD_CUTOFF: f64 = 3.5;
+
+/// Scales the median absolute deviation to the standard deviation of normally
+/// distributed values (the 0.75 quantile of the standard normal)
+const MAD_SCALE: f64 = 0.6745;
+
+/// Test used to flag outliers
+#[cfg_attr(feature = "server", derive(ToSchema))]
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize, Default)]
+#[serde(rename_all = "snake_case")]
+#[cfg_attr(feature = "cli", clap(rename_all = "snake_case"))]
+pub enum OutlierMethod {
+    /// Outside Tukey's fences, `threshold` interquartile ranges beyond the
+    /// quartiles (default, threshold 1.5)
+    #[default]
+    Iqr,
+    /// More than `threshold` standard deviations from the mean (threshold 3.0)
+    Zscore,
+    /// Modified z-score from the median absolute deviation beyond `threshold`
+    /// (threshold 3.5); robust for heavy-tailed data such as latencies
+    Mad,
+}
+
+impl fmt::Display for OutlierMethod {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            OutlierMethod::Iqr => write!(f, "iqr"),
+            OutlierMethod::Zscore => write!(f, "zscore"),
+            OutlierMethod::Mad => write!(f, "mad"),
+        }
+    }
+}
+
+impl OutlierMethod {
+    /// Threshold used when a caller doesn't choose one
+    pub fn default_threshold(self) -> f64 {
+        match self {
+            OutlierMethod::Iqr => DEFAULT_FENCE,
+            OutlierMethod::Zscore => DEFAULT_ZSCORE_THRESHOLD,
+            OutlierMethod::Mad => DEFAULT_MAD_CUTOFF,
+        }
+    }
+
+    /// Flag the outliers in `values`, at the method's default threshold
+    /// unless `threshold` is given
+    ///
+    /// # Examples
+    /// ```
+    /// use outlier::OutlierMethod;
+    ///
+    /// let values = [10.0, 12.0, 11.0, 13.0, 10.0, 11.0, 900.0];
+    /// for method in [OutlierMethod::Iqr, OutlierMethod::Mad] {
+    ///     let response = method.detect(&values, None).unwrap();
+    ///     assert_eq!(response.outliers[0].value, 900.0);
+    /// }
+    /// ```
+    pub fn detect(self, values: &[f64], threshold: Option<f64>) -> Result<OutlierResponse> {
+        let threshold = threshold.unwrap_or_else(|| self.default_threshold());
+        match self {
+            OutlierMethod::Iqr => detect_outliers_iqr(values, threshold),
+            OutlierMethod::Zscore => Ok(detect_outliers_zscore(values, threshold)?.response),
+            OutlierMethod::Mad => detect_outliers_mad(values, threshold),
+        }
+    }
+}
+
+/// Values outside Tukey's fences `[Q1 - k*IQR, Q3 + k*IQR]`, with their
+/// positions in `values`
+///
+/// `k` is usually [`DEFAULT_FENCE`]; 3.0 flags only "far out" values.
+///
+/// # Examples
+/// ```
+/// use outlier::detect_outliers_iqr;
+///
+/// let response = detect_outliers_iqr(&[1.0, 2.0, 3.0, 4.0, 100.0], 1.5).unwrap();
+/// assert_eq!(response.outliers.len(), 1);
+/// assert_eq!(response.outliers[0].index, 4);
+/// ```
+pub fn detect_outliers_iqr(values: &[f64], k: f64) -> Result<OutlierResponse> {
+    if !(k.is_finite() && k >= 0.0) {
+        anyhow::bail!("Outlier fence k must be a non-negative number");
+    }
+    let (lower_bound, upper_bound) = tukey_fences(&sort_values(values), k)?;
+
+    Ok(OutlierResponse {
+        count: values.len(),
+        lower_bound,
+        upper_bound,
+        outliers: values
+            .iter()
+            .enumerate()
+            .filter(|(_, v)| **v < lower_bound || **v > upper_bound)
+            .map(|(index, &value)| OutlierValue { index, value })
+            .collect(),
+    })
+}
+
+/// Outliers found by [`detect_outliers_zscore`] and the values left over
+#[derive(Debug, Clone, PartialEq)]
+pub struct OutlierSplit {
+    /// The bounds and the flagged values
+    pub response: OutlierResponse,
+    /// Values within the bounds, in input order, ready for further
+    /// percentile calculations
+    pub clean: Vec<f64>,
+}
+
+/// Values whose z-score, using the sample standard deviation, is beyond
+/// `threshold` in either direction, with the values that remain
+///
+/// `threshold` is usually [`DEFAULT_ZSCORE_THRESHOLD`]. When every value is
+/// the same nothing is flagged.
+///
+/// # Examples
+/// ```
+/// use outlier::{calculate_percentile, detect_outliers_zscore, PercentileMethod};
+///
+/// let mut values = vec![10.0; 20];
+/// values.push(1000.0);
+/// let split = detect_outliers_zscore(&values, 3.0).unwrap();
+/// assert_eq!(split.response.outliers[0].index, 20);
+/// let p99 = calculate_percentile(&split.clean, 99.0, PercentileMethod::Linear).unwrap();
+/// assert_eq!(p99, 10.0);
+/// ```
+pub fn detect_outliers_zscore(values: &[f64], threshold: f64) -> Result<OutlierSplit> {
+    if values.is_empty() {
+        anyhow::bail!("Cannot detect outliers in an empty dataset");
+    }
+    if !(threshold.is_finite() && threshold >= 0.0) {
+        anyhow::bail!("Z-score threshold must be a non-negative number");
+    }
+    let count = values.len() as f64;
+    let mean = values.iter().sum::<f64>() / count;
+    let std = if values.len() > 1 {
+        let sum_sq: f64 = values.iter().map(|v| (v - mean).powi(2)).sum();
+        (sum_sq / (count - 1.0)).sqrt()
+    } else {
+        0.0
+    };
+    let (lower_bound, upper_bound) = (mean - threshold * std, mean + threshold * std);
+
+    let mut outliers = Vec::new();
+    let mut clean = Vec::with_capacity(values.len());
+    for (index, &value) in values.iter().enumerate() {
+        if std > 0.0 && (value < lower_bound || value > upper_bound) {
+            outliers.push(OutlierValue { index, value });
+        } else {
+            clean.push(value);
+        }
+    }
+
+    Ok(OutlierSplit {
+        response: OutlierResponse {
+            count: values.len(),
+            lower_bound,
+            upper_bound,
+            outliers,
+        },
+        clean,
+    })
+}
+
+/// Values whose modified z-score `0.6745 * (x - median) / MAD` is beyond
+/// `cutoff` in either direction, where MAD is the median absolute deviation
+/// from the median
+///
+/// Unlike [`detect_outliers_zscore`], a few extreme values barely move the
+/// median and MAD, so they can't hide each other. `cutoff` is usually
+/// [`DEFAULT_MAD_CUTOFF`]. When more than half the values are equal the MAD
+/// is zero and nothing is flagged.
+///
+/// # Examples
+/// ```
+/// use outlier::detect_outliers_mad;
+///
+/// let response = detect_outliers_mad(&[10.0, 12.0, 11.0, 13.0, 900.0, 950.0], 3.5).unwrap();
+/// assert_eq!(response.outliers.len(), 2);
+/// ```
+pub fn detect_outliers_mad(values: &[f64], cutoff: f64) -> Result<OutlierResponse> {
+    if !(cutoff.is_finite() && cutoff >= 0.0) {
+        anyhow::bail!("MAD cutoff must be a non-negative number");
+    }
+    let median = percentile_of_sorted(&sort_values(values), 50.0, PercentileMethod::Linear)?;
+    let deviations: Vec<f64> = values.iter().map(|v| (v - median).abs()).collect();
+    let mad = percentile_of_sorted(&sort_values(&deviations), 50.0, PercentileMethod::Linear)?;
+    let reach = cutoff * mad / MAD_SCALE;
+    let (lower_bound, upper_bound) = (median - reach, median + reach);
+
+    Ok(OutlierResponse {
+        count: values.len(),
+        lower_bound,
+        upper_bound,
+        outliers: values
+            .iter()
+            .enumerate()
+            .filter(|(_, v)| mad > 0.0 && (**v < lower_bound || **v > upper_bound))
+            .map(|(index, &value)| OutlierValue { index, value })
+            .collect(),
+    })
+}
+
+/// Request structure for the outliers API endpoint
+#[cfg_attr(feature = "server", derive(ToSchema))]
+#[derive(Debug, Deserialize, Serialize)]
+pub struct OutlierRequest {
+    /// Array of numerical values
+    pub values: Vec<f64>,
+    /// Test used to flag outliers (defaults to iqr)
+    #[serde(default)]
+    pub method: OutlierMethod,
+    /// Fence `k` for iqr, standard deviations for zscore or the modified
+    /// z-score cutoff for mad (defaults to 1.5, 3.0 and 3.5)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub threshold: Option<f64>,
+}
+
+impl OutlierRequest {
+    /// Validate the request, collecting every problem rather than stopping at the first
+    pub fn validate(&self) -> std::result::Result<(), ValidationErrors> {
+        let mut errors = Vec::new();
+
+        if self.values.is_empty() {
+            errors.push(FieldError::new(
+                "values",
+                "empty",
+                "Cannot detect outliers in an empty dataset",
+            ));
+        } else if self.values.iter().any(|v| !v.is_finite()) {
+            errors.push(FieldError::new(
+                "values",
+                "non_finite",
+                "Values must be finite numbers",
+            ));
+        }
+        if self.threshold.is_some_and(|t| !(t.is_finite() && t >= 0.0)) {
+            errors.push(FieldError::new(
+                "threshold",
+                "out_of_range",
+                "Threshold must be a non-negative number",
+            ));
+        }
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(ValidationErrors(errors))
+        }
+    }
+
+    /// Build a request from a JSON object, reporting type errors for every field
+    /// together with the semantic checks from [`OutlierRequest::validate`]
+    pub fn from_json_value(
+        value: serde_json::Value,
+    ) -> std::result::Result<Self, ValidationErrors> {
+        let mut reader = FieldReader::new(value)?;
+        let request = Self {
+            values: reader
+                .required(
+                    "values",
+                    "invalid_type",
+                    "Values must be an array of numbers",
+                    "Values are required",
+                )
+                .unwrap_or_default(),
+            method: reader
+                .optional(
+                    "method",
+                    "invalid_value",
+                    "Method must be one of: iqr, zscore, mad",
+                )
+                .unwrap_or_default(),
+            threshold: reader.optional("threshold", "invalid_type", "Threshold must be a number"),
+        };
+        reader.finish(request, Self::validate)
+    }
+}
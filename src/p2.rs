@@ -0,0 +1,217 @@
+use crate::PercentileValue;
+use anyhow::{Context, Result};
+use std::io::BufRead;
+
+/// Streaming percentile estimate using the P² algorithm (Jain and Chlamtac,
+/// 1985)
+///
+/// Five markers track the minimum, the maximum, the target percentile and
+/// the percentiles halfway to either end. Each observation moves the markers
+/// along a piecewise-parabolic fit of the distribution, so memory and time
+/// per value stay constant however many values are observed. The first five
+/// values are kept exactly and give exact results.
+///
+/// # Examples
+/// ```
+/// use outlier::P2Estimator;
+///
+/// let mut p99 = P2Estimator::new(99.0).unwrap();
+/// for i in 0..100_000 {
+///     p99.observe(f64::from(i % 1000));
+/// }
+/// assert_eq!(p99.count(), 100_000);
+/// assert!((p99.quantile().unwrap() - 990.0).abs() < 5.0);
+/// ```
+#[derive(Debug, Clone)]
+pub struct P2Estimator {
+    /// Target percentile as a fraction
+    p: f64,
+    /// Marker heights
+    heights: [f64; 5],
+    /// Marker positions, 0-based ranks
+    positions: [f64; 5],
+    /// Where each marker should be
+    desired: [f64; 5],
+    /// How far each desired position moves per observation
+    increments: [f64; 5],
+    count: u64,
+}
+
+impl P2Estimator {
+    /// An estimator for `percentile` (0-100)
+    pub fn new(percentile: f64) -> Result<Self> {
+        if !(0.0..=100.0).contains(&percentile) {
+            anyhow::bail!("Percentile must be between 0 and 100");
+        }
+        let p = percentile / 100.0;
+        Ok(Self {
+            p,
+            heights: [0.0; 5],
+            positions: [0.0, 1.0, 2.0, 3.0, 4.0],
+            desired: [0.0, 2.0 * p, 4.0 * p, 2.0 + 2.0 * p, 4.0],
+            increments: [0.0, p / 2.0, p, (1.0 + p) / 2.0, 1.0],
+            count: 0,
+        })
+    }
+
+    /// The target percentile (0-100)
+    pub fn percentile(&self) -> f64 {
+        self.p * 100.0
+    }
+
+    /// Number of values observed
+    pub fn count(&self) -> u64 {
+        self.count
+    }
+
+    /// Add a value to the estimate; NaN is ignored
+    pub fn observe(&mut self, value: f64) {
+        if value.is_nan() {
+            return;
+        }
+        if self.count < 5 {
+            self.heights[self.count as usize] = value;
+            self.count += 1;
+            if self.count == 5 {
+                self.heights
+                    .sort_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
+            }
+            return;
+        }
+        self.count += 1;
+
+        // The cell the value lands in, widening the ends if it's a new extreme
+        let h = &mut self.heights;
+        let cell = if value < h[0] {
+            h[0] = value;
+            0
+        } else if value >= h[4] {
+            h[4] = value;
+            3
+        } else {
+            (1..4).find(|&i| value < h[i]).map_or(3, |i| i - 1)
+        };
+        for position in &mut self.positions[cell + 1..] {
+            *position += 1.0;
+        }
+        for (desired, increment) in self.desired.iter_mut().zip(self.increments) {
+            *desired += increment;
+        }
+
+        for i in 1..4 {
+            let offset = self.desired[i] - self.positions[i];
+            let room_above = self.positions[i + 1] - self.positions[i];
+            let room_below = self.positions[i - 1] - self.positions[i];
+            if (offset >= 1.0 && room_above > 1.0) || (offset <= -1.0 && room_below < -1.0) {
+                let step = offset.signum();
+                let parabolic = self.parabolic(i, step);
+                self.heights[i] =
+                    if self.heights[i - 1] < parabolic && parabolic < self.heights[i + 1] {
+                        parabolic
+                    } else {
+                        self.linear(i, step)
+                    };
+                self.positions[i] += step;
+            }
+        }
+    }
+
+    /// The current estimate, or `None` before any value is observed
+    ///
+    /// P0 and P100 are the exact minimum and maximum.
+    pub fn quantile(&self) -> Option<f64> {
+        match self.count {
+            0 => None,
+            // The markers only start tracking the target from the sixth value
+            1..=5 => {
+                let mut seen = self.heights[..self.count as usize].to_vec();
+                seen.sort_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
+                crate::percentile_of_sorted(
+                    &seen,
+                    self.percentile(),
+                    crate::PercentileMethod::Linear,
+                )
+                .ok()
+            }
+            _ if self.p == 0.0 => Some(self.heights[0]),
+            _ if self.p == 1.0 => Some(self.heights[4]),
+            _ => Some(self.heights[2]),
+        }
+    }
+
+    /// Piecewise-parabolic prediction of marker `i` moved by `step`
+    fn parabolic(&self, i: usize, step: f64) -> f64 {
+        let (q, n) = (&self.heights, &self.positions);
+        q[i] + step / (n[i + 1] - n[i - 1])
+            * ((n[i] - n[i - 1] + step) * (q[i + 1] - q[i]) / (n[i + 1] - n[i])
+                + (n[i + 1] - n[i] - step) * (q[i] - q[i - 1]) / (n[i] - n[i - 1]))
+    }
+
+    /// Linear prediction of marker `i` moved by `step`, toward its neighbour
+    fn linear(&self, i: usize, step: f64) -> f64 {
+        let j = if step > 0.0 { i + 1 } else { i - 1 };
+        let (q, n) = (&self.heights, &self.positions);
+        q[i] + step * (q[j] - q[i]) / (n[j] - n[i])
+    }
+}
+
+/// Estimates from [`estimate_percentiles`]
+#[derive(Debug, Clone, PartialEq)]
+pub struct StreamEstimate {
+    /// Number of values read
+    pub count: u64,
+    /// Estimated value of each requested percentile, in request order
+    pub percentiles: Vec<PercentileValue>,
+}
+
+/// Estimate percentiles of one number per line with a [`P2Estimator`] each,
+/// never holding more than a line in memory
+///
+/// Blank lines are skipped; anything else that isn't a finite number fails
+/// with its line number.
+///
+/// # Examples
+/// ```
+/// use outlier::estimate_percentiles;
+///
+/// let estimate = estimate_percentiles("3\n1\n\n2\n".as_bytes(), &[50.0]).unwrap();
+/// assert_eq!(estimate.count, 3);
+/// assert_eq!(estimate.percentiles[0].value, 2.0);
+/// ```
+pub fn estimate_percentiles(reader: impl BufRead, percentiles: &[f64]) -> Result<StreamEstimate> {
+    let mut estimators = percentiles
+        .iter()
+        .map(|&p| P2Estimator::new(p))
+        .collect::<Result<Vec<_>>>()?;
+    let mut count = 0;
+    for (number, line) in reader.lines().enumerate() {
+        let line = line.with_context(|| format!("Failed to read line {}", number + 1))?;
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+        let value: f64 = line
+            .parse()
+            .ok()
+            .filter(|v: &f64| v.is_finite())
+            .with_context(|| format!("Line {} is not a finite number: {line:?}", number + 1))?;
+        for estimator in &mut estimators {
+            estimator.observe(value);
+        }
+        count += 1;
+    }
+    if count == 0 {
+        anyhow::bail!("No values provided");
+    }
+
+    Ok(StreamEstimate {
+        count,
+        percentiles: estimators
+            .iter()
+            .map(|e| PercentileValue {
+                percentile: e.percentile(),
+                value: e.quantile().unwrap_or_default(),
+            })
+            .collect(),
+    })
+}
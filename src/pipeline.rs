@@ -0,0 +1,535 @@
+use crate::report::Summary;
+use crate::{
+    CsvOptions, DEFAULT_PERCENTILES, FieldError, FittedTransform, HistogramResponse, InputFormat,
+    OutlierResponse, PercentileMethod, PercentileValue, ValidationErrors, ValueTransform,
+    calculate_percentiles_with, detect_outliers_iqr, format_percentile_label, histogram,
+    read_values_from_file_with_format,
+};
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+#[cfg(feature = "server")]
+use utoipa::ToSchema;
+
+/// A reusable chain of source → filters → transforms → statistics → sinks
+///
+/// Written as TOML or JSON, e.g.
+///
+/// ```toml
+/// name = "checkout-latency"
+///
+/// [source]
+/// type = "file"
+/// path = "latencies.csv"
+///
+/// [[filters]]
+/// type = "range"
+/// max = 60000
+///
+/// [[statistics]]
+/// type = "percentiles"
+/// percentiles = [50, 99]
+/// ```
+#[cfg_attr(feature = "server", derive(ToSchema))]
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct Pipeline {
+    /// Name reported with the results and in metrics labels
+    pub name: String,
+    /// Where the values come from
+    pub source: Source,
+    /// Applied in order before any transform
+    #[serde(default)]
+    pub filters: Vec<Filter>,
+    /// Applied in order to the filtered values
+    #[serde(default)]
+    pub transforms: Vec<Transform>,
+    /// Statistics to compute; each kind at most once
+    pub statistics: Vec<Statistic>,
+    /// Where to deliver the results (CLI only)
+    #[serde(default)]
+    pub sinks: Vec<Sink>,
+}
+
+/// Where a pipeline reads its values from
+#[cfg_attr(feature = "server", derive(ToSchema))]
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case", deny_unknown_fields)]
+pub enum Source {
+    /// An input file, relative to the pipeline definition (CLI only)
+    File {
+        path: String,
+        #[serde(default)]
+        format: InputFormat,
+    },
+    /// Values given inline
+    Values { values: Vec<f64> },
+}
+
+/// A step that drops values
+#[cfg_attr(feature = "server", derive(ToSchema))]
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case", deny_unknown_fields)]
+pub enum Filter {
+    /// Keep values within `[min, max]`; either bound may be omitted
+    Range { min: Option<f64>, max: Option<f64> },
+    /// Drop this many leading values as warm-up
+    SkipFirst { count: usize },
+    /// Drop NaN and infinite values
+    Finite,
+}
+
+/// A step that maps every value
+#[cfg_attr(feature = "server", derive(ToSchema))]
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case", deny_unknown_fields)]
+pub enum Transform {
+    /// Multiply by `factor`, e.g. `0.001` for milliseconds to seconds
+    Scale { factor: f64 },
+    /// Base-10 logarithm; values must be positive
+    Log10,
+    /// Natural logarithm; values must be positive
+    Ln,
+    /// Box-Cox power transform; `lambda` is estimated from the values when
+    /// omitted, and values must be positive
+    BoxCox {
+        #[serde(default)]
+        lambda: Option<f64>,
+    },
+}
+
+/// A statistic to compute over the transformed values
+#[cfg_attr(feature = "server", derive(ToSchema))]
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case", deny_unknown_fields)]
+pub enum Statistic {
+    /// Percentiles (defaults to 50, 90, 95, 99 with linear interpolation)
+    Percentiles {
+        #[serde(default = "default_percentiles")]
+        percentiles: Vec<f64>,
+        #[serde(default)]
+        method: PercentileMethod,
+    },
+    /// Values outside Tukey's fences `[Q1 - k*IQR, Q3 + k*IQR]` (k defaults to 1.5)
+    Outliers {
+        #[serde(default = "default_fence")]
+        k: f64,
+    },
+    /// Equal-width histogram (defaults to 20 buckets)
+    Histogram {
+        #[serde(default = "default_buckets")]
+        buckets: usize,
+    },
+}
+
+/// Where the CLI delivers a pipeline's results
+#[cfg_attr(feature = "server", derive(ToSchema))]
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case", deny_unknown_fields)]
+pub enum Sink {
+    /// Write the results as JSON, relative to the pipeline definition
+    File { path: String },
+    /// POST the results as JSON
+    Webhook { url: String },
+    /// Write Prometheus text-format metrics, e.g. for node_exporter's textfile collector
+    Metrics {
+        path: String,
+        #[serde(default = "default_metric_prefix")]
+        prefix: String,
+    },
+    /// PUT Prometheus text-format metrics to a Pushgateway group URL
+    MetricsPush {
+        url: String,
+        #[serde(default = "default_metric_prefix")]
+        prefix: String,
+    },
+    /// Email a summary through the SMTP server in `OUTLIER_SMTP_URL` (needs
+    /// the `email` feature)
+    Email {
+        from: String,
+        to: Vec<String>,
+        /// Defaults to the pipeline name and value count
+        #[serde(default)]
+        subject: Option<String>,
+    },
+}
+
+fn default_percentiles() -> Vec<f64> {
+    DEFAULT_PERCENTILES.to_vec()
+}
+
+fn default_fence() -> f64 {
+    crate::annotate::DEFAULT_FENCE
+}
+
+fn default_buckets() -> usize {
+    20
+}
+
+fn default_metric_prefix() -> String {
+    "outlier".to_string()
+}
+
+/// What a pipeline run produced
+#[cfg_attr(feature = "server", derive(ToSchema))]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PipelineResult {
+    /// The pipeline's name
+    pub name: String,
+    /// Values left after filtering
+    pub count: usize,
+    /// Values removed by filters
+    pub filtered: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub percentiles: Option<Vec<PercentileValue>>,
+    /// `percentiles` mapped back through the transforms to the source's units
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub back_transformed: Option<Vec<PercentileValue>>,
+    /// Lambda used by the Box-Cox transform, estimated unless configured
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub box_cox_lambda: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub outliers: Option<OutlierResponse>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub histogram: Option<HistogramResponse>,
+}
+
+impl Pipeline {
+    /// Parse a definition, as TOML unless the file name ends in `.json`
+    pub fn parse(text: &str, filename: &str) -> Result<Self> {
+        if filename.ends_with(".json") {
+            serde_json::from_str(text).context("Failed to parse pipeline JSON")
+        } else {
+            toml::from_str(text).context("Failed to parse pipeline TOML")
+        }
+    }
+
+    /// Check the definition, collecting every problem rather than stopping at the first
+    pub fn validate(&self) -> std::result::Result<(), ValidationErrors> {
+        let mut errors = Vec::new();
+
+        if self.name.trim().is_empty() {
+            errors.push(FieldError::new(
+                "name",
+                "empty",
+                "Pipeline name is required",
+            ));
+        }
+        for filter in &self.filters {
+            if let Filter::Range {
+                min: Some(min),
+                max: Some(max),
+            } = filter
+                && min > max
+            {
+                errors.push(FieldError::new(
+                    "filters",
+                    "out_of_range",
+                    "Range filter min must not exceed max",
+                ));
+            }
+        }
+        for transform in &self.transforms {
+            match transform {
+                Transform::Scale { factor } if !factor.is_finite() || *factor == 0.0 => {
+                    errors.push(FieldError::new(
+                        "transforms",
+                        "non_finite",
+                        "Scale factor must be a finite, non-zero number",
+                    ));
+                }
+                Transform::BoxCox {
+                    lambda: Some(lambda),
+                } if !lambda.is_finite() => {
+                    errors.push(FieldError::new(
+                        "transforms",
+                        "non_finite",
+                        "Box-Cox lambda must be a finite number",
+                    ));
+                }
+                _ => {}
+            }
+        }
+        let box_cox_steps = self
+            .transforms
+            .iter()
+            .filter(|t| matches!(t, Transform::BoxCox { .. }))
+            .count();
+        if box_cox_steps > 1 {
+            errors.push(FieldError::new(
+                "transforms",
+                "duplicate",
+                "Only one Box-Cox transform may be listed",
+            ));
+        }
+
+        if self.statistics.is_empty() {
+            errors.push(FieldError::new(
+                "statistics",
+                "empty",
+                "At least one statistic is required",
+            ));
+        }
+        let kinds: Vec<_> = self.statistics.iter().map(std::mem::discriminant).collect();
+        if (1..kinds.len()).any(|i| kinds[..i].contains(&kinds[i])) {
+            errors.push(FieldError::new(
+                "statistics",
+                "duplicate",
+                "Each statistic may only be listed once",
+            ));
+        }
+        for statistic in &self.statistics {
+            match statistic {
+                Statistic::Percentiles { percentiles, .. } => {
+                    if percentiles.is_empty()
+                        || percentiles.iter().any(|p| !(0.0..=100.0).contains(p))
+                    {
+                        errors.push(FieldError::new(
+                            "statistics",
+                            "out_of_range",
+                            "Percentiles must be a non-empty list between 0 and 100",
+                        ));
+                    }
+                }
+                Statistic::Outliers { k } => {
+                    if !(k.is_finite() && *k >= 0.0) {
+                        errors.push(FieldError::new(
+                            "statistics",
+                            "out_of_range",
+                            "Outlier fence k must be a non-negative number",
+                        ));
+                    }
+                }
+                Statistic::Histogram { buckets } => {
+                    if *buckets == 0 {
+                        errors.push(FieldError::new(
+                            "statistics",
+                            "out_of_range",
+                            "Histogram buckets must be at least 1",
+                        ));
+                    }
+                }
+            }
+        }
+
+        for sink in &self.sinks {
+            if let Sink::Email { from, to, .. } = sink
+                && (to.is_empty() || !to.iter().chain([from]).all(|a| a.contains('@')))
+            {
+                errors.push(FieldError::new(
+                    "sinks",
+                    "invalid_value",
+                    "Email sinks need a from address and at least one to address",
+                ));
+            }
+        }
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(ValidationErrors(errors))
+        }
+    }
+
+    /// Read the source's values; file paths are resolved against `base_dir`
+    pub fn load_values(&self, base_dir: &Path, options: &CsvOptions) -> Result<Vec<f64>> {
+        match &self.source {
+            Source::Values { values } => Ok(values.clone()),
+            Source::File { path, format } => {
+                read_values_from_file_with_format(&base_dir.join(path), *format, options)
+            }
+        }
+    }
+
+    /// Run filters, transforms and statistics over `values`
+    pub fn run(&self, values: Vec<f64>) -> Result<PipelineResult> {
+        let total = values.len();
+        let mut values = values;
+        for filter in &self.filters {
+            values = apply_filter(filter, values);
+        }
+        if values.is_empty() {
+            anyhow::bail!("No values left after filtering");
+        }
+        let mut steps = Vec::with_capacity(self.transforms.len());
+        for transform in &self.transforms {
+            let step = fit_transform(transform, &values)?;
+            values = values.into_iter().map(|v| step.apply(v)).collect();
+            steps.push(step);
+        }
+
+        let mut result = PipelineResult {
+            name: self.name.clone(),
+            count: values.len(),
+            filtered: total - values.len(),
+            percentiles: None,
+            back_transformed: None,
+            box_cox_lambda: steps.iter().find_map(|step| match step {
+                Step::Fitted(fitted) => fitted.lambda(),
+                Step::Scale(_) => None,
+            }),
+            outliers: None,
+            histogram: None,
+        };
+        for statistic in &self.statistics {
+            match statistic {
+                Statistic::Percentiles {
+                    percentiles,
+                    method,
+                } => {
+                    let percentiles = calculate_percentiles_with(&values, percentiles, *method)?;
+                    if !steps.is_empty() {
+                        result.back_transformed = Some(
+                            percentiles
+                                .iter()
+                                .map(|p| PercentileValue {
+                                    percentile: p.percentile,
+                                    value: steps.iter().rev().fold(p.value, |v, s| s.invert(v)),
+                                })
+                                .collect(),
+                        );
+                    }
+                    result.percentiles = Some(percentiles);
+                }
+                Statistic::Outliers { k } => {
+                    result.outliers = Some(detect_outliers_iqr(&values, *k)?);
+                }
+                Statistic::Histogram { buckets } => {
+                    result.histogram = Some(histogram(&values, *buckets)?);
+                }
+            }
+        }
+        Ok(result)
+    }
+}
+
+fn apply_filter(filter: &Filter, values: Vec<f64>) -> Vec<f64> {
+    match filter {
+        Filter::Range { min, max } => values
+            .into_iter()
+            .filter(|v| min.is_none_or(|min| *v >= min) && max.is_none_or(|max| *v <= max))
+            .collect(),
+        Filter::SkipFirst { count } => values.into_iter().skip(*count).collect(),
+        Filter::Finite => values.into_iter().filter(|v| v.is_finite()).collect(),
+    }
+}
+
+/// A transform with its parameters fixed, so percentiles can be mapped back
+enum Step {
+    Scale(f64),
+    Fitted(FittedTransform),
+}
+
+impl Step {
+    fn apply(&self, value: f64) -> f64 {
+        match self {
+            Step::Scale(factor) => value * factor,
+            Step::Fitted(fitted) => fitted.apply(value),
+        }
+    }
+
+    fn invert(&self, value: f64) -> f64 {
+        match self {
+            Step::Scale(factor) => value / factor,
+            Step::Fitted(fitted) => fitted.invert(value),
+        }
+    }
+}
+
+fn fit_transform(transform: &Transform, values: &[f64]) -> Result<Step> {
+    if !matches!(transform, Transform::Scale { .. }) && values.iter().any(|v| *v <= 0.0) {
+        anyhow::bail!(
+            "Log and Box-Cox transforms need positive values; add a range filter with min > 0"
+        );
+    }
+    let fit = |kind| FittedTransform::fit(kind, values).map(Step::Fitted);
+    match transform {
+        Transform::Scale { factor } => Ok(Step::Scale(*factor)),
+        Transform::Log10 => fit(ValueTransform::Log10),
+        Transform::Ln => fit(ValueTransform::Ln),
+        Transform::BoxCox {
+            lambda: Some(lambda),
+        } => Ok(Step::Fitted(FittedTransform::BoxCox { lambda: *lambda })),
+        Transform::BoxCox { lambda: None } => fit(ValueTransform::BoxCox),
+    }
+}
+
+impl PipelineResult {
+    /// The counts and statistics as labelled rows
+    pub fn summary(&self) -> Summary {
+        let mut rows = vec![
+            ("Values".to_string(), self.count as f64),
+            ("Filtered out".to_string(), self.filtered as f64),
+        ];
+        let percentiles = |rows: &mut Vec<_>, values: &[PercentileValue], suffix: &str| {
+            for p in values {
+                rows.push((
+                    format!("{}{suffix}", format_percentile_label(p.percentile)),
+                    p.value,
+                ));
+            }
+        };
+        percentiles(
+            &mut rows,
+            self.percentiles.as_deref().unwrap_or_default(),
+            "",
+        );
+        percentiles(
+            &mut rows,
+            self.back_transformed.as_deref().unwrap_or_default(),
+            " (original units)",
+        );
+        if let Some(lambda) = self.box_cox_lambda {
+            rows.push(("Box-Cox lambda".to_string(), lambda));
+        }
+        if let Some(ref outliers) = self.outliers {
+            rows.push(("Outliers".to_string(), outliers.outliers.len() as f64));
+            rows.push(("Lower fence".to_string(), outliers.lower_bound));
+            rows.push(("Upper fence".to_string(), outliers.upper_bound));
+        }
+        Summary {
+            title: format!("Pipeline {}: {} values", self.name, self.count),
+            rows,
+        }
+    }
+
+    /// Render as Prometheus text-format gauges labelled with the pipeline name
+    pub fn to_prometheus(&self, prefix: &str) -> String {
+        let name = self.name.replace('\\', "\\\\").replace('"', "\\\"");
+        let mut out = String::new();
+        let mut gauge = |metric: &str, help: &str, samples: Vec<(String, f64)>| {
+            out.push_str(&format!("# HELP {prefix}_{metric} {help}\n"));
+            out.push_str(&format!("# TYPE {prefix}_{metric} gauge\n"));
+            for (labels, value) in samples {
+                out.push_str(&format!(
+                    "{prefix}_{metric}{{pipeline=\"{name}\"{labels}}} {value}\n"
+                ));
+            }
+        };
+
+        gauge(
+            "pipeline_values",
+            "Values left after filtering",
+            vec![(String::new(), self.count as f64)],
+        );
+        if let Some(ref percentiles) = self.percentiles {
+            gauge(
+                "pipeline_percentile",
+                "Percentile of the pipeline's values",
+                percentiles
+                    .iter()
+                    .map(|p| (format!(",quantile=\"{}\"", p.percentile / 100.0), p.value))
+                    .collect(),
+            );
+        }
+        if let Some(ref outliers) = self.outliers {
+            gauge(
+                "pipeline_outliers",
+                "Values outside the outlier fences",
+                vec![(String::new(), outliers.outliers.len() as f64)],
+            );
+        }
+        out
+    }
+}
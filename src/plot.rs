@@ -0,0 +1,108 @@
+use outlier::HistogramResponse;
+
+/// Block characters from one to eight eighths tall
+const BLOCKS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+
+/// Scale `value` within `[min, max]` to a number of eighths in `1..=8 * rows`
+fn eighths(value: f64, min: f64, max: f64, rows: usize) -> usize {
+    let levels = 8 * rows;
+    if max <= min {
+        return levels;
+    }
+    let scaled = (value - min) / (max - min) * (levels - 1) as f64;
+    scaled.round() as usize + 1
+}
+
+/// Render a series as a one-line sparkline, leaving gaps for missing points
+pub fn sparkline(series: &[Option<f64>]) -> String {
+    let present = series.iter().flatten().copied();
+    let min = present.clone().fold(f64::INFINITY, f64::min);
+    let max = present.fold(f64::NEG_INFINITY, f64::max);
+
+    series
+        .iter()
+        .map(|point| match point {
+            Some(v) => BLOCKS[eighths(*v, min, max, 1) - 1],
+            None => ' ',
+        })
+        .collect()
+}
+
+/// Render a histogram as vertical bars `height` rows tall, one column per bucket
+///
+/// The left axis shows the tallest bucket's count; the range of the data is
+/// printed underneath.
+pub fn vertical_histogram(
+    histogram: &HistogramResponse,
+    height: usize,
+    precision: usize,
+) -> String {
+    let tallest = histogram.buckets.iter().map(|b| b.count).max().unwrap_or(0);
+    let label_width = tallest.to_string().len();
+    let bars: Vec<usize> = histogram
+        .buckets
+        .iter()
+        .map(|b| {
+            if b.count == 0 {
+                0
+            } else {
+                eighths(b.count as f64, 0.0, tallest as f64, height)
+            }
+        })
+        .collect();
+
+    let mut out = String::new();
+    for row in (0..height).rev() {
+        let label = if row + 1 == height {
+            tallest.to_string()
+        } else {
+            String::new()
+        };
+        out.push_str(&format!("{label:>label_width$} │"));
+        for &bar in &bars {
+            let filled = bar.saturating_sub(row * 8).min(8);
+            out.push(if filled == 0 { ' ' } else { BLOCKS[filled - 1] });
+        }
+        out.push('\n');
+    }
+    out.push_str(&format!(
+        "{:>label_width$} └{}\n",
+        0,
+        "─".repeat(bars.len())
+    ));
+
+    if let (Some(first), Some(last)) = (histogram.buckets.first(), histogram.buckets.last()) {
+        let lower = format!("{:.*}", precision, first.lower);
+        let upper = format!("{:.*}", precision, last.upper);
+        let gap = bars.len().saturating_sub(lower.len() + upper.len()).max(1);
+        out.push_str(&format!(
+            "{:label_width$}  {lower}{}{upper}\n",
+            "",
+            " ".repeat(gap)
+        ));
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use outlier::histogram;
+
+    #[test]
+    fn sparkline_scales_and_leaves_gaps() {
+        assert_eq!(sparkline(&[Some(1.0), Some(8.0), None, Some(4.5)]), "▁█ ▅");
+        assert_eq!(sparkline(&[Some(3.0), Some(3.0)]), "██");
+    }
+
+    #[test]
+    fn vertical_histogram_draws_tallest_bucket_full_height() {
+        let hist = histogram(&[1.0, 1.0, 1.0, 1.0, 2.0, 3.0, 3.0], 3).unwrap();
+        let plot = vertical_histogram(&hist, 2, 0);
+        let lines: Vec<&str> = plot.lines().collect();
+        assert_eq!(lines[0], "4 │█ ▁");
+        assert_eq!(lines[1], "  │█▅█");
+        assert_eq!(lines[2], "0 └───");
+        assert!(lines[3].contains('1') && lines[3].contains('3'));
+    }
+}
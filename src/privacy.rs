@@ -0,0 +1,146 @@
+use crate::PercentileValue;
+use anyhow::Result;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+/// Fewest values a report can be shared for by default
+pub const DEFAULT_MIN_COUNT: u64 = 10;
+
+/// Equal-width buckets the noisy histogram splits `[lower, upper]` into
+const BUCKETS: usize = 1000;
+
+/// Settings for sharing percentiles under differential privacy
+///
+/// Values are clamped to `[lower, upper]` and counted into a histogram of
+/// that range, so one person's value changes a single count by one. Tight
+/// bounds give finer buckets and more accurate percentiles.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct PrivacyOptions {
+    /// Privacy budget; smaller is more private and noisier
+    pub epsilon: f64,
+    /// Smallest value a person can contribute
+    pub lower: f64,
+    /// Largest value a person can contribute
+    pub upper: f64,
+    /// Suppress reports whose noisy count falls below this
+    pub min_count: u64,
+}
+
+impl PrivacyOptions {
+    fn validate(&self) -> Result<()> {
+        if !(self.epsilon.is_finite() && self.epsilon > 0.0) {
+            anyhow::bail!("Epsilon must be a positive number");
+        }
+        if !(self.lower.is_finite() && self.upper.is_finite() && self.lower < self.upper) {
+            anyhow::bail!("Privacy bounds must be finite with lower below upper");
+        }
+        Ok(())
+    }
+}
+
+/// Percentiles derived from a noisy histogram, safe to share externally
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PrivateReport {
+    /// Noisy number of values, absent when suppressed
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub count: Option<u64>,
+    /// Noisy percentiles, empty when suppressed
+    pub percentiles: Vec<PercentileValue>,
+    /// Whether the noisy count fell below `min_count`
+    pub suppressed: bool,
+    pub epsilon: f64,
+}
+
+/// Sample Laplace noise with the given scale
+fn laplace(rng: &mut impl Rng, scale: f64) -> f64 {
+    loop {
+        let u: f64 = rng.random_range(-0.5..0.5);
+        // -0.5 would take the log of zero
+        if u > -0.5 {
+            return -scale * u.signum() * (1.0 - 2.0 * u.abs()).ln();
+        }
+    }
+}
+
+/// Calculate percentiles under `epsilon`-differential privacy
+///
+/// The budget is split in half. One half noises the count: the true count
+/// plus a single Laplace draw, which decides suppression and is reported.
+/// The other half adds Laplace noise to each histogram count, which is
+/// clamped at zero, and every percentile is read off that noisy histogram, so
+/// asking for more percentiles spends no extra budget. Within a bucket the
+/// percentile is interpolated linearly. The report is suppressed when the
+/// noisy count is below `options.min_count`.
+///
+/// The count isn't summed from the histogram: clamping its roughly 1000
+/// noisy buckets at zero adds several hundred to the total, so even an empty
+/// input would pass `min_count`.
+///
+/// # Examples
+/// ```
+/// use outlier::{PrivacyOptions, private_percentiles};
+///
+/// let latencies: Vec<f64> = (0..10_000).map(|i| (i % 200) as f64).collect();
+/// let options = PrivacyOptions { epsilon: 1.0, lower: 0.0, upper: 200.0, min_count: 10 };
+/// let report = private_percentiles(&latencies, &[50.0], &options, &mut rand::rng()).unwrap();
+/// assert!(!report.suppressed);
+/// assert!((report.percentiles[0].value - 100.0).abs() < 5.0);
+/// ```
+pub fn private_percentiles(
+    values: &[f64],
+    percentiles: &[f64],
+    options: &PrivacyOptions,
+    rng: &mut impl Rng,
+) -> Result<PrivateReport> {
+    options.validate()?;
+    if percentiles.iter().any(|p| !(0.0..=100.0).contains(p)) {
+        anyhow::bail!("Percentile must be between 0 and 100");
+    }
+
+    let width = (options.upper - options.lower) / BUCKETS as f64;
+    let mut counts = vec![0.0; BUCKETS];
+    for value in values {
+        let bucket = ((value.clamp(options.lower, options.upper) - options.lower) / width) as usize;
+        counts[bucket.min(BUCKETS - 1)] += 1.0;
+    }
+    let scale = 2.0 / options.epsilon;
+    let total = (values.len() as f64 + laplace(rng, scale)).round().max(0.0);
+    if total < options.min_count as f64 {
+        return Ok(PrivateReport {
+            count: None,
+            percentiles: Vec::new(),
+            suppressed: true,
+            epsilon: options.epsilon,
+        });
+    }
+
+    for count in &mut counts {
+        *count = (*count + laplace(rng, scale)).max(0.0);
+    }
+    let mass: f64 = counts.iter().sum();
+
+    let percentiles = percentiles
+        .iter()
+        .map(|&percentile| {
+            let target = percentile / 100.0 * mass;
+            let mut before = 0.0;
+            let mut value = options.upper;
+            for (i, &count) in counts.iter().enumerate() {
+                if count > 0.0 && before + count >= target {
+                    let within = ((target - before) / count).clamp(0.0, 1.0);
+                    value = options.lower + (i as f64 + within) * width;
+                    break;
+                }
+                before += count;
+            }
+            PercentileValue { percentile, value }
+        })
+        .collect();
+
+    Ok(PrivateReport {
+        count: Some(total as u64),
+        percentiles,
+        suppressed: false,
+        epsilon: options.epsilon,
+    })
+}
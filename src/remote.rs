@@ -0,0 +1,169 @@
+use anyhow::{Context, Result};
+use outlier::{CalculateResponse, ErrorResponse};
+use serde::Serialize;
+use std::path::Path;
+
+/// Environment variable holding the API key sent to `--remote` servers
+pub const API_KEY_VAR: &str = "OUTLIER_API_KEY";
+
+/// A `/calculate/file` upload: the form fields plus the file they apply to
+pub struct RemoteCalculation<'a> {
+    pub file: &'a Path,
+    pub fields: Vec<(&'static str, String)>,
+}
+
+impl RemoteCalculation<'_> {
+    /// Add a form field holding an option's wire name, e.g. `nearest_rank`
+    pub fn option(&mut self, name: &'static str, value: impl Serialize) -> Result<()> {
+        self.fields.push((name, wire_name(value)?));
+        Ok(())
+    }
+
+    /// Add a form field holding comma-separated wire names
+    pub fn options<T: Serialize>(&mut self, name: &'static str, values: &[T]) -> Result<()> {
+        let names = values.iter().map(wire_name).collect::<Result<Vec<_>>>()?;
+        self.fields.push((name, names.join(",")));
+        Ok(())
+    }
+
+    /// Stream the file to `{server}/calculate/file` and return the server's result
+    ///
+    /// The file is read in chunks as the request is sent, so it never has to
+    /// fit in memory here. `api_key` goes in `X-API-Key` when set.
+    pub async fn send(self, server: &str, api_key: Option<&str>) -> Result<CalculateResponse> {
+        let url = format!("{}/calculate/file", server.trim_end_matches('/'));
+        let mut form = reqwest::multipart::Form::new();
+        for (name, value) in self.fields {
+            form = form.text(name, value);
+        }
+        let form = form
+            .file("file", self.file)
+            .await
+            .with_context(|| format!("Failed to open input file {}", self.file.display()))?;
+
+        let mut request = reqwest::Client::new().post(&url).multipart(form);
+        if let Some(key) = api_key {
+            request = request.header("x-api-key", key);
+        }
+        let response = request
+            .send()
+            .await
+            .with_context(|| format!("Failed to reach {url}"))?;
+
+        let status = response.status();
+        let body = response.text().await?;
+        if !status.is_success() {
+            let Ok(error) = serde_json::from_str::<ErrorResponse>(&body) else {
+                anyhow::bail!("{url} returned {status}: {}", body.trim());
+            };
+            let mut message = format!("{url} returned {status}: {}", error.error);
+            for field in error.errors {
+                message.push_str(&format!("\n  {}: {}", field.field, field.message));
+            }
+            anyhow::bail!(message);
+        }
+        serde_json::from_str(&body).with_context(|| format!("Unexpected response from {url}"))
+    }
+}
+
+/// The name an option goes by in requests, as the server parses it
+fn wire_name(value: impl Serialize) -> Result<String> {
+    let value = serde_json::to_value(value)?;
+    let name = value.as_str().context("Option has no wire name")?;
+    Ok(name.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    /// Answer one request with `response`, returning the request as received
+    async fn serve_once(response: String) -> (String, tokio::task::JoinHandle<String>) {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let server = tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let mut request = Vec::new();
+            let mut buf = [0; 4096];
+            // The multipart body ends with the closing boundary
+            while !request.ends_with(b"--\r\n") {
+                let n = socket.read(&mut buf).await.unwrap();
+                if n == 0 {
+                    break;
+                }
+                request.extend_from_slice(&buf[..n]);
+            }
+            socket.write_all(response.as_bytes()).await.unwrap();
+            socket.shutdown().await.unwrap();
+            String::from_utf8_lossy(&request).to_string()
+        });
+        (url, server)
+    }
+
+    fn input_file(name: &str) -> std::path::PathBuf {
+        let path = std::env::temp_dir().join(format!("outlier-{name}-{}.csv", std::process::id()));
+        std::fs::write(&path, "value\n1\n2\n3\n4\n").unwrap();
+        path
+    }
+
+    #[tokio::test]
+    async fn uploads_file_and_options() {
+        let body = r#"{"count":4,"percentile":99.0,"result":3.97,"method":"nearest_rank","engine":"exact_sort"}"#;
+        let response = format!(
+            "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+            body.len()
+        );
+        let (url, server) = serve_once(response).await;
+        let path = input_file("remote-upload");
+
+        let mut upload = RemoteCalculation {
+            file: &path,
+            fields: vec![("percentile", "99".to_string())],
+        };
+        upload
+            .option("method", outlier::PercentileMethod::NearestRank)
+            .unwrap();
+        upload
+            .options(
+                "coerce",
+                &[
+                    outlier::CoercionRule::Currency,
+                    outlier::CoercionRule::Percent,
+                ],
+            )
+            .unwrap();
+        let result = upload.send(&format!("{url}/"), Some("k1")).await.unwrap();
+        assert_eq!(result.count, 4);
+        assert_eq!(result.result, 3.97);
+
+        let request = server.await.unwrap();
+        assert!(request.starts_with("POST /calculate/file "));
+        assert!(request.to_lowercase().contains("x-api-key: k1"));
+        assert!(request.contains("name=\"method\"\r\n\r\nnearest_rank\r\n"));
+        assert!(request.contains("name=\"coerce\"\r\n\r\ncurrency,percent\r\n"));
+        assert!(request.contains("value\n1\n2\n3\n4\n"));
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn reports_server_validation_errors() {
+        let body = r#"{"error":"Invalid request","errors":[{"field":"percentile","code":"out_of_range","message":"Percentile must be between 0 and 100"}]}"#;
+        let response = format!(
+            "HTTP/1.1 400 Bad Request\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+            body.len()
+        );
+        let (url, server) = serve_once(response).await;
+        let path = input_file("remote-error");
+
+        let upload = RemoteCalculation {
+            file: &path,
+            fields: vec![("percentile", "101".to_string())],
+        };
+        let error = upload.send(&url, None).await.unwrap_err().to_string();
+        assert!(error.contains("400 Bad Request: Invalid request"));
+        assert!(error.contains("percentile: Percentile must be between 0 and 100"));
+        server.await.unwrap();
+        std::fs::remove_file(&path).unwrap();
+    }
+}
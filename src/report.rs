@@ -0,0 +1,381 @@
+use crate::{AnalyzeResponse, PercentileMethod, PercentileValue, format_percentile_label};
+use anyhow::{Context, Result};
+use std::fmt::Write;
+use std::path::Path;
+
+/// Chart size in SVG user units; the page scales it to fit
+const CHART_WIDTH: f64 = 800.0;
+const CHART_HEIGHT: f64 = 260.0;
+/// Room under each chart for its axis labels
+const AXIS: f64 = 20.0;
+/// Colours of the chart marks and labels
+const ACCENT: &str = "#2f6fdf";
+const MUTED: &str = "#5f6b7a";
+
+/// Everything in a report bundle: the analysis plus the percentile curve
+pub struct Report {
+    pub analysis: AnalyzeResponse,
+    /// P0 to P100 in steps of one, for the curve chart
+    pub curve: Vec<PercentileValue>,
+}
+
+impl Report {
+    pub fn build(
+        values: &[f64],
+        percentiles: &[f64],
+        method: PercentileMethod,
+        buckets: usize,
+    ) -> Result<Self> {
+        Self::from_sorted(&crate::sort_values(values), percentiles, method, buckets)
+    }
+
+    /// [`Report::build`] for values already in ascending order
+    pub fn from_sorted(
+        sorted: &[f64],
+        percentiles: &[f64],
+        method: PercentileMethod,
+        buckets: usize,
+    ) -> Result<Self> {
+        use crate::{describe, histogram, percentile_of_sorted};
+
+        let at = |percentile: f64| -> Result<PercentileValue> {
+            Ok(PercentileValue {
+                percentile,
+                value: percentile_of_sorted(sorted, percentile, method)?,
+            })
+        };
+        Ok(Self {
+            analysis: AnalyzeResponse {
+                summary: describe(sorted)?,
+                percentiles: percentiles.iter().map(|&p| at(p)).collect::<Result<_>>()?,
+                method,
+                histogram: histogram(sorted, buckets)?,
+            },
+            curve: (0..=100).map(|p| at(p as f64)).collect::<Result<_>>()?,
+        })
+    }
+
+    /// Write the bundle into `dir`, creating it if needed: `analysis.json`,
+    /// `summary.csv`, `percentiles.csv`, `histogram.csv` and an `index.html`
+    /// that needs nothing beyond a browser
+    pub fn write(&self, dir: &Path, name: &str) -> Result<()> {
+        std::fs::create_dir_all(dir)
+            .with_context(|| format!("Failed to create {}", dir.display()))?;
+        let files = [
+            (
+                "analysis.json",
+                serde_json::to_string_pretty(&self.analysis)?,
+            ),
+            ("summary.csv", self.analysis.summary.to_csv("value")),
+            ("percentiles.csv", self.percentiles_csv()),
+            ("histogram.csv", self.histogram_csv()),
+            ("index.html", self.html(name)),
+        ];
+        for (file, contents) in files {
+            let path = dir.join(file);
+            std::fs::write(&path, contents)
+                .with_context(|| format!("Failed to write {}", path.display()))?;
+        }
+        Ok(())
+    }
+
+    fn percentiles_csv(&self) -> String {
+        let mut csv = String::from("percentile,value\n");
+        for p in &self.analysis.percentiles {
+            let _ = writeln!(csv, "{:?},{:?}", p.percentile, p.value);
+        }
+        csv
+    }
+
+    fn histogram_csv(&self) -> String {
+        let mut csv = String::from("lower,upper,count\n");
+        for b in &self.analysis.histogram.buckets {
+            let _ = writeln!(csv, "{:?},{:?},{}", b.lower, b.upper, b.count);
+        }
+        csv
+    }
+
+    /// A standalone HTML page titled `name`, with its styles and charts inline
+    pub fn html(&self, name: &str) -> String {
+        let s = &self.analysis.summary;
+        let summary = [
+            ("count", Some(s.count as f64)),
+            ("mean", Some(s.mean)),
+            ("std", s.std),
+            ("min", Some(s.min)),
+            ("25%", Some(s.p25)),
+            ("50%", Some(s.p50)),
+            ("75%", Some(s.p75)),
+            ("max", Some(s.max)),
+        ]
+        .iter()
+        .map(|(label, value)| row(label, value.map(number).as_deref().unwrap_or("–")))
+        .collect::<String>();
+        let percentiles = self
+            .analysis
+            .percentiles
+            .iter()
+            .map(|p| {
+                let label = format_percentile_label(p.percentile);
+                row(&label, &number(p.value))
+            })
+            .collect::<String>();
+        let name = escape(name);
+
+        format!(
+            r#"<!doctype html>
+<html lang="en">
+<head>
+<meta charset="utf-8">
+<title>{name} – outlier report</title>
+<style>
+body {{ font-family: system-ui, sans-serif; color: #1d2430; max-width: 60rem; margin: 0 auto; padding: 1.5rem 2rem; }}
+.tables {{ display: flex; flex-wrap: wrap; gap: 2rem; }}
+table {{ border-collapse: collapse; }}
+caption {{ text-align: left; font-weight: 600; padding-bottom: 0.25rem; }}
+th, td {{ padding: 0.2rem 1rem 0.2rem 0; text-align: right; font-variant-numeric: tabular-nums; }}
+th {{ text-align: left; font-weight: normal; color: #5f6b7a; }}
+svg {{ width: 100%; height: auto; margin-top: 1rem; }}
+footer {{ margin-top: 2rem; font-size: 0.85rem; color: #5f6b7a; }}
+</style>
+</head>
+<body>
+<h1>{name}</h1>
+<div class="tables">
+<table><caption>Summary</caption><tbody>
+{summary}</tbody></table>
+<table><caption>Percentiles ({method})</caption><tbody>
+{percentiles}</tbody></table>
+</div>
+<h2>Histogram</h2>
+{histogram}
+<h2>Percentile Curve</h2>
+{curve}
+<footer>Generated by outlier {version}. The data behind this page is in analysis.json and the CSV files beside it.</footer>
+</body>
+</html>
+"#,
+            method = self.analysis.method,
+            histogram = self.histogram_svg(),
+            curve = self.curve_svg(),
+            version = env!("CARGO_PKG_VERSION"),
+        )
+    }
+
+    /// The histogram as a standalone SVG image
+    pub fn histogram_svg(&self) -> String {
+        let buckets = &self.analysis.histogram.buckets;
+        let max = buckets.iter().map(|b| b.count).max().unwrap_or(0).max(1) as f64;
+        let bar_width = CHART_WIDTH / buckets.len() as f64;
+        let mut bars = String::new();
+        for (i, b) in buckets.iter().enumerate() {
+            let height = (CHART_HEIGHT - AXIS) * b.count as f64 / max;
+            let _ = writeln!(
+                bars,
+                r#"<rect x="{:.1}" y="{:.1}" width="{:.1}" height="{height:.1}" fill="{ACCENT}"><title>[{}, {}): {}</title></rect>"#,
+                i as f64 * bar_width + 1.0,
+                CHART_HEIGHT - AXIS - height,
+                (bar_width - 2.0).max(1.0),
+                number(b.lower),
+                number(b.upper),
+                b.count,
+            );
+        }
+        let (first, last) = (&buckets[0], &buckets[buckets.len() - 1]);
+        svg(bars, number(first.lower), number(last.upper))
+    }
+
+    /// Value against percentile, P0 to P100, as a standalone SVG image, with
+    /// the requested percentiles marked
+    pub fn curve_svg(&self) -> String {
+        let min = self.analysis.summary.min;
+        let span = self.analysis.summary.max - min;
+        let point = |p: &PercentileValue| {
+            let y = if span > 0.0 {
+                (p.value - min) / span
+            } else {
+                0.5
+            };
+            (
+                p.percentile / 100.0 * CHART_WIDTH,
+                (CHART_HEIGHT - AXIS) * (1.0 - y),
+            )
+        };
+        let line = self
+            .curve
+            .iter()
+            .map(|p| {
+                let (x, y) = point(p);
+                format!("{x:.1},{y:.1}")
+            })
+            .collect::<Vec<_>>()
+            .join(" ");
+        let mut shapes = format!(
+            "<polyline points=\"{line}\" fill=\"none\" stroke=\"{ACCENT}\" stroke-width=\"2\"/>\n"
+        );
+        for p in &self.analysis.percentiles {
+            let (x, y) = point(p);
+            let _ = writeln!(
+                shapes,
+                r#"<circle cx="{x:.1}" cy="{y:.1}" r="4" fill="{ACCENT}"><title>{}: {}</title></circle>"#,
+                format_percentile_label(p.percentile),
+                number(p.value),
+            );
+        }
+        svg(shapes, "P0".to_string(), "P100".to_string())
+    }
+}
+
+/// An SVG chart of `shapes` with `left` and `right` labelling the x axis ends
+fn svg(shapes: String, left: String, right: String) -> String {
+    let y = CHART_HEIGHT - 5.0;
+    let text = format!("y=\"{y}\" font-family=\"sans-serif\" font-size=\"11\" fill=\"{MUTED}\"");
+    format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{CHART_WIDTH}\" height=\"{CHART_HEIGHT}\" \
+         viewBox=\"0 0 {CHART_WIDTH} {CHART_HEIGHT}\" role=\"img\">\n{shapes}\
+         <text x=\"0\" {text}>{left}</text>\n\
+         <text x=\"{CHART_WIDTH}\" {text} text-anchor=\"end\">{right}</text>\n</svg>"
+    )
+}
+
+/// Key figures of a result as labelled rows, for people reading email
+/// rather than dashboards
+#[derive(Debug, Clone, PartialEq)]
+pub struct Summary {
+    pub title: String,
+    pub rows: Vec<(String, f64)>,
+}
+
+impl Summary {
+    /// Aligned plain text, for mail clients that don't show HTML
+    pub fn to_text(&self) -> String {
+        let width = self.rows.iter().map(|(l, _)| l.chars().count()).max();
+        let mut out = format!("{}\n\n", self.title);
+        for (label, value) in &self.rows {
+            let _ = writeln!(
+                out,
+                "{label:<width$}  {}",
+                number(*value),
+                width = width.unwrap_or(0)
+            );
+        }
+        out
+    }
+
+    /// A single table with inline styles, which mail clients keep
+    pub fn to_html(&self) -> String {
+        let rows = self
+            .rows
+            .iter()
+            .map(|(label, value)| row(label, &number(*value)))
+            .collect::<String>();
+        format!(
+            "<!doctype html>\n<html lang=\"en\">\n<body style=\"font-family: system-ui, sans-serif; color: #1d2430;\">\n\
+             <h2>{}</h2>\n<table style=\"border-collapse: collapse; font-variant-numeric: tabular-nums;\">\n{rows}</table>\n\
+             <p style=\"font-size: 0.85rem; color: {MUTED};\">Generated by outlier {}</p>\n</body>\n</html>\n",
+            escape(&self.title),
+            env!("CARGO_PKG_VERSION")
+        )
+    }
+}
+
+fn row(label: &str, value: &str) -> String {
+    format!("<tr><th>{}</th><td>{value}</td></tr>\n", escape(label))
+}
+
+/// Up to six significant digits, without trailing zeros
+fn number(value: f64) -> String {
+    if value == value.trunc() && value.abs() < 1e15 {
+        return format!("{value:.0}");
+    }
+    let digits = (5 - value.abs().log10().floor() as i32).clamp(0, 12) as usize;
+    let text = format!("{value:.digits$}");
+    text.trim_end_matches('0').trim_end_matches('.').to_string()
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn writes_a_self_contained_bundle() {
+        let dir = std::env::temp_dir().join(format!("outlier-report-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        let values: Vec<f64> = (1..=100).map(f64::from).collect();
+
+        let report = Report::build(&values, &[50.0, 99.0], PercentileMethod::Linear, 10).unwrap();
+        report.write(&dir, "<latency>.csv").unwrap();
+
+        let analysis: AnalyzeResponse =
+            serde_json::from_str(&std::fs::read_to_string(dir.join("analysis.json")).unwrap())
+                .unwrap();
+        assert_eq!(analysis.percentiles, report.analysis.percentiles);
+        assert_eq!(analysis.histogram.buckets.len(), 10);
+        assert_eq!(
+            std::fs::read_to_string(dir.join("percentiles.csv")).unwrap(),
+            "percentile,value\n50.0,50.5\n99.0,99.01\n"
+        );
+        let histogram = std::fs::read_to_string(dir.join("histogram.csv")).unwrap();
+        assert_eq!(histogram.lines().count(), 11);
+        assert!(
+            std::fs::read_to_string(dir.join("summary.csv"))
+                .unwrap()
+                .starts_with(",value\n")
+        );
+
+        let html = std::fs::read_to_string(dir.join("index.html")).unwrap();
+        assert!(html.contains("<h1>&lt;latency&gt;.csv</h1>"));
+        assert!(html.contains("<tr><th>P99</th><td>99.01</td></tr>"));
+        assert_eq!(html.matches("<rect ").count(), 10);
+        // Nothing is loaded from elsewhere
+        assert!(!html.contains("src=") && !html.contains("href="));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn summaries_render_as_text_and_html() {
+        let summary = Summary {
+            title: "api <prod>".to_string(),
+            rows: vec![("Values".to_string(), 1000.0), ("P99".to_string(), 812.25)],
+        };
+        assert_eq!(
+            summary.to_text(),
+            "api <prod>\n\nValues  1000\nP99     812.25\n"
+        );
+        let html = summary.to_html();
+        assert!(html.contains("<h2>api &lt;prod&gt;</h2>"));
+        assert!(html.contains("<tr><th>P99</th><td>812.25</td></tr>"));
+    }
+
+    #[test]
+    fn charts_render_as_standalone_svg() {
+        let values = [1.0, 2.0, 2.0, 3.0, 10.0];
+        let report = Report::build(&values, &[50.0, 90.0], PercentileMethod::Linear, 3).unwrap();
+
+        let histogram = report.histogram_svg();
+        assert!(histogram.starts_with("<svg xmlns=\"http://www.w3.org/2000/svg\" width="));
+        assert_eq!(histogram.matches("<rect ").count(), 3);
+        assert!(histogram.contains("<title>[1, 4): 4</title>"));
+
+        let curve = report.curve_svg();
+        assert_eq!(curve.matches("<circle ").count(), 2);
+        assert!(curve.contains("<title>P90: 7.2</title>"));
+        // Styling is inline so the image renders outside the report page
+        assert!(!curve.contains("<style"));
+    }
+
+    #[test]
+    fn numbers_are_trimmed_to_six_significant_digits() {
+        assert_eq!(number(42.0), "42");
+        assert_eq!(number(1234.56789), "1234.57");
+        assert_eq!(number(0.000123456789), "0.000123457");
+        assert_eq!(number(-2.5), "-2.5");
+    }
+}
@@ -0,0 +1,124 @@
+use serde::{Deserialize, Serialize};
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Instant;
+use utoipa::ToSchema;
+
+/// Heap bytes currently allocated through [`CountingAllocator`]
+static ALLOCATED: AtomicUsize = AtomicUsize::new(0);
+
+/// The global allocator the binary installs; with several allocator
+/// features enabled, jemalloc wins over mimalloc
+pub const ALLOCATOR: &str = if cfg!(feature = "jemalloc") {
+    "jemalloc"
+} else if cfg!(feature = "mimalloc") {
+    "mimalloc"
+} else {
+    "system"
+};
+
+/// An allocator, keeping a running total of live heap bytes
+///
+/// The binary wraps mimalloc in it, or the system allocator with the
+/// `alloc-stats` feature; the cost is one atomic add per allocation and free.
+/// jemalloc keeps its own statistics and isn't wrapped.
+pub struct CountingAllocator<A = System>(pub A);
+
+unsafe impl<A: GlobalAlloc> GlobalAlloc for CountingAllocator<A> {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let ptr = unsafe { self.0.alloc(layout) };
+        if !ptr.is_null() {
+            ALLOCATED.fetch_add(layout.size(), Ordering::Relaxed);
+        }
+        ptr
+    }
+
+    unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+        let ptr = unsafe { self.0.alloc_zeroed(layout) };
+        if !ptr.is_null() {
+            ALLOCATED.fetch_add(layout.size(), Ordering::Relaxed);
+        }
+        ptr
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        unsafe { self.0.dealloc(ptr, layout) };
+        ALLOCATED.fetch_sub(layout.size(), Ordering::Relaxed);
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        let new = unsafe { self.0.realloc(ptr, layout, new_size) };
+        if !new.is_null() {
+            ALLOCATED.fetch_add(new_size, Ordering::Relaxed);
+            ALLOCATED.fetch_sub(layout.size(), Ordering::Relaxed);
+        }
+        new
+    }
+}
+
+/// Process resource usage as reported by `GET /admin/runtime`
+///
+/// Fields the platform or build can't provide are `null`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct RuntimeStats {
+    /// Resident set size in bytes (Linux)
+    pub rss_bytes: Option<u64>,
+    /// Global allocator: `system`, `jemalloc` or `mimalloc`
+    pub allocator: String,
+    /// Live heap bytes (builds with the `jemalloc`, `mimalloc` or
+    /// `alloc-stats` feature)
+    pub allocated_bytes: Option<u64>,
+    /// Open file descriptors, sockets included (Linux)
+    pub open_fds: Option<u64>,
+    /// Tokio tasks that have been spawned and not yet finished
+    pub tokio_tasks: usize,
+    /// Tokio worker threads
+    pub tokio_workers: usize,
+    /// Seconds since the server started
+    pub uptime_secs: u64,
+}
+
+/// Current statistics of this process, which started at `started`
+///
+/// Must be called from within a Tokio runtime.
+pub fn collect(started: Instant) -> RuntimeStats {
+    let metrics = tokio::runtime::Handle::current().metrics();
+    RuntimeStats {
+        rss_bytes: rss_bytes(),
+        allocator: ALLOCATOR.to_string(),
+        allocated_bytes: allocated_bytes(),
+        open_fds: open_fds(),
+        tokio_tasks: metrics.num_alive_tasks(),
+        tokio_workers: metrics.num_workers(),
+        uptime_secs: started.elapsed().as_secs(),
+    }
+}
+
+#[cfg(feature = "jemalloc")]
+fn allocated_bytes() -> Option<u64> {
+    // jemalloc only refreshes its statistics when the epoch advances
+    tikv_jemalloc_ctl::epoch::advance().ok()?;
+    let allocated = tikv_jemalloc_ctl::stats::allocated::read().ok()?;
+    Some(allocated as u64)
+}
+
+#[cfg(not(feature = "jemalloc"))]
+fn allocated_bytes() -> Option<u64> {
+    // Nothing has gone through the counting allocator unless it is installed
+    let allocated = ALLOCATED.load(Ordering::Relaxed);
+    (allocated > 0).then_some(allocated as u64)
+}
+
+/// `VmRSS` from `/proc/self/status`
+fn rss_bytes() -> Option<u64> {
+    let status = std::fs::read_to_string("/proc/self/status").ok()?;
+    let line = status.lines().find(|l| l.starts_with("VmRSS:"))?;
+    let kib: u64 = line.split_whitespace().nth(1)?.parse().ok()?;
+    Some(kib * 1024)
+}
+
+/// Entries of `/proc/self/fd`, less the one listing it
+fn open_fds() -> Option<u64> {
+    let entries = std::fs::read_dir("/proc/self/fd").ok()?;
+    Some(entries.count().saturating_sub(1) as u64)
+}
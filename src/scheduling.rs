@@ -0,0 +1,98 @@
+use anyhow::{Context, Result};
+
+/// Lower (or, as root, raise) this process's scheduling priority
+///
+/// Must run before any other thread starts: on Linux the nice value is per
+/// thread, and only threads created afterwards inherit it.
+#[cfg(unix)]
+pub fn set_nice(nice: i32) -> Result<()> {
+    // SAFETY: setpriority only reads its arguments
+    if unsafe { libc::setpriority(libc::PRIO_PROCESS as _, 0, nice) } != 0 {
+        return Err(std::io::Error::last_os_error())
+            .with_context(|| format!("Failed to set nice level {nice}"));
+    }
+    Ok(())
+}
+
+#[cfg(not(unix))]
+pub fn set_nice(_nice: i32) -> Result<()> {
+    anyhow::bail!("--nice is only supported on Unix")
+}
+
+/// CPU indices, parsed from a list such as `0-3,6` as `taskset -c` takes it
+#[derive(Debug, Clone, PartialEq)]
+pub struct CpuList(pub Vec<usize>);
+
+/// Highest CPU index a `cpu_set_t` can hold, plus one
+const MAX_CPUS: usize = 1024;
+
+impl std::str::FromStr for CpuList {
+    type Err = String;
+
+    fn from_str(text: &str) -> Result<Self, String> {
+        let mut cpus = Vec::new();
+        for part in text.split(',').map(str::trim) {
+            let number = |n: &str| {
+                n.trim()
+                    .parse::<usize>()
+                    .map_err(|_| format!("invalid CPU '{n}' in '{text}'"))
+            };
+            let (first, last) = match part.split_once('-') {
+                Some((first, last)) => (number(first)?, number(last)?),
+                None => (number(part)?, number(part)?),
+            };
+            if first > last {
+                return Err(format!("CPU range '{part}' runs backwards"));
+            }
+            if last >= MAX_CPUS {
+                return Err(format!("CPU {last} is out of range (max {})", MAX_CPUS - 1));
+            }
+            cpus.extend(first..=last);
+        }
+        cpus.sort_unstable();
+        cpus.dedup();
+        Ok(Self(cpus))
+    }
+}
+
+/// Restrict this process to `cpus`
+///
+/// Like [`set_nice`], threads started earlier keep their old affinity.
+#[cfg(target_os = "linux")]
+pub fn pin_to_cpus(cpus: &CpuList) -> Result<()> {
+    // SAFETY: cpu_set_t is plain data that zeroed() leaves empty, and
+    // CpuList keeps indices within it
+    let result = unsafe {
+        let mut set: libc::cpu_set_t = std::mem::zeroed();
+        for &cpu in &cpus.0 {
+            libc::CPU_SET(cpu, &mut set);
+        }
+        libc::sched_setaffinity(0, std::mem::size_of::<libc::cpu_set_t>(), &set)
+    };
+    if result != 0 {
+        return Err(std::io::Error::last_os_error()).context("Failed to set CPU affinity");
+    }
+    Ok(())
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn pin_to_cpus(_cpus: &CpuList) -> Result<()> {
+    anyhow::bail!("--cpus is only supported on Linux")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_cpu_lists() {
+        let parse = |text: &str| text.parse::<CpuList>().map(|cpus| cpus.0);
+        assert_eq!(parse("2").unwrap(), vec![2]);
+        assert_eq!(parse("0-3,6").unwrap(), vec![0, 1, 2, 3, 6]);
+        assert_eq!(parse("3, 1-2,2").unwrap(), vec![1, 2, 3]);
+        assert!(parse("3-1").is_err());
+        assert!(parse("a").is_err());
+        assert!(parse("").is_err());
+        assert!(parse("0-4096").is_err());
+    }
+}
@@ -0,0 +1,161 @@
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+use std::path::Path;
+
+/// GitHub API endpoint for the newest published release
+const LATEST_RELEASE_URL: &str = "https://api.github.com/repos/wingnut128/outlier/releases/latest";
+
+#[derive(Deserialize)]
+struct Release {
+    tag_name: String,
+    assets: Vec<Asset>,
+}
+
+#[derive(Deserialize)]
+struct Asset {
+    name: String,
+    browser_download_url: String,
+}
+
+/// Release asset name of the binary built for this platform, e.g. `outlier-linux-x86_64`
+fn asset_name() -> String {
+    let suffix = if cfg!(windows) { ".exe" } else { "" };
+    format!(
+        "outlier-{}-{}{suffix}",
+        std::env::consts::OS,
+        std::env::consts::ARCH
+    )
+}
+
+/// Parse `v1.2.3` / `1.2.3` into comparable numeric parts
+fn parse_version(version: &str) -> Option<(u64, u64, u64)> {
+    let mut parts = version.trim_start_matches('v').splitn(3, '.');
+    let major = parts.next()?.parse().ok()?;
+    let minor = parts.next()?.parse().ok()?;
+    // Ignore any pre-release or build suffix on the patch number
+    let patch = parts
+        .next()?
+        .split(|c: char| !c.is_ascii_digit())
+        .next()?
+        .parse()
+        .ok()?;
+    Some((major, minor, patch))
+}
+
+/// Check a download against the first field of a `sha256sum`-style checksum file
+fn verify_checksum(bytes: &[u8], checksum_file: &str) -> Result<()> {
+    let expected = checksum_file
+        .split_whitespace()
+        .next()
+        .context("Checksum file is empty")?
+        .to_lowercase();
+    let actual = hex::encode(Sha256::digest(bytes));
+    if actual != expected {
+        anyhow::bail!("Checksum mismatch: expected {expected}, downloaded file has {actual}");
+    }
+    Ok(())
+}
+
+/// Atomically swap the running binary for `bytes`
+fn replace_binary(current: &Path, bytes: &[u8]) -> Result<()> {
+    let staged = current.with_extension("update");
+    std::fs::write(&staged, bytes)
+        .with_context(|| format!("Failed to write {}", staged.display()))?;
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        std::fs::set_permissions(&staged, std::fs::Permissions::from_mode(0o755))?;
+    }
+    // Windows won't overwrite a running executable, but it can be renamed aside
+    #[cfg(windows)]
+    std::fs::rename(current, current.with_extension("old"))?;
+
+    std::fs::rename(&staged, current)
+        .with_context(|| format!("Failed to replace {}", current.display()))
+}
+
+/// Update the running binary to the latest GitHub release
+///
+/// The download is checked against the release's published `.sha256` file
+/// before anything on disk is touched. With `check_only`, just reports whether
+/// an update is available.
+pub async fn run(check_only: bool) -> Result<()> {
+    let client = reqwest::Client::builder()
+        .user_agent(concat!("outlier/", env!("CARGO_PKG_VERSION")))
+        .build()?;
+    let release: Release = client
+        .get(LATEST_RELEASE_URL)
+        .send()
+        .await?
+        .error_for_status()
+        .context("Failed to fetch the latest release")?
+        .json()
+        .await?;
+
+    let current = env!("CARGO_PKG_VERSION");
+    let latest = parse_version(&release.tag_name)
+        .with_context(|| format!("Unrecognised release tag {}", release.tag_name))?;
+    if parse_version(current).is_some_and(|current| current >= latest) {
+        println!("outlier {current} is up to date");
+        return Ok(());
+    }
+    println!("Update available: {current} -> {}", release.tag_name);
+    if check_only {
+        return Ok(());
+    }
+
+    let name = asset_name();
+    let find = |name: &str| {
+        release
+            .assets
+            .iter()
+            .find(|a| a.name == name)
+            .with_context(|| format!("Release {} has no {name} asset", release.tag_name))
+    };
+    let binary = find(&name)?;
+    let checksum = find(&format!("{name}.sha256"))?;
+
+    let checksum = client
+        .get(&checksum.browser_download_url)
+        .send()
+        .await?
+        .error_for_status()?
+        .text()
+        .await?;
+    let bytes = client
+        .get(&binary.browser_download_url)
+        .send()
+        .await?
+        .error_for_status()?
+        .bytes()
+        .await?;
+    verify_checksum(&bytes, &checksum)?;
+
+    let exe = std::env::current_exe().context("Failed to locate the running binary")?;
+    replace_binary(&exe, &bytes)?;
+    println!("Updated {} to {}", exe.display(), release.tag_name);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn versions_compare_numerically() {
+        assert_eq!(parse_version("v0.10.2"), Some((0, 10, 2)));
+        assert_eq!(parse_version("1.2.3-rc.1"), Some((1, 2, 3)));
+        assert!(parse_version("v0.10.0") > parse_version("0.9.9"));
+        assert_eq!(parse_version("latest"), None);
+    }
+
+    #[test]
+    fn checksum_must_match() {
+        let digest = hex::encode(Sha256::digest(b"binary"));
+        assert!(verify_checksum(b"binary", &format!("{digest}  outlier-linux-x86_64\n")).is_ok());
+        assert!(verify_checksum(b"tampered", &digest).is_err());
+        assert!(verify_checksum(b"binary", "").is_err());
+    }
+}
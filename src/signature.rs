@@ -0,0 +1,106 @@
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+
+/// Header carrying the request signature
+pub const SIGNATURE_HEADER: &str = "x-signature";
+
+/// Prefix on signature values, as used by GitHub-style webhooks
+const SIGNATURE_PREFIX: &str = "sha256=";
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// Sign `body` with `secret`, returning the `sha256=<hex>` header value
+#[cfg(test)]
+pub fn sign(secret: &[u8], body: &[u8]) -> String {
+    let mut mac = HmacSha256::new_from_slice(secret).expect("HMAC accepts keys of any length");
+    mac.update(body);
+    format!(
+        "{SIGNATURE_PREFIX}{}",
+        hex::encode(mac.finalize().into_bytes())
+    )
+}
+
+/// Check a `sha256=<hex>` (or bare hex) signature over `body` in constant time
+pub fn verify(secret: &[u8], body: &[u8], signature: &str) -> bool {
+    Verifier::new(secret, signature).is_some_and(|mut verifier| {
+        verifier.update(body);
+        verifier.finish()
+    })
+}
+
+/// Checks a signature over a body that arrives in chunks
+pub struct Verifier {
+    mac: HmacSha256,
+    expected: Vec<u8>,
+}
+
+impl Verifier {
+    /// A verifier for a `sha256=<hex>` (or bare hex) signature, or `None`
+    /// when it isn't hex
+    pub fn new(secret: &[u8], signature: &str) -> Option<Self> {
+        let signature = signature.trim();
+        let hex_digest = signature
+            .strip_prefix(SIGNATURE_PREFIX)
+            .unwrap_or(signature);
+        let expected = hex::decode(hex_digest).ok()?;
+        let mac = HmacSha256::new_from_slice(secret).expect("HMAC accepts keys of any length");
+        Some(Self { mac, expected })
+    }
+
+    pub fn update(&mut self, chunk: &[u8]) {
+        self.mac.update(chunk);
+    }
+
+    /// Whether the chunks seen match the signature, compared in constant time
+    pub fn finish(self) -> bool {
+        self.mac.verify_slice(&self.expected).is_ok()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sign_matches_known_vector() {
+        // RFC 4231 test case 2
+        let signature = sign(b"Jefe", b"what do ya want for nothing?");
+        assert_eq!(
+            signature,
+            "sha256=5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843"
+        );
+    }
+
+    #[test]
+    fn verifier_accepts_chunked_bodies() {
+        let signature = sign(b"secret", b"payload");
+        let mut verifier = Verifier::new(b"secret", &signature).unwrap();
+        verifier.update(b"pay");
+        verifier.update(b"load");
+        assert!(verifier.finish());
+
+        let mut verifier = Verifier::new(b"secret", &signature).unwrap();
+        verifier.update(b"pay");
+        assert!(!verifier.finish());
+        assert!(Verifier::new(b"secret", "sha256=not-hex").is_none());
+    }
+
+    #[test]
+    fn verify_accepts_prefixed_and_bare_hex() {
+        let signature = sign(b"secret", b"payload");
+        assert!(verify(b"secret", b"payload", &signature));
+        assert!(verify(
+            b"secret",
+            b"payload",
+            signature.trim_start_matches(SIGNATURE_PREFIX)
+        ));
+    }
+
+    #[test]
+    fn verify_rejects_tampered_body_or_wrong_secret() {
+        let signature = sign(b"secret", b"payload");
+        assert!(!verify(b"secret", b"payload!", &signature));
+        assert!(!verify(b"other", b"payload", &signature));
+        assert!(!verify(b"secret", b"payload", "sha256=not-hex"));
+    }
+}
@@ -0,0 +1,675 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::collections::hash_map::Entry;
+use std::sync::{Arc, Mutex, RwLock};
+use utoipa::ToSchema;
+
+use crate::config::{DatasetsConfig, MetricSpec, RangeAction, RangeRule};
+use crate::engine::SavedSketch;
+use crate::{PercentileValue, TDigestSketch};
+
+/// Most sketches the server keeps at once
+pub const MAX_SKETCHES: usize = 1024;
+
+/// Longest sketch name
+const MAX_NAME_BYTES: usize = 128;
+
+/// Longest accepted ingest line; longer lines are rejected whole
+pub const MAX_LINE_BYTES: usize = 4 * 1024;
+
+/// Most upload hashes a sketch remembers; later uploads aren't checked for
+/// duplicates
+pub const MAX_UPLOADS: usize = 1024;
+
+/// Version of the archive [`SketchStore::snapshot`] writes
+pub const SNAPSHOT_VERSION: u32 = 1;
+
+/// A sketch shared between the ingest streams feeding it and its readers
+pub type SharedSketch = Arc<Mutex<Dataset>>;
+
+/// A sketch, the SHA-256 of each ingest body read into it in full, and what
+/// the registry and range rules say about its values
+#[derive(Debug, Default)]
+pub struct Dataset {
+    pub digest: TDigestSketch,
+    uploads: Vec<String>,
+    metric: Option<MetricSpec>,
+    range: Option<RangeRule>,
+    /// Values seen outside the expected range, rejected or not
+    pub violations: u64,
+}
+
+impl Dataset {
+    /// Add a value unless its range rule rejects it, returning whether it
+    /// was added
+    pub fn insert(&mut self, value: f64) -> bool {
+        let outside = self.range.as_ref().filter(|range| !range.contains(value));
+        if let Some(range) = outside {
+            self.violations += 1;
+            if range.action == RangeAction::Reject {
+                return false;
+            }
+        }
+        self.digest.insert(value);
+        true
+    }
+
+    /// The range rule applying to this dataset, if any
+    pub fn range(&self) -> Option<&RangeRule> {
+        self.range.as_ref()
+    }
+
+    /// The registered metric this dataset holds, if any
+    pub fn metric(&self) -> Option<&MetricSpec> {
+        self.metric.as_ref()
+    }
+
+    /// Remember an ingested body, returning whether the same body was
+    /// ingested before, so its values now count twice
+    pub fn record_upload(&mut self, sha256: String) -> bool {
+        if self.uploads.contains(&sha256) {
+            return true;
+        }
+        if self.uploads.len() < MAX_UPLOADS {
+            self.uploads.push(sha256);
+        }
+        false
+    }
+
+    /// Hashes of the bodies ingested in full, oldest first
+    pub fn uploads(&self) -> &[String] {
+        &self.uploads
+    }
+}
+
+/// Named t-digest sketches, held in memory until deleted or the server stops
+#[derive(Default)]
+pub struct SketchStore {
+    sketches: RwLock<HashMap<String, SharedSketch>>,
+    datasets: DatasetsConfig,
+}
+
+impl SketchStore {
+    /// An empty store giving new sketches their registered metric and range
+    /// rule from `datasets`
+    pub fn from_config(datasets: DatasetsConfig) -> anyhow::Result<Self> {
+        datasets.validate()?;
+        Ok(Self {
+            sketches: RwLock::default(),
+            datasets,
+        })
+    }
+
+    /// The sketch called `name`, created empty if missing; `None` once
+    /// [`MAX_SKETCHES`] exist
+    ///
+    /// `name` must already have passed [`validate_name`].
+    pub fn get_or_create(&self, name: &str) -> Option<SharedSketch> {
+        if let Some(sketch) = self.get(name) {
+            return Some(sketch);
+        }
+        let mut sketches = self.sketches.write().unwrap_or_else(|e| e.into_inner());
+        let len = sketches.len();
+        match sketches.entry(name.to_string()) {
+            Entry::Occupied(entry) => Some(Arc::clone(entry.get())),
+            Entry::Vacant(_) if len >= MAX_SKETCHES => None,
+            Entry::Vacant(entry) => {
+                let dataset = Dataset {
+                    metric: self.datasets.metric(name).cloned(),
+                    range: self.datasets.range(name),
+                    ..Dataset::default()
+                };
+                Some(Arc::clone(entry.insert(Arc::new(Mutex::new(dataset)))))
+            }
+        }
+    }
+
+    pub fn get(&self, name: &str) -> Option<SharedSketch> {
+        let sketches = self.sketches.read().unwrap_or_else(|e| e.into_inner());
+        sketches.get(name).map(Arc::clone)
+    }
+
+    /// Every sketch and its count, sorted by name
+    pub fn list(&self) -> Vec<SketchInfo> {
+        let sketches = self.sketches.read().unwrap_or_else(|e| e.into_inner());
+        let mut list: Vec<SketchInfo> = sketches
+            .iter()
+            .map(|(name, sketch)| SketchInfo {
+                name: name.clone(),
+                count: sketch
+                    .lock()
+                    .unwrap_or_else(|e| e.into_inner())
+                    .digest
+                    .count(),
+            })
+            .collect();
+        list.sort_by(|a, b| a.name.cmp(&b.name));
+        list
+    }
+
+    /// Drop a sketch; streams still feeding it keep their copy alive until
+    /// they end, but nothing can read it
+    pub fn remove(&self, name: &str) -> bool {
+        let mut sketches = self.sketches.write().unwrap_or_else(|e| e.into_inner());
+        sketches.remove(name).is_some()
+    }
+
+    /// Drop `sketch` if it's still stored as `name`, nothing else holds it
+    /// and nothing was added to it, returning whether it was dropped
+    ///
+    /// Undoes a [`get_or_create`](Self::get_or_create) for a stream that was
+    /// refused before any values went in.
+    pub fn remove_unused(&self, name: &str, sketch: SharedSketch) -> bool {
+        let mut sketches = self.sketches.write().unwrap_or_else(|e| e.into_inner());
+        // Handles are only cloned under the read lock, so the count can't grow
+        let unused = sketches.get(name).is_some_and(|stored| {
+            Arc::ptr_eq(stored, &sketch) && Arc::strong_count(&sketch) == 2 && {
+                let dataset = sketch.lock().unwrap_or_else(|e| e.into_inner());
+                dataset.digest.count() == 0 && dataset.uploads.is_empty() && dataset.violations == 0
+            }
+        });
+        if unused {
+            sketches.remove(name);
+        }
+        unused
+    }
+
+    /// Every sketch with its upload hashes and violation count, by name
+    pub fn snapshot(&self) -> Snapshot {
+        let sketches = self.sketches.read().unwrap_or_else(|e| e.into_inner());
+        let mut saved: Vec<SavedSketchEntry> = sketches
+            .iter()
+            .map(|(name, sketch)| {
+                let mut dataset = sketch.lock().unwrap_or_else(|e| e.into_inner());
+                SavedSketchEntry {
+                    name: name.clone(),
+                    sketch: dataset.digest.save(),
+                    uploads: dataset.uploads.clone(),
+                    violations: dataset.violations,
+                }
+            })
+            .collect();
+        saved.sort_by(|a, b| a.name.cmp(&b.name));
+        Snapshot {
+            version: SNAPSHOT_VERSION,
+            sketches: saved,
+        }
+    }
+
+    /// Load a snapshot, replacing sketches of the same name, and return how
+    /// many sketches it held
+    ///
+    /// Registered metrics and range rules come from this store's config, not
+    /// the snapshot. Nothing is loaded unless every sketch is valid.
+    pub fn restore(&self, snapshot: Snapshot) -> anyhow::Result<usize> {
+        if snapshot.version != SNAPSHOT_VERSION {
+            anyhow::bail!(
+                "Unsupported snapshot version {} (expected {SNAPSHOT_VERSION})",
+                snapshot.version
+            );
+        }
+        let mut restored = Vec::with_capacity(snapshot.sketches.len());
+        for saved in snapshot.sketches {
+            validate_name(&saved.name).map_err(anyhow::Error::msg)?;
+            let digest = TDigestSketch::restore(saved.sketch)
+                .map_err(|e| e.context(format!("Invalid sketch '{}' in snapshot", saved.name)))?;
+            restored.push((saved.name, digest, saved.uploads, saved.violations));
+        }
+        let count = restored.len();
+        for (name, digest, mut uploads, violations) in restored {
+            let sketch = self.get_or_create(&name).ok_or_else(|| {
+                anyhow::anyhow!("Snapshot holds more than {MAX_SKETCHES} sketches")
+            })?;
+            let mut dataset = sketch.lock().unwrap_or_else(|e| e.into_inner());
+            uploads.truncate(MAX_UPLOADS);
+            dataset.digest = digest;
+            dataset.uploads = uploads;
+            dataset.violations = violations;
+        }
+        Ok(count)
+    }
+}
+
+/// Every sketch the server holds, as `POST /admin/snapshot` writes it and
+/// `--restore` reads it
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct Snapshot {
+    pub version: u32,
+    pub sketches: Vec<SavedSketchEntry>,
+}
+
+/// One sketch in a [`Snapshot`]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct SavedSketchEntry {
+    pub name: String,
+    pub sketch: SavedSketch,
+    /// SHA-256 of each body ingested in full, oldest first
+    #[serde(default)]
+    pub uploads: Vec<String>,
+    #[serde(default)]
+    pub violations: u64,
+}
+
+/// Sketch names are 1-128 ASCII letters, digits, `.`, `_` or `-`, so they
+/// fit in a URL path unescaped
+pub fn validate_name(name: &str) -> Result<(), String> {
+    if name.is_empty() || name.len() > MAX_NAME_BYTES {
+        return Err(format!(
+            "Dataset name must be 1 to {MAX_NAME_BYTES} characters"
+        ));
+    }
+    if !name
+        .bytes()
+        .all(|b| b.is_ascii_alphanumeric() || matches!(b, b'.' | b'_' | b'-'))
+    {
+        return Err("Dataset name may only contain letters, digits, '.', '_' and '-'".to_string());
+    }
+    Ok(())
+}
+
+/// One entry of `GET /sketches`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct SketchInfo {
+    pub name: String,
+    /// Values ingested so far
+    pub count: u64,
+}
+
+/// Response of `GET /sketches/{name}`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct SketchSummary {
+    pub name: String,
+    /// Values ingested so far
+    pub count: u64,
+    pub min: f64,
+    pub max: f64,
+    /// Estimated percentiles; t-digest accuracy, exact at the extremes
+    pub percentiles: Vec<PercentileValue>,
+    /// SHA-256 of each `POST /ingest` body read in full, oldest first
+    pub uploads: Vec<String>,
+    /// Expected range from `datasets.ranges`, e.g. `0 to 600000`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expected_range: Option<String>,
+    /// Values seen outside the expected range, whether rejected or kept
+    pub violations: u64,
+    /// What the registry says about the metric, if it is registered
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub metric: Option<MetricMetadata>,
+}
+
+/// Registry entry of a metric, as `GET /sketches/{name}` reports it
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct MetricMetadata {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub unit: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub owner: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+}
+
+impl From<&MetricSpec> for MetricMetadata {
+    fn from(metric: &MetricSpec) -> Self {
+        Self {
+            unit: metric.unit.clone(),
+            owner: metric.owner.clone(),
+            description: metric.description.clone(),
+        }
+    }
+}
+
+/// Progress of a `POST /ingest` stream, sent as one NDJSON line
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct IngestAck {
+    /// Sketch the values went into
+    pub dataset: String,
+    /// Values this request has added so far
+    pub accepted: u64,
+    /// Lines this request skipped because they weren't a finite number or
+    /// fell outside the dataset's expected range
+    pub rejected: u64,
+    /// Values this request sent outside the dataset's expected range,
+    /// whether rejected or kept
+    pub out_of_range: u64,
+    /// Values in the sketch from every stream, this one included
+    pub count: u64,
+    /// Line number and reason of the first rejected line
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub first_rejection: Option<String>,
+    /// Why ingest stopped before the end of the body
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    /// SHA-256 of the body, on the final ack once it has been read in full
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sha256: Option<String>,
+    /// Whether the sketch had already ingested a body with the same SHA-256,
+    /// on the final ack alongside `sha256`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub duplicate: Option<bool>,
+    /// Whether this is the final ack of the stream
+    pub done: bool,
+}
+
+/// Splits a body arriving in arbitrary chunks into NDJSON values
+///
+/// Each line holds a bare number or an object with a `value` number. Blank
+/// lines are skipped; anything else is counted as rejected without stopping
+/// the stream, as are values a rejecting range rule excludes.
+#[derive(Debug, Default)]
+pub struct LineParser {
+    partial: Vec<u8>,
+    overlong: bool,
+    line: u64,
+    range: Option<RangeRule>,
+    pub accepted: u64,
+    pub rejected: u64,
+    pub out_of_range: u64,
+    pub first_rejection: Option<String>,
+}
+
+impl LineParser {
+    /// A parser checking values against a dataset's range rule
+    pub fn with_range(range: Option<RangeRule>) -> Self {
+        Self {
+            range,
+            ..Self::default()
+        }
+    }
+
+    /// Parse the complete lines of `chunk` into `values`, holding back a
+    /// trailing partial line for the next chunk
+    pub fn feed(&mut self, chunk: &[u8], values: &mut Vec<f64>) {
+        let mut rest = chunk;
+        while let Some(at) = rest.iter().position(|&b| b == b'\n') {
+            self.push(&rest[..at]);
+            self.end_line(values);
+            rest = &rest[at + 1..];
+        }
+        self.push(rest);
+    }
+
+    /// Parse a last line left without a newline
+    pub fn finish(&mut self, values: &mut Vec<f64>) {
+        if !self.partial.is_empty() || self.overlong {
+            self.end_line(values);
+        }
+    }
+
+    fn push(&mut self, bytes: &[u8]) {
+        if self.overlong {
+            return;
+        }
+        if self.partial.len() + bytes.len() > MAX_LINE_BYTES {
+            self.overlong = true;
+            self.partial.clear();
+        } else {
+            self.partial.extend_from_slice(bytes);
+        }
+    }
+
+    fn end_line(&mut self, values: &mut Vec<f64>) {
+        self.line += 1;
+        let parsed = if std::mem::take(&mut self.overlong) {
+            Err(format!("longer than {MAX_LINE_BYTES} bytes"))
+        } else {
+            parse_line(&self.partial)
+        };
+        self.partial.clear();
+        let parsed = parsed.and_then(|value| self.check_range(value));
+        match parsed {
+            Ok(Some(value)) => {
+                self.accepted += 1;
+                values.push(value);
+            }
+            Ok(None) => {}
+            Err(reason) => {
+                self.rejected += 1;
+                let line = self.line;
+                self.first_rejection
+                    .get_or_insert_with(|| format!("line {line}: {reason}"));
+            }
+        }
+    }
+
+    /// Count a value outside the range rule, turning it into a rejection if
+    /// the rule rejects
+    ///
+    /// Like [`parse_line`], the reason doesn't quote the value.
+    fn check_range(&mut self, value: Option<f64>) -> Result<Option<f64>, String> {
+        let (Some(range), Some(v)) = (&self.range, value) else {
+            return Ok(value);
+        };
+        if range.contains(v) {
+            return Ok(value);
+        }
+        self.out_of_range += 1;
+        match range.action {
+            RangeAction::Reject => Err(format!("outside the expected range {}", range.bounds())),
+            RangeAction::Count => Ok(value),
+        }
+    }
+}
+
+/// The value on one line, `None` for a blank line
+///
+/// Reasons don't quote the line, so they are safe to return with
+/// redaction enabled.
+fn parse_line(line: &[u8]) -> Result<Option<f64>, String> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Line {
+        Bare(f64),
+        Object { value: f64 },
+    }
+
+    let text = std::str::from_utf8(line)
+        .map_err(|_| "not valid UTF-8".to_string())?
+        .trim();
+    if text.is_empty() {
+        return Ok(None);
+    }
+    let value = match serde_json::from_str::<Line>(text) {
+        Ok(Line::Bare(value) | Line::Object { value }) => value,
+        Err(_) => return Err("expected a number or {\"value\": number}".to_string()),
+    };
+    if !value.is_finite() {
+        return Err("not a finite number".to_string());
+    }
+    Ok(Some(value))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lines_split_across_chunks_are_joined() {
+        let mut parser = LineParser::default();
+        let mut values = Vec::new();
+        parser.feed(b"1\n2.", &mut values);
+        assert_eq!(values, vec![1.0]);
+        parser.feed(b"5\n{\"value\": 3}\r\n\n4", &mut values);
+        parser.finish(&mut values);
+        assert_eq!(values, vec![1.0, 2.5, 3.0, 4.0]);
+        assert_eq!((parser.accepted, parser.rejected), (4, 0));
+    }
+
+    #[test]
+    fn bad_lines_are_counted_and_skipped() {
+        let mut parser = LineParser::default();
+        let mut values = Vec::new();
+        let long = "1".repeat(MAX_LINE_BYTES + 1);
+        parser.feed(
+            format!("1\nabc\n{long}\n{{\"v\": 2}}\n3").as_bytes(),
+            &mut values,
+        );
+        parser.finish(&mut values);
+        assert_eq!(values, vec![1.0, 3.0]);
+        assert_eq!(parser.rejected, 3);
+        assert_eq!(
+            parser.first_rejection.as_deref(),
+            Some("line 2: expected a number or {\"value\": number}")
+        );
+    }
+
+    #[test]
+    fn values_outside_the_range_are_counted() {
+        let rule = |dataset: &str, action| RangeRule {
+            dataset: dataset.to_string(),
+            min: Some(0.0),
+            max: Some(100.0),
+            action,
+        };
+        let mut parser = LineParser::with_range(Some(rule("api.*", RangeAction::Reject)));
+        let mut values = Vec::new();
+        parser.feed(b"5\n-1\n250\n100\n", &mut values);
+        assert_eq!(values, vec![5.0, 100.0]);
+        assert_eq!(
+            (parser.accepted, parser.rejected, parser.out_of_range),
+            (2, 2, 2)
+        );
+        assert_eq!(
+            parser.first_rejection.as_deref(),
+            Some("line 2: outside the expected range 0 to 100")
+        );
+
+        let mut parser = LineParser::with_range(Some(rule("api.*", RangeAction::Count)));
+        let mut values = Vec::new();
+        parser.feed(b"5\n250\n", &mut values);
+        assert_eq!(values, vec![5.0, 250.0]);
+        assert_eq!(
+            (parser.accepted, parser.rejected, parser.out_of_range),
+            (2, 0, 1)
+        );
+
+        let store = SketchStore::from_config(DatasetsConfig {
+            ranges: vec![
+                rule("api.*", RangeAction::Reject),
+                rule("api.slow", RangeAction::Count),
+            ],
+            ..DatasetsConfig::default()
+        })
+        .unwrap();
+        let sketch = store.get_or_create("api.slow").unwrap();
+        let mut dataset = sketch.lock().unwrap();
+        assert!(dataset.insert(50.0));
+        assert!(!dataset.insert(500.0));
+        assert_eq!((dataset.digest.count(), dataset.violations), (1, 1));
+        assert!(
+            store
+                .get_or_create("db")
+                .unwrap()
+                .lock()
+                .unwrap()
+                .range()
+                .is_none()
+        );
+
+        let mut invalid = rule("api.*", RangeAction::Reject);
+        invalid.min = Some(f64::NAN);
+        let datasets = DatasetsConfig {
+            ranges: vec![invalid],
+            ..DatasetsConfig::default()
+        };
+        assert!(SketchStore::from_config(datasets).is_err());
+    }
+
+    #[test]
+    fn store_validates_names_and_caps_size() {
+        assert!(validate_name("api.latency_ms-2").is_ok());
+        assert!(validate_name("").is_err());
+        assert!(validate_name("a/b").is_err());
+        assert!(validate_name(&"a".repeat(129)).is_err());
+
+        let store = SketchStore::default();
+        for i in 0..MAX_SKETCHES {
+            assert!(store.get_or_create(&format!("s{i}")).is_some());
+        }
+        assert!(store.get_or_create("one-more").is_none());
+        assert!(store.get_or_create("s0").is_some());
+        assert!(store.remove("s0"));
+        assert!(!store.remove("s0"));
+        assert!(store.get_or_create("one-more").is_some());
+    }
+
+    #[test]
+    fn only_unused_empty_sketches_are_removed() {
+        let store = SketchStore::default();
+        let sketch = store.get_or_create("filled").unwrap();
+        sketch.lock().unwrap().insert(1.0);
+        assert!(!store.remove_unused("filled", sketch));
+
+        let sketch = store.get_or_create("held").unwrap();
+        let _held = store.get("held").unwrap();
+        assert!(!store.remove_unused("held", sketch));
+
+        let sketch = store.get_or_create("empty").unwrap();
+        assert!(store.remove_unused("empty", sketch));
+        assert!(store.get("empty").is_none());
+        assert_eq!(store.list().len(), 2);
+    }
+
+    #[test]
+    fn repeated_uploads_are_duplicates() {
+        let mut dataset = Dataset::default();
+        assert!(!dataset.record_upload("a".to_string()));
+        assert!(!dataset.record_upload("b".to_string()));
+        assert!(dataset.record_upload("a".to_string()));
+        assert_eq!(dataset.uploads(), ["a", "b"]);
+    }
+
+    #[test]
+    fn snapshots_restore_into_a_new_store() {
+        let store = SketchStore::default();
+        {
+            let sketch = store.get_or_create("latency").unwrap();
+            let mut dataset = sketch.lock().unwrap();
+            for i in 1..=1000 {
+                dataset.insert(f64::from(i));
+            }
+            dataset.record_upload("abc".to_string());
+            dataset.violations = 3;
+        }
+        store.get_or_create("empty").unwrap();
+        let snapshot = store.snapshot();
+        let json = serde_json::to_string(&snapshot).unwrap();
+
+        let restored = SketchStore::default();
+        let snapshot: Snapshot = serde_json::from_str(&json).unwrap();
+        assert_eq!(restored.restore(snapshot).unwrap(), 2);
+        let before = store.get("latency").unwrap();
+        let after = restored.get("latency").unwrap();
+        let mut after = after.lock().unwrap();
+        assert_eq!(
+            after.digest.percentiles(&[50.0, 99.0]).unwrap(),
+            before
+                .lock()
+                .unwrap()
+                .digest
+                .percentiles(&[50.0, 99.0])
+                .unwrap()
+        );
+        assert_eq!(after.digest.range(), Some((1.0, 1000.0)));
+        assert_eq!(after.uploads(), ["abc"]);
+        assert_eq!(after.violations, 3);
+        assert_eq!(
+            restored
+                .get("empty")
+                .unwrap()
+                .lock()
+                .unwrap()
+                .digest
+                .count(),
+            0
+        );
+
+        let mut bad = store.snapshot();
+        bad.sketches[1].sketch.count += 1;
+        let fresh = SketchStore::default();
+        assert!(fresh.restore(bad).is_err());
+        assert!(fresh.list().is_empty());
+        let mut old = store.snapshot();
+        old.version = 0;
+        assert!(fresh.restore(old).is_err());
+    }
+}
@@ -0,0 +1,121 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicU64, Ordering};
+use utoipa::ToSchema;
+
+/// Key under which requests no route matched are counted
+pub const UNMATCHED: &str = "(unmatched)";
+
+/// Counters kept since the server started, reported by `GET /statsz`
+#[derive(Default)]
+pub struct ServerStats {
+    requests: Mutex<BTreeMap<String, u64>>,
+    parse_errors: Mutex<BTreeMap<String, u64>>,
+    cache_hits: AtomicU64,
+    cache_misses: AtomicU64,
+}
+
+impl ServerStats {
+    /// Count a request to `endpoint`, e.g. `POST /calculate`
+    ///
+    /// Endpoints are route templates such as `GET /sketches/{name}`, so the
+    /// map stays as small as the API however many datasets there are.
+    pub fn record_request(&self, endpoint: String) {
+        let mut requests = self.requests.lock().unwrap_or_else(|e| e.into_inner());
+        *requests.entry(endpoint).or_default() += 1;
+    }
+
+    /// Count an upload that failed to parse as `format`
+    pub fn record_parse_error(&self, format: &str) {
+        let mut errors = self.parse_errors.lock().unwrap_or_else(|e| e.into_inner());
+        match errors.get_mut(format) {
+            Some(count) => *count += 1,
+            None => {
+                errors.insert(format.to_string(), 1);
+            }
+        }
+    }
+
+    /// Count an idempotency cache lookup, `hit` when it replayed a response
+    pub fn record_cache(&self, hit: bool) {
+        let counter = if hit {
+            &self.cache_hits
+        } else {
+            &self.cache_misses
+        };
+        counter.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn report(&self, in_flight: usize, sketches: usize, uptime_secs: u64) -> Statsz {
+        Statsz {
+            uptime_secs,
+            requests: self
+                .requests
+                .lock()
+                .unwrap_or_else(|e| e.into_inner())
+                .clone(),
+            parse_errors: self
+                .parse_errors
+                .lock()
+                .unwrap_or_else(|e| e.into_inner())
+                .clone(),
+            cache: CacheStats {
+                hits: self.cache_hits.load(Ordering::Relaxed),
+                misses: self.cache_misses.load(Ordering::Relaxed),
+            },
+            in_flight,
+            sketches,
+        }
+    }
+}
+
+/// Internal counters as reported by `GET /statsz`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct Statsz {
+    /// Seconds since the server started
+    pub uptime_secs: u64,
+    /// Requests received per endpoint (`METHOD /route`), rejected or not
+    pub requests: BTreeMap<String, u64>,
+    /// Uploads that failed to parse, per input format
+    pub parse_errors: BTreeMap<String, u64>,
+    /// Idempotency cache lookups
+    pub cache: CacheStats,
+    /// Calculation and ingest requests queued or running
+    pub in_flight: usize,
+    /// Sketches held in memory
+    pub sketches: usize,
+}
+
+/// Lookups of the idempotency cache
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct CacheStats {
+    /// Requests answered with a stored response
+    pub hits: u64,
+    /// Requests with an `Idempotency-Key` the cache didn't hold
+    pub misses: u64,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn counters_accumulate() {
+        let stats = ServerStats::default();
+        stats.record_request("POST /calculate".to_string());
+        stats.record_request("POST /calculate".to_string());
+        stats.record_request(UNMATCHED.to_string());
+        stats.record_parse_error("csv");
+        stats.record_cache(true);
+        stats.record_cache(false);
+        stats.record_cache(false);
+
+        let report = stats.report(1, 2, 3);
+        assert_eq!(report.requests["POST /calculate"], 2);
+        assert_eq!(report.requests[UNMATCHED], 1);
+        assert_eq!(report.parse_errors["csv"], 1);
+        assert_eq!(report.cache, CacheStats { hits: 1, misses: 2 });
+        assert_eq!((report.in_flight, report.sketches), (1, 2));
+    }
+}
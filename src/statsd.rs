@@ -0,0 +1,138 @@
+use anyhow::Context;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use tokio::net::UdpSocket;
+use tracing::{debug, info, warn};
+
+use crate::redact::Redactor;
+use crate::sketches::{SketchStore, validate_name};
+
+/// Largest datagram read; anything longer is truncated by the OS
+const MAX_PACKET_BYTES: usize = 64 * 1024;
+
+/// Bind the StatsD socket, so a taken port fails startup rather than
+/// the first packet
+pub async fn bind(addr: SocketAddr) -> anyhow::Result<UdpSocket> {
+    let socket = UdpSocket::bind(addr)
+        .await
+        .with_context(|| format!("Failed to bind StatsD listener on {addr}"))?;
+    info!("StatsD listener on udp://{}", socket.local_addr()?);
+    Ok(socket)
+}
+
+/// Feed every timing received on `socket` into the sketch named after its
+/// metric, until the socket fails
+///
+/// StatsD is fire-and-forget, so malformed lines, other metric types,
+/// timings a range rule rejects and sketches over the store's limit are
+/// dropped with a debug log. With `redactor` set, a dropped line is logged
+/// as its hash rather than its text.
+pub async fn run(socket: UdpSocket, sketches: Arc<SketchStore>, redactor: Option<Arc<Redactor>>) {
+    let mut packet = vec![0; MAX_PACKET_BYTES];
+    loop {
+        let len = match socket.recv(&mut packet).await {
+            Ok(len) => len,
+            Err(e) => {
+                warn!("StatsD listener stopped: {e}");
+                return;
+            }
+        };
+        let Ok(text) = std::str::from_utf8(&packet[..len]) else {
+            debug!("Dropped StatsD packet that isn't UTF-8");
+            continue;
+        };
+        for line in text.lines().filter(|l| !l.trim().is_empty()) {
+            match parse_timing(line) {
+                Ok((name, value)) => match sketches.get_or_create(name) {
+                    Some(sketch) => {
+                        let mut dataset = sketch.lock().unwrap_or_else(|e| e.into_inner());
+                        if !dataset.insert(value) {
+                            debug!(name, "Dropped StatsD timing: outside the expected range");
+                        }
+                    }
+                    None => debug!(name, "Dropped StatsD timing: too many sketches"),
+                },
+                Err(reason) => match &redactor {
+                    Some(redactor) => {
+                        debug!(line = %redactor.hash(line), "Dropped StatsD line: {reason}")
+                    }
+                    None => debug!(line, "Dropped StatsD line: {reason}"),
+                },
+            }
+        }
+    }
+}
+
+/// The metric name and value of a timing line such as `api.latency:12.5|ms`
+///
+/// Histogram (`h`) and distribution (`d`) lines count as timings. A sample
+/// rate (`|@0.1`) and DogStatsD tags (`|#env:prod`) are accepted but don't
+/// change the value, which is added once.
+fn parse_timing(line: &str) -> Result<(&str, f64), &'static str> {
+    let (name, rest) = line.trim().split_once(':').ok_or("no ':' after the name")?;
+    let mut fields = rest.split('|');
+    let value = fields.next().unwrap_or_default();
+    match fields.next() {
+        Some("ms" | "h" | "d") => {}
+        Some(_) => return Err("not a timing"),
+        None => return Err("no metric type"),
+    }
+    validate_name(name).map_err(|_| "invalid metric name")?;
+    let value: f64 = value.parse().map_err(|_| "invalid value")?;
+    if !value.is_finite() {
+        return Err("invalid value");
+    }
+    Ok((name, value))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_timing_lines() {
+        assert_eq!(
+            parse_timing("api.latency:12.5|ms"),
+            Ok(("api.latency", 12.5))
+        );
+        assert_eq!(parse_timing("db_query:3|h|@0.1"), Ok(("db_query", 3.0)));
+        assert_eq!(parse_timing("rpc-ms:7|d|#env:prod"), Ok(("rpc-ms", 7.0)));
+        assert_eq!(parse_timing("hits:1|c"), Err("not a timing"));
+        assert_eq!(parse_timing("api.latency:12.5"), Err("no metric type"));
+        assert_eq!(parse_timing("api latency:1|ms"), Err("invalid metric name"));
+        assert_eq!(parse_timing("api.latency:NaN|ms"), Err("invalid value"));
+        assert_eq!(parse_timing("api.latency"), Err("no ':' after the name"));
+    }
+
+    #[tokio::test]
+    async fn timings_arrive_in_sketches() {
+        let socket = bind("127.0.0.1:0".parse().unwrap()).await.unwrap();
+        let addr = socket.local_addr().unwrap();
+        let sketches = Arc::new(SketchStore::default());
+        tokio::spawn(run(socket, Arc::clone(&sketches), None));
+
+        let client = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        client
+            .send_to(b"api.latency:10|ms\napi.latency:30|ms\nhits:1|c\n", addr)
+            .await
+            .unwrap();
+        client.send_to(b"db:5|h", addr).await.unwrap();
+
+        let counts = || {
+            let list = sketches.list();
+            list.iter()
+                .map(|s| (s.name.clone(), s.count))
+                .collect::<Vec<_>>()
+        };
+        for _ in 0..100 {
+            if counts().len() == 2 {
+                break;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+        }
+        assert_eq!(
+            counts(),
+            vec![("api.latency".to_string(), 2), ("db".to_string(), 1)]
+        );
+    }
+}
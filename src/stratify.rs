@@ -0,0 +1,237 @@
+use crate::{
+    DEFAULT_PERCENTILES, FieldError, FieldReader, PercentileMethod, PercentileValue,
+    ValidationErrors, calculate_percentile, calculate_percentiles_with,
+};
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+#[cfg(feature = "server")]
+use utoipa::ToSchema;
+
+/// Maximum number of strata
+pub const MAX_STRATA: usize = 100;
+
+/// Request structure for stratify API endpoint
+#[cfg_attr(feature = "server", derive(ToSchema))]
+#[derive(Debug, Deserialize, Serialize)]
+pub struct StratifyRequest {
+    /// Column to bucket rows by (e.g. payload size)
+    pub by: Vec<f64>,
+    /// Column to report percentiles of (e.g. latency), aligned index-by-index with `by`
+    pub values: Vec<f64>,
+    /// Number of quantile buckets of `by` (defaults to 4, i.e. quartiles)
+    #[serde(default = "default_strata")]
+    pub strata: usize,
+    /// Percentiles to report per bucket (defaults to 50, 90, 95, 99)
+    #[serde(default = "default_percentiles")]
+    pub percentiles: Vec<f64>,
+    /// Interpolation method (defaults to linear)
+    #[serde(default)]
+    pub method: PercentileMethod,
+}
+
+fn default_strata() -> usize {
+    4
+}
+
+fn default_percentiles() -> Vec<f64> {
+    DEFAULT_PERCENTILES.to_vec()
+}
+
+impl StratifyRequest {
+    /// Validate the request, collecting every problem rather than stopping at the first
+    pub fn validate(&self) -> std::result::Result<(), ValidationErrors> {
+        let mut errors = Vec::new();
+
+        for (name, series) in [("by", &self.by), ("values", &self.values)] {
+            if series.is_empty() {
+                errors.push(FieldError::new(
+                    name,
+                    "empty",
+                    format!("{name} must contain at least one value"),
+                ));
+            } else if series.iter().any(|v| !v.is_finite()) {
+                errors.push(FieldError::new(
+                    name,
+                    "non_finite",
+                    "Values must be finite numbers",
+                ));
+            }
+        }
+        if self.by.len() != self.values.len() && !errors.iter().any(|e| e.field == "values") {
+            errors.push(FieldError::new(
+                "values",
+                "length_mismatch",
+                format!(
+                    "Series must be the same length (by has {}, values has {})",
+                    self.by.len(),
+                    self.values.len()
+                ),
+            ));
+        }
+        if !(1..=MAX_STRATA).contains(&self.strata) {
+            errors.push(FieldError::new(
+                "strata",
+                "out_of_range",
+                format!("Strata must be between 1 and {MAX_STRATA}"),
+            ));
+        }
+        if self.percentiles.is_empty() {
+            errors.push(FieldError::new(
+                "percentiles",
+                "empty",
+                "At least one percentile is required",
+            ));
+        } else if self.percentiles.iter().any(|p| !(0.0..=100.0).contains(p)) {
+            errors.push(FieldError::new(
+                "percentiles",
+                "out_of_range",
+                "Percentiles must be between 0 and 100",
+            ));
+        }
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(ValidationErrors(errors))
+        }
+    }
+
+    /// Build a request from a JSON object, reporting type errors for every field
+    /// together with the semantic checks from [`StratifyRequest::validate`]
+    pub fn from_json_value(
+        value: serde_json::Value,
+    ) -> std::result::Result<Self, ValidationErrors> {
+        let mut reader = FieldReader::new(value)?;
+        let request = Self {
+            by: reader.series("by"),
+            values: reader.series("values"),
+            strata: reader
+                .optional("strata", "invalid_type", "Strata must be a positive integer")
+                .unwrap_or_else(default_strata),
+            percentiles: reader
+                .optional(
+                    "percentiles",
+                    "invalid_type",
+                    "Percentiles must be an array of numbers",
+                )
+                .unwrap_or_else(default_percentiles),
+            method: reader
+                .optional(
+                    "method",
+                    "invalid_value",
+                    "Method must be one of: linear, nearest_rank, lower, upper, midpoint, nearest_even",
+                )
+                .unwrap_or_default(),
+        };
+        reader.finish(request, Self::validate)
+    }
+}
+
+/// Response structure for stratify API endpoint
+#[cfg_attr(feature = "server", derive(ToSchema))]
+#[derive(Debug, Serialize, Deserialize)]
+pub struct StratifyResponse {
+    /// Number of rows
+    pub count: usize,
+    /// The interpolation method used
+    pub method: PercentileMethod,
+    /// One entry per quantile bucket of `by`, in ascending order
+    pub strata: Vec<Stratum>,
+}
+
+/// Rows whose `by` value falls in `[lower, upper)` (the last stratum includes `upper`)
+#[cfg_attr(feature = "server", derive(ToSchema))]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Stratum {
+    /// Inclusive lower bound on `by`
+    pub lower: f64,
+    /// Exclusive upper bound on `by`
+    pub upper: f64,
+    /// Number of rows in the stratum
+    pub count: usize,
+    /// Percentiles of `values` for these rows (empty when the stratum has no rows)
+    pub percentiles: Vec<PercentileValue>,
+}
+
+/// Bucket rows into `strata` quantile buckets of `by` and report percentiles of
+/// `values` within each bucket
+///
+/// Bucket edges are the linear-interpolated quantiles of `by`. Heavily tied
+/// data can produce empty strata, which are reported with a zero count.
+///
+/// # Examples
+/// ```
+/// use outlier::{stratify, PercentileMethod};
+///
+/// let by = [1.0, 2.0, 3.0, 4.0];
+/// let latency = [10.0, 11.0, 50.0, 60.0];
+/// let result = stratify(&by, &latency, 2, &[100.0], PercentileMethod::Linear).unwrap();
+/// assert_eq!(result.strata[0].percentiles[0].value, 11.0);
+/// assert_eq!(result.strata[1].percentiles[0].value, 60.0);
+/// ```
+pub fn stratify(
+    by: &[f64],
+    values: &[f64],
+    strata: usize,
+    percentiles: &[f64],
+    method: PercentileMethod,
+) -> Result<StratifyResponse> {
+    if by.len() != values.len() {
+        anyhow::bail!(
+            "Series must be the same length (by has {}, values has {})",
+            by.len(),
+            values.len()
+        );
+    }
+    if by.is_empty() {
+        anyhow::bail!("Cannot stratify an empty dataset");
+    }
+    if !(1..=MAX_STRATA).contains(&strata) {
+        anyhow::bail!("Strata must be between 1 and {}", MAX_STRATA);
+    }
+
+    let edges = (0..=strata)
+        .map(|i| {
+            calculate_percentile(
+                by,
+                i as f64 * 100.0 / strata as f64,
+                PercentileMethod::Linear,
+            )
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    let mut buckets = vec![Vec::new(); strata];
+    for (b, v) in by.iter().zip(values) {
+        buckets[stratum_of(*b, &edges)].push(*v);
+    }
+
+    let strata = buckets
+        .iter()
+        .zip(edges.windows(2))
+        .map(|(bucket, edge)| {
+            Ok(Stratum {
+                lower: edge[0],
+                upper: edge[1],
+                count: bucket.len(),
+                percentiles: if bucket.is_empty() {
+                    Vec::new()
+                } else {
+                    calculate_percentiles_with(bucket, percentiles, method)?
+                },
+            })
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    Ok(StratifyResponse {
+        count: by.len(),
+        method,
+        strata,
+    })
+}
+
+fn stratum_of(value: f64, edges: &[f64]) -> usize {
+    let last = edges.len() - 2;
+    // First inner edge strictly greater than the value
+    edges[1..=last].partition_point(|&edge| edge <= value)
+}
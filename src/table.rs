@@ -0,0 +1,101 @@
+use comfy_table::presets::UTF8_FULL_CONDENSED;
+use comfy_table::{Cell, CellAlignment, Color, ContentArrangement, Table};
+use outlier::{PercentileValue, format_percentile_label};
+
+/// Limits that color table values green, yellow or red
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Thresholds {
+    /// Values at or above this are yellow
+    pub warn: Option<f64>,
+    /// Values at or above this are red
+    pub crit: Option<f64>,
+}
+
+impl Thresholds {
+    /// Color for a value, or `None` when no thresholds are set
+    fn color(&self, value: f64) -> Option<Color> {
+        if self.warn.is_none() && self.crit.is_none() {
+            return None;
+        }
+        if self.crit.is_some_and(|crit| value >= crit) {
+            Some(Color::Red)
+        } else if self.warn.is_some_and(|warn| value >= warn) {
+            Some(Color::Yellow)
+        } else {
+            Some(Color::Green)
+        }
+    }
+}
+
+/// Whether `NO_COLOR` (https://no-color.org) asks for plain output
+fn no_color() -> bool {
+    std::env::var_os("NO_COLOR").is_some_and(|v| !v.is_empty())
+}
+
+/// Render percentiles as a two-column table
+///
+/// Colors are only emitted when stdout is a terminal and `NO_COLOR` is unset.
+pub fn percentile_table(
+    rows: &[PercentileValue],
+    precision: usize,
+    unit: Option<&str>,
+    thresholds: Thresholds,
+) -> Table {
+    let mut table = Table::new();
+    table
+        .load_preset(UTF8_FULL_CONDENSED)
+        .set_content_arrangement(ContentArrangement::Dynamic)
+        .set_header(vec!["Percentile", "Value"]);
+    if no_color() {
+        table.force_no_tty();
+    }
+
+    let suffix = unit.map(|u| format!(" {u}")).unwrap_or_default();
+    for row in rows {
+        let mut value = Cell::new(format!("{:.*}{suffix}", precision, row.value))
+            .set_alignment(CellAlignment::Right);
+        if let Some(color) = thresholds.color(row.value) {
+            value = value.fg(color);
+        }
+        table.add_row(vec![
+            Cell::new(format_percentile_label(row.percentile)),
+            value,
+        ]);
+    }
+    table
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn thresholds_pick_colors() {
+        let thresholds = Thresholds {
+            warn: Some(100.0),
+            crit: Some(200.0),
+        };
+        assert_eq!(thresholds.color(50.0), Some(Color::Green));
+        assert_eq!(thresholds.color(100.0), Some(Color::Yellow));
+        assert_eq!(thresholds.color(250.0), Some(Color::Red));
+        assert_eq!(Thresholds::default().color(250.0), None);
+    }
+
+    #[test]
+    fn table_shows_labels_and_units() {
+        let rows = [
+            PercentileValue {
+                percentile: 50.0,
+                value: 12.0,
+            },
+            PercentileValue {
+                percentile: 99.9,
+                value: 140.256,
+            },
+        ];
+        let table = percentile_table(&rows, 1, Some("ms"), Thresholds::default()).to_string();
+        assert!(table.contains("P50"));
+        assert!(table.contains("P99.9"));
+        assert!(table.contains("140.3 ms"));
+    }
+}
@@ -0,0 +1,72 @@
+use axum::Router;
+
+use crate::server::{AppState, build_app};
+
+/// Build the full application router with authentication and rate limiting disabled
+///
+/// Drive it in-process with `tower::ServiceExt::oneshot` instead of spawning
+/// the binary.
+///
+/// # Examples
+/// ```
+/// use axum::body::Body;
+/// use axum::http::{Request, StatusCode};
+/// use tower::ServiceExt;
+///
+/// # #[tokio::main]
+/// # async fn main() {
+/// let response = outlier::test_support::router()
+///     .oneshot(
+///         Request::post("/calculate")
+///             .header("content-type", "application/json")
+///             .body(Body::from(r#"{"values":[1,2,3],"percentile":50}"#))
+///             .unwrap(),
+///     )
+///     .await
+///     .unwrap();
+/// assert_eq!(response.status(), StatusCode::OK);
+/// # }
+/// ```
+pub fn router() -> Router {
+    build_app(AppState::unrestricted())
+}
+
+/// Build the application router requiring one of `keys` in the `X-API-Key` header
+///
+/// Keys are granted the `calculate` scope, as plain keys from `auth.api_keys` are.
+pub fn router_with_api_keys(keys: &[&str]) -> Router {
+    let keys: Vec<String> = keys.iter().map(|k| k.to_string()).collect();
+    build_app(AppState::with_api_keys(&keys))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::body::Body;
+    use axum::http::{Request, StatusCode};
+    use tower::ServiceExt;
+
+    fn calculate(key: Option<&str>) -> Request<Body> {
+        let mut builder = Request::post("/calculate").header("content-type", "application/json");
+        if let Some(key) = key {
+            builder = builder.header("X-API-Key", key);
+        }
+        builder
+            .body(Body::from(r#"{"values":[1,2,3],"percentile":50}"#))
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn router_with_api_keys_requires_key() {
+        let app = router_with_api_keys(&["downstream-key"]);
+
+        let response = app.clone().oneshot(calculate(None)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+
+        let response = app
+            .oneshot(calculate(Some("downstream-key")))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+}
@@ -221,6 +221,108 @@ fn test_all_methods_p100() {
     }
 }
 
+#[test]
+fn test_percentile_of_sorted_matches_unsorted() {
+    let values = vec![9.0, 2.0, 7.0, 4.0, 5.0, 1.0];
+    let sorted = sort_values(&values);
+    assert_eq!(sorted, vec![1.0, 2.0, 4.0, 5.0, 7.0, 9.0]);
+    let methods = [
+        PercentileMethod::Linear,
+        PercentileMethod::NearestRank,
+        PercentileMethod::Lower,
+        PercentileMethod::Upper,
+        PercentileMethod::Midpoint,
+        PercentileMethod::NearestEven,
+    ];
+    for method in methods {
+        assert_eq!(
+            percentile_of_sorted(&sorted, 37.5, method).unwrap(),
+            calculate_percentile(&values, 37.5, method).unwrap(),
+            "mismatch for method {:?}",
+            method
+        );
+    }
+    assert!(percentile_of_sorted(&[], 50.0, PercentileMethod::Linear).is_err());
+}
+
+#[test]
+fn test_calculate_percentile_in_place() {
+    let values = vec![9.0, 2.0, 7.0, 4.0, 5.0, 1.0, 4.0];
+    for method in [
+        PercentileMethod::Linear,
+        PercentileMethod::NearestRank,
+        PercentileMethod::Midpoint,
+    ] {
+        let mut in_place = values.clone();
+        assert_eq!(
+            calculate_percentile_mut(&mut in_place, 62.5, method).unwrap(),
+            calculate_percentile(&values, 62.5, method).unwrap()
+        );
+        assert_eq!(in_place, sort_values(&values));
+        assert_eq!(
+            calculate_percentile_owned(values.clone(), 62.5, method).unwrap(),
+            calculate_percentile(&values, 62.5, method).unwrap()
+        );
+    }
+
+    // Invalid requests leave the values untouched
+    let mut untouched = values.clone();
+    assert!(calculate_percentile_mut(&mut untouched, 101.0, PercentileMethod::Linear).is_err());
+    assert_eq!(untouched, values);
+    assert!(calculate_percentile_mut(&mut [], 50.0, PercentileMethod::Linear).is_err());
+    assert!(calculate_percentile_owned(Vec::new(), 50.0, PercentileMethod::Linear).is_err());
+}
+
+#[test]
+fn test_calculate_percentiles() {
+    let values = vec![9.0, 2.0, 7.0, 4.0, 5.0, 1.0, 4.0];
+    let percentiles = [99.0, 50.0, 90.0, 95.0];
+    let results = calculate_percentiles(&values, &percentiles).unwrap();
+    for (&p, result) in percentiles.iter().zip(results) {
+        assert_eq!(
+            result,
+            calculate_percentile(&values, p, PercentileMethod::Linear).unwrap()
+        );
+    }
+    assert!(calculate_percentiles(&values, &[50.0, 101.0]).is_err());
+    assert!(calculate_percentiles(&[], &[50.0]).is_err());
+}
+
+#[test]
+fn test_calculate_percentile_sorted() {
+    let sorted: Vec<f64> = (0..10_000).map(f64::from).collect();
+    assert_eq!(
+        calculate_percentile_sorted(&sorted, 99.0, PercentileMethod::Linear).unwrap(),
+        calculate_percentile(&sorted, 99.0, PercentileMethod::Linear).unwrap()
+    );
+
+    let mut reversed = sorted.clone();
+    reversed.reverse();
+    assert!(calculate_percentile_sorted(&reversed, 99.0, PercentileMethod::Linear).is_err());
+    assert!(calculate_percentile_sorted(&[], 50.0, PercentileMethod::Linear).is_err());
+}
+
+#[test]
+fn test_is_sorted_ascending() {
+    assert!(is_sorted_ascending(&[]));
+    assert!(is_sorted_ascending(&[1.0, 1.0, 2.0]));
+    assert!(!is_sorted_ascending(&[2.0, 1.0]));
+    assert!(!is_sorted_ascending(&[1.0, f64::NAN, 2.0]));
+    assert!(!is_sorted_ascending(&[f64::NAN]));
+
+    // Every pair is checked, not just a sample
+    let mut large: Vec<f64> = (0..100_000).map(f64::from).collect();
+    assert!(is_sorted_ascending(&large));
+    for i in [0, 500, 12_345, 99_998] {
+        large.swap(i, i + 1);
+        assert!(!is_sorted_ascending(&large), "swap at {i}");
+        large.swap(i, i + 1);
+    }
+    let mut with_nan: Vec<f64> = (0..1000).map(f64::from).collect();
+    with_nan[500] = f64::NAN;
+    assert!(!is_sorted_ascending(&with_nan));
+}
+
 #[test]
 fn test_all_methods_single_value() {
     let values = vec![42.0];
@@ -298,3 +400,2352 @@ fn test_percentile_method_display() {
     assert_eq!(PercentileMethod::Midpoint.to_string(), "midpoint");
     assert_eq!(PercentileMethod::NearestEven.to_string(), "nearest_even");
 }
+
+// ========================
+// Fractional percentile tests
+// ========================
+
+#[test]
+fn test_fractional_percentile_exact_rank() {
+    // P99.99 of 1..=10001: index = 99.99 * 10000 / 100 = 9999 exactly
+    let values: Vec<f64> = (1..=10001).map(|x| x as f64).collect();
+    for method in [
+        PercentileMethod::Linear,
+        PercentileMethod::Lower,
+        PercentileMethod::Upper,
+    ] {
+        let result = calculate_percentile(&values, 99.99, method).unwrap();
+        assert_eq!(result, 10000.0, "P99.99 failed for method {:?}", method);
+    }
+}
+
+#[test]
+fn test_fractional_percentile_p99_999() {
+    // index = 99.999 * 10000 / 100 = 9999.9
+    let values: Vec<f64> = (1..=10001).map(|x| x as f64).collect();
+    let result = calculate_percentile(&values, 99.999, PercentileMethod::Linear).unwrap();
+    assert!((result - 10000.9).abs() < 1e-9);
+    let result = calculate_percentile(&values, 99.999, PercentileMethod::Lower).unwrap();
+    assert_eq!(result, 10000.0);
+}
+
+#[test]
+fn test_integer_percentile_exact_rank_not_perturbed() {
+    // (57 / 100) * 10000 = 5699.999999999999 without index snapping
+    let values: Vec<f64> = (1..=10001).map(|x| x as f64).collect();
+    let result = calculate_percentile(&values, 57.0, PercentileMethod::Lower).unwrap();
+    assert_eq!(result, 5701.0);
+}
+
+#[test]
+fn test_percentile_nan_rejected() {
+    let values = vec![1.0, 2.0, 3.0];
+    assert!(calculate_percentile(&values, f64::NAN, PercentileMethod::Linear).is_err());
+}
+
+#[test]
+fn test_format_percentile_label() {
+    assert_eq!(format_percentile_label(95.0), "P95");
+    assert_eq!(format_percentile_label(50.0), "P50");
+    assert_eq!(format_percentile_label(99.95), "P99.95");
+    assert_eq!(format_percentile_label(99.999), "P99.999");
+}
+
+// ========================
+// Precision and rounding tests
+// ========================
+
+#[test]
+fn test_round_half_even_ties() {
+    assert_eq!(
+        round_to_precision(2.345, 2, RoundingMode::HalfEven).unwrap(),
+        2.34
+    );
+    assert_eq!(
+        round_to_precision(2.355, 2, RoundingMode::HalfEven).unwrap(),
+        2.36
+    );
+    assert_eq!(
+        round_to_precision(0.5, 0, RoundingMode::HalfEven).unwrap(),
+        0.0
+    );
+    assert_eq!(
+        round_to_precision(1.5, 0, RoundingMode::HalfEven).unwrap(),
+        2.0
+    );
+}
+
+#[test]
+fn test_round_floor_and_ceil() {
+    assert_eq!(
+        round_to_precision(9.559, 2, RoundingMode::Floor).unwrap(),
+        9.55
+    );
+    assert_eq!(
+        round_to_precision(9.551, 2, RoundingMode::Ceil).unwrap(),
+        9.56
+    );
+    assert_eq!(
+        round_to_precision(-1.25, 1, RoundingMode::Floor).unwrap(),
+        -1.3
+    );
+    assert_eq!(
+        round_to_precision(-1.25, 1, RoundingMode::Ceil).unwrap(),
+        -1.2
+    );
+}
+
+#[test]
+fn test_round_floor_ignores_binary_noise() {
+    // 2.3 * 100 = 229.99999999999997 in binary floating point
+    assert_eq!(
+        round_to_precision(2.3, 2, RoundingMode::Floor).unwrap(),
+        2.3
+    );
+    assert_eq!(round_to_precision(2.3, 2, RoundingMode::Ceil).unwrap(), 2.3);
+}
+
+#[test]
+fn test_round_just_off_a_boundary() {
+    // (value, mode, expected) either side of 1.24 and of the 2.345 tie
+    let cases = [
+        (1.2399999999, RoundingMode::Floor, 1.23),
+        (1.2400000001, RoundingMode::Floor, 1.24),
+        (123456.7899999, RoundingMode::Floor, 123456.78),
+        (-1.2400000001, RoundingMode::Floor, -1.25),
+        (1.2399999999, RoundingMode::Ceil, 1.24),
+        (1.2400000001, RoundingMode::Ceil, 1.25),
+        (123456.7800001, RoundingMode::Ceil, 123456.79),
+        (-1.2399999999, RoundingMode::Ceil, -1.23),
+        (2.3449999999, RoundingMode::HalfEven, 2.34),
+        (2.3450000001, RoundingMode::HalfEven, 2.35),
+        (2.3549999999, RoundingMode::HalfEven, 2.35),
+        (2.3550000001, RoundingMode::HalfEven, 2.36),
+    ];
+    for (value, mode, expected) in cases {
+        assert_eq!(
+            round_to_precision(value, 2, mode).unwrap(),
+            expected,
+            "{mode} of {value}"
+        );
+    }
+
+    // Float noise still snaps: 0.1 + 0.2 = 0.30000000000000004
+    assert_eq!(
+        round_to_precision(0.1 + 0.2, 2, RoundingMode::Ceil).unwrap(),
+        0.3
+    );
+    assert_eq!(
+        round_to_precision(2.675, 2, RoundingMode::HalfEven).unwrap(),
+        2.68
+    );
+}
+
+#[test]
+fn test_round_huge_values_unchanged() {
+    // Scaling these by 10^precision would overflow to infinity
+    for value in [1e300, f64::MAX, -f64::MAX, 2f64.powi(52) + 1.0] {
+        for mode in [
+            RoundingMode::HalfEven,
+            RoundingMode::Floor,
+            RoundingMode::Ceil,
+        ] {
+            for precision in [0, 10, MAX_PRECISION] {
+                assert_eq!(
+                    round_to_precision(value, precision, mode).unwrap(),
+                    value,
+                    "{mode} of {value} to {precision}"
+                );
+            }
+        }
+    }
+}
+
+#[test]
+fn test_round_precision_out_of_range() {
+    assert!(round_to_precision(1.0, MAX_PRECISION + 1, RoundingMode::HalfEven).is_err());
+}
+
+#[test]
+fn test_rounding_mode_serde_and_display() {
+    assert_eq!(
+        serde_json::to_string(&RoundingMode::HalfEven).unwrap(),
+        "\"half_even\""
+    );
+    assert_eq!(RoundingMode::Floor.to_string(), "floor");
+    assert_eq!(RoundingMode::Ceil.to_string(), "ceil");
+}
+
+#[test]
+fn test_calculate_request_default_rounding() {
+    let json = r#"{"values": [1.0, 2.0]}"#;
+    let req: CalculateRequest = serde_json::from_str(json).unwrap();
+    assert_eq!(req.precision, None);
+    assert_eq!(req.rounding, RoundingMode::HalfEven);
+}
+
+// ========================
+// Locale-aware CSV parsing tests
+// ========================
+
+#[test]
+fn test_parse_localized_number_de() {
+    let de = CsvOptions::for_locale(NumberLocale::De);
+    assert_eq!(parse_localized_number("1.234,56", &de), Some(1234.56));
+    assert_eq!(parse_localized_number("1,5", &de), Some(1.5));
+    assert_eq!(
+        parse_localized_number("-12.345.678", &de),
+        Some(-12345678.0)
+    );
+    assert_eq!(parse_localized_number("42", &de), Some(42.0));
+}
+
+#[test]
+fn test_parse_localized_number_rejects_wrong_convention() {
+    let de = CsvOptions::for_locale(NumberLocale::De);
+    assert_eq!(parse_localized_number("1,234.56", &de), None);
+    assert_eq!(parse_localized_number("12.34", &de), None);
+    assert_eq!(parse_localized_number("1,2,3", &de), None);
+}
+
+#[test]
+fn test_parse_localized_number_en_and_fr() {
+    let en = CsvOptions::for_locale(NumberLocale::En);
+    assert_eq!(parse_localized_number("1,234.56", &en), Some(1234.56));
+    let fr = CsvOptions::for_locale(NumberLocale::Fr);
+    assert_eq!(parse_localized_number("1 234,56", &fr), Some(1234.56));
+}
+
+#[test]
+fn test_read_csv_bytes_with_de_locale() {
+    let csv = b"value\n1,5\n\"2,5\"\n1.000,25\n";
+    let de = CsvOptions::for_locale(NumberLocale::De);
+    let values = read_values_from_bytes_with_options(csv, "data.csv", &de).unwrap();
+    assert_eq!(values, vec![1.5, 2.5, 1000.25]);
+}
+
+#[test]
+fn test_read_csv_bytes_default_rejects_comma_decimals() {
+    let csv = b"value\n\"1,5\"\n";
+    let result = read_values_from_bytes(csv, "data.csv");
+    assert!(result.is_err());
+}
+
+#[test]
+fn test_read_csv_bytes_picks_value_column() {
+    let csv = b"id,value\n1,10.5\n2,20.5\n";
+    let values = read_values_from_bytes(csv, "data.csv").unwrap();
+    assert_eq!(values, vec![10.5, 20.5]);
+}
+
+// ========================
+// Lenient vs strict CSV number tests
+// ========================
+
+#[test]
+fn test_lenient_accepts_scientific_inf_and_padding() {
+    let opts = CsvOptions::default();
+    assert_eq!(parse_localized_number("1.2e-3", &opts), Some(0.0012));
+    assert_eq!(parse_localized_number("+inf", &opts), Some(f64::INFINITY));
+    assert_eq!(parse_localized_number("  42.5 ", &opts), Some(42.5));
+}
+
+#[test]
+fn test_lenient_accepts_hex_floats() {
+    let opts = CsvOptions::default();
+    assert_eq!(parse_localized_number("0x1.8p3", &opts), Some(12.0));
+    assert_eq!(parse_localized_number("-0xAp-2", &opts), Some(-2.5));
+    assert_eq!(parse_localized_number("0x10", &opts), Some(16.0));
+    assert_eq!(parse_localized_number("0x", &opts), None);
+    assert_eq!(parse_localized_number("0xg", &opts), None);
+}
+
+#[test]
+fn test_strict_rejects_non_plain_numbers() {
+    let opts = CsvOptions {
+        strict: true,
+        ..CsvOptions::default()
+    };
+    assert_eq!(parse_localized_number("1.5", &opts), Some(1.5));
+    assert_eq!(parse_localized_number("-7", &opts), Some(-7.0));
+    assert_eq!(parse_localized_number("1.2e-3", &opts), None);
+    assert_eq!(parse_localized_number("+inf", &opts), None);
+    assert_eq!(parse_localized_number(" 42", &opts), None);
+    assert_eq!(parse_localized_number("0x1.8p3", &opts), None);
+}
+
+#[test]
+fn test_nan_rejected_in_csv() {
+    let opts = CsvOptions::default();
+    assert_eq!(parse_localized_number("NaN", &opts), None);
+    assert!(read_values_from_bytes(b"value\n1.0\nNaN\n", "data.csv").is_err());
+}
+
+#[test]
+fn test_read_csv_bytes_lenient_mixed_formats() {
+    let csv = b"value\n 1.0\n1e1\n0x1p2\n";
+    let values = read_values_from_bytes(csv, "data.csv").unwrap();
+    assert_eq!(values, vec![1.0, 10.0, 4.0]);
+}
+
+// ========================
+// Structured validation tests
+// ========================
+
+#[test]
+fn test_validate_collects_all_errors() {
+    let req = CalculateRequest {
+        values: vec![],
+        percentile: Some(-5.0),
+        percentiles: vec![],
+        method: PercentileMethod::Linear,
+        precision: Some(MAX_PRECISION + 1),
+        rounding: RoundingMode::HalfEven,
+        skip_first: 0,
+        transform: None,
+        engine: None,
+        summary: false,
+        sorted: false,
+    };
+    let err = req.validate().unwrap_err();
+    let fields: Vec<&str> = err.0.iter().map(|e| e.field.as_str()).collect();
+    assert_eq!(fields, vec!["values", "percentile", "precision"]);
+    assert!(err.to_string().contains("empty dataset"));
+    assert!(err.to_string().contains("between 0 and 100"));
+}
+
+#[test]
+fn test_from_json_value_reports_type_and_range_errors() {
+    let value = serde_json::json!({
+        "values": "oops",
+        "percentile": 101,
+        "rounding": "sideways"
+    });
+    let err = CalculateRequest::from_json_value(value).unwrap_err();
+    let codes: Vec<(&str, &str)> = err
+        .0
+        .iter()
+        .map(|e| (e.field.as_str(), e.code.as_str()))
+        .collect();
+    assert_eq!(
+        codes,
+        vec![
+            ("values", "invalid_type"),
+            ("rounding", "invalid_value"),
+            ("percentile", "out_of_range"),
+        ]
+    );
+}
+
+#[test]
+fn test_from_json_value_missing_values_and_defaults() {
+    let err = CalculateRequest::from_json_value(serde_json::json!({})).unwrap_err();
+    assert_eq!(err.0.len(), 1);
+    assert_eq!(err.0[0].code, "required");
+
+    let req = CalculateRequest::from_json_value(serde_json::json!({"values": [1, 2]})).unwrap();
+    assert_eq!(req.percentile, None);
+    assert_eq!(req.requested_percentiles(), [DEFAULT_PERCENTILE]);
+    assert_eq!(req.method, PercentileMethod::Linear);
+}
+
+#[test]
+fn test_from_json_value_rejects_non_object() {
+    let err = CalculateRequest::from_json_value(serde_json::json!([1, 2, 3])).unwrap_err();
+    assert_eq!(err.0[0].field, "body");
+}
+
+// --- CalculateRequest builder ---
+
+#[test]
+fn test_builder_defaults() {
+    let request = CalculateRequest::builder()
+        .values([1.0, 2.0, 3.0])
+        .build()
+        .unwrap();
+    assert_eq!(request.percentile, None);
+    assert_eq!(request.requested_percentiles(), [95.0]);
+    assert_eq!(request.method, PercentileMethod::Linear);
+    assert_eq!(request.precision, None);
+    assert_eq!(request.rounding, RoundingMode::HalfEven);
+}
+
+#[test]
+fn test_builder_sets_every_field() {
+    let request = CalculateRequest::builder()
+        .values(vec![1.0, 2.0])
+        .percentile(99.9)
+        .method(PercentileMethod::Upper)
+        .precision(3)
+        .rounding(RoundingMode::Ceil)
+        .build()
+        .unwrap();
+    assert_eq!(request.percentile, Some(99.9));
+    assert_eq!(request.method, PercentileMethod::Upper);
+    assert_eq!(request.precision, Some(3));
+    assert_eq!(request.rounding, RoundingMode::Ceil);
+}
+
+#[test]
+fn test_builder_reports_all_errors() {
+    let err = CalculateRequest::builder()
+        .percentile(150.0)
+        .precision(99)
+        .build()
+        .unwrap_err();
+    let codes: Vec<(&str, &str)> = err
+        .0
+        .iter()
+        .map(|e| (e.field.as_str(), e.code.as_str()))
+        .collect();
+    assert_eq!(
+        codes,
+        vec![
+            ("values", "required"),
+            ("percentile", "out_of_range"),
+            ("precision", "out_of_range"),
+        ]
+    );
+}
+
+// --- Paired compare ---
+
+#[test]
+fn test_paired_differences_and_ratios() {
+    assert_eq!(
+        paired_differences(&[10.0, 20.0], &[15.0, 10.0]).unwrap(),
+        vec![5.0, -10.0]
+    );
+    assert_eq!(
+        paired_ratios(&[10.0, 20.0], &[15.0, 10.0]).unwrap(),
+        Some(vec![1.5, 0.5])
+    );
+}
+
+#[test]
+fn test_paired_ratios_none_when_baseline_has_zero() {
+    assert_eq!(paired_ratios(&[0.0, 1.0], &[1.0, 1.0]).unwrap(), None);
+    let result =
+        compare_paired(&[0.0, 1.0], &[1.0, 1.0], &[50.0], PercentileMethod::Linear).unwrap();
+    assert!(result.ratio.is_none());
+    assert_eq!(result.difference[0].value, 0.5);
+}
+
+#[test]
+fn test_paired_rejects_misaligned_series() {
+    assert!(paired_differences(&[1.0, 2.0], &[1.0]).is_err());
+    assert!(paired_differences(&[], &[]).is_err());
+}
+
+#[test]
+fn test_paired_compare_request_defaults() {
+    let request =
+        PairedCompareRequest::from_json_value(serde_json::json!({"before": [1], "after": [2]}))
+            .unwrap();
+    assert_eq!(request.percentiles, vec![50.0, 90.0, 95.0, 99.0]);
+    assert_eq!(request.method, PercentileMethod::Linear);
+
+    let errors = PairedCompareRequest::from_json_value(serde_json::json!({"after": "x"}))
+        .unwrap_err()
+        .0;
+    let fields: Vec<_> = errors
+        .iter()
+        .map(|e| (e.field.as_str(), e.code.as_str()))
+        .collect();
+    assert_eq!(
+        fields,
+        vec![("before", "required"), ("after", "invalid_type")]
+    );
+}
+
+// --- Stratify ---
+
+#[test]
+fn test_stratify_splits_by_quartiles() {
+    let by: Vec<f64> = (1..=8).map(f64::from).collect();
+    let latency = [1.0, 2.0, 3.0, 4.0, 5.0, 6.0, 70.0, 80.0];
+    let result = stratify(&by, &latency, 4, &[50.0], PercentileMethod::Linear).unwrap();
+
+    let counts: Vec<_> = result.strata.iter().map(|s| s.count).collect();
+    assert_eq!(counts, vec![2, 2, 2, 2]);
+    assert_eq!(result.strata[3].percentiles[0].value, 75.0);
+    assert_eq!(result.strata[0].lower, 1.0);
+    assert_eq!(result.strata[3].upper, 8.0);
+}
+
+#[test]
+fn test_stratify_tied_column_leaves_empty_strata() {
+    let result = stratify(
+        &[5.0, 5.0, 5.0],
+        &[1.0, 2.0, 3.0],
+        2,
+        &[50.0],
+        PercentileMethod::Linear,
+    )
+    .unwrap();
+    assert_eq!(result.strata[0].count, 0);
+    assert!(result.strata[0].percentiles.is_empty());
+    assert_eq!(result.strata[1].count, 3);
+}
+
+#[test]
+fn test_stratify_rejects_misaligned_series() {
+    assert!(stratify(&[1.0], &[1.0, 2.0], 4, &[50.0], PercentileMethod::Linear).is_err());
+    assert!(stratify(&[1.0], &[1.0], 0, &[50.0], PercentileMethod::Linear).is_err());
+}
+
+// --- Percentiles over time ---
+
+#[test]
+fn test_percentiles_over_time_per_bucket() {
+    let timestamps = [0.0, 1.0, 2.5, 3.0, 7.9];
+    let values = [1.0, 3.0, 10.0, 20.0, 5.0];
+    let result = percentiles_over_time(
+        &timestamps,
+        &values,
+        2.0,
+        false,
+        &[50.0],
+        PercentileMethod::Linear,
+    )
+    .unwrap();
+
+    let counts: Vec<_> = result.buckets.iter().map(|b| b.count).collect();
+    assert_eq!(counts, vec![2, 2, 0, 1]);
+    assert_eq!(result.buckets[0].percentiles[0].value, 2.0);
+    assert!(result.buckets[2].percentiles.is_empty());
+    assert_eq!(result.buckets[3].start, 6.0);
+}
+
+#[test]
+fn test_percentiles_over_time_cumulative_converges() {
+    let timestamps = [0.0, 1.0, 2.5, 3.0, 7.9];
+    let values = [1.0, 3.0, 10.0, 20.0, 5.0];
+    let result = percentiles_over_time(
+        &timestamps,
+        &values,
+        2.0,
+        true,
+        &[50.0],
+        PercentileMethod::Linear,
+    )
+    .unwrap();
+
+    let counts: Vec<_> = result.buckets.iter().map(|b| b.count).collect();
+    assert_eq!(counts, vec![2, 4, 4, 5]);
+    assert_eq!(result.buckets[3].percentiles[0].value, 5.0);
+}
+
+#[test]
+fn test_percentiles_over_time_rejects_bad_bucket_width() {
+    assert!(
+        percentiles_over_time(
+            &[0.0],
+            &[1.0],
+            0.0,
+            false,
+            &[50.0],
+            PercentileMethod::Linear
+        )
+        .is_err()
+    );
+    assert!(
+        percentiles_over_time(
+            &[0.0, 1e9],
+            &[1.0, 2.0],
+            1.0,
+            false,
+            &[50.0],
+            PercentileMethod::Linear
+        )
+        .is_err()
+    );
+}
+
+#[test]
+fn test_timeseries_bands_pivot_buckets() {
+    let result = percentiles_over_time(
+        &[0.0, 1.0, 5.0],
+        &[1.0, 3.0, 7.0],
+        2.0,
+        false,
+        &[0.0, 100.0],
+        PercentileMethod::Linear,
+    )
+    .unwrap();
+    let bands = result.bands(&[0.0, 100.0]);
+
+    assert_eq!(bands.len(), 2);
+    assert_eq!(bands[0].target, "P0");
+    assert_eq!(
+        bands[0].datapoints,
+        vec![(Some(1.0), 0.0), (None, 2000.0), (Some(7.0), 4000.0)]
+    );
+    assert_eq!(bands[1].percentile, 100.0);
+    assert_eq!(bands[1].datapoints[0], (Some(3.0), 0.0));
+}
+
+// --- Warm-up exclusion ---
+
+#[test]
+fn test_calculate_request_skip_first() {
+    let request = CalculateRequest::builder()
+        .values(vec![500.0, 1.0, 2.0, 3.0])
+        .skip_first(1)
+        .build()
+        .unwrap();
+    assert_eq!(request.measured_values(), &[1.0, 2.0, 3.0]);
+
+    let err =
+        CalculateRequest::from_json_value(serde_json::json!({"values": [1, 2], "skip_first": 2}))
+            .unwrap_err();
+    assert_eq!(err.0[0].field, "skip_first");
+    assert_eq!(err.0[0].code, "out_of_range");
+}
+
+#[test]
+fn test_skip_warmup_by_count_and_duration() {
+    let timestamps = [0.0, 10.0, 20.0, 30.0];
+    let values = [9.0, 8.0, 7.0, 6.0];
+    assert_eq!(
+        skip_warmup(&timestamps, &values, 2, 0.0),
+        (vec![20.0, 30.0], vec![7.0, 6.0])
+    );
+    assert_eq!(
+        skip_warmup(&timestamps, &values, 0, 25.0),
+        (vec![30.0], vec![6.0])
+    );
+}
+
+#[test]
+fn test_timeseries_request_rejects_skipping_everything() {
+    let err = TimeseriesRequest::from_json_value(serde_json::json!({
+        "timestamps": [0, 1],
+        "values": [1, 2],
+        "bucket_secs": 1,
+        "skip_secs": 5
+    }))
+    .unwrap_err();
+    assert_eq!(err.0[0].field, "skip_secs");
+}
+
+// --- Downsampling ---
+
+#[test]
+fn test_downsample_keeps_extremes_and_shape() {
+    // Heavy tail: 990 fast values and 10 slow ones
+    let mut values = vec![1.0; 990];
+    values.extend(std::iter::repeat_n(100.0, 10));
+    let reduced = downsample(&values, 101).unwrap();
+
+    assert_eq!(reduced.len(), 101);
+    assert_eq!(reduced[0], 1.0);
+    assert_eq!(reduced[100], 100.0);
+    // ~1% of the reduced points sit in the tail, as in the original
+    assert_eq!(reduced.iter().filter(|&&v| v == 100.0).count(), 1);
+}
+
+#[test]
+fn test_downsample_small_dataset_is_returned_sorted() {
+    assert_eq!(
+        downsample(&[3.0, 1.0, 2.0], 10).unwrap(),
+        vec![1.0, 2.0, 3.0]
+    );
+    assert!(downsample(&[], 10).is_err());
+    assert!(downsample(&[1.0], 1).is_err());
+}
+
+// --- HdrHistogram interchange ---
+
+#[cfg(feature = "hdr")]
+#[test]
+fn test_hdr_round_trip_preserves_percentiles() {
+    let values: Vec<f64> = (1..=10_000).map(f64::from).collect();
+    let decoded = decode_hdr_histogram(&encode_hdr_histogram(&values).unwrap()).unwrap();
+
+    assert_eq!(decoded.len(), values.len());
+    for p in [50.0, 99.0, 99.9] {
+        let expected = calculate_percentile(&values, p, PercentileMethod::NearestRank).unwrap();
+        let actual = calculate_percentile(&decoded, p, PercentileMethod::NearestRank).unwrap();
+        // 3 significant digits
+        assert!(
+            (actual - expected).abs() / expected <= 1e-3,
+            "P{p}: {actual} vs {expected}"
+        );
+    }
+}
+
+#[cfg(feature = "hdr")]
+#[test]
+fn test_hdr_upload_and_bad_input() {
+    let encoded = encode_hdr_histogram(&[5.0, 7.0]).unwrap();
+    let values = read_values_from_bytes(encoded.as_bytes(), "latency.hdr").unwrap();
+    assert_eq!(values, vec![5.0, 7.0]);
+
+    assert!(decode_hdr_histogram("not base64!").is_err());
+    assert!(decode_hdr_histogram("aGVsbG8=").is_err());
+    assert!(encode_hdr_histogram(&[-1.0]).is_err());
+}
+
+// --- Input inspection ---
+
+#[test]
+fn test_inspect_csv_reports_columns_and_warnings() {
+    let csv = b"id,value,note\n1,5,ok\n2,-3,\n3,7,7\n";
+    let report = inspect_input(csv, "data.csv", InputFormat::Auto, &CsvOptions::default()).unwrap();
+    assert_eq!(report.format, InputFormat::Csv);
+    assert_eq!(report.count, 3);
+    let kinds: Vec<(&str, ColumnKind)> = report
+        .columns
+        .iter()
+        .map(|c| (c.name.as_str(), c.kind))
+        .collect();
+    assert_eq!(
+        kinds,
+        vec![
+            ("id", ColumnKind::Int),
+            ("value", ColumnKind::Int),
+            ("note", ColumnKind::Mixed)
+        ]
+    );
+    assert_eq!(report.warnings, vec!["1 value(s) are negative"]);
+}
+
+#[test]
+fn test_inspect_reports_detected_format_and_parse_errors() {
+    let report = inspect_input(
+        b"[1, 2]",
+        "data.json",
+        InputFormat::Auto,
+        &CsvOptions::default(),
+    )
+    .unwrap();
+    assert_eq!(report.format, InputFormat::Json);
+    assert!(report.warnings.is_empty());
+
+    let err = inspect_input(
+        b"value\nabc\n",
+        "data.csv",
+        InputFormat::Auto,
+        &CsvOptions::default(),
+    )
+    .unwrap_err();
+    assert!(err.to_string().contains("invalid number"));
+
+    let empty = inspect_values(&[]);
+    assert_eq!(empty.count, 0);
+    assert_eq!(empty.warnings.len(), 1);
+}
+
+#[test]
+fn test_inspect_infers_column_types() {
+    let csv = b"value,latency,took,at,host\n\
+        1,12,250ms,2024-03-01T10:00:00Z,a\n\
+        2,13.5,1.5s,2024-03-01 10:00:01,b\n";
+    let report = inspect_input(csv, "data.csv", InputFormat::Csv, &CsvOptions::default()).unwrap();
+    let kinds: Vec<ColumnKind> = report.columns.iter().map(|c| c.kind).collect();
+    assert_eq!(
+        kinds,
+        vec![
+            ColumnKind::Int,
+            ColumnKind::Float,
+            ColumnKind::Duration,
+            ColumnKind::Timestamp,
+            ColumnKind::String
+        ]
+    );
+    assert!(report.columns.iter().all(|c| c.coerced == 0));
+}
+
+#[test]
+fn test_coercion_rules_read_dirty_values_and_are_reported() {
+    let csv = "value\n\"$1,234.50\"\n-$5\n12.5%\n250ms\n1.5 s\n7\n".as_bytes();
+    let plain = CsvOptions::for_locale(NumberLocale::En);
+    assert!(read_values_from_bytes(csv, "data.csv").is_err());
+
+    let options = CsvOptions {
+        coerce: Coercions::from_rules(&[
+            CoercionRule::Currency,
+            CoercionRule::Percent,
+            CoercionRule::Duration,
+        ]),
+        ..plain
+    };
+    let values = read_values_from_bytes_with_options(csv, "data.csv", &options).unwrap();
+    assert_eq!(values, vec![1234.5, -5.0, 12.5, 250.0, 1500.0, 7.0]);
+
+    let report = inspect_input(csv, "data.csv", InputFormat::Auto, &options).unwrap();
+    assert_eq!(report.columns[0].kind, ColumnKind::Mixed);
+    assert_eq!(report.columns[0].coerced, 5);
+    assert!(
+        report
+            .warnings
+            .contains(&"5 cell(s) in column 'value' were coerced to numbers".to_string())
+    );
+
+    // Only the enabled rules apply
+    let currency_only = CsvOptions {
+        coerce: Coercions::from_rules(&[CoercionRule::Currency]),
+        ..plain
+    };
+    assert_eq!(currency_only.coerce.apply("€12,50", &currency_only), None);
+    assert_eq!(currency_only.coerce.apply("12.5%", &currency_only), None);
+    let de = CsvOptions {
+        coerce: Coercions::from_rules(&[CoercionRule::Currency]),
+        ..CsvOptions::for_locale(NumberLocale::De)
+    };
+    assert_eq!(de.coerce.apply("1.234,50 €", &de), Some(1234.5));
+    assert_eq!(parse_duration_ms("2min", &plain), Some(120_000.0));
+    assert_eq!(parse_duration_ms("ms", &plain), None);
+}
+
+// --- Pipelines ---
+
+#[cfg(feature = "pipeline")]
+#[test]
+fn test_pipeline_from_toml_filters_transforms_and_computes() {
+    let pipeline = Pipeline::parse(
+        r#"
+        name = "checkout"
+
+        [source]
+        type = "values"
+        values = [5000, 100, 200, 300, 400, 100000]
+
+        [[filters]]
+        type = "skip_first"
+        count = 1
+
+        [[filters]]
+        type = "range"
+        max = 60000
+
+        [[transforms]]
+        type = "scale"
+        factor = 0.001
+
+        [[statistics]]
+        type = "percentiles"
+        percentiles = [50, 100]
+        method = "lower"
+
+        [[statistics]]
+        type = "histogram"
+        buckets = 2
+        "#,
+        "checkout.toml",
+    )
+    .unwrap();
+    pipeline.validate().unwrap();
+
+    let values = pipeline
+        .load_values(Path::new("."), &CsvOptions::default())
+        .unwrap();
+    let result = pipeline.run(values).unwrap();
+    assert_eq!(result.count, 4);
+    assert_eq!(result.filtered, 2);
+    let percentiles = result.percentiles.unwrap();
+    assert_eq!(percentiles[0].value, 0.2);
+    assert_eq!(percentiles[1].value, 0.4);
+    assert_eq!(result.histogram.unwrap().buckets.len(), 2);
+    assert!(result.outliers.is_none());
+}
+
+#[cfg(feature = "pipeline")]
+#[test]
+fn test_pipeline_outliers_and_metrics() {
+    let pipeline = Pipeline::parse(
+        r#"{
+            "name": "api",
+            "source": {"type": "values", "values": []},
+            "statistics": [{"type": "outliers"}, {"type": "percentiles", "percentiles": [99]}]
+        }"#,
+        "api.json",
+    )
+    .unwrap();
+    let result = pipeline
+        .run(vec![10.0, 11.0, 12.0, 13.0, 14.0, 90.0])
+        .unwrap();
+    let outliers = result.outliers.as_ref().unwrap();
+    assert_eq!(outliers.outliers.len(), 1);
+    assert_eq!(outliers.outliers[0].index, 5);
+
+    let metrics = result.to_prometheus("outlier");
+    assert!(metrics.contains("# TYPE outlier_pipeline_values gauge\n"));
+    assert!(metrics.contains("outlier_pipeline_values{pipeline=\"api\"} 6\n"));
+    assert!(metrics.contains("outlier_pipeline_outliers{pipeline=\"api\"} 1\n"));
+    assert!(metrics.contains("quantile=\"0.99\""));
+
+    let summary = result.summary();
+    assert_eq!(summary.title, "Pipeline api: 6 values");
+    assert_eq!(summary.rows[0], ("Values".to_string(), 6.0));
+    assert!(summary.rows.contains(&("Outliers".to_string(), 1.0)));
+    assert!(summary.rows.iter().any(|(label, _)| label == "P99"));
+}
+
+#[cfg(feature = "pipeline")]
+#[test]
+fn test_pipeline_validation_and_run_errors() {
+    let pipeline = Pipeline::parse(
+        r#"
+        name = ""
+        source = { type = "values", values = [1] }
+        filters = [{ type = "range", min = 10, max = 1 }]
+        statistics = [{ type = "histogram", buckets = 0 }, { type = "histogram" }]
+        "#,
+        "bad.toml",
+    )
+    .unwrap();
+    let errors = pipeline.validate().unwrap_err().0;
+    let codes: Vec<(&str, &str)> = errors
+        .iter()
+        .map(|e| (e.field.as_str(), e.code.as_str()))
+        .collect();
+    assert_eq!(
+        codes,
+        vec![
+            ("name", "empty"),
+            ("filters", "out_of_range"),
+            ("statistics", "duplicate"),
+            ("statistics", "out_of_range")
+        ]
+    );
+
+    assert!(Pipeline::parse("name = \"x\"\nbogus = 1", "x.toml").is_err());
+
+    let email = Pipeline::parse(
+        r#"
+        name = "mail"
+        source = { type = "values", values = [1] }
+        statistics = [{ type = "percentiles" }]
+        sinks = [{ type = "email", from = "outlier@example.com", to = [] }]
+        "#,
+        "mail.toml",
+    )
+    .unwrap();
+    assert_eq!(email.validate().unwrap_err().0[0].field, "sinks");
+
+    let log = Pipeline::parse(
+        r#"
+        name = "log"
+        source = { type = "values", values = [0, 1] }
+        transforms = [{ type = "log10" }]
+        statistics = [{ type = "percentiles" }]
+        "#,
+        "log.toml",
+    )
+    .unwrap();
+    assert!(
+        log.run(vec![0.0, 1.0])
+            .unwrap_err()
+            .to_string()
+            .contains("positive")
+    );
+}
+
+// --- Transforms ---
+
+#[test]
+fn test_box_cox_round_trips_and_matches_log_at_zero() {
+    for lambda in [-1.5, -0.5, 0.0, 0.25, 1.0, 2.0] {
+        for value in [0.01, 1.0, 7.5, 1234.0] {
+            let transformed = box_cox(value, lambda);
+            assert!((inverse_box_cox(transformed, lambda) - value).abs() < 1e-9 * value.max(1.0));
+        }
+    }
+    assert_eq!(box_cox(10.0, 0.0), 10f64.ln());
+    assert!((box_cox(10.0, 1e-12) - 10f64.ln()).abs() < 1e-9);
+    assert_eq!(box_cox(3.0, 1.0), 2.0);
+}
+
+#[test]
+fn test_box_cox_lambda_estimation() {
+    // Symmetric in log space, so the likelihood peaks at lambda = 0
+    let log_normal: Vec<f64> = [-2.0, -1.0, -0.5, 0.0, 0.5, 1.0, 2.0]
+        .iter()
+        .map(|x: &f64| x.exp())
+        .collect();
+    assert!(estimate_box_cox_lambda(&log_normal).unwrap().abs() < 1e-6);
+
+    // A long right tail needs a strong transform
+    let skewed = [1.0, 2.0, 3.0, 4.0, 5.0, 6.0, 7.0, 8.0, 9.0, 100.0];
+    assert!(estimate_box_cox_lambda(&skewed).unwrap() < 0.0);
+
+    assert!(estimate_box_cox_lambda(&[2.0, 2.0]).is_err());
+    assert!(estimate_box_cox_lambda(&[1.0, 0.0]).is_err());
+    assert!(FittedTransform::fit(ValueTransform::Ln, &[1.0, -1.0]).is_err());
+}
+
+#[test]
+fn test_calculate_request_rejects_non_positive_values_for_transform() {
+    let err = CalculateRequest::builder()
+        .values(vec![0.0, 1.0, 2.0])
+        .transform(ValueTransform::Log10)
+        .build()
+        .unwrap_err();
+    assert_eq!(err.0[0].field, "transform");
+    assert_eq!(err.0[0].code, "non_positive");
+
+    // Only the measured values need to be positive
+    let request = CalculateRequest::from_json_value(serde_json::json!({
+        "values": [0, 1, 2],
+        "skip_first": 1,
+        "transform": "box_cox"
+    }))
+    .unwrap();
+    assert_eq!(request.transform, Some(ValueTransform::BoxCox));
+
+    let err = CalculateRequest::from_json_value(serde_json::json!({
+        "values": [1, 2],
+        "transform": "sqrt"
+    }))
+    .unwrap_err();
+    assert_eq!(err.0[0].code, "invalid_value");
+}
+
+#[cfg(feature = "pipeline")]
+#[test]
+fn test_pipeline_reports_back_transformed_percentiles() {
+    let pipeline = Pipeline::parse(
+        r#"
+        name = "log"
+        source = { type = "values", values = [1, 10, 100, 1000, 10000] }
+        transforms = [{ type = "scale", factor = 0.1 }, { type = "log10" }]
+        statistics = [{ type = "percentiles", percentiles = [50, 75] }]
+        "#,
+        "log.toml",
+    )
+    .unwrap();
+    let result = pipeline
+        .run(vec![1.0, 10.0, 100.0, 1000.0, 10000.0])
+        .unwrap();
+    let transformed: Vec<f64> = result
+        .percentiles
+        .unwrap()
+        .iter()
+        .map(|p| p.value)
+        .collect();
+    assert_eq!(transformed, vec![1.0, 2.0]);
+    let back: Vec<f64> = result
+        .back_transformed
+        .unwrap()
+        .iter()
+        .map(|p| p.value)
+        .collect();
+    assert!((back[0] - 100.0).abs() < 1e-9);
+    assert!((back[1] - 1000.0).abs() < 1e-9);
+    assert_eq!(result.box_cox_lambda, None);
+
+    let box_cox = Pipeline::parse(
+        r#"
+        name = "bc"
+        source = { type = "values", values = [1, 2, 3] }
+        transforms = [{ type = "box_cox", lambda = 0.5 }, { type = "box_cox" }]
+        statistics = [{ type = "percentiles" }]
+        "#,
+        "bc.toml",
+    )
+    .unwrap();
+    assert_eq!(box_cox.validate().unwrap_err().0[0].code, "duplicate");
+}
+
+// --- Percentile engines ---
+
+const ALL_METHODS: [PercentileMethod; 6] = [
+    PercentileMethod::Linear,
+    PercentileMethod::NearestRank,
+    PercentileMethod::Lower,
+    PercentileMethod::Upper,
+    PercentileMethod::Midpoint,
+    PercentileMethod::NearestEven,
+];
+
+/// Deterministic, unsorted values with duplicates
+fn engine_values(count: usize) -> Vec<f64> {
+    (0..count)
+        .map(|i| ((i * 7919) % 1000) as f64 / 10.0)
+        .collect()
+}
+
+#[test]
+fn test_quick_select_matches_exact_sort_for_every_method() {
+    let percentiles = [0.0, 1.0, 25.0, 50.0, 90.0, 99.9, 100.0];
+    for count in [1, 2, 7, 1001] {
+        let values = engine_values(count);
+        for method in ALL_METHODS {
+            assert_eq!(
+                QuickSelect
+                    .percentiles(&values, &percentiles, method)
+                    .unwrap(),
+                ExactSort
+                    .percentiles(&values, &percentiles, method)
+                    .unwrap(),
+                "{count} values, {method}"
+            );
+        }
+    }
+}
+
+#[test]
+fn test_t_digest_is_exact_for_small_data_and_close_for_large() {
+    // Below the compression budget every value keeps its own centroid
+    let values = engine_values(50);
+    let percentiles = [0.0, 10.0, 50.0, 95.0, 100.0];
+    let exact = ExactSort
+        .percentiles(&values, &percentiles, PercentileMethod::Linear)
+        .unwrap();
+    let digest = TDigest::default()
+        .percentiles(&values, &percentiles, PercentileMethod::Linear)
+        .unwrap();
+    for (e, d) in exact.iter().zip(&digest) {
+        assert!((e - d).abs() < 1e-9, "{e} vs {d}");
+    }
+
+    let values: Vec<f64> = (0..100_000)
+        .map(|i| ((i * 7919) % 100_000) as f64)
+        .collect();
+    let percentiles = [0.0, 1.0, 50.0, 99.0, 99.9, 100.0];
+    let digest = TDigest::default()
+        .percentiles(&values, &percentiles, PercentileMethod::Linear)
+        .unwrap();
+    assert_eq!(digest[0], 0.0);
+    assert_eq!(digest[5], 99_999.0);
+    for (p, d) in percentiles.iter().zip(&digest) {
+        let exact = p / 100.0 * 99_999.0;
+        assert!((d - exact).abs() < 100.0, "P{p}: {d} vs {exact}");
+    }
+}
+
+#[test]
+fn test_t_digest_sketch_matches_the_batch_engine() {
+    let values: Vec<f64> = (0..10_000).map(|i| ((i * 7919) % 10_000) as f64).collect();
+    let percentiles = [0.0, 1.0, 50.0, 99.0, 100.0];
+    let mut sketch = TDigestSketch::default();
+    assert!(sketch.percentiles(&percentiles).is_err());
+    for &v in &values {
+        sketch.insert(v);
+    }
+    let batch = TDigest::default()
+        .percentiles(&values, &percentiles, PercentileMethod::Linear)
+        .unwrap();
+    assert_eq!(sketch.count(), 10_000);
+    assert_eq!(sketch.range(), Some((0.0, 9_999.0)));
+    assert_eq!(sketch.percentiles(&percentiles).unwrap(), batch);
+    assert!(sketch.percentiles(&[101.0]).is_err());
+}
+
+#[test]
+fn test_engines_reject_invalid_input() {
+    for kind in [
+        EngineKind::ExactSort,
+        EngineKind::QuickSelect,
+        EngineKind::TDigest,
+    ] {
+        let engine = kind.build(None).unwrap();
+        assert!(
+            engine
+                .percentile(&[], 50.0, PercentileMethod::Linear)
+                .is_err()
+        );
+        assert!(
+            engine
+                .percentile(&[1.0], 100.5, PercentileMethod::Linear)
+                .is_err()
+        );
+    }
+    let err = EngineKind::External.build(None).err().unwrap();
+    assert!(err.to_string().contains("needs a command"));
+}
+
+#[cfg(unix)]
+#[test]
+fn test_external_engine_speaks_json_over_stdio() {
+    let sh = |script: &str| External {
+        program: "sh".to_string(),
+        args: vec!["-c".to_string(), script.to_string()],
+    };
+
+    let engine = sh("cat > /dev/null; echo '[1.5, 2.5]'");
+    let results = engine
+        .percentiles(&[1.0, 2.0, 3.0], &[50.0, 90.0], PercentileMethod::Linear)
+        .unwrap();
+    assert_eq!(results, vec![1.5, 2.5]);
+
+    let err = engine
+        .percentile(&[1.0], 50.0, PercentileMethod::Linear)
+        .unwrap_err();
+    assert!(
+        err.to_string()
+            .contains("returned 2 results for 1 percentiles")
+    );
+
+    let err = sh("echo broken >&2; exit 3")
+        .percentile(&[1.0], 50.0, PercentileMethod::Linear)
+        .unwrap_err();
+    assert_eq!(err.to_string(), "External engine failed (exit status 3)");
+
+    assert_eq!(
+        External::from_command(&["python3".to_string(), "engine.py".to_string()]),
+        Some(External {
+            program: "python3".to_string(),
+            args: vec!["engine.py".to_string()],
+        })
+    );
+    assert_eq!(External::from_command(&[]), None);
+}
+
+#[test]
+fn test_calculate_request_parses_engine() {
+    let request = CalculateRequest::from_json_value(serde_json::json!({
+        "values": [1, 2, 3],
+        "engine": "t_digest"
+    }))
+    .unwrap();
+    assert_eq!(request.engine, Some(EngineKind::TDigest));
+
+    let err = CalculateRequest::from_json_value(serde_json::json!({
+        "values": [1, 2, 3],
+        "engine": "gpu"
+    }))
+    .unwrap_err();
+    assert_eq!(err.0[0].field, "engine");
+    assert_eq!(err.0[0].code, "invalid_value");
+}
+
+// --- Histogram ---
+
+#[test]
+fn test_histogram_equal_width_buckets() {
+    let result = histogram(&[0.0, 1.0, 2.0, 3.0, 4.0, 10.0], 5).unwrap();
+    assert_eq!(result.count, 6);
+    let counts: Vec<usize> = result.buckets.iter().map(|b| b.count).collect();
+    assert_eq!(counts, vec![2, 2, 1, 0, 1]);
+    assert_eq!(result.buckets[1].lower, 2.0);
+    assert_eq!(result.buckets[4].upper, 10.0);
+}
+
+#[test]
+fn test_histogram_constant_and_invalid_input() {
+    let result = histogram(&[3.0, 3.0, 3.0], 10).unwrap();
+    assert_eq!(result.buckets.len(), 1);
+    assert_eq!(result.buckets[0].count, 3);
+    assert!(histogram(&[], 10).is_err());
+    assert!(histogram(&[1.0], 0).is_err());
+    assert!(histogram(&[1.0, f64::NAN], 2).is_err());
+}
+
+// --- pandas describe() ---
+
+#[test]
+fn test_describe_matches_pandas() {
+    // pd.Series([1, 2, 3, 4, 10]).describe()
+    let d = describe(&[1.0, 2.0, 3.0, 4.0, 10.0]).unwrap();
+    assert_eq!(d.count, 5);
+    assert_eq!(d.mean, 4.0);
+    assert!((d.std.unwrap() - 3.535_533_905_932_738).abs() < 1e-12);
+    assert_eq!(
+        (d.min, d.p25, d.p50, d.p75, d.max),
+        (1.0, 2.0, 3.0, 4.0, 10.0)
+    );
+
+    let json = serde_json::to_string(&describe(&[1.0, 3.0]).unwrap()).unwrap();
+    assert_eq!(
+        json,
+        r#"{"count":2,"mean":2.0,"std":1.4142135623730951,"min":1.0,"25%":1.5,"50%":2.0,"75%":2.5,"max":3.0}"#
+    );
+    assert!(describe(&[]).is_err());
+}
+
+#[test]
+fn test_describe_csv_single_value() {
+    let csv = describe(&[7.0]).unwrap().to_csv("value");
+    assert_eq!(
+        csv,
+        ",value\ncount,1.0\nmean,7.0\nstd,\nmin,7.0\n25%,7.0\n50%,7.0\n75%,7.0\nmax,7.0\n"
+    );
+}
+
+#[test]
+fn test_summary_stats() {
+    let s = summary_stats(&[1.0, 2.0, 3.0, 4.0, 10.0]).unwrap();
+    assert_eq!((s.count, s.min, s.max, s.sum), (5, 1.0, 10.0, 20.0));
+    assert_eq!((s.mean, s.median, s.variance), (4.0, 3.0, 12.5));
+    assert!(
+        (s.std_dev - describe(&[1.0, 2.0, 3.0, 4.0, 10.0]).unwrap().std.unwrap()).abs() < 1e-12
+    );
+
+    let single = summary_stats(&[7.0]).unwrap();
+    assert_eq!(
+        (single.variance, single.std_dev, single.median),
+        (0.0, 0.0, 7.0)
+    );
+    assert!(summary_stats(&[]).is_err());
+}
+
+// --- Durations between paired events ---
+
+#[cfg(feature = "durations")]
+#[test]
+fn test_durations_from_mixed_timestamp_formats() {
+    let csv = b"id,start,end\n\
+        a,2024-03-01T10:00:00Z,2024-03-01T10:00:01.250Z\n\
+        b,2024-03-01 10:00:00,2024-03-01 10:00:00.5\n\
+        c,1709287200,1709287200.75\n\
+        d,2024-03-01T10:00:00Z,\n";
+    let durations =
+        read_durations_from_bytes(csv, &DurationOptions::default(), &CsvOptions::default())
+            .unwrap();
+    assert_eq!(durations, vec![1250.0, 500.0, 750.0]);
+}
+
+#[cfg(feature = "durations")]
+#[test]
+fn test_durations_with_format_timezone_and_unit() {
+    // Clocks in Berlin jump from 02:00 to 03:00 that night, so two wall-clock
+    // hours apart is one real hour
+    let csv = b"opened;closed\n31.03.2024 01:30:00;31.03.2024 03:30:00\n";
+    let options = DurationOptions {
+        start_column: "opened".to_string(),
+        end_column: "closed".to_string(),
+        time_format: Some("%d.%m.%Y %H:%M:%S".to_string()),
+        timezone: parse_timezone("Europe/Berlin").unwrap(),
+        unit: DurationUnit::S,
+    };
+    let csv_options = CsvOptions {
+        delimiter: b';',
+        ..CsvOptions::default()
+    };
+    assert_eq!(
+        read_durations_from_bytes(csv, &options, &csv_options).unwrap(),
+        vec![3600.0]
+    );
+
+    let csv = b"opened;closed\n2024-03-31T01:30:00Z;2024-03-31T01:31:00Z\n";
+    let err = read_durations_from_bytes(csv, &options, &csv_options).unwrap_err();
+    assert!(format!("{err:#}").contains("doesn't match time format"));
+}
+
+#[cfg(feature = "durations")]
+#[test]
+fn test_durations_reject_bad_rows() {
+    let options = DurationOptions::default();
+    let csv_options = CsvOptions::default();
+
+    let err = read_durations_from_bytes(b"start,end\n5,3\n", &options, &csv_options).unwrap_err();
+    assert_eq!(err.to_string(), "Row 2: end is before start");
+
+    let err = read_durations_from_bytes(b"begin,end\n1,2\n", &options, &csv_options).unwrap_err();
+    assert_eq!(err.to_string(), "CSV has no 'start' column");
+
+    let err =
+        read_durations_from_bytes(b"start,end\n1,yesterday\n", &options, &csv_options).unwrap_err();
+    assert!(format!("{err:#}").starts_with("Row 2: Unrecognised timestamp \"yesterday\""));
+
+    // 02:30 is skipped by the spring-forward change in Berlin
+    let options = DurationOptions {
+        timezone: parse_timezone("Europe/Berlin").unwrap(),
+        ..options
+    };
+    let csv = b"start,end\n2024-03-31 02:30:00,2024-03-31 03:30:00\n";
+    assert!(read_durations_from_bytes(csv, &options, &csv_options).is_err());
+    assert!(parse_timezone("Mars/Olympus").is_err());
+}
+
+// --- Load-test result files ---
+
+const GATLING_LOG: &str = "RUN\tBasicSimulation\tbasic\t1700000000000\t \t3.9.5
+USER\tScenario\tSTART\t1700000000100
+REQUEST\t\trequest_1\t1700000000200\t1700000000242\tOK\t
+REQUEST\t\trequest_2\t1700000000300\t1700000000450\tKO\tstatus.find.is(200)
+USER\tScenario\tEND\t1700000000500
+";
+
+#[test]
+fn test_gatling_log_response_times() {
+    let values = loadtest::read_gatling_log(GATLING_LOG.as_bytes()).unwrap();
+    assert_eq!(values, vec![42.0, 150.0]);
+    assert!(loadtest::read_gatling_log(b"RUN\tSim\n").is_err());
+}
+
+#[test]
+fn test_jmeter_jtl_csv_and_xml() {
+    let csv = b"timeStamp,elapsed,label,responseCode\n1700000000000,120,home,200\n1700000000100,80,home,200\n";
+    assert_eq!(loadtest::read_jmeter_jtl(csv).unwrap(), vec![120.0, 80.0]);
+
+    let xml = br#"<?xml version="1.0" encoding="UTF-8"?>
+<testResults version="1.2">
+<httpSample t="35" lt="30" ts="1700000000000" s="true" lb="home" rc="200"/>
+<sample t="12" ts="1700000000100" s="true" lb="login"/>
+</testResults>"#;
+    assert_eq!(loadtest::read_jmeter_jtl(xml).unwrap(), vec![35.0, 12.0]);
+}
+
+#[test]
+fn test_k6_json_uses_http_req_duration_points() {
+    let output = br#"{"type":"Metric","data":{"name":"http_req_duration","type":"trend"},"metric":"http_req_duration"}
+{"type":"Point","data":{"time":"2024-01-01T00:00:00Z","value":12.5,"tags":{}},"metric":"http_req_duration"}
+{"type":"Point","data":{"time":"2024-01-01T00:00:01Z","value":1,"tags":{}},"metric":"http_reqs"}
+{"type":"Point","data":{"time":"2024-01-01T00:00:02Z","value":30.25,"tags":{}},"metric":"http_req_duration"}
+"#;
+    assert_eq!(loadtest::read_k6_json(output).unwrap(), vec![12.5, 30.25]);
+
+    let summary = br#"{"metrics":{"http_req_duration":{"p(95)":40.1}}}"#;
+    let err = loadtest::read_k6_json(summary).unwrap_err();
+    assert!(err.to_string().contains("--out json"));
+}
+
+#[test]
+fn test_vegeta_json_latencies_in_ms() {
+    let output = br#"{"attack":"","seq":0,"code":200,"timestamp":"2024-01-01T00:00:00Z","latency":12500000,"bytes_out":0,"bytes_in":2,"error":""}
+{"attack":"","seq":1,"code":500,"timestamp":"2024-01-01T00:00:01Z","latency":3000000,"bytes_out":0,"bytes_in":0,"error":"500 Internal Server Error"}
+"#;
+    assert_eq!(loadtest::read_vegeta_json(output).unwrap(), vec![12.5, 3.0]);
+    assert!(loadtest::read_vegeta_json(b"{\"seq\":0}").is_err());
+}
+
+const WRK2_OUTPUT: &str = "Running 30s test @ http://127.0.0.1:8080/
+  2 threads and 100 connections
+  Thread Stats   Avg      Stdev     Max   +/- Stdev
+    Latency     1.50ms  500.00us   3.00ms   70.00%
+  Latency Distribution (HdrHistogram - Recorded Latency)
+ 50.000%    1.00ms
+100.000%    3.00ms
+
+  Detailed Percentile spectrum:
+       Value   Percentile   TotalCount 1/(1-Percentile)
+
+       1.000     0.000000            2         1.00
+       2.000     0.500000            5         2.00
+       3.000     1.000000            6          inf
+#[Mean    =        1.667, StdDeviation   =        0.745]
+#[Max     =        3.000, Total count    =            6]
+----------------------------------------------------------
+  6 requests in 30.00s, 1.00KB read
+";
+
+#[test]
+fn test_wrk_latency_spectrum_expanded() {
+    let values = loadtest::read_wrk_latency(WRK2_OUTPUT.as_bytes()).unwrap();
+    assert_eq!(values, vec![1.0, 1.0, 2.0, 2.0, 2.0, 3.0]);
+
+    let plain_wrk =
+        b"Running 10s test @ http://127.0.0.1/\n  Latency Distribution\n     50%  1.00ms\n";
+    let err = loadtest::read_wrk_latency(plain_wrk).unwrap_err();
+    assert!(err.to_string().contains("wrk2"));
+}
+
+#[test]
+fn test_input_format_detection() {
+    use loadtest::sniff_format;
+
+    assert_eq!(
+        sniff_format(GATLING_LOG.as_bytes(), "simulation.txt"),
+        Some(InputFormat::Gatling)
+    );
+    assert_eq!(
+        sniff_format(b"timeStamp,elapsed,label\n1,2,a\n", "results.csv"),
+        Some(InputFormat::Jmeter)
+    );
+    assert_eq!(
+        sniff_format(br#"{"type":"Point","metric":"vus"}"#, "out.json"),
+        Some(InputFormat::K6)
+    );
+    assert_eq!(
+        sniff_format(br#"{"seq":0,"latency":1000}"#, "results.json"),
+        Some(InputFormat::Vegeta)
+    );
+    assert_eq!(
+        sniff_format(WRK2_OUTPUT.as_bytes(), "wrk.txt"),
+        Some(InputFormat::Wrk)
+    );
+    assert_eq!(
+        sniff_format(b"[1, 2]", "data.json"),
+        Some(InputFormat::Json)
+    );
+    assert_eq!(
+        sniff_format(b"value\n1\n", "data.csv"),
+        Some(InputFormat::Csv)
+    );
+    assert_eq!(sniff_format(b"1 2 3", "data.txt"), None);
+
+    // Sniffed files are read without an explicit format
+    let values = read_values_from_bytes(GATLING_LOG.as_bytes(), "simulation.log").unwrap();
+    assert_eq!(values.len(), 2);
+}
+
+#[test]
+fn test_explicit_input_format_overrides_detection() {
+    let jtl = b"elapsed,label\n7,home\n";
+    let values = read_values_from_bytes_with_format(
+        jtl,
+        "upload.bin",
+        InputFormat::Jmeter,
+        &CsvOptions::default(),
+    )
+    .unwrap();
+    assert_eq!(values, vec![7.0]);
+    assert!(read_values_from_bytes(jtl, "upload.bin").is_err());
+}
+
+#[test]
+fn test_input_format_parses_streams() {
+    let mut csv = "value\n3\n1\n".as_bytes();
+    let values = InputFormat::Csv
+        .parse_stream(&mut csv, &CsvOptions::default())
+        .unwrap();
+    assert_eq!(values, vec![3.0, 1.0]);
+
+    let err = InputFormat::Auto
+        .parse_stream(&mut "[1]".as_bytes(), &CsvOptions::default())
+        .unwrap_err();
+    assert!(err.to_string().contains("Detect the input format"));
+}
+
+#[test]
+fn test_format_registry_accepts_custom_formats() {
+    /// Space-separated numbers after a `NUMS` marker
+    struct Nums;
+
+    impl Format for Nums {
+        fn name(&self) -> &str {
+            "nums"
+        }
+        fn sniff(&self, head: &[u8]) -> bool {
+            head.starts_with(b"NUMS ")
+        }
+        fn parse_stream(
+            &self,
+            reader: &mut dyn std::io::Read,
+            _options: &CsvOptions,
+        ) -> Result<Vec<f64>> {
+            let mut text = String::new();
+            reader.read_to_string(&mut text)?;
+            Ok(text
+                .split_whitespace()
+                .skip(1)
+                .map(str::parse)
+                .collect::<std::result::Result<_, _>>()?)
+        }
+    }
+
+    let mut registry = FormatRegistry::builtin();
+    registry.register(Nums);
+    let options = CsvOptions::default();
+
+    // Contents win over a misleading extension
+    assert_eq!(
+        registry
+            .read(b"NUMS 4 5", "data.csv", None, &options)
+            .unwrap(),
+        vec![4.0, 5.0]
+    );
+    assert_eq!(
+        registry
+            .read(b"value\n1\n", "data.csv", None, &options)
+            .unwrap(),
+        vec![1.0]
+    );
+    assert!(registry.names().any(|name| name == "nums"));
+
+    let err = registry
+        .read(b"1", "data.txt", Some("parquet"), &options)
+        .unwrap_err();
+    assert!(err.to_string().contains("Unknown input format"));
+    assert!(
+        FormatRegistry::new()
+            .read(b"[1]", "data.json", None, &options)
+            .is_err()
+    );
+}
+
+// --- OpenTelemetry exponential histograms ---
+
+/// One OTLP/JSON request holding an exponential histogram data point
+fn otel_request(name: &str, temporality: u8, time: &str, point: &str) -> String {
+    format!(
+        r#"{{"resourceMetrics":[{{"scopeMetrics":[{{"metrics":[{{"name":"{name}","exponentialHistogram":{{"aggregationTemporality":{temporality},"dataPoints":[{{"timeUnixNano":"{time}",{point}}}]}}}}]}}]}}]}}"#
+    )
+}
+
+#[test]
+fn test_otel_buckets_expanded_on_a_log_scale() {
+    // Scale 0 buckets are (1, 2] and (2, 4]; max clamps the top value
+    let point = r#""scale":0,"zeroCount":"1","positive":{"offset":0,"bucketCounts":["1","2"]},"min":0,"max":3"#;
+    let input = otel_request("latency", 1, "1", point);
+    assert_eq!(
+        InputFormat::sniff(input.as_bytes(), "metrics.json"),
+        Some(InputFormat::Otel)
+    );
+    let values = read_values_from_bytes(input.as_bytes(), "metrics.json").unwrap();
+    assert_eq!(values.len(), 4);
+    assert_eq!(values[0], 0.0);
+    assert!((values[1] - 2f64.sqrt()).abs() < 1e-12);
+    assert!((values[2] - 2f64.powf(1.25)).abs() < 1e-12);
+    assert_eq!(values[3], 3.0);
+
+    let negative = otel_request(
+        "latency",
+        1,
+        "1",
+        r#""scale":1,"negative":{"offset":2,"bucketCounts":[1]}"#,
+    );
+    let values = otel::read_exponential_histograms(negative.as_bytes()).unwrap();
+    assert!((values[0] + 2f64.powf(1.25)).abs() < 1e-12);
+}
+
+#[test]
+fn test_otel_cumulative_points_keep_the_latest() {
+    let point = |count: &str| format!(r#""positive":{{"offset":3,"bucketCounts":["{count}"]}}"#);
+    // Cumulative snapshots of the same series would double count if summed
+    let cumulative = [
+        otel_request("latency", 2, "200", &point("5")),
+        otel_request("latency", 2, "100", &point("3")),
+    ]
+    .join("\n");
+    let values = otel::read_exponential_histograms(cumulative.as_bytes()).unwrap();
+    assert_eq!(values.len(), 5);
+
+    let delta = [
+        otel_request("latency", 1, "100", &point("3")),
+        otel_request("latency", 1, "200", &point("5")),
+    ]
+    .join("\n");
+    let values = otel::read_exponential_histograms(delta.as_bytes()).unwrap();
+    assert_eq!(values.len(), 8);
+
+    let mixed = [
+        otel_request("latency", 1, "1", &point("1")),
+        otel_request("errors", 1, "1", &point("1")),
+    ]
+    .join("\n");
+    let err = otel::read_exponential_histograms(mixed.as_bytes()).unwrap_err();
+    assert!(err.to_string().contains("several metrics"));
+
+    let bad_scale = otel_request("latency", 1, "1", r#""scale":21,"zeroCount":1"#);
+    assert!(otel::read_exponential_histograms(bad_scale.as_bytes()).is_err());
+    let empty = r#"{"resourceMetrics":[]}"#;
+    assert!(otel::read_exponential_histograms(empty.as_bytes()).is_err());
+}
+
+// --- Shared response types ---
+
+#[test]
+fn test_health_response_reports_crate_version() {
+    let health = HealthResponse::healthy();
+    assert_eq!(health.status, "healthy");
+    assert_eq!(health.version, env!("CARGO_PKG_VERSION"));
+}
+
+#[test]
+fn test_summary_response_round_trips() {
+    let summary = SummaryResponse {
+        count: 3,
+        min: 1.0,
+        max: 3.0,
+        mean: 2.0,
+        median: 2.0,
+        std_dev: 1.0,
+        percentiles: vec![PercentileValue {
+            percentile: 50.0,
+            value: 2.0,
+        }],
+        method: PercentileMethod::Linear,
+    };
+    let json = serde_json::to_value(&summary).unwrap();
+    assert_eq!(json["percentiles"][0]["percentile"], 50.0);
+    assert_eq!(json["method"], "linear");
+    let back: SummaryResponse = serde_json::from_value(json).unwrap();
+    assert_eq!(back, summary);
+}
+
+#[test]
+fn test_histogram_and_outlier_shapes() {
+    let histogram = HistogramResponse {
+        count: 2,
+        buckets: vec![HistogramBucket {
+            lower: 0.0,
+            upper: 1.0,
+            count: 2,
+        }],
+    };
+    assert_eq!(
+        serde_json::to_value(&histogram).unwrap(),
+        serde_json::json!({"count": 2, "buckets": [{"lower": 0.0, "upper": 1.0, "count": 2}]})
+    );
+
+    let outliers = OutlierResponse {
+        count: 5,
+        lower_bound: -1.0,
+        upper_bound: 10.0,
+        outliers: vec![OutlierValue {
+            index: 4,
+            value: 99.0,
+        }],
+    };
+    let json = serde_json::to_value(&outliers).unwrap();
+    assert_eq!(json["outliers"][0]["index"], 4);
+}
+
+// --- Correlation ---
+
+#[test]
+fn test_pearson_perfect_negative() {
+    let r = pearson_correlation(&[1.0, 2.0, 3.0, 4.0], &[8.0, 6.0, 4.0, 2.0]).unwrap();
+    assert!((r.unwrap() + 1.0).abs() < 1e-12);
+}
+
+#[test]
+fn test_pearson_constant_series_is_none() {
+    assert_eq!(
+        pearson_correlation(&[1.0, 2.0, 3.0], &[5.0, 5.0, 5.0]).unwrap(),
+        None
+    );
+}
+
+#[test]
+fn test_spearman_averages_tied_ranks() {
+    // Ranks of y with ties: [1.5, 1.5, 3, 4]
+    let r = spearman_correlation(&[1.0, 2.0, 3.0, 4.0], &[10.0, 10.0, 20.0, 30.0])
+        .unwrap()
+        .unwrap();
+    assert!((r - 0.9486832980505138).abs() < 1e-12);
+}
+
+#[test]
+fn test_correlation_rejects_misaligned_series() {
+    assert!(pearson_correlation(&[1.0, 2.0], &[1.0]).is_err());
+    assert!(spearman_correlation(&[1.0], &[1.0]).is_err());
+}
+
+#[test]
+fn test_scatter_buckets_counts_pairs() {
+    let x = [0.0, 1.0, 2.0, 3.0, 4.0];
+    let y = [0.0, 0.0, 10.0, 10.0, 10.0];
+    let scatter = scatter_buckets(&x, &y, 2).unwrap();
+    assert_eq!(scatter.x_edges, vec![0.0, 2.0, 4.0]);
+    assert_eq!(scatter.y_edges, vec![0.0, 5.0, 10.0]);
+    // x=2.0 lands on an edge and belongs to the upper bucket; the maximum stays in the last
+    assert_eq!(scatter.counts, vec![vec![2, 0], vec![0, 3]]);
+}
+
+#[test]
+fn test_correlate_request_reports_all_errors() {
+    let value = serde_json::json!({"x": [1, 2, 3], "y": [1, "a"], "buckets": 0});
+    let err = CorrelateRequest::from_json_value(value).unwrap_err();
+    let codes: Vec<(&str, &str)> = err
+        .0
+        .iter()
+        .map(|e| (e.field.as_str(), e.code.as_str()))
+        .collect();
+    assert_eq!(
+        codes,
+        vec![("y", "invalid_type"), ("buckets", "out_of_range")]
+    );
+}
+
+#[test]
+fn test_correlate_request_length_mismatch() {
+    let value = serde_json::json!({"x": [1, 2, 3], "y": [1, 2]});
+    let err = CorrelateRequest::from_json_value(value).unwrap_err();
+    assert_eq!(err.0[0].code, "length_mismatch");
+}
+
+#[test]
+fn test_read_pairs_from_csv() {
+    let csv = b"latency,payload\n1.5,100\n2.5,200\n";
+    let (x, y) =
+        correlation::read_pairs_from_bytes_with_options(csv, "pairs.csv", &CsvOptions::default())
+            .unwrap();
+    assert_eq!(x, vec![1.5, 2.5]);
+    assert_eq!(y, vec![100.0, 200.0]);
+
+    let named = b"y,label,x\n1,a,10\n2,b,20\n";
+    let (x, y) =
+        correlation::read_pairs_from_bytes_with_options(named, "pairs.csv", &CsvOptions::default())
+            .unwrap();
+    assert_eq!(x, vec![10.0, 20.0]);
+    assert_eq!(y, vec![1.0, 2.0]);
+}
+
+// --- Cancellation ---
+
+#[test]
+fn test_sort_values_cancellable_matches_sort_values() {
+    let values: Vec<f64> = (0..200_000)
+        .map(|i| ((i * 7919) % 100_003) as f64)
+        .collect();
+    let token = CancellationToken::new();
+    assert_eq!(
+        sort_values_cancellable(&values, &token).unwrap(),
+        sort_values(&values)
+    );
+
+    token.cancel();
+    let err = sort_values_cancellable(&values, &token).unwrap_err();
+    assert!(err.is::<Cancelled>());
+}
+
+#[test]
+fn test_read_values_cancellable_stops_parsing() {
+    let csv = b"value\n1\n2\n3\n";
+    let token = CancellationToken::new();
+    let values = read_values_from_bytes_cancellable(
+        csv,
+        "data.csv",
+        InputFormat::Auto,
+        &CsvOptions::default(),
+        &token,
+    )
+    .unwrap();
+    assert_eq!(values, vec![1.0, 2.0, 3.0]);
+
+    let guard = token.clone().drop_guard();
+    drop(guard);
+    for format in [InputFormat::Csv, InputFormat::Json] {
+        let err = read_values_from_bytes_cancellable(
+            b"[1, 2, 3]",
+            "data",
+            format,
+            &CsvOptions::default(),
+            &token,
+        )
+        .unwrap_err();
+        assert!(err.is::<Cancelled>());
+    }
+}
+
+#[test]
+fn test_disarmed_drop_guard_does_not_cancel() {
+    let token = CancellationToken::new();
+    let token = token.drop_guard().disarm();
+    assert!(!token.is_cancelled());
+    assert!(token.check().is_ok());
+}
+
+// --- Annotation ---
+
+#[test]
+fn test_annotate_ranks_ties_and_flags_outliers() {
+    let annotations = annotate(&[2.0, 1.0, 2.0, 3.0, 40.0], 1.5).unwrap();
+    let ranks: Vec<f64> = annotations.iter().map(|a| a.percentile_rank).collect();
+    assert_eq!(ranks, vec![40.0, 10.0, 40.0, 70.0, 90.0]);
+    let outliers: Vec<bool> = annotations.iter().map(|a| a.is_outlier).collect();
+    assert_eq!(outliers, vec![false, false, false, false, true]);
+    assert!(annotations[4].zscore.unwrap() > 1.7);
+
+    let constant = annotate(&[5.0, 5.0], 1.5).unwrap();
+    assert_eq!(constant[0].zscore, None);
+    assert_eq!(constant[0].percentile_rank, 50.0);
+    assert!(annotate(&[], 1.5).is_err());
+}
+
+#[test]
+fn test_annotate_csv_writes_a_value_column_for_other_formats() {
+    let csv = annotate_csv(
+        b"[3, 1]",
+        "data.json",
+        InputFormat::Auto,
+        &CsvOptions::default(),
+        1.5,
+    )
+    .unwrap();
+    assert_eq!(
+        String::from_utf8(csv).unwrap(),
+        "value,percentile_rank,is_outlier,zscore\n3,75,false,0.7071\n1,25,false,-0.7071\n"
+    );
+
+    let err = annotate_csv(
+        b"id,latency\n1,5\n",
+        "data.csv",
+        InputFormat::Csv,
+        &CsvOptions::default(),
+        1.5,
+    )
+    .unwrap_err();
+    assert!(err.to_string().contains("missing \"value\" header"));
+}
+
+#[test]
+fn test_detect_outliers_iqr() {
+    let values = [5.0, 1.0, 2.0, 3.0, 4.0, 100.0, 6.0, 7.0, 8.0, 9.0, 10.0];
+    let response = detect_outliers_iqr(&values, 1.5).unwrap();
+    assert_eq!(response.count, 11);
+    assert_eq!((response.lower_bound, response.upper_bound), (-4.0, 16.0));
+    assert_eq!(
+        response.outliers,
+        vec![OutlierValue {
+            index: 5,
+            value: 100.0
+        }]
+    );
+
+    let tight = detect_outliers_iqr(&values, 0.0).unwrap();
+    let indices: Vec<usize> = tight.outliers.iter().map(|o| o.index).collect();
+    assert_eq!(indices, vec![1, 2, 3, 5, 9, 10]);
+
+    assert!(detect_outliers_iqr(&values, -1.0).is_err());
+    assert!(detect_outliers_iqr(&values, f64::NAN).is_err());
+    assert!(detect_outliers_iqr(&[], 1.5).is_err());
+}
+
+#[test]
+fn test_detect_outliers_zscore() {
+    let mut values = [10.0, 11.0, 9.0, 10.0, 12.0, 8.0, 10.0, 11.0, 9.0, 10.0].repeat(2);
+    values.insert(3, 100.0);
+    let split = detect_outliers_zscore(&values, DEFAULT_ZSCORE_THRESHOLD).unwrap();
+    assert_eq!(split.response.count, 21);
+    assert_eq!(
+        split.response.outliers,
+        vec![OutlierValue {
+            index: 3,
+            value: 100.0
+        }]
+    );
+    assert_eq!(split.clean.len(), 20);
+    assert!(!split.clean.contains(&100.0));
+    assert!(split.response.upper_bound < 100.0 && split.response.lower_bound < 8.0);
+
+    // Nothing is flagged when there's no spread, and a zero threshold
+    // flags everything off the mean
+    let constant = detect_outliers_zscore(&[5.0, 5.0, 5.0], 0.0).unwrap();
+    assert!(constant.response.outliers.is_empty());
+    assert_eq!(constant.clean, vec![5.0, 5.0, 5.0]);
+    let strict = detect_outliers_zscore(&[1.0, 2.0, 3.0], 0.0).unwrap();
+    assert_eq!(strict.clean, vec![2.0]);
+
+    assert!(detect_outliers_zscore(&[], 3.0).is_err());
+    assert!(detect_outliers_zscore(&values, -1.0).is_err());
+    assert!(detect_outliers_zscore(&values, f64::INFINITY).is_err());
+}
+
+#[test]
+fn test_detect_outliers_mad() {
+    // Median 11, MAD 1, so the bounds sit 3.5 / 0.6745 either side of 11
+    let values = [10.0, 12.0, 11.0, 13.0, 10.0, 11.0, 12.0, 10.0, 11.0, 40.0];
+    let response = detect_outliers_mad(&values, DEFAULT_MAD_CUTOFF).unwrap();
+    assert!((response.upper_bound - (11.0 + 3.5 / 0.6745)).abs() < 1e-9);
+    assert_eq!(
+        response.outliers,
+        vec![OutlierValue {
+            index: 9,
+            value: 40.0
+        }]
+    );
+    // The z-score test misses the same value, which inflates the deviation
+    let zscore = OutlierMethod::Zscore.detect(&values, None).unwrap();
+    assert!(zscore.outliers.is_empty());
+
+    // More than half the values equal: the MAD is zero and nothing is flagged
+    let flat = detect_outliers_mad(&[5.0, 5.0, 5.0, 9.0], 3.5).unwrap();
+    assert!(flat.outliers.is_empty());
+
+    assert!(detect_outliers_mad(&[], 3.5).is_err());
+    assert!(detect_outliers_mad(&values, f64::NAN).is_err());
+}
+
+#[test]
+fn test_outlier_method() {
+    let values = [1.0, 2.0, 3.0, 4.0, 100.0];
+    assert_eq!(
+        OutlierMethod::Iqr.detect(&values, None).unwrap(),
+        detect_outliers_iqr(&values, 1.5).unwrap()
+    );
+    assert_eq!(
+        OutlierMethod::Mad.detect(&values, Some(2.0)).unwrap(),
+        detect_outliers_mad(&values, 2.0).unwrap()
+    );
+    assert_eq!(OutlierMethod::default(), OutlierMethod::Iqr);
+    assert_eq!(OutlierMethod::Zscore.default_threshold(), 3.0);
+    assert_eq!(OutlierMethod::Mad.to_string(), "mad");
+    let method: OutlierMethod = serde_json::from_str("\"zscore\"").unwrap();
+    assert_eq!(method, OutlierMethod::Zscore);
+}
+
+// --- Integer percentiles ---
+
+#[test]
+fn test_integer_percentile_exact_ranks_stay_integers() {
+    let depths = [3, 3, 3, 3, 3, 3, 3, 3, 3, 3];
+    for p in [0.0, 33.3, 50.0, 99.9] {
+        assert_eq!(
+            calculate_integer_percentile(&depths, p, PercentileMethod::Linear).unwrap(),
+            IntegerPercentile::Exact(3)
+        );
+    }
+    // 5 * 0.6 lands on a whole number in exact arithmetic
+    assert_eq!(
+        calculate_integer_percentile(&[0, 0, 0, 0, 5], 90.0, PercentileMethod::Linear).unwrap(),
+        IntegerPercentile::Exact(3)
+    );
+    assert_eq!(
+        calculate_integer_percentile(&[1, 2, 3, 4], 50.0, PercentileMethod::Linear).unwrap(),
+        IntegerPercentile::Interpolated(2.5)
+    );
+}
+
+#[test]
+fn test_integer_percentile_methods_match_float_ranks() {
+    let values: Vec<i64> = vec![15, 20, 35, 40, 50, 7, 100, 3];
+    let floats: Vec<f64> = values.iter().map(|&v| v as f64).collect();
+    for method in [
+        PercentileMethod::Linear,
+        PercentileMethod::NearestRank,
+        PercentileMethod::Lower,
+        PercentileMethod::Upper,
+        PercentileMethod::Midpoint,
+        PercentileMethod::NearestEven,
+    ] {
+        for p in [0.0, 10.0, 25.0, 50.0, 75.0, 95.0, 100.0] {
+            let integer = calculate_integer_percentile(&values, p, method).unwrap();
+            let float = calculate_percentile(&floats, p, method).unwrap();
+            assert!((integer.as_f64() - float).abs() < 1e-9, "{method} P{p}");
+        }
+    }
+    assert_eq!(
+        calculate_integer_percentile(&values, 50.0, PercentileMethod::Lower)
+            .unwrap()
+            .exact(),
+        Some(20)
+    );
+}
+
+#[test]
+fn test_integer_percentile_keeps_precision_beyond_f64() {
+    let big = [9_007_199_254_740_993, 9_007_199_254_740_995];
+    assert_eq!(
+        calculate_integer_percentile(&big, 50.0, PercentileMethod::Midpoint).unwrap(),
+        IntegerPercentile::Exact(9_007_199_254_740_994)
+    );
+    assert_eq!(
+        calculate_integer_percentile(&big, 100.0, PercentileMethod::Linear).unwrap(),
+        IntegerPercentile::Exact(9_007_199_254_740_995)
+    );
+}
+
+#[test]
+fn test_read_integers_from_bytes() {
+    let options = CsvOptions::default();
+    assert_eq!(
+        read_integers_from_bytes(b"[1, 2, 9007199254740993]", "depths.json", &options).unwrap(),
+        vec![1, 2, 9_007_199_254_740_993]
+    );
+    assert_eq!(
+        read_integers_from_bytes(b"value\n4\n 5 \n", "depths.csv", &options).unwrap(),
+        vec![4, 5]
+    );
+    assert!(read_integers_from_bytes(b"[1, 2.5]", "depths.json", &options).is_err());
+    let err = read_integers_from_bytes(b"value\n4\n1e3\n", "depths.csv", &options).unwrap_err();
+    assert!(err.to_string().contains("invalid integer \"1e3\""));
+}
+
+// --- Value counts ---
+
+#[test]
+fn test_value_counts_match_expanded_values() {
+    let values = [5.0, 1.0, 5.0, 3.0, 5.0, 1.0, 8.0, 3.0, 3.0, 3.0];
+    let counts = ValueCounts::from_values(&values);
+    assert_eq!(counts.len(), 10);
+    assert_eq!(counts.distinct(), 4);
+    assert_eq!(
+        counts.counts().collect::<Vec<_>>(),
+        vec![(1.0, 2), (3.0, 4), (5.0, 3), (8.0, 1)]
+    );
+    for method in [
+        PercentileMethod::Linear,
+        PercentileMethod::NearestRank,
+        PercentileMethod::Lower,
+        PercentileMethod::Upper,
+        PercentileMethod::Midpoint,
+        PercentileMethod::NearestEven,
+    ] {
+        for p in [0.0, 15.0, 50.0, 66.7, 90.0, 100.0] {
+            assert_eq!(
+                counts.percentile(p, method).unwrap(),
+                calculate_percentile(&values, p, method).unwrap(),
+                "{method} P{p}"
+            );
+        }
+    }
+}
+
+#[test]
+fn test_value_counts_merge_pairs_and_reject_empty() {
+    let counts = ValueCounts::from_counts([(2.0, 3), (1.0, 0), (2.0, 2), (4.0, 5)]);
+    assert_eq!(
+        counts.counts().collect::<Vec<_>>(),
+        vec![(2.0, 5), (4.0, 5)]
+    );
+    assert_eq!(
+        counts.percentile(50.0, PercentileMethod::Linear).unwrap(),
+        3.0
+    );
+
+    let empty = ValueCounts::from_counts([(1.0, 0)]);
+    assert!(empty.is_empty());
+    assert!(empty.percentile(50.0, PercentileMethod::Linear).is_err());
+}
+
+// --- Differential privacy ---
+
+#[cfg(feature = "privacy")]
+fn privacy_options(epsilon: f64) -> PrivacyOptions {
+    PrivacyOptions {
+        epsilon,
+        lower: 0.0,
+        upper: 1000.0,
+        min_count: DEFAULT_MIN_COUNT,
+    }
+}
+
+#[cfg(feature = "privacy")]
+#[test]
+fn test_private_percentiles_stay_near_true_values() {
+    use rand::SeedableRng;
+    let values: Vec<f64> = (0..100_000).map(|i| (i % 1000) as f64).collect();
+    let mut rng = rand::rngs::StdRng::seed_from_u64(7);
+    let report =
+        private_percentiles(&values, &[50.0, 99.0], &privacy_options(1.0), &mut rng).unwrap();
+    assert!(!report.suppressed);
+    assert!(report.count.unwrap().abs_diff(100_000) < 1_000);
+    assert!((report.percentiles[0].value - 500.0).abs() < 5.0);
+    assert!((report.percentiles[1].value - 990.0).abs() < 5.0);
+}
+
+#[cfg(feature = "privacy")]
+#[test]
+fn test_private_percentiles_clamp_to_bounds() {
+    use rand::SeedableRng;
+    let values = [-50.0, 5_000.0, 2_000.0, 1_500.0].repeat(1_000);
+    let mut rng = rand::rngs::StdRng::seed_from_u64(7);
+    let report =
+        private_percentiles(&values, &[0.0, 100.0], &privacy_options(0.5), &mut rng).unwrap();
+    for p in &report.percentiles {
+        assert!((0.0..=1000.0).contains(&p.value), "{p:?}");
+    }
+}
+
+#[cfg(feature = "privacy")]
+#[test]
+fn test_private_percentiles_suppress_small_datasets() {
+    use rand::SeedableRng;
+    let mut rng = rand::rngs::StdRng::seed_from_u64(7);
+    let options = PrivacyOptions {
+        min_count: 1_000,
+        ..privacy_options(1.0)
+    };
+    let report = private_percentiles(&[1.0, 2.0, 3.0], &[50.0], &options, &mut rng).unwrap();
+    assert!(report.suppressed);
+    assert_eq!(report.count, None);
+    assert!(report.percentiles.is_empty());
+}
+
+#[cfg(feature = "privacy")]
+#[test]
+fn test_private_percentiles_suppress_empty_and_tiny_inputs() {
+    use rand::SeedableRng;
+    // Clamped bucket noise must not count towards min_count
+    for seed in 0..20 {
+        let mut rng = rand::rngs::StdRng::seed_from_u64(seed);
+        for values in [&[][..], &[1.0, 2.0, 3.0][..]] {
+            let report =
+                private_percentiles(values, &[50.0], &privacy_options(1.0), &mut rng).unwrap();
+            assert!(report.suppressed, "seed {seed}, {values:?}: {report:?}");
+            assert_eq!(report.count, None);
+        }
+    }
+}
+
+#[cfg(feature = "privacy")]
+#[test]
+fn test_private_percentiles_reject_bad_options() {
+    let mut rng = rand::rng();
+    for options in [
+        privacy_options(0.0),
+        privacy_options(f64::NAN),
+        PrivacyOptions {
+            lower: 10.0,
+            upper: 10.0,
+            ..privacy_options(1.0)
+        },
+    ] {
+        assert!(private_percentiles(&[1.0], &[50.0], &options, &mut rng).is_err());
+    }
+    assert!(private_percentiles(&[1.0], &[101.0], &privacy_options(1.0), &mut rng).is_err());
+}
+
+// --- Chart images ---
+
+#[cfg(feature = "charts")]
+#[test]
+fn test_png_charts_are_written() {
+    use crate::report::Report;
+    let values: Vec<f64> = (1..=200).map(f64::from).collect();
+    let report = Report::build(&values, &[50.0, 99.0], PercentileMethod::Linear, 20).unwrap();
+    for curve in [false, true] {
+        let path =
+            std::env::temp_dir().join(format!("outlier-chart-{curve}-{}.png", std::process::id()));
+        crate::charts::write_png(&report, curve, &path).unwrap();
+        let png = std::fs::read(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(&png[..8], b"\x89PNG\r\n\x1a\n");
+        // IHDR width and height
+        assert_eq!(&png[16..24], [0, 0, 3, 32, 0, 0, 1, 144]);
+    }
+
+    // A single repeated value still has an axis to draw on
+    let report = Report::build(&[5.0; 10], &[50.0], PercentileMethod::Linear, 5).unwrap();
+    let path = std::env::temp_dir().join(format!("outlier-chart-flat-{}.png", std::process::id()));
+    crate::charts::write_png(&report, false, &path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+}
+
+// --- Webhook signatures ---
+
+#[cfg(feature = "webhook")]
+#[test]
+fn test_verify_webhook_rejects_tampering() {
+    let body = br#"{"count": 4}"#;
+    let signature = sign_webhook(b"secret", 1_700_000_000, body);
+    let verify = |secret: &[u8], timestamp: &str, body: &[u8], signature: &str| {
+        verify_webhook(secret, timestamp, body, signature, 1_700_000_000, 300)
+    };
+
+    assert!(verify(b"secret", "1700000000", body, &signature));
+    assert!(verify(
+        b"secret",
+        "1700000000",
+        body,
+        signature.trim_start_matches("sha256=")
+    ));
+    assert!(!verify(b"other", "1700000000", body, &signature));
+    assert!(!verify(
+        b"secret",
+        "1700000000",
+        br#"{"count": 5}"#,
+        &signature
+    ));
+    // The timestamp is signed, so it can't be refreshed to pass the window
+    assert!(!verify(b"secret", "1700000001", body, &signature));
+    assert!(!verify(b"secret", "soon", body, &signature));
+    assert!(!verify(b"secret", "1700000000", body, "sha256=zz"));
+}
+
+// --- P² streaming estimates ---
+
+#[test]
+fn test_p2_estimator_tracks_the_exact_percentiles() {
+    // A shuffled, skewed sample: squares of a pseudo-random walk over 0..1000
+    let mut state = 42u64;
+    let values: Vec<f64> = (0..50_000)
+        .map(|_| {
+            state = state
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407);
+            let x = ((state >> 33) % 1000) as f64;
+            x * x
+        })
+        .collect();
+
+    for percentile in [10.0, 50.0, 90.0, 99.0] {
+        let mut estimator = P2Estimator::new(percentile).unwrap();
+        for &v in &values {
+            estimator.observe(v);
+        }
+        let exact = calculate_percentile(&values, percentile, PercentileMethod::Linear).unwrap();
+        let estimate = estimator.quantile().unwrap();
+        let range = 999.0 * 999.0;
+        assert!(
+            (estimate - exact).abs() / range < 0.01,
+            "P{percentile}: estimate {estimate}, exact {exact}"
+        );
+    }
+}
+
+#[test]
+fn test_p2_estimator_small_counts_and_extremes() {
+    let mut median = P2Estimator::new(50.0).unwrap();
+    assert_eq!(median.quantile(), None);
+    for v in [4.0, 1.0, 3.0] {
+        median.observe(v);
+    }
+    median.observe(f64::NAN);
+    assert_eq!(median.count(), 3);
+    assert_eq!(median.quantile(), Some(3.0));
+
+    let mut p99 = P2Estimator::new(99.0).unwrap();
+    for v in [5.0, 1.0, 4.0, 2.0, 3.0] {
+        p99.observe(v);
+    }
+    assert_eq!(p99.quantile(), Some(4.96));
+
+    let mut min = P2Estimator::new(0.0).unwrap();
+    let mut max = P2Estimator::new(100.0).unwrap();
+    for i in 0..1000 {
+        let v = f64::from((i * 7919) % 1000);
+        min.observe(v);
+        max.observe(v);
+    }
+    assert_eq!((min.quantile(), max.quantile()), (Some(0.0), Some(999.0)));
+
+    assert!(P2Estimator::new(100.5).is_err());
+}
+
+#[test]
+fn test_estimate_percentiles_reads_lines() {
+    let input: String = (1..=1000).map(|i| format!("{i}\n")).collect();
+    let estimate = estimate_percentiles(input.as_bytes(), &[50.0, 100.0]).unwrap();
+    assert_eq!(estimate.count, 1000);
+    assert!((estimate.percentiles[0].value - 500.5).abs() < 5.0);
+    assert_eq!(estimate.percentiles[1].value, 1000.0);
+
+    let err = estimate_percentiles("1\n2\nabc\n".as_bytes(), &[50.0]).unwrap_err();
+    assert!(err.to_string().contains("Line 3"));
+    assert!(estimate_percentiles("1\ninf\n".as_bytes(), &[50.0]).is_err());
+    assert!(estimate_percentiles("\n\n".as_bytes(), &[50.0]).is_err());
+    assert!(estimate_percentiles("1\n".as_bytes(), &[101.0]).is_err());
+}
+
+// --- Tracing spans ---
+
+#[cfg(feature = "cli")]
+#[test]
+fn test_library_spans_break_down_a_calculation() {
+    use std::sync::{Arc, Mutex};
+    use tracing::span::{Attributes, Id};
+    use tracing_subscriber::layer::{Context, Layer, SubscriberExt};
+    use tracing_subscriber::registry::LookupSpan;
+
+    /// Each new span as `name`, or `name < parent` when nested
+    #[derive(Clone, Default)]
+    struct Recorder(Arc<Mutex<Vec<String>>>);
+
+    impl<S: tracing::Subscriber + for<'a> LookupSpan<'a>> Layer<S> for Recorder {
+        fn on_new_span(&self, _attrs: &Attributes<'_>, id: &Id, ctx: Context<'_, S>) {
+            let span = ctx.span(id).unwrap();
+            let entry = match span.parent() {
+                Some(parent) => format!("{} < {}", span.name(), parent.name()),
+                None => span.name().to_string(),
+            };
+            self.0.lock().unwrap().push(entry);
+        }
+    }
+
+    let recorder = Recorder::default();
+    let subscriber = tracing_subscriber::registry().with(recorder.clone());
+    tracing::subscriber::with_default(subscriber, || {
+        calculate_percentile(&[3.0, 1.0, 2.0], 50.0, PercentileMethod::Linear).unwrap();
+        InputFormat::Csv
+            .parse_stream(&mut &b"value\n1\n"[..], &CsvOptions::default())
+            .unwrap();
+    });
+
+    assert_eq!(
+        *recorder.0.lock().unwrap(),
+        [
+            "calculate_percentile",
+            "sort < calculate_percentile",
+            "interpolate < calculate_percentile",
+            "parse",
+        ]
+    );
+}
@@ -0,0 +1,379 @@
+use crate::{
+    DEFAULT_PERCENTILES, FieldError, FieldReader, PercentileMethod, PercentileValue,
+    ValidationErrors, calculate_percentiles_with, format_percentile_label,
+};
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+#[cfg(feature = "server")]
+use utoipa::ToSchema;
+
+/// Maximum number of time buckets in a response
+pub const MAX_TIME_BUCKETS: usize = 1000;
+
+/// Percentiles reported by `POST /timeseries/bands` when none are requested:
+/// the interquartile range, median and P95 envelope
+pub const BAND_PERCENTILES: [f64; 4] = [25.0, 50.0, 75.0, 95.0];
+
+/// Request structure for timeseries API endpoint
+#[cfg_attr(feature = "server", derive(ToSchema))]
+#[derive(Debug, Deserialize, Serialize)]
+pub struct TimeseriesRequest {
+    /// Timestamp of each value, in seconds (e.g. Unix time or seconds since start)
+    pub timestamps: Vec<f64>,
+    /// Measured values, aligned index-by-index with `timestamps`
+    pub values: Vec<f64>,
+    /// Width of each time bucket in seconds
+    pub bucket_secs: f64,
+    /// Report the running percentile of all values up to the end of each bucket
+    /// instead of each bucket on its own
+    #[serde(default)]
+    pub cumulative: bool,
+    /// Percentiles to report per bucket (defaults to 50, 90, 95, 99)
+    #[serde(default = "default_percentiles")]
+    pub percentiles: Vec<f64>,
+    /// Interpolation method (defaults to linear)
+    #[serde(default)]
+    pub method: PercentileMethod,
+    /// Number of leading rows to drop as warm-up
+    #[serde(default)]
+    pub skip_first: usize,
+    /// Drop rows within this many seconds of the earliest timestamp as warm-up
+    #[serde(default)]
+    pub skip_secs: f64,
+}
+
+fn default_percentiles() -> Vec<f64> {
+    DEFAULT_PERCENTILES.to_vec()
+}
+
+impl TimeseriesRequest {
+    /// Validate the request, collecting every problem rather than stopping at the first
+    pub fn validate(&self) -> std::result::Result<(), ValidationErrors> {
+        let mut errors = Vec::new();
+
+        for (name, series) in [("timestamps", &self.timestamps), ("values", &self.values)] {
+            if series.is_empty() {
+                errors.push(FieldError::new(
+                    name,
+                    "empty",
+                    format!("{name} must contain at least one value"),
+                ));
+            } else if series.iter().any(|v| !v.is_finite()) {
+                errors.push(FieldError::new(
+                    name,
+                    "non_finite",
+                    "Values must be finite numbers",
+                ));
+            }
+        }
+        if self.timestamps.len() != self.values.len() && !errors.iter().any(|e| e.field == "values")
+        {
+            errors.push(FieldError::new(
+                "values",
+                "length_mismatch",
+                format!(
+                    "Series must be the same length (timestamps has {}, values has {})",
+                    self.timestamps.len(),
+                    self.values.len()
+                ),
+            ));
+        }
+        if !(self.bucket_secs.is_finite() && self.bucket_secs > 0.0) {
+            errors.push(FieldError::new(
+                "bucket_secs",
+                "out_of_range",
+                "Bucket width must be a positive number of seconds",
+            ));
+        }
+        if !(self.skip_secs.is_finite() && self.skip_secs >= 0.0) {
+            errors.push(FieldError::new(
+                "skip_secs",
+                "out_of_range",
+                "skip_secs must be a non-negative number of seconds",
+            ));
+        }
+        if errors.is_empty() {
+            let (timestamps, _) = skip_warmup(
+                &self.timestamps,
+                &self.values,
+                self.skip_first,
+                self.skip_secs,
+            );
+            if timestamps.is_empty() {
+                let field = if self.skip_first >= self.timestamps.len() {
+                    "skip_first"
+                } else {
+                    "skip_secs"
+                };
+                errors.push(FieldError::new(
+                    field,
+                    "out_of_range",
+                    "Warm-up exclusion must leave at least one value",
+                ));
+            } else if bucket_count(&timestamps, self.bucket_secs) > MAX_TIME_BUCKETS {
+                errors.push(FieldError::new(
+                    "bucket_secs",
+                    "too_many_buckets",
+                    format!("Bucket width yields more than {MAX_TIME_BUCKETS} buckets"),
+                ));
+            }
+        }
+        if self.percentiles.is_empty() {
+            errors.push(FieldError::new(
+                "percentiles",
+                "empty",
+                "At least one percentile is required",
+            ));
+        } else if self.percentiles.iter().any(|p| !(0.0..=100.0).contains(p)) {
+            errors.push(FieldError::new(
+                "percentiles",
+                "out_of_range",
+                "Percentiles must be between 0 and 100",
+            ));
+        }
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(ValidationErrors(errors))
+        }
+    }
+
+    /// Build a request from a JSON object, reporting type errors for every field
+    /// together with the semantic checks from [`TimeseriesRequest::validate`]
+    pub fn from_json_value(
+        value: serde_json::Value,
+    ) -> std::result::Result<Self, ValidationErrors> {
+        let mut reader = FieldReader::new(value)?;
+        let request = Self {
+            timestamps: reader.series("timestamps"),
+            values: reader.series("values"),
+            bucket_secs: reader
+                .required(
+                    "bucket_secs",
+                    "invalid_type",
+                    "Bucket width must be a number",
+                    "bucket_secs is required",
+                )
+                .unwrap_or(f64::NAN),
+            cumulative: reader
+                .optional("cumulative", "invalid_type", "Cumulative must be a boolean")
+                .unwrap_or_default(),
+            percentiles: reader
+                .optional(
+                    "percentiles",
+                    "invalid_type",
+                    "Percentiles must be an array of numbers",
+                )
+                .unwrap_or_else(default_percentiles),
+            method: reader
+                .optional(
+                    "method",
+                    "invalid_value",
+                    "Method must be one of: linear, nearest_rank, lower, upper, midpoint, nearest_even",
+                )
+                .unwrap_or_default(),
+            skip_first: reader
+                .optional(
+                    "skip_first",
+                    "invalid_type",
+                    "skip_first must be a non-negative integer",
+                )
+                .unwrap_or_default(),
+            skip_secs: reader
+                .optional("skip_secs", "invalid_type", "skip_secs must be a number")
+                .unwrap_or_default(),
+        };
+        reader.finish(request, Self::validate)
+    }
+}
+
+/// Response structure for timeseries API endpoint
+#[cfg_attr(feature = "server", derive(ToSchema))]
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TimeseriesResponse {
+    /// Number of values
+    pub count: usize,
+    /// The interpolation method used
+    pub method: PercentileMethod,
+    /// Whether each bucket reports all values up to its end
+    pub cumulative: bool,
+    /// Buckets in time order, starting at the earliest timestamp
+    pub buckets: Vec<TimeBucket>,
+}
+
+/// Percentiles for one time window
+#[cfg_attr(feature = "server", derive(ToSchema))]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TimeBucket {
+    /// Inclusive start of the bucket, in the same units as the input timestamps
+    pub start: f64,
+    /// Exclusive end of the bucket
+    pub end: f64,
+    /// Number of values summarized (in the bucket, or up to `end` when cumulative)
+    pub count: usize,
+    /// Percentiles of those values (empty when `count` is 0)
+    pub percentiles: Vec<PercentileValue>,
+}
+
+/// One percentile's line across every time bucket, in the time-series shape
+/// Grafana's JSON datasource expects
+#[cfg_attr(feature = "server", derive(ToSchema))]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PercentileBand {
+    /// Series name, e.g. `P95`
+    pub target: String,
+    /// The percentile this series follows (0-100)
+    pub percentile: f64,
+    /// `[value, time]` pairs, one per bucket: `time` is the bucket start in
+    /// milliseconds and `value` is `null` for empty buckets
+    #[cfg_attr(feature = "server", schema(value_type = Vec<Vec<f64>>))]
+    pub datapoints: Vec<(Option<f64>, f64)>,
+}
+
+impl TimeseriesResponse {
+    /// Pivot the buckets into one series per percentile, in `percentiles` order
+    ///
+    /// `percentiles` must be the list the response was calculated with.
+    /// Timestamps are taken to be seconds and converted to milliseconds.
+    pub fn bands(&self, percentiles: &[f64]) -> Vec<PercentileBand> {
+        percentiles
+            .iter()
+            .enumerate()
+            .map(|(i, &percentile)| PercentileBand {
+                target: format_percentile_label(percentile),
+                percentile,
+                datapoints: self
+                    .buckets
+                    .iter()
+                    .map(|bucket| {
+                        let value = bucket.percentiles.get(i).map(|p| p.value);
+                        (value, bucket.start * 1000.0)
+                    })
+                    .collect(),
+            })
+            .collect()
+    }
+}
+
+/// Percentiles of timestamped values over fixed-width time buckets
+///
+/// With `cumulative` set, each bucket summarizes every value up to its end,
+/// showing how a long run's percentiles converge.
+///
+/// # Examples
+/// ```
+/// use outlier::{percentiles_over_time, PercentileMethod};
+///
+/// let timestamps = [0.0, 1.0, 2.0, 3.0];
+/// let latency = [100.0, 10.0, 10.0, 10.0];
+/// let result =
+///     percentiles_over_time(&timestamps, &latency, 2.0, true, &[100.0], PercentileMethod::Linear)
+///         .unwrap();
+/// assert_eq!(result.buckets[1].count, 4);
+/// assert_eq!(result.buckets[1].percentiles[0].value, 100.0);
+/// ```
+pub fn percentiles_over_time(
+    timestamps: &[f64],
+    values: &[f64],
+    bucket_secs: f64,
+    cumulative: bool,
+    percentiles: &[f64],
+    method: PercentileMethod,
+) -> Result<TimeseriesResponse> {
+    if timestamps.len() != values.len() {
+        anyhow::bail!(
+            "Series must be the same length (timestamps has {}, values has {})",
+            timestamps.len(),
+            values.len()
+        );
+    }
+    if timestamps.is_empty() {
+        anyhow::bail!("Cannot bucket an empty dataset");
+    }
+    if !(bucket_secs.is_finite() && bucket_secs > 0.0) {
+        anyhow::bail!("Bucket width must be a positive number of seconds");
+    }
+    let bucket_total = bucket_count(timestamps, bucket_secs);
+    if bucket_total > MAX_TIME_BUCKETS {
+        anyhow::bail!("Bucket width yields more than {} buckets", MAX_TIME_BUCKETS);
+    }
+
+    let start = timestamps.iter().copied().fold(f64::INFINITY, f64::min);
+    let mut grouped = vec![Vec::new(); bucket_total];
+    for (t, v) in timestamps.iter().zip(values) {
+        grouped[bucket_index(*t, start, bucket_secs, bucket_total)].push(*v);
+    }
+
+    let mut running = Vec::new();
+    let mut buckets = Vec::with_capacity(bucket_total);
+    for (i, bucket) in grouped.iter().enumerate() {
+        if cumulative {
+            running.extend_from_slice(bucket);
+        }
+        let summarized = if cumulative { &running } else { bucket };
+        buckets.push(TimeBucket {
+            start: start + i as f64 * bucket_secs,
+            end: start + (i + 1) as f64 * bucket_secs,
+            count: summarized.len(),
+            percentiles: if summarized.is_empty() {
+                Vec::new()
+            } else {
+                calculate_percentiles_with(summarized, percentiles, method)?
+            },
+        });
+    }
+
+    Ok(TimeseriesResponse {
+        count: values.len(),
+        method,
+        cumulative,
+        buckets,
+    })
+}
+
+/// Drop a warm-up period from aligned timestamped series
+///
+/// Removes the first `skip_first` rows in input order, then every row whose
+/// timestamp is within `skip_secs` of the earliest timestamp.
+///
+/// # Examples
+/// ```
+/// use outlier::skip_warmup;
+///
+/// let (timestamps, values) = skip_warmup(&[0.0, 10.0, 20.0, 40.0], &[9.0, 8.0, 1.0, 2.0], 1, 15.0);
+/// assert_eq!(timestamps, vec![20.0, 40.0]);
+/// assert_eq!(values, vec![1.0, 2.0]);
+/// ```
+pub fn skip_warmup(
+    timestamps: &[f64],
+    values: &[f64],
+    skip_first: usize,
+    skip_secs: f64,
+) -> (Vec<f64>, Vec<f64>) {
+    let start = timestamps.iter().copied().fold(f64::INFINITY, f64::min);
+    timestamps
+        .iter()
+        .zip(values)
+        .skip(skip_first)
+        .filter(|(t, _)| **t >= start + skip_secs)
+        .map(|(t, v)| (*t, *v))
+        .unzip()
+}
+
+/// Number of `bucket_secs`-wide buckets needed to cover every timestamp
+fn bucket_count(timestamps: &[f64], bucket_secs: f64) -> usize {
+    let start = timestamps.iter().copied().fold(f64::INFINITY, f64::min);
+    let end = timestamps.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+    let span = ((end - start) / bucket_secs).floor();
+    if span >= MAX_TIME_BUCKETS as f64 {
+        MAX_TIME_BUCKETS + 1
+    } else {
+        span as usize + 1
+    }
+}
+
+fn bucket_index(timestamp: f64, start: f64, bucket_secs: f64, bucket_total: usize) -> usize {
+    (((timestamp - start) / bucket_secs) as usize).min(bucket_total - 1)
+}
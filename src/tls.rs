@@ -0,0 +1,102 @@
+use crate::config::TlsConfig;
+use anyhow::{Context, Result};
+use rustls::crypto::{CryptoProvider, aws_lc_rs};
+
+/// The aws-lc-rs crypto provider, limited to the configured cipher suites
+///
+/// Built with the `fips` feature, aws-lc-rs is the FIPS-validated module, and
+/// `require_fips` refuses to start otherwise.
+pub fn crypto_provider(config: &TlsConfig) -> Result<CryptoProvider> {
+    let mut provider = aws_lc_rs::default_provider();
+    if !config.cipher_suites.is_empty() {
+        provider.cipher_suites = config
+            .cipher_suites
+            .iter()
+            .map(|name| {
+                provider
+                    .cipher_suites
+                    .iter()
+                    .find(|suite| format!("{:?}", suite.suite()) == *name)
+                    .copied()
+                    .with_context(|| format!("Unsupported TLS cipher suite: {name}"))
+            })
+            .collect::<Result<_>>()?;
+    }
+    if config.require_fips && !provider.fips() {
+        anyhow::bail!(
+            "tls.require_fips is set but this build doesn't use FIPS crypto. \
+             Rebuild with the fips feature."
+        );
+    }
+    Ok(provider)
+}
+
+/// Make the configured provider the process default, so every outbound TLS
+/// connection (JWKS fetches, telemetry export) uses it
+///
+/// Must run before the first TLS client is built.
+pub fn install(config: &TlsConfig) -> Result<()> {
+    let provider = crypto_provider(config)?;
+    let fips = provider.fips();
+    if provider.install_default().is_err() {
+        anyhow::bail!("A TLS crypto provider was installed before the configuration was read");
+    }
+    tracing::info!(
+        "TLS crypto: aws-lc-rs{}, {} cipher suite(s)",
+        if fips { " (FIPS)" } else { "" },
+        CryptoProvider::get_default().map_or(0, |p| p.cipher_suites.len())
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cipher_suites_are_restricted_in_order() {
+        let config = TlsConfig {
+            cipher_suites: vec![
+                "TLS13_AES_256_GCM_SHA384".to_string(),
+                "TLS_ECDHE_ECDSA_WITH_AES_256_GCM_SHA384".to_string(),
+            ],
+            ..TlsConfig::default()
+        };
+        let provider = crypto_provider(&config).unwrap();
+        let names: Vec<String> = provider
+            .cipher_suites
+            .iter()
+            .map(|suite| format!("{:?}", suite.suite()))
+            .collect();
+        assert_eq!(names, config.cipher_suites);
+    }
+
+    #[test]
+    fn unknown_cipher_suites_are_rejected() {
+        let config = TlsConfig {
+            cipher_suites: vec!["TLS_RSA_WITH_RC4_128_SHA".to_string()],
+            ..TlsConfig::default()
+        };
+        let err = crypto_provider(&config).unwrap_err();
+        assert!(err.to_string().contains("TLS_RSA_WITH_RC4_128_SHA"));
+    }
+
+    #[test]
+    fn default_config_keeps_every_suite() {
+        let provider = crypto_provider(&TlsConfig::default()).unwrap();
+        assert_eq!(
+            provider.cipher_suites.len(),
+            aws_lc_rs::default_provider().cipher_suites.len()
+        );
+    }
+
+    #[cfg(not(feature = "fips"))]
+    #[test]
+    fn require_fips_fails_without_the_fips_build() {
+        let config = TlsConfig {
+            require_fips: true,
+            ..TlsConfig::default()
+        };
+        assert!(crypto_provider(&config).is_err());
+    }
+}
@@ -0,0 +1,182 @@
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+#[cfg(feature = "server")]
+use utoipa::ToSchema;
+
+/// Range searched for the Box-Cox lambda that best normalizes the data
+const LAMBDA_BOUNDS: (f64, f64) = (-5.0, 5.0);
+
+/// Precision the lambda search stops at
+const LAMBDA_TOLERANCE: f64 = 1e-9;
+
+/// A variance-stabilizing transform applied to values before calculating
+#[cfg_attr(feature = "server", derive(ToSchema))]
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+#[cfg_attr(feature = "cli", clap(rename_all = "snake_case"))]
+pub enum ValueTransform {
+    /// Base-10 logarithm
+    Log10,
+    /// Natural logarithm
+    Ln,
+    /// Box-Cox power transform with lambda estimated by maximum likelihood
+    BoxCox,
+}
+
+impl fmt::Display for ValueTransform {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ValueTransform::Log10 => write!(f, "log10"),
+            ValueTransform::Ln => write!(f, "ln"),
+            ValueTransform::BoxCox => write!(f, "box_cox"),
+        }
+    }
+}
+
+/// A transform with its parameters fixed, so results can be mapped back to
+/// the original units
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FittedTransform {
+    Log10,
+    Ln,
+    BoxCox { lambda: f64 },
+}
+
+impl FittedTransform {
+    /// Fit `transform` to `values`, estimating lambda for Box-Cox
+    ///
+    /// Every value must be positive.
+    pub fn fit(transform: ValueTransform, values: &[f64]) -> Result<Self> {
+        check_positive(values)?;
+        Ok(match transform {
+            ValueTransform::Log10 => FittedTransform::Log10,
+            ValueTransform::Ln => FittedTransform::Ln,
+            ValueTransform::BoxCox => FittedTransform::BoxCox {
+                lambda: estimate_box_cox_lambda(values)?,
+            },
+        })
+    }
+
+    /// The transform this was fitted from
+    pub fn kind(&self) -> ValueTransform {
+        match self {
+            FittedTransform::Log10 => ValueTransform::Log10,
+            FittedTransform::Ln => ValueTransform::Ln,
+            FittedTransform::BoxCox { .. } => ValueTransform::BoxCox,
+        }
+    }
+
+    /// The Box-Cox lambda, if this is a Box-Cox transform
+    pub fn lambda(&self) -> Option<f64> {
+        match self {
+            FittedTransform::BoxCox { lambda } => Some(*lambda),
+            _ => None,
+        }
+    }
+
+    /// Map a value into transformed space
+    pub fn apply(&self, value: f64) -> f64 {
+        match self {
+            FittedTransform::Log10 => value.log10(),
+            FittedTransform::Ln => value.ln(),
+            FittedTransform::BoxCox { lambda } => box_cox(value, *lambda),
+        }
+    }
+
+    /// Map a transformed value back to the original units
+    pub fn invert(&self, value: f64) -> f64 {
+        match self {
+            FittedTransform::Log10 => 10f64.powf(value),
+            FittedTransform::Ln => value.exp(),
+            FittedTransform::BoxCox { lambda } => inverse_box_cox(value, *lambda),
+        }
+    }
+}
+
+/// A result in transformed space, reported next to its back-transformed value
+#[cfg_attr(feature = "server", derive(ToSchema))]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TransformedResult {
+    /// The transform applied before calculating
+    pub transform: ValueTransform,
+    /// The estimated Box-Cox lambda
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub lambda: Option<f64>,
+    /// The result in transformed space
+    pub result: f64,
+}
+
+/// Box-Cox transform of a positive value: `(x^λ - 1) / λ`, or `ln x` when λ is 0
+pub fn box_cox(value: f64, lambda: f64) -> f64 {
+    if lambda == 0.0 {
+        value.ln()
+    } else {
+        // expm1 keeps precision when λ is close to zero
+        (lambda * value.ln()).exp_m1() / lambda
+    }
+}
+
+/// Inverse of [`box_cox`]
+pub fn inverse_box_cox(value: f64, lambda: f64) -> f64 {
+    if lambda == 0.0 {
+        value.exp()
+    } else {
+        ((lambda * value).ln_1p() / lambda).exp()
+    }
+}
+
+/// Estimate the Box-Cox lambda that makes `values` most nearly normal
+///
+/// Maximizes the profile log-likelihood over λ in [-5, 5], as
+/// `scipy.stats.boxcox` does. Values must be positive and not all equal.
+pub fn estimate_box_cox_lambda(values: &[f64]) -> Result<f64> {
+    check_positive(values)?;
+    if values.iter().all(|v| *v == values[0]) {
+        anyhow::bail!("Box-Cox needs at least two distinct values");
+    }
+
+    let n = values.len() as f64;
+    let log_sum: f64 = values.iter().map(|v| v.ln()).sum();
+    let log_likelihood = |lambda: f64| {
+        let transformed: Vec<f64> = values.iter().map(|v| box_cox(*v, lambda)).collect();
+        let mean = transformed.iter().sum::<f64>() / n;
+        let variance = transformed.iter().map(|t| (t - mean).powi(2)).sum::<f64>() / n;
+        (lambda - 1.0) * log_sum - n / 2.0 * variance.ln()
+    };
+
+    // Golden-section search; the log-likelihood is concave in λ
+    let ratio = (5f64.sqrt() - 1.0) / 2.0;
+    let (mut low, mut high) = LAMBDA_BOUNDS;
+    let mut left = high - ratio * (high - low);
+    let mut right = low + ratio * (high - low);
+    let (mut f_left, mut f_right) = (log_likelihood(left), log_likelihood(right));
+    while high - low > LAMBDA_TOLERANCE {
+        if f_left < f_right {
+            low = left;
+            left = right;
+            f_left = f_right;
+            right = low + ratio * (high - low);
+            f_right = log_likelihood(right);
+        } else {
+            high = right;
+            right = left;
+            f_right = f_left;
+            left = high - ratio * (high - low);
+            f_left = log_likelihood(left);
+        }
+    }
+    Ok((low + high) / 2.0)
+}
+
+fn check_positive(values: &[f64]) -> Result<()> {
+    if values.is_empty() {
+        anyhow::bail!("Cannot transform an empty dataset");
+    }
+    if values.iter().any(|v| !(*v > 0.0 && v.is_finite())) {
+        anyhow::bail!("Log and Box-Cox transforms need positive, finite values");
+    }
+    Ok(())
+}
@@ -0,0 +1,119 @@
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+
+/// Header carrying the `sha256=<hex>` signature of an outbound payload
+pub const SIGNATURE_HEADER: &str = "x-signature";
+/// Header naming the key the payload was signed with
+pub const KEY_ID_HEADER: &str = "x-signature-key-id";
+/// Header carrying the Unix time the payload was signed at
+pub const TIMESTAMP_HEADER: &str = "x-signature-timestamp";
+
+/// How far a signature's timestamp may be from the receiver's clock
+pub const DEFAULT_TOLERANCE_SECS: u64 = 300;
+
+/// Prefix on signature values
+const SIGNATURE_PREFIX: &str = "sha256=";
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// A shared secret that outbound payloads are signed with
+///
+/// The key id lets receivers rotate secrets: they keep the old secret under
+/// its id until every sender has switched.
+#[derive(Clone)]
+pub struct WebhookKey {
+    pub id: String,
+    pub secret: String,
+}
+
+impl WebhookKey {
+    /// Key from `OUTLIER_WEBHOOK_SECRET` and `OUTLIER_WEBHOOK_KEY_ID` (default
+    /// `"default"`); `None` when no secret is set
+    pub fn from_env() -> Option<Self> {
+        let secret = std::env::var("OUTLIER_WEBHOOK_SECRET")
+            .ok()
+            .filter(|s| !s.is_empty())?;
+        let id = std::env::var("OUTLIER_WEBHOOK_KEY_ID")
+            .ok()
+            .filter(|s| !s.is_empty())
+            .unwrap_or_else(|| "default".to_string());
+        Some(Self { id, secret })
+    }
+
+    /// Signature, key id and timestamp headers for `body` sent at `timestamp`
+    pub fn headers(&self, body: &[u8], timestamp: u64) -> [(&'static str, String); 3] {
+        [
+            (
+                SIGNATURE_HEADER,
+                sign_webhook(self.secret.as_bytes(), timestamp, body),
+            ),
+            (KEY_ID_HEADER, self.id.clone()),
+            (TIMESTAMP_HEADER, timestamp.to_string()),
+        ]
+    }
+}
+
+impl std::fmt::Debug for WebhookKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("WebhookKey")
+            .field("id", &self.id)
+            .finish_non_exhaustive()
+    }
+}
+
+fn mac(secret: &[u8], timestamp: u64, body: &[u8]) -> HmacSha256 {
+    let mut mac = HmacSha256::new_from_slice(secret).expect("HMAC accepts keys of any length");
+    mac.update(timestamp.to_string().as_bytes());
+    mac.update(b".");
+    mac.update(body);
+    mac
+}
+
+/// Sign `"<timestamp>.<body>"` with `secret`, returning the `sha256=<hex>`
+/// header value
+pub fn sign_webhook(secret: &[u8], timestamp: u64, body: &[u8]) -> String {
+    format!(
+        "{SIGNATURE_PREFIX}{}",
+        hex::encode(mac(secret, timestamp, body).finalize().into_bytes())
+    )
+}
+
+/// Check a signed payload as a receiver would
+///
+/// `timestamp` and `signature` are the header values; the check fails when
+/// the timestamp is more than `tolerance_secs` from `now` (Unix seconds), so
+/// a captured request can't be replayed later. The comparison is constant time.
+///
+/// # Examples
+/// ```
+/// use outlier::{sign_webhook, verify_webhook};
+///
+/// let body = br#"{"count": 4}"#;
+/// let signature = sign_webhook(b"secret", 1_700_000_000, body);
+/// assert!(verify_webhook(b"secret", "1700000000", body, &signature, 1_700_000_030, 300));
+/// assert!(!verify_webhook(b"secret", "1700000000", body, &signature, 1_700_009_999, 300));
+/// ```
+pub fn verify_webhook(
+    secret: &[u8],
+    timestamp: &str,
+    body: &[u8],
+    signature: &str,
+    now: u64,
+    tolerance_secs: u64,
+) -> bool {
+    let Ok(timestamp) = timestamp.trim().parse::<u64>() else {
+        return false;
+    };
+    if timestamp.abs_diff(now) > tolerance_secs {
+        return false;
+    }
+    let signature = signature.trim();
+    let Ok(expected) = hex::decode(
+        signature
+            .strip_prefix(SIGNATURE_PREFIX)
+            .unwrap_or(signature),
+    ) else {
+        return false;
+    };
+    mac(secret, timestamp, body).verify_slice(&expected).is_ok()
+}
@@ -0,0 +1,162 @@
+// Ad-hoc analysis page: uploads a file to /analyze/file and draws the result.
+"use strict";
+
+const form = document.getElementById("analyze");
+const drop = document.getElementById("drop");
+const fileInput = document.getElementById("file");
+const apiKey = document.getElementById("api-key");
+const errorBox = document.getElementById("error");
+const results = document.getElementById("results");
+
+apiKey.value = sessionStorage.getItem("outlier-api-key") || "";
+
+let chosen = null;
+
+function choose(file) {
+  chosen = file;
+  document.getElementById("drop-text").textContent = file ? file.name : "";
+  if (file) {
+    form.requestSubmit();
+  }
+}
+
+fileInput.addEventListener("change", () => choose(fileInput.files[0]));
+drop.addEventListener("dragover", (event) => {
+  event.preventDefault();
+  drop.classList.add("active");
+});
+drop.addEventListener("dragleave", () => drop.classList.remove("active"));
+drop.addEventListener("drop", (event) => {
+  event.preventDefault();
+  drop.classList.remove("active");
+  choose(event.dataTransfer.files[0]);
+});
+
+form.addEventListener("submit", async (event) => {
+  event.preventDefault();
+  if (!chosen) {
+    showError("Choose a file first.");
+    return;
+  }
+  const body = new FormData();
+  body.append("percentiles", document.getElementById("percentiles").value);
+  body.append("method", document.getElementById("method").value);
+  body.append("buckets", document.getElementById("buckets").value);
+  body.append("file", chosen, chosen.name);
+
+  const headers = {};
+  if (apiKey.value) {
+    headers["X-API-Key"] = apiKey.value;
+    sessionStorage.setItem("outlier-api-key", apiKey.value);
+  }
+
+  try {
+    const response = await fetch("/analyze/file", { method: "POST", headers, body });
+    const json = await response.json().catch(() => null);
+    if (!response.ok) {
+      const details = json && json.errors ? json.errors.map((e) => e.message) : [];
+      showError([json ? json.error : response.statusText, ...details].join(" — "));
+      return;
+    }
+    render(chosen.name, json);
+  } catch (err) {
+    showError(`Request failed: ${err.message}`);
+  }
+});
+
+function showError(message) {
+  errorBox.textContent = message;
+  errorBox.hidden = false;
+  results.hidden = true;
+}
+
+function format(value) {
+  if (value === null || value === undefined) {
+    return "–";
+  }
+  if (Number.isInteger(value)) {
+    return value.toString();
+  }
+  // Only zeros after a decimal point are padding: toPrecision gives 120000.4
+  // as "120000", which has none, and 1e10 + 0.5 as "1.00000e+10", whose
+  // padding comes before the exponent
+  const [mantissa, exponent] = value.toPrecision(6).split("e");
+  const trimmed = mantissa.includes(".") ? mantissa.replace(/\.?0+$/, "") : mantissa;
+  return exponent === undefined ? trimmed : `${trimmed}e${exponent}`;
+}
+
+function fillTable(id, rows) {
+  const tbody = document.querySelector(`#${id} tbody`);
+  tbody.replaceChildren(
+    ...rows.map(([label, value]) => {
+      const row = document.createElement("tr");
+      const th = document.createElement("th");
+      const td = document.createElement("td");
+      th.textContent = label;
+      td.textContent = format(value);
+      row.append(th, td);
+      return row;
+    }),
+  );
+}
+
+function render(name, analysis) {
+  errorBox.hidden = true;
+  results.hidden = false;
+  document.getElementById("results-title").textContent = name;
+
+  const s = analysis.summary;
+  fillTable("summary", [
+    ["count", s.count],
+    ["mean", s.mean],
+    ["std", s.std],
+    ["min", s.min],
+    ["25%", s["25%"]],
+    ["50%", s["50%"]],
+    ["75%", s["75%"]],
+    ["max", s.max],
+  ]);
+  fillTable(
+    "percentile-table",
+    analysis.percentiles.map((p) => [`P${p.percentile} (${analysis.method})`, p.value]),
+  );
+  drawHistogram(analysis.histogram.buckets);
+}
+
+function drawHistogram(buckets) {
+  const svg = document.getElementById("histogram");
+  const width = svg.clientWidth || 800;
+  const height = svg.clientHeight || 260;
+  const axis = 20;
+  const max = Math.max(...buckets.map((b) => b.count), 1);
+  const barWidth = width / buckets.length;
+  const ns = "http://www.w3.org/2000/svg";
+
+  const shapes = buckets.map((bucket, i) => {
+    const barHeight = ((height - axis) * bucket.count) / max;
+    const rect = document.createElementNS(ns, "rect");
+    rect.setAttribute("x", i * barWidth + 1);
+    rect.setAttribute("y", height - axis - barHeight);
+    rect.setAttribute("width", Math.max(barWidth - 2, 1));
+    rect.setAttribute("height", barHeight);
+    const title = document.createElementNS(ns, "title");
+    title.textContent = `[${format(bucket.lower)}, ${format(bucket.upper)}): ${bucket.count}`;
+    rect.append(title);
+    return rect;
+  });
+
+  const label = (x, anchor, value) => {
+    const text = document.createElementNS(ns, "text");
+    text.setAttribute("x", x);
+    text.setAttribute("y", height - 5);
+    text.setAttribute("text-anchor", anchor);
+    text.textContent = format(value);
+    return text;
+  };
+  svg.setAttribute("viewBox", `0 0 ${width} ${height}`);
+  svg.replaceChildren(
+    ...shapes,
+    label(0, "start", buckets[0].lower),
+    label(width, "end", buckets[buckets.length - 1].upper),
+  );
+}